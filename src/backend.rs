@@ -0,0 +1,159 @@
+//! Selection of the way the output files are written to the disk.
+//!
+//! # Example
+//! ```rust,no_run
+//! use pcap_file::backend::WriterBackend;
+//! use pcap_file::pcapng::PcapNgWriter;
+//!
+//! let file_out = WriterBackend::default().create("out.pcapng").unwrap();
+//! let mut pcapng_writer = PcapNgWriter::new(file_out).unwrap();
+//!
+//! // Write the packets
+//!
+//! pcapng_writer.finish().unwrap().finish().unwrap();
+//! ```
+
+use std::fs::File;
+use std::io::{BufWriter, Result as IoResult, Seek, SeekFrom, Write};
+use std::path::Path;
+
+#[cfg(feature = "mmap")]
+use crate::mmap::MmapWriter;
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+use crate::uring::UringWriter;
+use crate::FileWriter;
+
+
+/// Way the output files are written to the disk, the faster ones requiring a feature.
+///
+/// The enum is non-exhaustive because its variants depend on the enabled features.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum WriterBackend {
+    /// Buffered calls to `write()`
+    #[default]
+    File,
+
+    /// Preallocated file mapped in memory, see [`MmapWriter`]
+    #[cfg(feature = "mmap")]
+    Mmap,
+
+    /// Asynchronous writes submitted to io_uring, see [`UringWriter`]
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    IoUring,
+}
+
+impl WriterBackend {
+    /// Creates a file, or truncates an existing one, and returns a [`BackendWriter`] writing it with this backend.
+    pub fn create<P: AsRef<Path>>(&self, path: P) -> IoResult<BackendWriter> {
+        let inner = match self {
+            WriterBackend::File => Inner::File(BufWriter::new(File::create(path)?)),
+            #[cfg(feature = "mmap")]
+            WriterBackend::Mmap => Inner::Mmap(MmapWriter::create(path)?),
+            #[cfg(all(feature = "io-uring", target_os = "linux"))]
+            WriterBackend::IoUring => Inner::IoUring(Box::new(UringWriter::create(path)?)),
+        };
+
+        Ok(BackendWriter { inner })
+    }
+}
+
+/// Output file written with a [`WriterBackend`], returned by [`WriterBackend::create()`].
+///
+/// It can be wrapped by any writer of the crate, like a [`File`]. Use [`finish()`](Self::finish) to check the errors of
+/// the last writes, which are otherwise ignored when it is dropped.
+#[derive(Debug)]
+pub struct BackendWriter {
+    inner: Inner,
+}
+
+#[derive(Debug)]
+enum Inner {
+    File(BufWriter<File>),
+    #[cfg(feature = "mmap")]
+    Mmap(MmapWriter),
+    // Boxed because of the size of the ring
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    IoUring(Box<UringWriter>),
+}
+
+impl BackendWriter {
+    /// Returns the backend of the writer.
+    pub fn backend(&self) -> WriterBackend {
+        match &self.inner {
+            Inner::File(_) => WriterBackend::File,
+            #[cfg(feature = "mmap")]
+            Inner::Mmap(_) => WriterBackend::Mmap,
+            #[cfg(all(feature = "io-uring", target_os = "linux"))]
+            Inner::IoUring(_) => WriterBackend::IoUring,
+        }
+    }
+
+    /// Completes the pending writes and returns the file.
+    pub fn finish(self) -> IoResult<File> {
+        match self.inner {
+            Inner::File(writer) => writer.into_inner().map_err(|err| err.into_error()),
+            #[cfg(feature = "mmap")]
+            Inner::Mmap(writer) => writer.finish(),
+            #[cfg(all(feature = "io-uring", target_os = "linux"))]
+            Inner::IoUring(writer) => writer.finish(),
+        }
+    }
+
+    fn writer(&mut self) -> &mut dyn FileSeekWriter {
+        match &mut self.inner {
+            Inner::File(writer) => writer,
+            #[cfg(feature = "mmap")]
+            Inner::Mmap(writer) => writer,
+            #[cfg(all(feature = "io-uring", target_os = "linux"))]
+            Inner::IoUring(writer) => writer.as_mut(),
+        }
+    }
+}
+
+/// Writer of a [`BackendWriter`]
+trait FileSeekWriter: FileWriter + Seek {}
+
+impl<W: FileWriter + Seek> FileSeekWriter for W {}
+
+impl Write for BackendWriter {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        self.writer().write(buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> IoResult<()> {
+        self.writer().write_all(buf)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.writer().flush()
+    }
+}
+
+impl Seek for BackendWriter {
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        self.writer().seek(pos)
+    }
+}
+
+impl FileWriter for BackendWriter {
+    fn file(&self) -> &File {
+        match &self.inner {
+            Inner::File(writer) => writer.get_ref(),
+            #[cfg(feature = "mmap")]
+            Inner::Mmap(writer) => writer.file(),
+            #[cfg(all(feature = "io-uring", target_os = "linux"))]
+            Inner::IoUring(writer) => writer.file(),
+        }
+    }
+
+    fn data_len(&self) -> IoResult<u64> {
+        match &self.inner {
+            Inner::File(writer) => writer.get_ref().data_len(),
+            #[cfg(feature = "mmap")]
+            Inner::Mmap(writer) => writer.data_len(),
+            #[cfg(all(feature = "io-uring", target_os = "linux"))]
+            Inner::IoUring(writer) => writer.data_len(),
+        }
+    }
+}
@@ -1,5 +1,12 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Error, ErrorKind, IoSlice, Seek, Write};
+use std::sync::RwLock;
+
 use byteorder_slice::{BigEndian, ByteOrder, LittleEndian};
 
+use crate::{PcapError, PcapResult};
+
 /// Timestamp resolution of the pcap
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum TsResolution {
@@ -9,6 +16,16 @@ pub enum TsResolution {
     NanoSecond,
 }
 
+impl TsResolution {
+    /// Returns the number of timestamp units per second.
+    pub fn units_per_sec(self) -> u64 {
+        match self {
+            TsResolution::MicroSecond => 1_000_000,
+            TsResolution::NanoSecond => 1_000_000_000,
+        }
+    }
+}
+
 /// Endianness of the pcap
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Endianness {
@@ -55,6 +72,239 @@ impl Endianness {
     }
 }
 
+/// Maximum number of bytes captured per packet, as stored in a pcap header or an interface description block.
+///
+/// A snaplen of 0 means no limit.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct SnapLen(pub u32);
+
+impl SnapLen {
+    /// No limit
+    pub const UNLIMITED: SnapLen = SnapLen(0);
+
+    /// True if the snaplen doesn't limit the captured length
+    pub fn is_unlimited(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the limit, or `None` if there is no limit
+    pub fn limit(self) -> Option<u32> {
+        match self.0 {
+            0 => None,
+            snaplen => Some(snaplen),
+        }
+    }
+
+    /// True if a packet of `len` bytes fits in the snaplen
+    pub fn allows(self, len: usize) -> bool {
+        match self.limit() {
+            Some(limit) => len <= limit as usize,
+            None => true,
+        }
+    }
+
+    /// Returns the number of bytes captured from a packet of `len` bytes
+    pub fn captured_len(self, len: usize) -> usize {
+        self.limit().map_or(len, |limit| len.min(limit as usize))
+    }
+}
+
+impl From<u32> for SnapLen {
+    fn from(snaplen: u32) -> Self {
+        SnapLen(snaplen)
+    }
+}
+
+impl From<SnapLen> for u32 {
+    fn from(snaplen: SnapLen) -> Self {
+        snaplen.0
+    }
+}
+
+/// Counters of a reader or a writer, which can be retrieved at any time, e.g. to be exported as service metrics.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct IoStats {
+    /// Number of blocks read or written, including the section headers. For a pcap, the number of packets
+    pub blocks: u64,
+    /// Number of packets read or written
+    pub packets: u64,
+    /// Number of bytes read or written, including the file header
+    pub bytes: u64,
+    /// Number of blocks or packets which returned an error, e.g. invalid ones, even in lenient mode
+    pub errors: u64,
+    /// Number of blocks skipped because of their type, see
+    /// [`PcapNgParserConfig::skip_block_types`](crate::pcapng::PcapNgParserConfig::skip_block_types). Also counted in `blocks`
+    pub skipped: u64,
+    /// Number of blocks or packets accepted after a repair in lenient mode, e.g. a fixed trailer or a clamped length.
+    /// Also counted in `blocks`
+    pub repaired: u64,
+    /// Number of packets returned unchanged despite their inconsistent lengths, see
+    /// [`LengthPolicy::Accept`](crate::pcap::LengthPolicy::Accept). Also counted in `blocks`
+    pub warnings: u64,
+}
+
+impl IoStats {
+    /// Records a block and its length in bytes, or the error returned instead, and returns the block
+    pub(crate) fn record<T, E>(&mut self, res: Result<(T, u64), E>, is_packet: impl FnOnce(&T) -> bool) -> Result<T, E> {
+        match res {
+            Ok((value, len)) => {
+                self.blocks += 1;
+                self.packets += is_packet(&value) as u64;
+                self.bytes += len;
+                Ok(value)
+            },
+            Err(err) => {
+                self.errors += 1;
+                Err(err)
+            },
+        }
+    }
+
+    /// Records a block like [`Self::record()`], along with the number of repaired blocks so far
+    pub(crate) fn record_repaired<T, E>(&mut self, res: Result<((T, u64), u64), E>, is_packet: impl FnOnce(&T) -> bool) -> Result<T, E> {
+        let res = res.map(|((value, repaired), len)| {
+            self.repaired = repaired;
+            (value, len)
+        });
+        self.record(res, is_packet)
+    }
+}
+
+/// Progress of a reader, given to the callback registered with e.g.
+/// [`PcapNgReader::set_progress_callback()`](crate::pcapng::PcapNgReader::set_progress_callback).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Progress {
+    /// Counters of the reader, whose bytes are the bytes consumed so far
+    pub stats: IoStats,
+    /// Total size of the input in bytes, if known
+    pub total: Option<u64>,
+}
+
+impl Progress {
+    /// Returns the consumed fraction of the input, between 0 and 1, if its total size is known
+    pub fn fraction(&self) -> Option<f64> {
+        self.total.map(|total| if total == 0 { 1.0 } else { (self.stats.bytes as f64 / total as f64).min(1.0) })
+    }
+}
+
+/// Progress callback of a reader, called each time `interval` bytes have been consumed
+pub(crate) struct ProgressCallback {
+    interval: u64,
+    total: Option<u64>,
+    next: u64,
+    callback: Box<dyn FnMut(Progress) + Send>,
+}
+
+impl ProgressCallback {
+    /// Creates a new ProgressCallback, `stats` being the current counters of the reader
+    pub fn new<F: FnMut(Progress) + Send + 'static>(stats: &IoStats, interval: u64, total: Option<u64>, callback: F) -> Self {
+        let interval = interval.max(1);
+        Self { interval, total, next: stats.bytes.saturating_add(interval), callback: Box::new(callback) }
+    }
+
+    /// Calls the callback if enough bytes have been consumed since the last call
+    pub fn update(progress: &mut Option<Self>, stats: &IoStats) {
+        if let Some(progress) = progress {
+            if stats.bytes >= progress.next {
+                (progress.callback)(Progress { stats: *stats, total: progress.total });
+                progress.next = stats.bytes.saturating_add(progress.interval);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgressCallback").field("interval", &self.interval).field("total", &self.total).finish()
+    }
+}
+
+/// Writer backed by a [`File`], whose data can be synced to the disk by the `sync_data()` and `sync_all()` methods of the writers.
+pub trait FileWriter: Write {
+    /// Returns the underlying file
+    fn file(&self) -> &File;
+
+    /// Returns the length of the data written to the file, excluding the data still buffered by the writer
+    ///
+    /// Defaults to the position of the file, for the writers which write at its position.
+    fn data_len(&self) -> std::io::Result<u64> {
+        let mut file = self.file();
+        file.stream_position()
+    }
+}
+
+impl FileWriter for File {
+    fn file(&self) -> &File {
+        self
+    }
+}
+
+impl FileWriter for BufWriter<File> {
+    fn file(&self) -> &File {
+        self.get_ref()
+    }
+}
+
+/// Reserves the disk space of the first `len` bytes of a file, extending it if needed but never shrinking it.
+///
+/// The space is allocated with `posix_fallocate()`, so that a full disk is reported here rather than by a later write.
+/// The file is only extended with [`File::set_len()`], which creates a sparse file, where it isn't supported.
+pub(crate) fn allocate(file: &File, len: u64) -> std::io::Result<()> {
+    #[cfg(unix)]
+    if let Ok(len) = libc::off_t::try_from(len) {
+        use std::os::unix::io::AsRawFd;
+
+        // Sound because the descriptor is owned by `file`, which outlives the call
+        match unsafe { libc::posix_fallocate(file.as_raw_fd(), 0, len) } {
+            0 => return Ok(()),
+            // Not supported by the file system
+            libc::EINVAL | libc::EOPNOTSUPP => {},
+            err => return Err(Error::from_raw_os_error(err)),
+        }
+    }
+
+    if len > file.metadata()?.len() {
+        file.set_len(len)?;
+    }
+
+    Ok(())
+}
+
+/// Reserves the disk space of `len` more bytes after the written data of a [`FileWriter`], see [`allocate()`]
+pub(crate) fn preallocate<W: FileWriter>(writer: &W, len: u64) -> PcapResult<()> {
+    let end = writer
+        .data_len()
+        .map_err(PcapError::IoError)?
+        .checked_add(len)
+        .ok_or(PcapError::InvalidField("FileWriter: preallocated length > u64::MAX"))?;
+
+    allocate(writer.file(), end).map_err(PcapError::IoError)
+}
+
+/// Truncates the file of a [`FileWriter`] to the written data, removing the preallocated space
+pub(crate) fn truncate<W: FileWriter>(writer: &W) -> PcapResult<()> {
+    let len = writer.data_len().map_err(PcapError::IoError)?;
+    writer.file().set_len(len).map_err(PcapError::IoError)
+}
+
+/// Adds the length of the parsed data to the output of a parser, for [`IoStats::record()`]
+pub(crate) fn with_len<'a, T>(src: &'a [u8], res: PcapResult<(&'a [u8], T)>) -> PcapResult<(&'a [u8], (T, u64))> {
+    res.map(|(rem, value)| (rem, (value, (src.len() - rem.len()) as u64)))
+}
+
+/// Reads the `N` consecutive u32 fields of a fixed-layout header at the start of `slice`, which must be long enough.
+///
+/// The fields are read in the native endianness, which compiles to plain loads, then only swapped if the endianness of
+/// the file isn't the one of the host.
+#[inline]
+pub(crate) fn read_u32_fields<B: ByteOrder, const N: usize>(slice: &[u8]) -> [u32; N] {
+    let swap = Endianness::from_byteorder::<B>() != Endianness::native();
+    std::array::from_fn(|i| {
+        let field = u32::from_ne_bytes(slice[i * 4..i * 4 + 4].try_into().unwrap());
+        if swap { field.swap_bytes() } else { field }
+    })
+}
+
 pub(crate) trait RuntimeByteorder: ByteOrder {
     fn endianness() -> Endianness;
 }
@@ -71,6 +321,28 @@ impl RuntimeByteorder for LittleEndian {
     }
 }
 
+/// Writes all the buffers with as few calls to [`Write::write_vectored()`] as possible.
+pub(crate) fn write_all_vectored<W: Write, const N: usize>(writer: &mut W, mut bufs: [&[u8]; N]) -> std::io::Result<()> {
+    // The buffers are advanced by hand, IoSlice::advance_slices() requiring Rust 1.81
+    while let Some(first) = bufs.iter().position(|buf| !buf.is_empty()) {
+        let slices = bufs.map(IoSlice::new);
+        match writer.write_vectored(&slices[first..]) {
+            Ok(0) => return Err(Error::from(ErrorKind::WriteZero)),
+            Ok(mut len) => {
+                for buf in &mut bufs[first..] {
+                    let written = len.min(buf.len());
+                    *buf = &buf[written..];
+                    len -= written;
+                }
+            },
+            Err(err) if err.kind() == ErrorKind::Interrupted => {},
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(())
+}
+
 
 /// Data link type
 ///
@@ -734,3 +1006,78 @@ impl From<DataLink> for u32 {
         }
     }
 }
+
+/// Operating system whose libpcap `DLT_*` values are converted.
+///
+/// Some `DLT_*` values differ between platforms and from the portable `LINKTYPE_*` values stored in the capture files,
+/// which a raw libpcap header or a BPF device may still contain.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum DltPlatform {
+    /// Linux, Windows and the other platforms using the default libpcap values
+    #[default]
+    Common,
+    /// OpenBSD
+    OpenBsd,
+    /// FreeBSD and DragonFly BSD
+    FreeBsd,
+    /// NetBSD
+    NetBsd,
+    /// macOS and the other Apple platforms
+    Darwin,
+}
+
+impl DltPlatform {
+    /// Returns the `(DLT_*, LINKTYPE_*)` pairs whose values differ on this platform
+    fn dlt_table(self) -> &'static [(u32, u32)] {
+        match self {
+            DltPlatform::Common => &[(11, 100), (12, 101), (15, 102), (16, 103), (19, 106)],
+            DltPlatform::OpenBsd => &[(11, 100), (14, 101), (15, 102), (16, 103), (19, 106), (12, 108), (13, 109), (18, 246)],
+            DltPlatform::FreeBsd | DltPlatform::NetBsd => &[(11, 100), (12, 101), (13, 102), (14, 103), (19, 106)],
+            DltPlatform::Darwin => &[(11, 100), (12, 101), (15, 102), (16, 103), (19, 106), (149, 258)],
+        }
+    }
+}
+
+impl DataLink {
+    /// Creates a [`DataLink`] from a `DLT_*` value of the given platform.
+    ///
+    /// The values without a platform specific meaning are the same as the `LINKTYPE_*` values.
+    pub fn from_dlt(dlt: u32, platform: DltPlatform) -> DataLink {
+        let linktype = platform.dlt_table().iter().find(|&&(d, _)| d == dlt).map_or(dlt, |&(_, linktype)| linktype);
+        DataLink::from(linktype)
+    }
+
+    /// Returns the `DLT_*` value of the [`DataLink`] on the given platform.
+    pub fn to_dlt(self, platform: DltPlatform) -> u32 {
+        let linktype = u32::from(self);
+        platform.dlt_table().iter().find(|&&(_, l)| l == linktype).map_or(linktype, |&(dlt, _)| dlt)
+    }
+}
+
+/// Display names registered by the applications for their private linktypes
+static LINKTYPE_NAMES: RwLock<BTreeMap<u32, String>> = RwLock::new(BTreeMap::new());
+
+impl DataLink {
+    /// Registers the name displayed for a linktype, typically one of the private [`DataLink::USER0`] to [`DataLink::USER15`].
+    ///
+    /// The name is used by the [`Display`](std::fmt::Display) implementation of [`DataLink`] for the whole process,
+    /// replacing a previously registered name.
+    pub fn register_name(linktype: u32, name: impl Into<String>) {
+        LINKTYPE_NAMES.write().unwrap_or_else(|err| err.into_inner()).insert(linktype, name.into());
+    }
+
+    /// Returns the name registered with [`DataLink::register_name()`] for this linktype.
+    pub fn registered_name(self) -> Option<String> {
+        LINKTYPE_NAMES.read().unwrap_or_else(|err| err.into_inner()).get(&u32::from(self)).cloned()
+    }
+}
+
+/// Displays the registered name of the linktype, or the name of its variant
+impl std::fmt::Display for DataLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.registered_name() {
+            Some(name) => f.write_str(&name),
+            None => write!(f, "{self:?}"),
+        }
+    }
+}
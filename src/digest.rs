@@ -0,0 +1,241 @@
+//! Per-packet digests, to deduplicate the packets or to build integrity manifests.
+//!
+//! Requires the `digest` feature.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::collections::HashSet;
+//! use std::fs::File;
+//!
+//! use pcap_file::digest::{with_digest, DigestAlgorithm};
+//! use pcap_file::pcap::PcapReader;
+//!
+//! let file_in = File::open("test.pcap").expect("Error opening file");
+//! let pcap_reader = PcapReader::new(file_in).unwrap();
+//!
+//! let mut seen = HashSet::new();
+//! for res in with_digest(pcap_reader, DigestAlgorithm::Xxh3) {
+//!     let (packet, digest) = res.unwrap();
+//!     if !seen.insert(digest) {
+//!         println!("Duplicate packet at {:?}", packet.timestamp);
+//!     }
+//! }
+//! ```
+
+use std::fmt::{Debug, Display, Formatter};
+use std::io::{Result as IoResult, Write};
+
+use sha2::{Digest, Sha256};
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::pcap::PcapPacket;
+use crate::pcapng::blocks::enhanced_packet::EnhancedPacketBlock;
+use crate::pcapng::PcapNgPacket;
+
+
+/// Algorithm of a [`PacketDigest`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum DigestAlgorithm {
+    /// 64 bits XXH3, a fast non-cryptographic hash suited to deduplication
+    #[default]
+    Xxh3,
+    /// 128 bits XXH3, with fewer collisions on very large captures
+    Xxh3_128,
+    /// SHA-256, a cryptographic hash suited to integrity manifests
+    Sha256,
+}
+
+impl DigestAlgorithm {
+    /// Computes the digest of `data`.
+    pub fn compute(&self, data: &[u8]) -> PacketDigest {
+        match self {
+            DigestAlgorithm::Xxh3 => PacketDigest::new(*self, &xxhash_rust::xxh3::xxh3_64(data).to_be_bytes()),
+            DigestAlgorithm::Xxh3_128 => PacketDigest::new(*self, &xxhash_rust::xxh3::xxh3_128(data).to_be_bytes()),
+            DigestAlgorithm::Sha256 => PacketDigest::new(*self, &Sha256::digest(data)),
+        }
+    }
+
+    /// Creates a [`DigestHasher`] computing the digest of data given in several parts.
+    pub fn hasher(&self) -> DigestHasher {
+        let state = match self {
+            DigestAlgorithm::Xxh3 | DigestAlgorithm::Xxh3_128 => HasherState::Xxh3(Box::new(Xxh3::new())),
+            DigestAlgorithm::Sha256 => HasherState::Sha256(Sha256::new()),
+        };
+
+        DigestHasher { algorithm: *self, state }
+    }
+
+    /// Returns the length in bytes of the digests.
+    pub fn digest_len(&self) -> usize {
+        match self {
+            DigestAlgorithm::Xxh3 => 8,
+            DigestAlgorithm::Xxh3_128 => 16,
+            DigestAlgorithm::Sha256 => 32,
+        }
+    }
+}
+
+/// Computes the digest of data given in several parts, created by [`DigestAlgorithm::hasher()`].
+///
+/// It implements [`Write`] to digest the output of a writer.
+#[derive(Clone)]
+pub struct DigestHasher {
+    algorithm: DigestAlgorithm,
+    state: HasherState,
+}
+
+#[derive(Clone)]
+enum HasherState {
+    Xxh3(Box<Xxh3>),
+    Sha256(Sha256),
+}
+
+impl DigestHasher {
+    /// Returns the algorithm of the hasher.
+    pub fn algorithm(&self) -> DigestAlgorithm {
+        self.algorithm
+    }
+
+    /// Adds `data` to the digested data.
+    pub fn update(&mut self, data: &[u8]) {
+        match &mut self.state {
+            HasherState::Xxh3(hasher) => hasher.update(data),
+            HasherState::Sha256(hasher) => Digest::update(hasher, data),
+        }
+    }
+
+    /// Returns the digest of all the data given.
+    pub fn finish(self) -> PacketDigest {
+        match (self.algorithm, self.state) {
+            (DigestAlgorithm::Xxh3_128, HasherState::Xxh3(hasher)) => PacketDigest::new(self.algorithm, &hasher.digest128().to_be_bytes()),
+            (_, HasherState::Xxh3(hasher)) => PacketDigest::new(self.algorithm, &hasher.digest().to_be_bytes()),
+            (_, HasherState::Sha256(hasher)) => PacketDigest::new(self.algorithm, &hasher.finalize()),
+        }
+    }
+}
+
+impl Debug for DigestHasher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DigestHasher").field("algorithm", &self.algorithm).finish_non_exhaustive()
+    }
+}
+
+impl Write for DigestHasher {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        Ok(())
+    }
+}
+
+/// Digest of the data of a packet.
+///
+/// It is cheap to copy and can be used as the key of a map, e.g. to find the duplicated packets.
+/// It is displayed in hexadecimal.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct PacketDigest {
+    algorithm: DigestAlgorithm,
+    bytes: [u8; 32],
+    len: u8,
+}
+
+impl PacketDigest {
+    /// Creates a [`PacketDigest`] from its bytes, which must be as long as the digests of the algorithm.
+    pub(crate) fn new(algorithm: DigestAlgorithm, digest: &[u8]) -> Self {
+        let mut bytes = [0_u8; 32];
+        bytes[..digest.len()].copy_from_slice(digest);
+
+        PacketDigest { algorithm, bytes, len: digest.len() as u8 }
+    }
+
+    /// Returns the algorithm of the digest.
+    pub fn algorithm(&self) -> DigestAlgorithm {
+        self.algorithm
+    }
+
+    /// Returns the bytes of the digest, the integers being in big endian.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+}
+
+impl Display for PacketDigest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.as_bytes().iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+/// Packet whose data can be digested.
+pub trait PacketData {
+    /// Returns the captured data of the packet.
+    fn packet_data(&self) -> &[u8];
+}
+
+impl PacketData for PcapPacket<'_> {
+    fn packet_data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl PacketData for PcapNgPacket<'_> {
+    fn packet_data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl PacketData for EnhancedPacketBlock<'_> {
+    fn packet_data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Wraps an iterator of packets, e.g. [`PcapReader::packets()`](crate::pcap::PcapReader::packets), to yield
+/// each packet with the digest of its data.
+///
+/// The errors of the inner iterator are returned unchanged.
+pub fn with_digest<I, P, E>(packets: I, algorithm: DigestAlgorithm) -> WithDigest<I::IntoIter>
+where
+    I: IntoIterator<Item = Result<P, E>>,
+    P: PacketData,
+{
+    WithDigest { packets: packets.into_iter(), algorithm }
+}
+
+/// Iterator over packets and their digest, returned by [`with_digest()`].
+pub struct WithDigest<I> {
+    packets: I,
+    algorithm: DigestAlgorithm,
+}
+
+impl<I> WithDigest<I> {
+    /// Returns the algorithm of the digests.
+    pub fn algorithm(&self) -> DigestAlgorithm {
+        self.algorithm
+    }
+
+    /// Consumes [`Self`], returning the inner iterator.
+    pub fn into_inner(self) -> I {
+        self.packets
+    }
+}
+
+impl<I, P, E> Iterator for WithDigest<I>
+where
+    I: Iterator<Item = Result<P, E>>,
+    P: PacketData,
+{
+    type Item = Result<(P, PacketDigest), E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let algorithm = self.algorithm;
+        self.packets.next().map(|res| {
+            res.map(|packet| {
+                let digest = algorithm.compute(packet.packet_data());
+                (packet, digest)
+            })
+        })
+    }
+}
@@ -0,0 +1,161 @@
+//! Export of a packet summary to CSV.
+//!
+//! One row is written per packet, with the following columns:
+//! `index,timestamp,interface,linktype,captured_len,original_len,flags,comment`.
+//!
+//! - `index` is the 0-based index of the packet in the export.
+//! - `timestamp` is an RFC3339 UTC string, empty if the interface of the packet is unknown.
+//! - `linktype` is the name of the link type of the interface, as displayed by [`DataLink`](crate::DataLink), empty if the interface is unknown.
+//! - `flags` is the hexadecimal value of the `epb_flags` option, empty if absent.
+//! - `comment` contains the comments of the packet, separated by `"; "`.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::fs::File;
+//!
+//! use pcap_file::export::csv::CsvExporter;
+//! use pcap_file::pcapng::PcapNgReader;
+//!
+//! let file_in = File::open("test.pcapng").expect("Error opening file");
+//! let mut pcapng_reader = PcapNgReader::new(file_in).unwrap();
+//!
+//! let file_out = File::create("summary.csv").expect("Error creating file");
+//! let mut exporter = CsvExporter::new(file_out).unwrap();
+//!
+//! while let Some(block) = pcapng_reader.next_block() {
+//!     exporter.export_block(&block.unwrap()).unwrap();
+//! }
+//! ```
+
+use std::io::Write;
+
+use super::block_comments;
+use crate::fmt::rfc3339;
+use crate::pcap::PcapPacket;
+use crate::pcapng::blocks::enhanced_packet::EnhancedPacketOption;
+use crate::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use crate::pcapng::blocks::packet::PacketOption;
+use crate::pcapng::Block;
+use crate::{DataLink, PcapError, PcapResult};
+
+
+/// Header row of the CSV
+const HEADER: &str = "index,timestamp,interface,linktype,captured_len,original_len,flags,comment";
+
+/// Exports a one row per packet summary of a capture as CSV.
+///
+/// Keeps track of the [`InterfaceDescriptionBlock`]s of the current section to render the timestamps
+/// and link types of the packets.
+pub struct CsvExporter<W: Write> {
+    writer: W,
+    interfaces: Vec<InterfaceDescriptionBlock<'static>>,
+    index: u64,
+}
+
+impl<W: Write> CsvExporter<W> {
+    /// Creates a new [`CsvExporter`] and writes the header row.
+    pub fn new(mut writer: W) -> PcapResult<Self> {
+        writeln!(writer, "{HEADER}").map_err(PcapError::IoError)?;
+        Ok(Self { writer, interfaces: vec![], index: 0 })
+    }
+
+    /// Exports a pcapng [`Block`].
+    ///
+    /// Writes a row if the block is a packet, else only updates the state of the exporter.
+    pub fn export_block(&mut self, block: &Block) -> PcapResult<()> {
+        let comment = block_comments(block).join("; ");
+
+        match block {
+            Block::SectionHeader(_) => {
+                self.interfaces.clear();
+                Ok(())
+            },
+            Block::InterfaceDescription(idb) => {
+                self.interfaces.push(idb.clone().into_owned());
+                Ok(())
+            },
+            Block::EnhancedPacket(b) => {
+                let flags = b.options.iter().find_map(|opt| match opt {
+                    EnhancedPacketOption::Flags(flags) => Some(flags.bits()),
+                    _ => None,
+                });
+                let timestamp = b.timestamp.as_nanos() as u64;
+                self.write_row(Some(b.interface_id), Some(timestamp), b.data.len(), b.original_len, flags, &comment)
+            },
+            Block::Packet(b) => {
+                let flags = b.options.iter().find_map(|opt| match opt {
+                    PacketOption::Flags(flags) => Some(flags.bits()),
+                    _ => None,
+                });
+                self.write_row(Some(b.interface_id as u32), Some(b.timestamp), b.data.len(), b.original_len, flags, &comment)
+            },
+            Block::SimplePacket(b) => self.write_row(Some(0), None, b.data.len(), b.original_len, None, &comment),
+            _ => Ok(()),
+        }
+    }
+
+    /// Exports a [`PcapPacket`] of a pcap whose header declares the given [`DataLink`].
+    pub fn export_pcap_packet(&mut self, packet: &PcapPacket, datalink: DataLink) -> PcapResult<()> {
+        let row = format!(
+            "{},{},,{},{},{},,",
+            self.index,
+            rfc3339(packet.timestamp),
+            escape(&datalink.to_string()),
+            packet.data.len(),
+            packet.orig_len
+        );
+
+        self.index += 1;
+        writeln!(self.writer, "{row}").map_err(PcapError::IoError)
+    }
+
+    /// Consumes [`Self`], returning the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_row(
+        &mut self,
+        interface_id: Option<u32>,
+        timestamp: Option<u64>,
+        captured_len: usize,
+        original_len: u32,
+        flags: Option<u32>,
+        comment: &str,
+    ) -> PcapResult<()> {
+        let interface = interface_id.and_then(|id| self.interfaces.get(id as usize));
+
+        let timestamp = match (interface, timestamp) {
+            (Some(interface), Some(ts)) => rfc3339(interface.timestamp_to_duration(ts)),
+            _ => String::new(),
+        };
+        let linktype = interface.map(|interface| escape(&interface.linktype.to_string())).unwrap_or_default();
+        let interface_id = interface_id.map(|id| id.to_string()).unwrap_or_default();
+        let flags = flags.map(|flags| format!("{flags:#010x}")).unwrap_or_default();
+
+        let row = format!(
+            "{},{},{},{},{},{},{},{}",
+            self.index,
+            timestamp,
+            interface_id,
+            linktype,
+            captured_len,
+            original_len,
+            flags,
+            escape(comment)
+        );
+
+        self.index += 1;
+        writeln!(self.writer, "{row}").map_err(PcapError::IoError)
+    }
+}
+
+/// Escapes a CSV field, quoting it if it contains a separator, a quote or a line break.
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    }
+    else {
+        field.to_string()
+    }
+}
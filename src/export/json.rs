@@ -0,0 +1,287 @@
+//! Export of blocks and packets to JSON.
+//!
+//! Each block or packet is exported as a JSON object on its own line (JSON Lines), which makes the output easy
+//! to process with jq-based tooling and log pipelines.
+//!
+//! # Schema
+//!
+//! Every object has a `"block"` field naming the kind of the exported item, the other fields depend on it:
+//!
+//! | `block`                  | Fields                                                                                            |
+//! |--------------------------|---------------------------------------------------------------------------------------------------|
+//! | `"section_header"`       | `endianness`, `major_version`, `minor_version`, `section_length`, `comments`                      |
+//! | `"interface_description"`| `linktype`, `snaplen`, `comments`                                                                 |
+//! | `"packet"`               | `interface_id`, `timestamp`, `timestamp_raw`, `drop_count`, `captured_len`, `original_len`, `data`, `comments` |
+//! | `"simple_packet"`        | `captured_len`, `original_len`, `data`                                                            |
+//! | `"name_resolution"`      | `records` (array of `{ "ip", "names" }`), `comments`                                              |
+//! | `"interface_statistics"` | `interface_id`, `timestamp`, `timestamp_raw`, `comments`                                          |
+//! | `"enhanced_packet"`      | `interface_id`, `timestamp`, `timestamp_raw`, `captured_len`, `original_len`, `data`, `comments`  |
+//! | `"systemd_journal_export"` | `entry`                                                                                         |
+//! | `"decryption_secrets"`   | `secrets_type`, `secrets_len`, `comments`                                                         |
+//! | `"hone_machine_info"`    | `comments`                                                                                        |
+//! | `"hone_connection_event"`| `connection_id`, `process_id`, `timestamp_raw`, `comments`                                        |
+//! | `"unknown"`              | `type`, `length`                                                                                  |
+//! | `"pcap_packet"`          | `timestamp`, `captured_len`, `original_len`, `data`                                               |
+//!
+//! - `timestamp` is an RFC3339 UTC string, or `null` if the interface of the packet is unknown.
+//! - `timestamp_raw` is the timestamp in the units of the interface.
+//! - `data` is encoded according to the [`DataEncoding`] of the exporter.
+//! - `linktype` is the numerical value of the link type.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::fs::File;
+//!
+//! use pcap_file::export::json::JsonExporter;
+//! use pcap_file::pcapng::PcapNgReader;
+//!
+//! let file_in = File::open("test.pcapng").expect("Error opening file");
+//! let mut pcapng_reader = PcapNgReader::new(file_in).unwrap();
+//!
+//! let mut exporter = JsonExporter::new(std::io::stdout());
+//! exporter.export_block(&pcapng_reader.section().clone().into()).unwrap();
+//!
+//! while let Some(block) = pcapng_reader.next_block() {
+//!     exporter.export_block(&block.unwrap()).unwrap();
+//! }
+//! ```
+
+use std::io::Write;
+
+use serde_json::{json, Value};
+
+use super::block_comments;
+use crate::fmt::rfc3339;
+use crate::pcap::PcapPacket;
+use crate::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use crate::pcapng::blocks::name_resolution::Record;
+use crate::pcapng::Block;
+use crate::{PcapError, PcapResult};
+
+
+/// Encoding of the packet data in the exported JSON.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum DataEncoding {
+    /// Lowercase hexadecimal string
+    #[default]
+    Hex,
+    /// Standard base64 string, with padding
+    Base64,
+}
+
+impl DataEncoding {
+    /// Encodes the data into a string.
+    pub fn encode(&self, data: &[u8]) -> String {
+        match self {
+            DataEncoding::Hex => data.iter().map(|b| format!("{b:02x}")).collect(),
+            DataEncoding::Base64 => base64(data),
+        }
+    }
+}
+
+/// Encodes data in standard base64 with padding.
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        out.push(ALPHABET[(n >> 18) as usize & 0x3F] as char);
+        out.push(ALPHABET[(n >> 12) as usize & 0x3F] as char);
+        out.push(if chunk.len() > 1 { ALPHABET[(n >> 6) as usize & 0x3F] as char } else { '=' });
+        out.push(if chunk.len() > 2 { ALPHABET[n as usize & 0x3F] as char } else { '=' });
+    }
+
+    out
+}
+
+
+/// Exports blocks and packets as JSON Lines to a writer.
+///
+/// Keeps track of the [`InterfaceDescriptionBlock`]s of the current section to render the timestamps of the packets.
+pub struct JsonExporter<W: Write> {
+    writer: W,
+    data_encoding: DataEncoding,
+    interfaces: Vec<InterfaceDescriptionBlock<'static>>,
+}
+
+impl<W: Write> JsonExporter<W> {
+    /// Creates a new [`JsonExporter`] encoding the packet data in hexadecimal.
+    pub fn new(writer: W) -> Self {
+        Self::with_data_encoding(writer, DataEncoding::Hex)
+    }
+
+    /// Creates a new [`JsonExporter`] with the given [`DataEncoding`].
+    pub fn with_data_encoding(writer: W, data_encoding: DataEncoding) -> Self {
+        Self { writer, data_encoding, interfaces: vec![] }
+    }
+
+    /// Exports a pcapng [`Block`].
+    pub fn export_block(&mut self, block: &Block) -> PcapResult<()> {
+        match block {
+            Block::SectionHeader(_) => self.interfaces.clear(),
+            Block::InterfaceDescription(idb) => self.interfaces.push(idb.clone().into_owned()),
+            _ => (),
+        }
+
+        let value = block_to_json(block, &self.interfaces, self.data_encoding);
+        self.write_value(&value)
+    }
+
+    /// Exports a [`PcapPacket`].
+    pub fn export_pcap_packet(&mut self, packet: &PcapPacket) -> PcapResult<()> {
+        let value = pcap_packet_to_json(packet, self.data_encoding);
+        self.write_value(&value)
+    }
+
+    /// Consumes [`Self`], returning the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_value(&mut self, value: &Value) -> PcapResult<()> {
+        serde_json::to_writer(&mut self.writer, value).map_err(|e| PcapError::IoError(e.into()))?;
+        self.writer.write_all(b"\n").map_err(PcapError::IoError)
+    }
+}
+
+
+/// Converts a pcapng [`Block`] into a JSON value.
+///
+/// The `interfaces` of the current section are used to render the timestamps of the packets.
+pub fn block_to_json(block: &Block, interfaces: &[InterfaceDescriptionBlock], data_encoding: DataEncoding) -> Value {
+    let timestamp = |interface_id: u32, ts: u64| match interfaces.get(interface_id as usize) {
+        Some(interface) => Value::String(rfc3339(interface.timestamp_to_duration(ts))),
+        None => Value::Null,
+    };
+    let comments = block_comments(block);
+
+    match block {
+        Block::SectionHeader(b) => json!({
+            "block": "section_header",
+            "endianness": format!("{:?}", b.endianness),
+            "major_version": b.major_version,
+            "minor_version": b.minor_version,
+            "section_length": b.section_length,
+            "comments": comments,
+        }),
+        Block::InterfaceDescription(b) => json!({
+            "block": "interface_description",
+            "linktype": u32::from(b.linktype),
+            "snaplen": b.snaplen,
+            "comments": comments,
+        }),
+        Block::Packet(b) => json!({
+            "block": "packet",
+            "interface_id": b.interface_id,
+            "timestamp": timestamp(b.interface_id as u32, b.timestamp),
+            "timestamp_raw": b.timestamp,
+            "drop_count": b.drop_count,
+            "captured_len": b.data.len(),
+            "original_len": b.original_len,
+            "data": data_encoding.encode(&b.data),
+            "comments": comments,
+        }),
+        Block::SimplePacket(b) => json!({
+            "block": "simple_packet",
+            "captured_len": b.data.len(),
+            "original_len": b.original_len,
+            "data": data_encoding.encode(&b.data),
+        }),
+        Block::NameResolution(b) => {
+            let records: Vec<Value> = b
+                .records
+                .iter()
+                .filter_map(|record| match record {
+                    Record::Ipv4(r) => Some(json!({ "ip": ip_to_string(&r.ip_addr), "names": r.names })),
+                    Record::Ipv6(r) => Some(json!({ "ip": ip_to_string(&r.ip_addr), "names": r.names })),
+                    Record::End | Record::Unknown(_) => None,
+                })
+                .collect();
+
+            json!({
+                "block": "name_resolution",
+                "records": records,
+                "comments": comments,
+            })
+        },
+        Block::InterfaceStatistics(b) => json!({
+            "block": "interface_statistics",
+            "interface_id": b.interface_id,
+            "timestamp": timestamp(b.interface_id, b.timestamp),
+            "timestamp_raw": b.timestamp,
+            "comments": comments,
+        }),
+        Block::EnhancedPacket(b) => json!({
+            "block": "enhanced_packet",
+            "interface_id": b.interface_id,
+            "timestamp": timestamp(b.interface_id, b.timestamp.as_nanos() as u64),
+            "timestamp_raw": b.timestamp.as_nanos() as u64,
+            "captured_len": b.data.len(),
+            "original_len": b.original_len,
+            "data": data_encoding.encode(&b.data),
+            "comments": comments,
+        }),
+        Block::SystemdJournalExport(b) => json!({
+            "block": "systemd_journal_export",
+            "entry": String::from_utf8_lossy(&b.journal_entry),
+        }),
+        Block::DecryptionSecrets(b) => json!({
+            "block": "decryption_secrets",
+            "secrets_type": u32::from(b.secrets_type),
+            "secrets_len": b.secrets_data.len(),
+            "comments": comments,
+        }),
+        Block::HoneMachineInfo(_) => json!({
+            "block": "hone_machine_info",
+            "comments": comments,
+        }),
+        Block::HoneConnectionEvent(b) => json!({
+            "block": "hone_connection_event",
+            "connection_id": b.connection_id,
+            "process_id": b.process_id,
+            "timestamp_raw": b.timestamp,
+            "comments": comments,
+        }),
+        Block::Custom(b) => json!({
+            "block": "custom",
+            "pen": b.pen,
+            "copiable": b.copiable,
+            "data_len": b.data.len(),
+        }),
+        Block::Registered(b) => json!({
+            "block": "registered",
+            "type": b.block_type(),
+        }),
+        Block::Unknown(b) => json!({
+            "block": "unknown",
+            "type": b.type_,
+            "length": b.length,
+        }),
+    }
+}
+
+/// Converts a [`PcapPacket`] into a JSON value.
+pub fn pcap_packet_to_json(packet: &PcapPacket, data_encoding: DataEncoding) -> Value {
+    json!({
+        "block": "pcap_packet",
+        "timestamp": rfc3339(packet.timestamp),
+        "captured_len": packet.data.len(),
+        "original_len": packet.orig_len,
+        "data": data_encoding.encode(&packet.data),
+    })
+}
+
+/// Renders an IPv4 or IPv6 address, falling back to hexadecimal for invalid lengths.
+fn ip_to_string(ip: &[u8]) -> String {
+    if let Ok(ip) = <[u8; 4]>::try_from(ip) {
+        return std::net::Ipv4Addr::from(ip).to_string();
+    }
+    if let Ok(ip) = <[u8; 16]>::try_from(ip) {
+        return std::net::Ipv6Addr::from(ip).to_string();
+    }
+
+    DataEncoding::Hex.encode(ip)
+}
@@ -0,0 +1,98 @@
+//! Export of captures to other formats.
+//!
+//! The [`json`] exporter requires the `json` feature.
+
+pub mod csv;
+#[cfg(feature = "json")]
+pub mod json;
+
+use crate::pcapng::blocks::decryption_secrets::DecryptionSecretsOption;
+use crate::pcapng::blocks::enhanced_packet::EnhancedPacketOption;
+use crate::pcapng::blocks::hone_connection_event::HoneConnectionEventOption;
+use crate::pcapng::blocks::hone_machine_info::HoneMachineInfoOption;
+use crate::pcapng::blocks::interface_description::InterfaceDescriptionOption;
+use crate::pcapng::blocks::interface_statistics::InterfaceStatisticsOption;
+use crate::pcapng::blocks::name_resolution::NameResolutionOption;
+use crate::pcapng::blocks::packet::PacketOption;
+use crate::pcapng::blocks::section_header::SectionHeaderOption;
+use crate::pcapng::Block;
+
+
+/// Returns the comments stored in the options of a block.
+pub(crate) fn block_comments<'b>(block: &'b Block) -> Vec<&'b str> {
+    match block {
+        Block::SectionHeader(b) => b
+            .options
+            .iter()
+            .filter_map(|opt| match opt {
+                SectionHeaderOption::Comment(c) => Some(&**c),
+                _ => None,
+            })
+            .collect(),
+        Block::InterfaceDescription(b) => b
+            .options
+            .iter()
+            .filter_map(|opt| match opt {
+                InterfaceDescriptionOption::Comment(c) => Some(&**c),
+                _ => None,
+            })
+            .collect(),
+        Block::Packet(b) => b
+            .options
+            .iter()
+            .filter_map(|opt| match opt {
+                PacketOption::Comment(c) => Some(&**c),
+                _ => None,
+            })
+            .collect(),
+        Block::NameResolution(b) => b
+            .options
+            .iter()
+            .filter_map(|opt| match opt {
+                NameResolutionOption::Comment(c) => Some(&**c),
+                _ => None,
+            })
+            .collect(),
+        Block::InterfaceStatistics(b) => b
+            .options
+            .iter()
+            .filter_map(|opt| match opt {
+                InterfaceStatisticsOption::Comment(c) => Some(&**c),
+                _ => None,
+            })
+            .collect(),
+        Block::EnhancedPacket(b) => b
+            .options
+            .iter()
+            .filter_map(|opt| match opt {
+                EnhancedPacketOption::Comment(c) => Some(&**c),
+                _ => None,
+            })
+            .collect(),
+        Block::DecryptionSecrets(b) => b
+            .options
+            .iter()
+            .filter_map(|opt| match opt {
+                DecryptionSecretsOption::Comment(c) => Some(&**c),
+                _ => None,
+            })
+            .collect(),
+        Block::HoneMachineInfo(b) => b
+            .options
+            .iter()
+            .filter_map(|opt| match opt {
+                HoneMachineInfoOption::Comment(c) => Some(&**c),
+                _ => None,
+            })
+            .collect(),
+        Block::HoneConnectionEvent(b) => b
+            .options
+            .iter()
+            .filter_map(|opt| match opt {
+                HoneConnectionEventOption::Comment(c) => Some(&**c),
+                _ => None,
+            })
+            .collect(),
+        Block::SimplePacket(_) | Block::SystemdJournalExport(_) | Block::Custom(_) | Block::Registered(_) | Block::Unknown(_) => vec![],
+    }
+}
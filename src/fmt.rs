@@ -0,0 +1,264 @@
+//! Human-readable rendering of blocks and packets.
+//!
+//! Mostly useful to debug malformed captures.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::fs::File;
+//!
+//! use pcap_file::fmt::BlockDump;
+//! use pcap_file::pcapng::PcapNgReader;
+//!
+//! let file_in = File::open("test.pcapng").expect("Error opening file");
+//! let mut pcapng_reader = PcapNgReader::new(file_in).unwrap();
+//!
+//! while let Some(block) = pcapng_reader.next_block() {
+//!     let block = block.unwrap();
+//!     println!("{}", BlockDump::new(&block));
+//! }
+//! ```
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+
+use crate::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+use crate::pcapng::blocks::name_resolution::Record;
+use crate::pcapng::Block;
+
+
+/// Formats a duration since the Unix epoch as an RFC3339 UTC timestamp with nanosecond precision.
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+///
+/// use pcap_file::fmt::rfc3339;
+///
+/// assert_eq!(rfc3339(Duration::new(1_600_000_000, 123)), "2020-09-13T12:26:40.000000123Z");
+/// ```
+pub fn rfc3339(since_epoch: Duration) -> String {
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let secs_of_day = secs % 86400;
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:09}Z",
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+        since_epoch.subsec_nanos()
+    )
+}
+
+/// Converts a number of days since the Unix epoch into a (year, month, day) date of the proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+
+/// Canonical hex and ASCII dump of packet data, 16 bytes per line, prefixed by the offset.
+///
+/// The output is formatted like the one of `tshark -x` and can be fed to text2pcap.
+///
+/// # Example
+/// ```rust
+/// use pcap_file::fmt::HexDump;
+///
+/// let dump = HexDump::new(b"Hello world").to_string();
+/// assert_eq!(dump, "0000  48 65 6c 6c 6f 20 77 6f 72 6c 64                  Hello world\n");
+/// ```
+pub struct HexDump<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> HexDump<'a> {
+    /// Creates a new [`HexDump`] of the data.
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+}
+
+impl<'a> Display for HexDump<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for (idx, line) in self.data.chunks(16).enumerate() {
+            write!(f, "{:04x} ", idx * 16)?;
+
+            for byte in line {
+                write!(f, " {byte:02x}")?;
+            }
+            for _ in line.len()..16 {
+                write!(f, "   ")?;
+            }
+
+            write!(f, "   ")?;
+            for &byte in line {
+                let c = if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' };
+                write!(f, "{c}")?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+
+/// Textual rendering of a [`Block`], similar to the output of capinfos or tshark.
+///
+/// The timestamps of the packets are rendered in RFC3339 if the [`InterfaceDescriptionBlock`]s
+/// of the section are given with [`BlockDump::with_interfaces()`], else their raw value is rendered.
+pub struct BlockDump<'a, 'b> {
+    block: &'b Block<'a>,
+    interfaces: &'b [InterfaceDescriptionBlock<'static>],
+}
+
+impl<'a, 'b> BlockDump<'a, 'b> {
+    /// Creates a new [`BlockDump`] of the block.
+    pub fn new(block: &'b Block<'a>) -> Self {
+        Self { block, interfaces: &[] }
+    }
+
+    /// Uses the [`InterfaceDescriptionBlock`]s of the section to render the timestamps.
+    pub fn with_interfaces(mut self, interfaces: &'b [InterfaceDescriptionBlock<'static>]) -> Self {
+        self.interfaces = interfaces;
+        self
+    }
+
+    /// Renders a timestamp expressed in the units of the given interface.
+    fn timestamp(&self, interface_id: u32, timestamp: u64) -> String {
+        match self.interfaces.get(interface_id as usize) {
+            Some(interface) => rfc3339(interface.timestamp_to_duration(timestamp)),
+            None => format!("{timestamp} (unknown resolution)"),
+        }
+    }
+}
+
+impl<'a, 'b> Display for BlockDump<'a, 'b> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self.block {
+            Block::SectionHeader(b) => {
+                writeln!(f, "Section Header Block")?;
+                writeln!(f, "    Endianness: {:?}", b.endianness)?;
+                writeln!(f, "    Version: {}.{}", b.major_version, b.minor_version)?;
+                writeln!(f, "    Section length: {}", b.section_length)?;
+                write_options(f, &b.options)
+            },
+            Block::InterfaceDescription(b) => {
+                writeln!(f, "Interface Description Block")?;
+                writeln!(f, "    Link type: {}", b.linktype)?;
+                writeln!(f, "    Snap length: {}", b.snaplen)?;
+                write_options(f, &b.options)
+            },
+            Block::Packet(b) => {
+                writeln!(f, "Packet Block")?;
+                writeln!(f, "    Interface ID: {}", b.interface_id)?;
+                writeln!(f, "    Drop count: {}", b.drop_count)?;
+                writeln!(f, "    Timestamp: {}", self.timestamp(b.interface_id as u32, b.timestamp))?;
+                writeln!(f, "    Captured length: {}", b.captured_len)?;
+                writeln!(f, "    Original length: {}", b.original_len)?;
+                write_options(f, &b.options)
+            },
+            Block::SimplePacket(b) => {
+                writeln!(f, "Simple Packet Block")?;
+                writeln!(f, "    Captured length: {}", b.data.len())?;
+                writeln!(f, "    Original length: {}", b.original_len)
+            },
+            Block::NameResolution(b) => {
+                writeln!(f, "Name Resolution Block")?;
+                writeln!(f, "    Records:")?;
+                for record in &b.records {
+                    match record {
+                        Record::End => (),
+                        Record::Ipv4(r) => match <[u8; 4]>::try_from(&r.ip_addr[..]) {
+                            Ok(addr) => writeln!(f, "        {}: {}", Ipv4Addr::from(addr), r.names.join(", "))?,
+                            Err(_) => writeln!(f, "        {:?}: {}", r.ip_addr, r.names.join(", "))?,
+                        },
+                        Record::Ipv6(r) => match <[u8; 16]>::try_from(&r.ip_addr[..]) {
+                            Ok(addr) => writeln!(f, "        {}: {}", Ipv6Addr::from(addr), r.names.join(", "))?,
+                            Err(_) => writeln!(f, "        {:?}: {}", r.ip_addr, r.names.join(", "))?,
+                        },
+                        Record::Unknown(r) => writeln!(f, "        Unknown record type {}: {:?}", r.type_, r.value)?,
+                    }
+                }
+                write_options(f, &b.options)
+            },
+            Block::InterfaceStatistics(b) => {
+                writeln!(f, "Interface Statistics Block")?;
+                writeln!(f, "    Interface ID: {}", b.interface_id)?;
+                writeln!(f, "    Timestamp: {}", self.timestamp(b.interface_id, b.timestamp))?;
+                write_options(f, &b.options)
+            },
+            Block::EnhancedPacket(b) => {
+                writeln!(f, "Enhanced Packet Block")?;
+                writeln!(f, "    Interface ID: {}", b.interface_id)?;
+                writeln!(f, "    Timestamp: {}", self.timestamp(b.interface_id, b.timestamp.as_nanos() as u64))?;
+                writeln!(f, "    Captured length: {}", b.data.len())?;
+                writeln!(f, "    Original length: {}", b.original_len)?;
+                write_options(f, &b.options)
+            },
+            Block::SystemdJournalExport(b) => {
+                writeln!(f, "Systemd Journal Export Block")?;
+                writeln!(f, "    Entry length: {}", b.journal_entry.len())
+            },
+            Block::DecryptionSecrets(b) => {
+                writeln!(f, "Decryption Secrets Block")?;
+                writeln!(f, "    Secrets type: {:?}", b.secrets_type)?;
+                writeln!(f, "    Secrets length: {}", b.secrets_data.len())?;
+                write_options(f, &b.options)
+            },
+            Block::HoneMachineInfo(b) => {
+                writeln!(f, "Hone Machine Info Block")?;
+                write_options(f, &b.options)
+            },
+            Block::HoneConnectionEvent(b) => {
+                writeln!(f, "Hone Connection Event Block")?;
+                writeln!(f, "    Connection ID: {}", b.connection_id)?;
+                writeln!(f, "    Process ID: {}", b.process_id)?;
+                writeln!(f, "    Timestamp: {}", b.timestamp)?;
+                write_options(f, &b.options)
+            },
+            Block::Custom(b) => {
+                writeln!(f, "Custom Block")?;
+                writeln!(f, "    Private Enterprise Number: {}", b.pen)?;
+                writeln!(f, "    Copiable: {}", b.copiable)?;
+                writeln!(f, "    Data length: {}", b.data.len())
+            },
+            Block::Registered(b) => {
+                writeln!(f, "Registered Block")?;
+                writeln!(f, "    Type: {:#010X}", b.block_type())
+            },
+            Block::Unknown(b) => {
+                writeln!(f, "Unknown Block")?;
+                writeln!(f, "    Type: {:#010X}", b.type_)?;
+                writeln!(f, "    Length: {}", b.length)
+            },
+        }
+    }
+}
+
+/// Renders the options of a block, one per line.
+fn write_options<O: std::fmt::Debug>(f: &mut Formatter<'_>, options: &[O]) -> FmtResult {
+    if options.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(f, "    Options:")?;
+    for opt in options {
+        writeln!(f, "        {opt:?}")?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,101 @@
+//! Reading of captures which are still being written, like `tail -f`.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::fs::File;
+//!
+//! use pcap_file::follow::FollowReader;
+//! use pcap_file::pcapng::PcapNgReader;
+//!
+//! // Written by `tcpdump -w capture.pcapng`
+//! let file_in = File::open("capture.pcapng").expect("Error opening file");
+//! let mut pcapng_reader = PcapNgReader::new(FollowReader::new(file_in)).unwrap();
+//!
+//! // Never returns None, waits for the next blocks instead
+//! while let Some(block) = pcapng_reader.next_block() {
+//!     let block = block.unwrap();
+//! }
+//! ```
+
+use std::io::{ErrorKind, Read};
+use std::time::{Duration, Instant};
+
+
+/// Reader which waits for more data instead of returning the end of file.
+///
+/// Can be wrapped by a [`PcapReader`](crate::pcap::PcapReader) or a [`PcapNgReader`](crate::pcapng::PcapNgReader)
+/// to process a capture file while it is being written.
+pub struct FollowReader<R: Read> {
+    reader: R,
+    wait: Box<dyn FnMut(u32) -> bool>,
+}
+
+impl<R: Read> FollowReader<R> {
+    /// Creates a new [`FollowReader`] which polls the reader forever, with a backoff from 10 ms to 1 s.
+    pub fn new(reader: R) -> Self {
+        Self::with_backoff(reader, Duration::from_millis(10), Duration::from_secs(1), None)
+    }
+
+    /// Creates a new [`FollowReader`] which polls the reader with an exponential backoff.
+    ///
+    /// The delay between two polls starts at `initial` and doubles up to `max`. If `timeout` is set and no data
+    /// has been read for this duration, the end of file is returned.
+    pub fn with_backoff(reader: R, initial: Duration, max: Duration, timeout: Option<Duration>) -> Self {
+        let mut since = Instant::now();
+        let wait = move |attempt: u32| {
+            if attempt == 1 {
+                since = Instant::now();
+            }
+            if timeout.is_some_and(|timeout| since.elapsed() >= timeout) {
+                return false;
+            }
+
+            let delay = initial.saturating_mul(2_u32.saturating_pow(attempt - 1)).min(max);
+            std::thread::sleep(delay);
+            true
+        };
+
+        Self::with_wait(reader, wait)
+    }
+
+    /// Creates a new [`FollowReader`] which calls `wait` each time the end of the reader is reached.
+    ///
+    /// The callback receives the number of consecutive empty reads, starting at 1. It should block until more data
+    /// may be available, e.g. until a file system notification, then return true to read again, or false to return
+    /// the end of file.
+    pub fn with_wait<F: FnMut(u32) -> bool + 'static>(reader: R, wait: F) -> Self {
+        FollowReader { reader, wait: Box::new(wait) }
+    }
+
+    /// Returns a reference to the inner reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns the inner reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> Read for FollowReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let mut attempt = 0_u32;
+        loop {
+            match self.reader.read(buf) {
+                Ok(0) => {
+                    attempt = attempt.saturating_add(1);
+                    if !(self.wait)(attempt) {
+                        return Ok(0);
+                    }
+                },
+                Err(err) if err.kind() == ErrorKind::Interrupted => {},
+                res => return res,
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Sidecar time index of a capture, to seek a reader to a given time without reading the whole capture.
+//!
+//! The index keeps the timestamp and the position of one packet every `interval` packets. It is built once by reading
+//! the capture with [`PcapReader::build_index()`](crate::pcap::PcapReader::build_index) or
+//! [`PcapNgReader::build_index()`](crate::pcapng::PcapNgReader::build_index), saved next to it with [`write_index()`],
+//! then loaded with [`load_index()`] to seek the readers with `seek_to_time()`.
+//!
+//! The positions are relative to the start of the reader, so the readers must be created at the start of the file.
+//! The index also records the length of the capture, which is checked when seeking so that an index isn't used with
+//! another capture or after the capture was modified.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::fs::File;
+//! use std::time::Duration;
+//!
+//! use pcap_file::index::{load_index, write_index};
+//! use pcap_file::pcapng::PcapNgReader;
+//!
+//! let mut pcapng_reader = PcapNgReader::new(File::open("test.pcapng").unwrap()).unwrap();
+//! let index = pcapng_reader.build_index(1000).unwrap();
+//! write_index(&index, File::create("test.pcapng.idx").unwrap()).unwrap();
+//!
+//! // Later
+//! let index = load_index(File::open("test.pcapng.idx").unwrap()).unwrap();
+//! let mut pcapng_reader = PcapNgReader::new(File::open("test.pcapng").unwrap()).unwrap();
+//! pcapng_reader.seek_to_time(&index, Duration::from_secs(1_700_000_000)).unwrap();
+//!
+//! while let Some(packet) = pcapng_reader.next_packet() {
+//!     let packet = packet.unwrap();
+//! }
+//! ```
+
+use std::io::{Read, Write};
+use std::time::Duration;
+
+use byteorder_slice::byteorder::{ReadBytesExt, WriteBytesExt};
+use byteorder_slice::LittleEndian;
+
+use crate::{PcapError, PcapResult};
+
+
+/// Magic number at the start of an index file
+const INDEX_MAGIC: [u8; 8] = *b"PCAPIDX\0";
+/// Version of the format of the index file
+const INDEX_VERSION: u32 = 2;
+
+/// Indexed packet of a capture.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct IndexEntry {
+    /// Timestamp of the packet, since the Unix epoch
+    pub timestamp: Duration,
+    /// Position of the packet
+    pub offset: u64,
+    /// Position of the header the packet depends on: the section header of a pcapng, the global header of a pcap
+    pub section: u64,
+}
+
+/// Time index of a capture, see the [module documentation](self).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TimeIndex {
+    interval: u32,
+    capture_len: u64,
+    entries: Vec<IndexEntry>,
+    /// Running maximum of the timestamps of the entries, which is increasing even if the packets are out of order
+    max_timestamps: Vec<Duration>,
+}
+
+impl TimeIndex {
+    /// Creates an empty [`TimeIndex`] keeping one packet every `interval` packets.
+    pub fn new(interval: u32) -> Self {
+        TimeIndex { interval: interval.max(1), capture_len: 0, entries: vec![], max_timestamps: vec![] }
+    }
+
+    /// Returns the number of packets between two indexed packets.
+    pub fn interval(&self) -> u32 {
+        self.interval
+    }
+
+    /// Returns the length in bytes of the indexed capture, relative to the start of the reader which built the index.
+    pub fn capture_len(&self) -> u64 {
+        self.capture_len
+    }
+
+    /// Returns the indexed packets, in the order of the capture.
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    /// Returns the last indexed packet such that neither it nor the packets indexed before it are after `timestamp`,
+    /// or the first one.
+    ///
+    /// When the packets are in order, it is the last indexed packet whose timestamp is not after `timestamp`. An indexed
+    /// packet out of order only makes the returned packet earlier, the packets which aren't indexed aren't checked.
+    pub fn find(&self, timestamp: Duration) -> Option<&IndexEntry> {
+        let pos = self.max_timestamps.partition_point(|max| *max <= timestamp);
+        self.entries.get(pos.saturating_sub(1))
+    }
+
+    /// Adds the `count`th packet of the capture, if it must be indexed.
+    pub(crate) fn add_packet(&mut self, count: u64, entry: IndexEntry) {
+        if count % self.interval as u64 == 0 {
+            self.push(entry);
+        }
+    }
+
+    /// Sets the length of the capture once it has been read entirely.
+    pub(crate) fn set_capture_len(&mut self, capture_len: u64) {
+        self.capture_len = capture_len;
+    }
+
+    /// Checks that the index was built from a capture of `capture_len` bytes.
+    pub(crate) fn check_capture_len(&self, capture_len: u64) -> PcapResult<()> {
+        if capture_len != self.capture_len {
+            return Err(PcapError::InvalidField("TimeIndex: capture length != indexed length"));
+        }
+
+        Ok(())
+    }
+
+    fn push(&mut self, entry: IndexEntry) {
+        let max = self.max_timestamps.last().map_or(entry.timestamp, |max| entry.timestamp.max(*max));
+        self.max_timestamps.push(max);
+        self.entries.push(entry);
+    }
+}
+
+/// Writes a [`TimeIndex`] to a writer.
+pub fn write_index<W: Write>(index: &TimeIndex, mut writer: W) -> PcapResult<()> {
+    return inner(index, &mut writer).map_err(PcapError::IoError);
+
+    fn inner<W: Write>(index: &TimeIndex, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&INDEX_MAGIC)?;
+        writer.write_u32::<LittleEndian>(INDEX_VERSION)?;
+        writer.write_u32::<LittleEndian>(index.interval)?;
+        writer.write_u64::<LittleEndian>(index.capture_len)?;
+        writer.write_u64::<LittleEndian>(index.entries.len() as u64)?;
+
+        for entry in &index.entries {
+            writer.write_u64::<LittleEndian>(entry.timestamp.as_secs())?;
+            writer.write_u32::<LittleEndian>(entry.timestamp.subsec_nanos())?;
+            writer.write_u64::<LittleEndian>(entry.offset)?;
+            writer.write_u64::<LittleEndian>(entry.section)?;
+        }
+
+        writer.flush()
+    }
+}
+
+/// Reads a [`TimeIndex`] written by [`write_index()`] from a reader.
+pub fn load_index<R: Read>(mut reader: R) -> PcapResult<TimeIndex> {
+    let mut magic = [0_u8; 8];
+    reader.read_exact(&mut magic).map_err(PcapError::IoError)?;
+    if magic != INDEX_MAGIC {
+        return Err(PcapError::InvalidField("TimeIndex: invalid magic number"));
+    }
+
+    if reader.read_u32::<LittleEndian>().map_err(PcapError::IoError)? != INDEX_VERSION {
+        return Err(PcapError::InvalidField("TimeIndex: unsupported version"));
+    }
+
+    let interval = reader.read_u32::<LittleEndian>().map_err(PcapError::IoError)?;
+    if interval == 0 {
+        return Err(PcapError::InvalidField("TimeIndex: interval == 0"));
+    }
+
+    let mut index = TimeIndex::new(interval);
+    index.capture_len = reader.read_u64::<LittleEndian>().map_err(PcapError::IoError)?;
+
+    let len = reader.read_u64::<LittleEndian>().map_err(PcapError::IoError)?;
+    index.entries.reserve(len.min(1 << 20) as usize);
+    index.max_timestamps.reserve(len.min(1 << 20) as usize);
+    for _ in 0..len {
+        let secs = reader.read_u64::<LittleEndian>().map_err(PcapError::IoError)?;
+        let nanos = reader.read_u32::<LittleEndian>().map_err(PcapError::IoError)?;
+        if nanos >= 1_000_000_000 {
+            return Err(PcapError::InvalidField("TimeIndex: nanoseconds >= 1_000_000_000"));
+        }
+
+        let offset = reader.read_u64::<LittleEndian>().map_err(PcapError::IoError)?;
+        let section = reader.read_u64::<LittleEndian>().map_err(PcapError::IoError)?;
+        index.push(IndexEntry { timestamp: Duration::new(secs, nanos), offset, section });
+    }
+
+    Ok(index)
+}
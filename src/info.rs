@@ -0,0 +1,232 @@
+//! Extraction of the metadata of a capture, similar to what `capinfos` prints.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::fs::File;
+//!
+//! use pcap_file::info::capture_info;
+//!
+//! let file_in = File::open("test.pcapng").expect("Error opening file");
+//! let info = capture_info(file_in).unwrap();
+//!
+//! println!("{:?}: {} packets in {:?}", info.format, info.packet_count, info.duration());
+//! ```
+
+use std::io::{Cursor, Read};
+use std::time::Duration;
+
+use crate::export::block_comments;
+use crate::pcap::PcapReader;
+use crate::pcapng::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption};
+use crate::pcapng::blocks::section_header::SectionHeaderOption;
+use crate::pcapng::{Block, PcapNgReader};
+use crate::{DataLink, Endianness, PcapError, PcapResult, TsResolution};
+
+
+/// Format of a capture file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum CaptureFormat {
+    /// Legacy pcap, with the version of its header
+    Pcap {
+        /// Major version number
+        version_major: u16,
+        /// Minor version number
+        version_minor: u16,
+    },
+    /// PcapNg
+    PcapNg,
+}
+
+/// Metadata of a capture, returned by [`capture_info`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CaptureInfo {
+    /// Format of the capture
+    pub format: CaptureFormat,
+
+    /// Endianness of the first section, or of the pcap header
+    pub endianness: Endianness,
+
+    /// Number of sections, always 1 for a pcap
+    pub section_count: usize,
+
+    /// Interfaces of all the sections, in order of appearance
+    pub interfaces: Vec<InterfaceInfo>,
+
+    /// Timestamp of the earliest packet, since the Unix epoch
+    pub first_timestamp: Option<Duration>,
+
+    /// Timestamp of the latest packet, since the Unix epoch
+    pub last_timestamp: Option<Duration>,
+
+    /// Number of packets
+    pub packet_count: u64,
+
+    /// Number of captured bytes of the packets
+    pub captured_bytes: u64,
+
+    /// Number of bytes of the packets on the wire
+    pub original_bytes: u64,
+
+    /// True if any block contains a comment
+    pub has_comments: bool,
+
+    /// True if the capture contains a Decryption Secrets Block
+    pub has_secrets: bool,
+}
+
+impl CaptureInfo {
+    /// Returns the time elapsed between the earliest and the latest packets.
+    pub fn duration(&self) -> Option<Duration> {
+        Some(self.last_timestamp?.saturating_sub(self.first_timestamp?))
+    }
+
+    fn new(format: CaptureFormat, endianness: Endianness) -> Self {
+        CaptureInfo {
+            format,
+            endianness,
+            section_count: 1,
+            interfaces: vec![],
+            first_timestamp: None,
+            last_timestamp: None,
+            packet_count: 0,
+            captured_bytes: 0,
+            original_bytes: 0,
+            has_comments: false,
+            has_secrets: false,
+        }
+    }
+
+    fn add_packet(&mut self, interface: Option<usize>, timestamp: Option<Duration>, captured_len: usize, original_len: u32) {
+        self.packet_count += 1;
+        self.captured_bytes += captured_len as u64;
+        self.original_bytes += original_len as u64;
+
+        if let Some(interface) = interface.and_then(|idx| self.interfaces.get_mut(idx)) {
+            interface.packet_count += 1;
+        }
+
+        if let Some(timestamp) = timestamp {
+            self.first_timestamp = Some(self.first_timestamp.map_or(timestamp, |first| first.min(timestamp)));
+            self.last_timestamp = Some(self.last_timestamp.map_or(timestamp, |last| last.max(timestamp)));
+        }
+    }
+}
+
+/// Metadata of an interface of a capture.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InterfaceInfo {
+    /// Index of the section of the interface, starting at 0
+    pub section: usize,
+
+    /// Name of the interface (`if_name`)
+    pub name: Option<String>,
+
+    /// Link type of the interface
+    pub linktype: DataLink,
+
+    /// Maximum number of captured bytes per packet, 0 if unlimited
+    pub snaplen: u32,
+
+    /// Timestamp resolution, encoded like the `if_tsresol` option
+    pub tsresol: u8,
+
+    /// Number of packets captured on the interface
+    pub packet_count: u64,
+}
+
+
+/// Reads a whole pcap or pcapng capture and returns its metadata.
+///
+/// The format is detected from the magic number at the start of the stream.
+pub fn capture_info<R: Read>(mut reader: R) -> PcapResult<CaptureInfo> {
+    let mut magic = [0_u8; 4];
+    reader.read_exact(&mut magic).map_err(PcapError::IoError)?;
+    let reader = Cursor::new(magic).chain(reader);
+
+    return match magic {
+        [0x0A, 0x0D, 0x0D, 0x0A] => pcapng_info(reader),
+        _ => pcap_info(reader),
+    };
+
+    fn pcap_info<R: Read>(reader: R) -> PcapResult<CaptureInfo> {
+        let mut reader = PcapReader::new(reader)?;
+        let header = reader.header();
+
+        let mut info = CaptureInfo::new(
+            CaptureFormat::Pcap { version_major: header.version_major, version_minor: header.version_minor },
+            header.endianness,
+        );
+        info.interfaces.push(InterfaceInfo {
+            section: 0,
+            name: None,
+            linktype: header.datalink,
+            snaplen: header.snaplen,
+            tsresol: match header.ts_resolution {
+                TsResolution::MicroSecond => 6,
+                TsResolution::NanoSecond => 9,
+            },
+            packet_count: 0,
+        });
+
+        while let Some(packet) = reader.next_packet() {
+            let packet = packet?;
+            info.add_packet(Some(0), Some(packet.timestamp), packet.data.len(), packet.orig_len);
+        }
+
+        Ok(info)
+    }
+
+    fn pcapng_info<R: Read>(reader: R) -> PcapResult<CaptureInfo> {
+        let mut reader = PcapNgReader::new(reader)?;
+
+        let mut info = CaptureInfo::new(CaptureFormat::PcapNg, reader.section().endianness);
+        info.has_comments = reader.section().options.iter().any(|opt| matches!(opt, SectionHeaderOption::Comment(_)));
+
+        // Interfaces of the current section, with their index in `info.interfaces`
+        let mut interfaces: Vec<(InterfaceDescriptionBlock<'static>, usize)> = vec![];
+
+        while let Some(block) = reader.next_block() {
+            let block = block?;
+            info.has_comments |= !block_comments(&block).is_empty();
+
+            match &block {
+                Block::SectionHeader(_) => {
+                    info.section_count += 1;
+                    interfaces.clear();
+                },
+                Block::InterfaceDescription(idb) => {
+                    interfaces.push((idb.clone().into_owned(), info.interfaces.len()));
+                    info.interfaces.push(InterfaceInfo {
+                        section: info.section_count - 1,
+                        name: idb.options.iter().find_map(|opt| match opt {
+                            InterfaceDescriptionOption::IfName(name) => Some(name.to_string()),
+                            _ => None,
+                        }),
+                        linktype: idb.linktype,
+                        snaplen: idb.snaplen,
+                        tsresol: idb.ts_resol(),
+                        packet_count: 0,
+                    });
+                },
+                Block::EnhancedPacket(epb) => {
+                    let interface = interfaces.get(epb.interface_id as usize);
+                    let timestamp = interface.map(|(idb, _)| idb.timestamp_to_duration(epb.timestamp.as_nanos() as u64));
+                    info.add_packet(interface.map(|(_, idx)| *idx), timestamp, epb.data.len(), epb.original_len);
+                },
+                Block::Packet(pb) => {
+                    let interface = interfaces.get(pb.interface_id as usize);
+                    let timestamp = interface.map(|(idb, _)| idb.timestamp_to_duration(pb.timestamp));
+                    info.add_packet(interface.map(|(_, idx)| *idx), timestamp, pb.data.len(), pb.original_len);
+                },
+                Block::SimplePacket(spb) => {
+                    let interface = interfaces.first().map(|(_, idx)| *idx);
+                    info.add_packet(interface, None, spb.data.len(), spb.original_len);
+                },
+                Block::DecryptionSecrets(_) => info.has_secrets = true,
+                _ => {},
+            }
+        }
+
+        Ok(info)
+    }
+}
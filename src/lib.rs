@@ -16,9 +16,26 @@ pub use errors::*;
 pub(crate) mod common;
 pub(crate) mod errors;
 pub(crate) mod read_buffer;
+pub(crate) mod write_buffer;
 
+pub mod backend;
+#[cfg(feature = "digest")]
+pub mod digest;
+pub mod export;
+pub mod fmt;
+pub mod follow;
+pub mod index;
+pub mod info;
+#[cfg(feature = "mmap")]
+pub mod mmap;
 pub mod pcap;
 pub mod pcapng;
+pub mod pseudo_header;
+#[cfg(feature = "remote")]
+pub mod remote;
+pub mod timestamp;
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+pub mod uring;
 
 
 #[allow(dead_code)]
@@ -0,0 +1,184 @@
+//! Memory-mapped output files, to write the captures to the disk at high rates.
+//!
+//! Requires the `mmap` feature.
+//!
+//! # Example
+//! ```rust,no_run
+//! use pcap_file::mmap::MmapWriter;
+//! use pcap_file::pcapng::PcapNgWriter;
+//!
+//! let file_out = MmapWriter::create("out.pcapng").unwrap();
+//! let mut pcapng_writer = PcapNgWriter::new(file_out).unwrap();
+//!
+//! // Write the packets
+//!
+//! // Truncates the file to the written data
+//! pcapng_writer.finish().unwrap().finish().unwrap();
+//! ```
+
+use std::fs::File;
+use std::io::{Error, ErrorKind, Result as IoResult, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use memmap2::MmapMut;
+
+use crate::common::allocate;
+use crate::FileWriter;
+
+
+/// Writer which preallocates its file and maps it in memory, the data being copied into the mapping instead of being
+/// given to `write()`.
+///
+/// The file is grown by steps of the preallocation size when the data doesn't fit anymore, and truncated to the written
+/// data by [`finish()`](Self::finish) or when the writer is dropped. Until then, the file is longer than its data.
+///
+/// The disk space is reserved before being mapped, so that a full disk is reported as an error by the write rather than
+/// by a `SIGBUS` when the mapping is written, except on the file systems which can't reserve it.
+///
+/// It can be wrapped by any writer of the crate, like a [`File`]. The mapping assumes that the file is not modified
+/// by another process while it is written.
+#[derive(Debug)]
+pub struct MmapWriter {
+    /// Taken by [`finish()`](Self::finish)
+    file: Option<File>,
+    map: Option<MmapMut>,
+    /// Size of the steps by which the file is grown
+    preallocation: u64,
+    /// Position of the next write
+    pos: u64,
+    /// Length of the written data
+    len: u64,
+    /// Length of the data already synced to the file by [`flush()`](Write::flush)
+    flushed: u64,
+}
+
+impl MmapWriter {
+    /// Default size of the steps by which the file is grown, 64 MB
+    pub const DEFAULT_PREALLOCATION: u64 = 64 * 1024 * 1024;
+
+    /// Creates a file, or truncates an existing one, and maps it with the default preallocation size.
+    pub fn create<P: AsRef<Path>>(path: P) -> IoResult<Self> {
+        let file = File::options().read(true).write(true).create(true).truncate(true).open(path)?;
+        Self::with_preallocation(file, Self::DEFAULT_PREALLOCATION)
+    }
+
+    /// Maps a file, which must be opened for reading and writing, growing it by steps of `preallocation` bytes.
+    ///
+    /// The file is written from its start, its previous content being overwritten.
+    pub fn with_preallocation(file: File, preallocation: u64) -> IoResult<Self> {
+        let mut writer = MmapWriter { file: Some(file), map: None, preallocation: preallocation.max(4096), pos: 0, len: 0, flushed: 0 };
+        writer.grow(0)?;
+        Ok(writer)
+    }
+
+    /// Returns the length of the written data.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns true if no data was written.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the length of the preallocated file.
+    pub fn capacity(&self) -> u64 {
+        self.map.as_ref().map_or(0, |map| map.len() as u64)
+    }
+
+    /// Syncs the mapping and truncates the file to the written data, returning it.
+    pub fn finish(mut self) -> IoResult<File> {
+        self.close()?;
+        Ok(self.file.take().expect("MmapWriter: file already taken"))
+    }
+
+    /// Grows the file and its mapping so that it holds at least `needed` bytes.
+    ///
+    /// The whole file is mapped, so that the space already preallocated by the writers is used before growing it again.
+    fn grow(&mut self, needed: u64) -> IoResult<()> {
+        let file_len = self.file().metadata()?.len();
+        let capacity = self.capacity().saturating_add(self.preallocation).max(needed).max(file_len);
+        let capacity_usize = usize::try_from(capacity).map_err(|_| Error::new(ErrorKind::OutOfMemory, "MmapWriter: file too large to be mapped"))?;
+
+        // The previous mapping is released first, its data stays in the page cache
+        self.map = None;
+        allocate(self.file(), capacity)?;
+        let map = unsafe { memmap2::MmapOptions::new().len(capacity_usize).map_mut(self.file())? };
+        self.map = Some(map);
+
+        Ok(())
+    }
+
+    /// Syncs the mapping, releases it and truncates the file to the written data.
+    fn close(&mut self) -> IoResult<()> {
+        if let Some(map) = self.map.take() {
+            map.flush()?;
+        }
+
+        self.file().set_len(self.len)
+    }
+}
+
+impl Write for MmapWriter {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        let end = self.pos + buf.len() as u64;
+        if end > self.capacity() {
+            self.grow(end)?;
+        }
+
+        let map = self.map.as_mut().ok_or_else(|| Error::from(ErrorKind::BrokenPipe))?;
+        map[self.pos as usize..end as usize].copy_from_slice(buf);
+        self.pos = end;
+        self.len = self.len.max(end);
+
+        Ok(buf.len())
+    }
+
+    /// Syncs the data written since the last flush to the file.
+    fn flush(&mut self) -> IoResult<()> {
+        if let Some(map) = &self.map {
+            if self.len > self.flushed {
+                map.flush_range(self.flushed as usize, (self.len - self.flushed) as usize)?;
+            }
+        }
+        self.flushed = self.len;
+
+        Ok(())
+    }
+}
+
+impl Seek for MmapWriter {
+    /// Seeks within the data, the end being the end of the written data rather than the end of the preallocated file.
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        let pos = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        };
+
+        self.pos = pos.ok_or_else(|| Error::new(ErrorKind::InvalidInput, "MmapWriter: invalid seek to a negative position"))?;
+        // The data before a rewritten part must be synced again
+        self.flushed = self.flushed.min(self.pos);
+
+        Ok(self.pos)
+    }
+}
+
+impl FileWriter for MmapWriter {
+    fn file(&self) -> &File {
+        self.file.as_ref().expect("MmapWriter: file already taken")
+    }
+
+    fn data_len(&self) -> IoResult<u64> {
+        Ok(self.len)
+    }
+}
+
+impl Drop for MmapWriter {
+    fn drop(&mut self) {
+        // The errors can't be reported, use finish() to check them
+        if self.file.is_some() {
+            let _ = self.close();
+        }
+    }
+}
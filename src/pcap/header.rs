@@ -1,4 +1,5 @@
 use std::io::Write;
+use std::time::Duration;
 
 use byteorder_slice::byteorder::WriteBytesExt;
 use byteorder_slice::result::ReadSlice;
@@ -17,13 +18,15 @@ pub struct PcapHeader {
     /// Minor version number
     pub version_minor: u16,
 
-    /// GMT to local timezone correction, should always be 0
+    /// GMT to local timezone correction in seconds, should always be 0
+    ///
+    /// Added to the timestamps to get UTC, see [`Self::apply_ts_correction()`].
     pub ts_correction: i32,
 
     /// Timestamp accuracy, should always be 0
     pub ts_accuracy: u32,
 
-    /// Max length of captured packet, typically 65535
+    /// Max length of captured packet, typically 65535, 0 meaning no limit (see [`SnapLen`](crate::SnapLen))
     pub snaplen: u32,
 
     /// DataLink type (first layer in the packet)
@@ -33,10 +36,33 @@ pub struct PcapHeader {
     pub ts_resolution: TsResolution,
 
     /// Endianness of the pcap (excluding the packet data)
+    ///
+    /// Read from the magic number, so a [`PcapWriter`](super::PcapWriter) created with the header of a
+    /// [`PcapReader`](super::PcapReader) writes the same bytes as the source.
     pub endianness: Endianness,
 }
 
 impl PcapHeader {
+    /// Creates a new [`PcapHeaderBuilder`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use pcap_file::pcap::PcapHeader;
+    /// use pcap_file::{DataLink, TsResolution};
+    ///
+    /// let header = PcapHeader::builder()
+    ///     .datalink(DataLink::RAW)
+    ///     .snaplen(1500)
+    ///     .ts_resolution(TsResolution::NanoSecond)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(header.version_major, 2);
+    /// ```
+    pub fn builder() -> PcapHeaderBuilder {
+        PcapHeaderBuilder::default()
+    }
+
     /// Creates a new [`PcapHeader`] from a slice of bytes.
     ///
     /// Returns an error if the reader doesn't contain a valid pcap
@@ -81,6 +107,20 @@ impl PcapHeader {
         }
     }
 
+    /// Converts the timestamp of a packet to UTC by applying the `ts_correction`, for the old captures whose timestamps
+    /// are in local time.
+    ///
+    /// The result saturates at the Unix epoch.
+    pub fn apply_ts_correction(&self, timestamp: Duration) -> Duration {
+        let correction = Duration::from_secs(self.ts_correction.unsigned_abs() as u64);
+        if self.ts_correction >= 0 {
+            timestamp.saturating_add(correction)
+        }
+        else {
+            timestamp.saturating_sub(correction)
+        }
+    }
+
     /// Writes a [`PcapHeader`] to a writer.
     ///
     /// Uses the endianness of the header.
@@ -137,3 +177,80 @@ impl Default for PcapHeader {
         }
     }
 }
+
+
+/// Builder of a [`PcapHeader`].
+///
+/// Defaults to the [`PcapHeader::default()`] values, but in the native endianness of the CPU.
+#[derive(Clone, Debug)]
+pub struct PcapHeaderBuilder {
+    header: PcapHeader,
+}
+
+impl Default for PcapHeaderBuilder {
+    fn default() -> Self {
+        Self { header: PcapHeader { endianness: Endianness::native(), ..Default::default() } }
+    }
+}
+
+impl PcapHeaderBuilder {
+    /// Sets the version of the format.
+    pub fn version(mut self, version_major: u16, version_minor: u16) -> Self {
+        self.header.version_major = version_major;
+        self.header.version_minor = version_minor;
+        self
+    }
+
+    /// Sets the maximum number of bytes captured from each packet.
+    pub fn snaplen(mut self, snaplen: u32) -> Self {
+        self.header.snaplen = snaplen;
+        self
+    }
+
+    /// Sets the link type of the packets, which can be [`DataLink::Unknown`].
+    pub fn datalink(mut self, datalink: DataLink) -> Self {
+        self.header.datalink = datalink;
+        self
+    }
+
+    /// Sets the resolution of the timestamps, which determines the magic number.
+    pub fn ts_resolution(mut self, ts_resolution: TsResolution) -> Self {
+        self.header.ts_resolution = ts_resolution;
+        self
+    }
+
+    /// Sets the endianness of the file, which determines the magic number.
+    pub fn endianness(mut self, endianness: Endianness) -> Self {
+        self.header.endianness = endianness;
+        self
+    }
+
+    /// Sets the GMT to local timezone correction in seconds, see [`PcapHeader::ts_correction`].
+    pub fn ts_correction(mut self, ts_correction: i32) -> Self {
+        self.header.ts_correction = ts_correction;
+        self
+    }
+
+    /// Sets the accuracy of the timestamps.
+    pub fn ts_accuracy(mut self, ts_accuracy: u32) -> Self {
+        self.header.ts_accuracy = ts_accuracy;
+        self
+    }
+
+    /// Builds the [`PcapHeader`].
+    ///
+    /// Returns an error if the major version is not 2 or if the minor version is above 4.
+    ///
+    /// A [`DataLink::Unknown`] is accepted, to write the experimental linktypes not yet in the registry.
+    pub fn build(self) -> Result<PcapHeader, PcapError> {
+        if self.header.version_major != 2 {
+            return Err(PcapError::InvalidField("PcapHeader: major version != 2"));
+        }
+
+        if self.header.version_minor > 4 {
+            return Err(PcapError::InvalidField("PcapHeader: minor version > 4"));
+        }
+
+        Ok(self.header)
+    }
+}
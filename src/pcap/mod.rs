@@ -4,10 +4,12 @@ mod header;
 mod packet;
 mod parser;
 mod reader;
+mod record;
 mod writer;
 
 pub use header::*;
 pub use packet::*;
 pub use parser::*;
 pub use reader::*;
+pub use record::*;
 pub use writer::*;
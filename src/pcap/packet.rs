@@ -2,13 +2,14 @@ use std::borrow::Cow;
 use std::io::Write;
 use std::time::Duration;
 
-use byteorder_slice::byteorder::WriteBytesExt;
-use byteorder_slice::result::ReadSlice;
 use byteorder_slice::ByteOrder;
 use derive_into_owned::IntoOwned;
 
+use super::LengthPolicy;
+use crate::common::{read_u32_fields, write_all_vectored};
 use crate::errors::*;
-use crate::TsResolution;
+use crate::timestamp::{from_duration, from_pcap, IntoTimestamp, Timestamp};
+use crate::{SnapLen, TsResolution};
 
 /// Pcap packet.
 ///
@@ -34,6 +35,43 @@ impl<'a> PcapPacket<'a> {
         PcapPacket { timestamp, orig_len, data: Cow::Owned(data) }
     }
 
+    /// Creates a new borrowed [`PcapPacket`] captured at the given time, from any [`IntoTimestamp`].
+    pub fn with_time<T: IntoTimestamp>(time: T, orig_len: u32, data: &'a [u8]) -> PcapResult<PcapPacket<'a>> {
+        let timestamp = time.since_epoch().ok_or(PcapError::InvalidField("PcapPacket: timestamp before the Unix epoch"))?;
+        Ok(PcapPacket::new(timestamp, orig_len, data))
+    }
+
+    /// Returns the timestamp of the packet as any [`Timestamp`].
+    ///
+    /// The ticks of the timestamp, e.g. returned as a `u64`, are nanoseconds since the Unix epoch.
+    pub fn timestamp_as<T: Timestamp>(&self) -> Option<T> {
+        from_duration(self.timestamp)
+    }
+
+    /// Returns true if the lengths of the packet are consistent with the snaplen of its file, which is always the case
+    /// unless it was parsed with [`LengthPolicy::Accept`].
+    pub fn has_consistent_lengths(&self, snap_len: u32) -> bool {
+        check_lengths(self.data.len(), self.orig_len, snap_len).is_ok()
+    }
+
+    /// Returns the timestamp of the packet as a `chrono::DateTime<Utc>`.
+    #[cfg(feature = "chrono")]
+    pub fn timestamp_utc(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.timestamp_as()
+    }
+
+    /// Returns the timestamp of the packet as a `time::OffsetDateTime`.
+    #[cfg(feature = "time")]
+    pub fn timestamp_offset_date_time(&self) -> Option<time::OffsetDateTime> {
+        self.timestamp_as()
+    }
+
+    /// Sets the timestamp of the packet from any [`IntoTimestamp`].
+    pub fn set_timestamp<T: IntoTimestamp>(&mut self, time: T) -> PcapResult<()> {
+        self.timestamp = time.since_epoch().ok_or(PcapError::InvalidField("PcapPacket: timestamp before the Unix epoch"))?;
+        Ok(())
+    }
+
     /// Parses a new borrowed [`PcapPacket`] from a slice.
     pub fn from_slice<B: ByteOrder>(slice: &'a [u8], ts_resolution: TsResolution, snap_len: u32) -> PcapResult<(&'a [u8], PcapPacket<'a>)> {
         let (rem, raw_packet) = RawPcapPacket::from_slice::<B>(slice)?;
@@ -60,7 +98,7 @@ impl<'a> PcapPacket<'a> {
         let incl_len = self.data.len().try_into().map_err(|_| PcapError::InvalidField("PcapPacket: incl_len > u32::MAX"))?;
         let orig_len = self.orig_len;
 
-        if incl_len > snap_len {
+        if !SnapLen(snap_len).allows(incl_len as usize) {
             return Err(PcapError::InvalidField("PcapPacket: incl_len > snap_len"));
         }
 
@@ -75,6 +113,16 @@ impl<'a> PcapPacket<'a> {
 
     /// Tries to create a [`PcapPacket`] from a [`RawPcapPacket`].
     pub fn try_from_raw_packet(raw: RawPcapPacket<'a>, ts_resolution: TsResolution, snap_len: u32) -> PcapResult<Self> {
+        Self::try_from_raw_packet_with_policy(raw, ts_resolution, snap_len, LengthPolicy::Error)
+    }
+
+    /// Tries to create a [`PcapPacket`] from a [`RawPcapPacket`], handling its inconsistent lengths according to `policy`.
+    pub fn try_from_raw_packet_with_policy(
+        raw: RawPcapPacket<'a>,
+        ts_resolution: TsResolution,
+        snap_len: u32,
+        policy: LengthPolicy,
+    ) -> PcapResult<Self> {
         // Validate timestamps //
         let ts_sec = raw.ts_sec;
         let mut ts_nsec = raw.ts_frac;
@@ -88,20 +136,21 @@ impl<'a> PcapPacket<'a> {
         // Validate lengths //
         let incl_len = raw.incl_len;
         let orig_len = raw.orig_len;
-
-        if incl_len > snap_len {
-            return Err(PcapError::InvalidField("PacketHeader incl_len > snap_len"));
+        let mut data = raw.data;
+
+        match policy {
+            LengthPolicy::Error => check_lengths(incl_len as usize, orig_len, snap_len)?,
+            LengthPolicy::Clamp => {
+                let len = SnapLen(snap_len).captured_len(data.len().min(orig_len as usize));
+                match &mut data {
+                    Cow::Borrowed(slice) => *slice = &slice[..len],
+                    Cow::Owned(vec) => vec.truncate(len),
+                }
+            },
+            LengthPolicy::Accept => {},
         }
 
-        if orig_len > snap_len {
-            return Err(PcapError::InvalidField("PacketHeader orig_len > snap_len"));
-        }
-
-        if incl_len > orig_len {
-            return Err(PcapError::InvalidField("PacketHeader incl_len > orig_len"));
-        }
-
-        Ok(PcapPacket { timestamp: Duration::new(ts_sec as u64, ts_nsec), orig_len, data: raw.data })
+        Ok(PcapPacket { timestamp: Duration::new(ts_sec as u64, ts_nsec), orig_len, data })
     }
 }
 
@@ -124,6 +173,13 @@ pub struct RawPcapPacket<'a> {
 }
 
 impl<'a> RawPcapPacket<'a> {
+    /// Returns the timestamp of the packet as any [`Timestamp`], converted directly from its fields.
+    ///
+    /// The ticks of the timestamp, e.g. returned as a `u64`, are in the resolution of the capture.
+    pub fn timestamp_as<T: Timestamp>(&self, ts_resolution: TsResolution) -> Option<T> {
+        from_pcap(self.ts_sec, self.ts_frac, ts_resolution)
+    }
+
     /// Parses a new borrowed [`RawPcapPacket`] from a slice.
     pub fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> PcapResult<(&'a [u8], Self)> {
         // Check header length
@@ -132,11 +188,8 @@ impl<'a> RawPcapPacket<'a> {
         }
 
         // Read packet header  //
-        // Can unwrap because the length check is done before
-        let ts_sec = slice.read_u32::<B>().unwrap();
-        let ts_frac = slice.read_u32::<B>().unwrap();
-        let incl_len = slice.read_u32::<B>().unwrap();
-        let orig_len = slice.read_u32::<B>().unwrap();
+        let [ts_sec, ts_frac, incl_len, orig_len] = read_u32_fields::<B, 4>(slice);
+        slice = &slice[16..];
 
         let pkt_len = incl_len as usize;
         if slice.len() < pkt_len {
@@ -152,11 +205,14 @@ impl<'a> RawPcapPacket<'a> {
     /// Writes a [`RawPcapPacket`] to a writer.
     /// The fields of the packet are not validated.
     pub fn write_to<W: Write, B: ByteOrder>(&self, writer: &mut W) -> PcapResult<usize> {
-        writer.write_u32::<B>(self.ts_sec).map_err(PcapError::IoError)?;
-        writer.write_u32::<B>(self.ts_frac).map_err(PcapError::IoError)?;
-        writer.write_u32::<B>(self.incl_len).map_err(PcapError::IoError)?;
-        writer.write_u32::<B>(self.orig_len).map_err(PcapError::IoError)?;
-        writer.write_all(&self.data).map_err(PcapError::IoError)?;
+        let mut header = [0_u8; 16];
+        B::write_u32(&mut header[0..4], self.ts_sec);
+        B::write_u32(&mut header[4..8], self.ts_frac);
+        B::write_u32(&mut header[8..12], self.incl_len);
+        B::write_u32(&mut header[12..16], self.orig_len);
+
+        // The header and the data in a single call
+        write_all_vectored(writer, [&header, &self.data]).map_err(PcapError::IoError)?;
 
         Ok(16 + self.data.len())
     }
@@ -166,3 +222,21 @@ impl<'a> RawPcapPacket<'a> {
         PcapPacket::try_from_raw_packet(self, ts_resolution, snap_len)
     }
 }
+
+/// Checks that the captured and original lengths of a packet are within the snaplen, and that the packet isn't longer
+/// than it was on the wire
+fn check_lengths(incl_len: usize, orig_len: u32, snap_len: u32) -> PcapResult<()> {
+    if !SnapLen(snap_len).allows(incl_len) {
+        return Err(PcapError::InvalidField("PacketHeader incl_len > snap_len"));
+    }
+
+    if !SnapLen(snap_len).allows(orig_len as usize) {
+        return Err(PcapError::InvalidField("PacketHeader orig_len > snap_len"));
+    }
+
+    if incl_len > orig_len as usize {
+        return Err(PcapError::InvalidField("PacketHeader incl_len > orig_len"));
+    }
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use byteorder_slice::{BigEndian, LittleEndian};
+use byteorder_slice::{BigEndian, ByteOrder, LittleEndian};
 
 use super::RawPcapPacket;
 use crate::errors::*;
@@ -6,6 +6,34 @@ use crate::pcap::{PcapHeader, PcapPacket};
 use crate::Endianness;
 
 
+/// Configuration of a [`PcapParser`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct PcapParserConfig {
+    /// Apply the `ts_correction` of the header to the timestamps of the [`PcapPacket`]s, which are then in UTC even if
+    /// the capture was written in local time, see [`PcapHeader::apply_ts_correction()`].
+    ///
+    /// The [`RawPcapPacket`]s are left unchanged.
+    pub apply_ts_correction: bool,
+
+    /// Behavior for the [`PcapPacket`]s whose captured length is greater than their original length or than the snaplen.
+    pub length_policy: LengthPolicy,
+}
+
+/// Behavior of a [`PcapParser`] for the packets whose lengths are inconsistent.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum LengthPolicy {
+    /// Returns an error, also when the original length is greater than the snaplen
+    #[default]
+    Error,
+    /// Truncates the data to the original length and to the snaplen
+    Clamp,
+    /// Returns the packets unchanged, their data being longer than their original length or than the snaplen.
+    ///
+    /// They are counted in [`IoStats::warnings`](crate::IoStats::warnings) by the reader, and can be recognized with
+    /// [`PcapPacket::has_consistent_lengths()`].
+    Accept,
+}
+
 /// Parses a Pcap from a slice of bytes.
 ///
 /// You can match on [`PcapError::IncompleteBuffer`](crate::errors::PcapError) to known if the parser need more data.
@@ -43,6 +71,7 @@ use crate::Endianness;
 #[derive(Debug)]
 pub struct PcapParser {
     header: PcapHeader,
+    config: PcapParserConfig,
 }
 
 impl PcapParser {
@@ -50,19 +79,35 @@ impl PcapParser {
     ///
     /// Returns the remainder and the parser.
     pub fn new(slice: &[u8]) -> PcapResult<(&[u8], PcapParser)> {
+        Self::with_config(slice, PcapParserConfig::default())
+    }
+
+    /// Creates a new [`PcapParser`] with the given [`PcapParserConfig`].
+    ///
+    /// Returns the remainder and the parser.
+    pub fn with_config(slice: &[u8], config: PcapParserConfig) -> PcapResult<(&[u8], PcapParser)> {
         let (slice, header) = PcapHeader::from_slice(slice)?;
 
-        let parser = PcapParser { header };
+        let parser = PcapParser { header, config };
 
         Ok((slice, parser))
     }
 
     /// Returns the remainder and the next [`PcapPacket`].
     pub fn next_packet<'a>(&self, slice: &'a [u8]) -> PcapResult<(&'a [u8], PcapPacket<'a>)> {
-        match self.header.endianness {
-            Endianness::Big => PcapPacket::from_slice::<BigEndian>(slice, self.header.ts_resolution, self.header.snaplen),
-            Endianness::Little => PcapPacket::from_slice::<LittleEndian>(slice, self.header.ts_resolution, self.header.snaplen),
+        let (rem, raw_packet) = self.next_raw_packet(slice)?;
+        let mut packet = PcapPacket::try_from_raw_packet_with_policy(
+            raw_packet,
+            self.header.ts_resolution,
+            self.header.snaplen,
+            self.config.length_policy,
+        )?;
+
+        if self.config.apply_ts_correction {
+            packet.timestamp = self.header.apply_ts_correction(packet.timestamp);
         }
+
+        Ok((rem, packet))
     }
 
     /// Returns the remainder and the next [`RawPcapPacket`].
@@ -73,8 +118,38 @@ impl PcapParser {
         }
     }
 
+    /// Parses the global header of a new pcap file if the next data is one instead of a packet, as in the captures
+    /// concatenated with `cat a.pcap b.pcap`.
+    ///
+    /// Returns the remainder and the new header, which is then used to parse the next packets, or `None` if the next data
+    /// is a packet.
+    pub fn next_header<'a>(&mut self, slice: &'a [u8]) -> PcapResult<Option<(&'a [u8], PcapHeader)>> {
+        if slice.len() < 4 {
+            return Err(PcapError::IncompleteBuffer);
+        }
+
+        // A packet may start with the same bytes if its timestamp is one of the magic numbers, read in either endianness:
+        // 2011-01-23 22:58:41 UTC, in 2055 or in 2083. Its fractional timestamp must then also read as version 2
+        if !matches!(BigEndian::read_u32(slice), 0xA1B2C3D4 | 0xA1B23C4D | 0xD4C3B2A1 | 0x4D3CB2A1) {
+            return Ok(None);
+        }
+
+        let (rem, header) = PcapHeader::from_slice(slice)?;
+        if header.version_major != 2 {
+            return Ok(None);
+        }
+        self.header = header;
+
+        Ok(Some((rem, header)))
+    }
+
     /// Returns the header of the pcap file.
     pub fn header(&self) -> PcapHeader {
         self.header
     }
+
+    /// Returns the [`PcapParserConfig`] of the parser.
+    pub fn config(&self) -> PcapParserConfig {
+        self.config
+    }
 }
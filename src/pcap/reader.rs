@@ -1,9 +1,15 @@
-use std::io::Read;
+use std::borrow::BorrowMut;
+use std::io::{Read, Seek, StdinLock};
+use std::marker::PhantomData;
+use std::time::Duration;
 
-use super::{PcapParser, RawPcapPacket};
+use super::{PcapParser, PcapParserConfig, RawPcapPacket};
 use crate::errors::*;
 use crate::pcap::{PcapHeader, PcapPacket};
 use crate::read_buffer::ReadBuffer;
+use crate::common::{with_len, ProgressCallback};
+use crate::index::{IndexEntry, TimeIndex};
+use crate::{IoStats, Progress};
 
 
 /// Reads a pcap from a reader.
@@ -26,10 +32,22 @@ use crate::read_buffer::ReadBuffer;
 ///     //Do something
 /// }
 /// ```
+///
+/// The reader never seeks, so it can read from a pipe or a socket.
+///
+/// The data is read in large chunks into an internal buffer and the packets are parsed from it, so the reader
+/// doesn't need to be wrapped in a [`BufReader`](std::io::BufReader).
+///
+/// The captures concatenated with `cat a.pcap b.pcap` are read as one: a global header found instead of a packet
+/// replaces the current [`header()`](Self::header).
 #[derive(Debug)]
 pub struct PcapReader<R: Read> {
     parser: PcapParser,
     reader: ReadBuffer<R>,
+    stats: IoStats,
+    progress: Option<ProgressCallback>,
+    /// Position of the global header of the current capture
+    header_position: u64,
 }
 
 impl<R: Read> PcapReader<R> {
@@ -46,8 +64,39 @@ impl<R: Read> PcapReader<R> {
     pub fn new(reader: R) -> Result<PcapReader<R>, PcapError> {
         let mut reader = ReadBuffer::new(reader);
         let parser = reader.parse_with(PcapParser::new)?;
+        let stats = IoStats { bytes: reader.position(), ..Default::default() };
+
+        Ok(PcapReader { parser, reader, stats, progress: None, header_position: 0 })
+    }
+
+    /// Creates a new [`PcapReader`] from an existing reader with the given [`PcapParserConfig`].
+    ///
+    /// # Errors
+    /// The data stream is not in a valid pcap file format.
+    ///
+    /// The underlying data are not readable.
+    pub fn with_config(reader: R, config: PcapParserConfig) -> Result<PcapReader<R>, PcapError> {
+        let mut reader = ReadBuffer::new(reader);
+        let parser = reader.parse_with(|src| PcapParser::with_config(src, config))?;
+        let stats = IoStats { bytes: reader.position(), ..Default::default() };
+
+        Ok(PcapReader { parser, reader, stats, progress: None, header_position: 0 })
+    }
+
+    /// Creates a new [`PcapReader`] from an existing reader, with an internal buffer of `capacity` bytes instead of 8 MB.
+    ///
+    /// The capacity must be larger than the biggest packet of the capture, which otherwise can't be read.
+    ///
+    /// # Errors
+    /// The data stream is not in a valid pcap file format.
+    ///
+    /// The underlying data are not readable.
+    pub fn with_capacity(reader: R, capacity: usize) -> Result<PcapReader<R>, PcapError> {
+        let mut reader = ReadBuffer::with_capacity(reader, capacity);
+        let parser = reader.parse_with(PcapParser::new)?;
+        let stats = IoStats { bytes: reader.position(), ..Default::default() };
 
-        Ok(PcapReader { parser, reader })
+        Ok(PcapReader { parser, reader, stats, progress: None, header_position: 0 })
     }
 
     /// Consumes [`Self`], returning the wrapped reader.
@@ -55,38 +104,262 @@ impl<R: Read> PcapReader<R> {
         self.reader.into_inner()
     }
 
+    /// Consumes [`Self`], returning the wrapped reader, like [`Self::into_reader()`].
+    ///
+    /// The data already read into the internal buffer but not returned as packets yet is lost.
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+
+    /// Gets a reference to the wrapped reader.
+    pub fn get_ref(&self) -> &R {
+        self.reader.get_ref()
+    }
+
+    /// Gets a mutable reference to the wrapped reader.
+    ///
+    /// You should not read from it or seek it unless you really know what you're doing, the internal buffer being bypassed.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.reader.get_mut()
+    }
+
     /// Returns the next [`PcapPacket`].
     pub fn next_packet(&mut self) -> Option<Result<PcapPacket, PcapError>> {
-        match self.reader.has_data_left() {
+        match self.skip_headers() {
             Ok(has_data) => {
                 if has_data {
-                    Some(self.reader.parse_with(|src| self.parser.next_packet(src)))
+                    let res = self.reader.parse_with(|src| with_len(src, self.parser.next_packet(src)));
+                    // A packet shorter than its record was truncated by LengthPolicy::Clamp
+                    if let Ok((packet, len)) = &res {
+                        self.stats.repaired += (packet.data.len() as u64 + 16 < *len) as u64;
+                        self.stats.warnings += !packet.has_consistent_lengths(self.parser.header().snaplen) as u64;
+                    }
+                    let res = self.stats.record(res, |_| true);
+                    ProgressCallback::update(&mut self.progress, &self.stats);
+                    Some(res)
                 }
                 else {
                     None
                 }
             },
-            Err(e) => Some(Err(PcapError::IoError(e))),
+            Err(e) => Some(Err(e)),
         }
     }
 
     /// Returns the next [`RawPcapPacket`].
     pub fn next_raw_packet(&mut self) -> Option<Result<RawPcapPacket, PcapError>> {
-        match self.reader.has_data_left() {
+        match self.skip_headers() {
             Ok(has_data) => {
                 if has_data {
-                    Some(self.reader.parse_with(|src| self.parser.next_raw_packet(src)))
+                    let res = self.reader.parse_with(|src| with_len(src, self.parser.next_raw_packet(src)));
+                    let res = self.stats.record(res, |_| true);
+                    ProgressCallback::update(&mut self.progress, &self.stats);
+                    Some(res)
                 }
                 else {
                     None
                 }
             },
-            Err(e) => Some(Err(PcapError::IoError(e))),
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    /// Consumes the global headers of the concatenated captures found instead of the next packet,
+    /// and returns true if there is a packet left.
+    fn skip_headers(&mut self) -> Result<bool, PcapError> {
+        loop {
+            if !self.reader.has_data_left().map_err(PcapError::IoError)? {
+                return Ok(false);
+            }
+
+            let offset = self.reader.position();
+            let parser = &mut self.parser;
+            let res = self.reader.parse_with(|src| match parser.next_header(src)? {
+                Some((rem, _)) => Ok((rem, (src.len() - rem.len()) as u64)),
+                None => Ok((src, 0)),
+            });
+            match res {
+                Ok(0) => return Ok(true),
+                Ok(len) => {
+                    self.stats.bytes += len;
+                    self.header_position = offset;
+                },
+                Err(err) => {
+                    self.stats.errors += 1;
+                    return Err(err);
+                },
+            }
+        }
+    }
+
+    /// Returns the offset in the stream of the next packet, relative to the start of the reader.
+    pub fn position(&self) -> u64 {
+        self.reader.position()
+    }
+
+    /// Reads all the remaining packets and returns a [`TimeIndex`] of one packet every `interval` packets.
+    ///
+    /// The reader must have been created at the start of the capture for the index to be used by [`Self::seek_to_time()`].
+    pub fn build_index(&mut self, interval: u32) -> Result<TimeIndex, PcapError> {
+        let mut index = TimeIndex::new(interval);
+        let mut count = 0;
+
+        while self.skip_headers()? {
+            let offset = self.position();
+            let section = self.header_position;
+            let packet = match self.next_packet() {
+                Some(packet) => packet?,
+                None => break,
+            };
+
+            index.add_packet(count, IndexEntry { timestamp: packet.timestamp, offset, section });
+            count += 1;
         }
+        index.set_capture_len(self.position());
+
+        Ok(index)
     }
 
-    /// Returns the global header of the pcap.
+    /// Returns the global header of the pcap, the one of the current capture if several were concatenated.
     pub fn header(&self) -> PcapHeader {
         self.parser.header()
     }
+
+    /// Returns the counters of the packets read since the creation of the reader, the bytes including the global header.
+    pub fn stats(&self) -> IoStats {
+        self.stats
+    }
+
+    /// Registers a callback called each time `interval` bytes have been consumed, to report the progress of the reader.
+    ///
+    /// The `total` size of the input, e.g. the length of the file, is given back to the callback to compute the
+    /// [`Progress::fraction()`].
+    pub fn set_progress_callback<F: FnMut(Progress) + Send + 'static>(&mut self, interval: u64, total: Option<u64>, callback: F) {
+        self.progress = Some(ProgressCallback::new(&self.stats, interval, total, callback));
+    }
+
+    /// Returns an iterator over the remaining packets, yielding owned packets.
+    ///
+    /// Unlike [`Self::into_iter()`], the reader is only borrowed, so it can still be used once the iterator is dropped.
+    pub fn packets(&mut self) -> Packets<'_, R> {
+        PcapIntoIter { reader: self, _reader: PhantomData }
+    }
+}
+
+impl<R: Read + Seek> PcapReader<R> {
+    /// Seeks to the last packet of the [`TimeIndex`] whose timestamp is not after `timestamp`, or to the first one.
+    ///
+    /// The next packets are read from there, the first packet at `timestamp` being at most [`TimeIndex::interval()`]
+    /// packets further. Returns `false` and does nothing if the index is empty, and an error if the length of the
+    /// capture isn't the one of the index.
+    pub fn seek_to_time(&mut self, index: &TimeIndex, timestamp: Duration) -> Result<bool, PcapError> {
+        let entry = match index.find(timestamp) {
+            Some(entry) => *entry,
+            None => return Ok(false),
+        };
+        index.check_capture_len(self.reader.stream_len().map_err(PcapError::IoError)?)?;
+
+        // The global header of the packet may differ from the current one in a concatenated capture
+        self.reader.seek_to(entry.section).map_err(PcapError::IoError)?;
+        self.skip_headers()?;
+        if self.position() == entry.section {
+            return Err(PcapError::InvalidField("TimeIndex: no pcap header at the indexed position"));
+        }
+
+        self.reader.seek_to(entry.offset).map_err(PcapError::IoError)?;
+
+        Ok(true)
+    }
+}
+
+impl PcapReader<StdinLock<'static>> {
+    /// Creates a new [`PcapReader`] reading from the locked standard input.
+    ///
+    /// # Errors
+    /// The data stream is not in a valid pcap file format.
+    pub fn from_stdin() -> Result<Self, PcapError> {
+        Self::new(std::io::stdin().lock())
+    }
+}
+
+impl<R: Read> IntoIterator for PcapReader<R> {
+    type Item = Result<PcapPacket<'static>, PcapError>;
+    type IntoIter = PcapIntoIter<R>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        PcapIntoIter { reader: self, _reader: PhantomData }
+    }
+}
+
+/// Yields owned packets, like [`PcapReader::packets()`], with the errors separated from the items.
+#[cfg(feature = "fallible-iterator")]
+impl<R: Read> fallible_iterator::FallibleIterator for PcapReader<R> {
+    type Item = PcapPacket<'static>;
+    type Error = PcapError;
+
+    fn next(&mut self) -> Result<Option<Self::Item>, Self::Error> {
+        self.next_packet().transpose().map(|packet| packet.map(PcapPacket::into_owned))
+    }
+}
+
+
+/// Iterator over the packets of a [`PcapReader`], yielding owned packets.
+///
+/// Unlike the packets returned by [`PcapReader::next_packet()`], they don't borrow the reader, so they can be stored
+/// or sent to other threads.
+///
+/// The iterator either owns the reader, when returned by [`PcapReader::into_iter()`], or borrows it, see [`Packets`].
+///
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+///
+/// use pcap_file::pcap::PcapReader;
+///
+/// let file_in = File::open("test.pcap").expect("Error opening file");
+/// let pcap_reader = PcapReader::new(file_in).unwrap();
+///
+/// let packets = pcap_reader.into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct PcapIntoIter<R: Read, Rd = PcapReader<R>> {
+    reader: Rd,
+    _reader: PhantomData<PcapReader<R>>,
+}
+
+impl<R: Read, Rd: BorrowMut<PcapReader<R>>> PcapIntoIter<R, Rd> {
+    /// Returns a reference to the [`PcapReader`].
+    pub fn reader(&self) -> &PcapReader<R> {
+        self.reader.borrow()
+    }
+
+    /// Consumes [`Self`], returning the [`PcapReader`], or the reference to it.
+    pub fn into_reader(self) -> Rd {
+        self.reader
+    }
+}
+
+impl<R: Read, Rd: BorrowMut<PcapReader<R>>> Iterator for PcapIntoIter<R, Rd> {
+    type Item = Result<PcapPacket<'static>, PcapError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.borrow_mut().next_packet().map(|res| res.map(|packet| packet.into_owned()))
+    }
 }
+
+
+/// [`PcapIntoIter`] borrowing the [`PcapReader`], returned by [`PcapReader::packets()`].
+///
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+///
+/// use pcap_file::pcap::PcapReader;
+///
+/// let file_in = File::open("test.pcap").expect("Error opening file");
+/// let mut pcap_reader = PcapReader::new(file_in).unwrap();
+///
+/// let first_packets = pcap_reader.packets().take_while(Result::is_ok).take(10).collect::<Result<Vec<_>, _>>().unwrap();
+/// let remaining = pcap_reader.packets().count();
+/// ```
+pub type Packets<'a, R> = PcapIntoIter<R, &'a mut PcapReader<R>>;
@@ -0,0 +1,150 @@
+use std::io::Read;
+use std::time::Duration;
+
+use byteorder_slice::{BigEndian, ByteOrder, LittleEndian};
+
+use super::{PcapHeader, PcapParser};
+use crate::common::read_u32_fields;
+use crate::errors::*;
+use crate::read_buffer::ReadBuffer;
+use crate::timestamp::{from_pcap, Timestamp};
+use crate::{Endianness, TsResolution};
+
+
+/// Reads the records of a pcap with as little work as possible per packet.
+///
+/// Each 16-byte record header is decoded in place and the packet data is returned as a slice of the internal buffer,
+/// which is reused for the whole capture: reading a record never allocates. Unlike a [`PcapReader`](super::PcapReader),
+/// the records are returned as they are stored, their timestamp and lengths being neither converted nor checked, and
+/// the concatenated captures aren't supported.
+///
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+///
+/// use pcap_file::pcap::RecordReader;
+///
+/// let file_in = File::open("test.pcap").expect("Error opening file");
+/// let mut record_reader = RecordReader::new(file_in).unwrap();
+///
+/// let mut bytes = 0;
+/// while let Some(record) = record_reader.next_record() {
+///     bytes += record.unwrap().data.len();
+/// }
+/// ```
+#[derive(Debug)]
+pub struct RecordReader<R: Read> {
+    reader: ReadBuffer<R>,
+    header: PcapHeader,
+}
+
+/// Record of a pcap, borrowing its data from the buffer of a [`RecordReader`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct PcapRecord<'a> {
+    /// Timestamp in seconds
+    pub ts_sec: u32,
+    /// Nanosecond or microsecond part of the timestamp, see [`PcapHeader::ts_resolution`]
+    pub ts_frac: u32,
+    /// Original length of the packet when captured on the wire
+    pub orig_len: u32,
+    /// Captured data of the packet
+    pub data: &'a [u8],
+}
+
+impl PcapRecord<'_> {
+    /// Returns the timestamp of the record, with the timestamp resolution of the capture.
+    pub fn timestamp(&self, ts_resolution: TsResolution) -> Duration {
+        let nanos = match ts_resolution {
+            TsResolution::MicroSecond => self.ts_frac as u64 * 1000,
+            TsResolution::NanoSecond => self.ts_frac as u64,
+        };
+
+        Duration::from_secs(self.ts_sec as u64) + Duration::from_nanos(nanos)
+    }
+
+    /// Returns the timestamp of the record as any [`Timestamp`], converted directly from its fields.
+    ///
+    /// The ticks of the timestamp, e.g. returned as a `u64`, are in the resolution of the capture.
+    pub fn timestamp_as<T: Timestamp>(&self, ts_resolution: TsResolution) -> Option<T> {
+        from_pcap(self.ts_sec, self.ts_frac, ts_resolution)
+    }
+}
+
+impl<R: Read> RecordReader<R> {
+    /// Creates a new [`RecordReader`] from an existing reader, reading the global header of the pcap.
+    ///
+    /// # Errors
+    /// The data stream is not in a valid pcap file format.
+    ///
+    /// The underlying data are not readable.
+    pub fn new(reader: R) -> PcapResult<Self> {
+        Self::from_buffer(ReadBuffer::new(reader))
+    }
+
+    /// Creates a new [`RecordReader`] from an existing reader, with an internal buffer of `capacity` bytes instead of 8 MB.
+    ///
+    /// The capacity must be larger than the biggest record of the capture, which otherwise can't be read.
+    ///
+    /// # Errors
+    /// The data stream is not in a valid pcap file format.
+    ///
+    /// The underlying data are not readable.
+    pub fn with_capacity(reader: R, capacity: usize) -> PcapResult<Self> {
+        Self::from_buffer(ReadBuffer::with_capacity(reader, capacity))
+    }
+
+    fn from_buffer(mut reader: ReadBuffer<R>) -> PcapResult<Self> {
+        let header = reader.parse_with(PcapParser::new)?.header();
+        Ok(RecordReader { reader, header })
+    }
+
+    /// Returns the next [`PcapRecord`].
+    pub fn next_record(&mut self) -> Option<PcapResult<PcapRecord<'_>>> {
+        match self.reader.has_data_left() {
+            Ok(true) => {},
+            Ok(false) => return None,
+            Err(err) => return Some(Err(PcapError::IoError(err))),
+        }
+
+        let res = match self.header.endianness {
+            Endianness::Big => self.reader.parse_with(parse_record::<BigEndian>),
+            Endianness::Little => self.reader.parse_with(parse_record::<LittleEndian>),
+        };
+
+        return Some(res);
+
+        fn parse_record<B: ByteOrder>(src: &[u8]) -> PcapResult<(&[u8], PcapRecord<'_>)> {
+            if src.len() < 16 {
+                return Err(PcapError::IncompleteBuffer);
+            }
+
+            let [ts_sec, ts_frac, incl_len, orig_len] = read_u32_fields::<B, 4>(src);
+            let end = (incl_len as usize).checked_add(16).ok_or(PcapError::InvalidField("PcapRecord: incl_len > usize::MAX - 16"))?;
+            let data = src.get(16..end).ok_or(PcapError::IncompleteBuffer)?;
+
+            Ok((&src[end..], PcapRecord { ts_sec, ts_frac, orig_len, data }))
+        }
+    }
+
+    /// Returns the global header of the pcap.
+    pub fn header(&self) -> PcapHeader {
+        self.header
+    }
+
+    /// Returns the offset in the stream of the next record, relative to the start of the reader.
+    pub fn position(&self) -> u64 {
+        self.reader.position()
+    }
+
+    /// Consumes [`Self`], returning the wrapped reader.
+    ///
+    /// The data already read into the internal buffer but not returned as records yet is lost.
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+
+    /// Gets a reference to the wrapped reader.
+    pub fn get_ref(&self) -> &R {
+        self.reader.get_ref()
+    }
+}
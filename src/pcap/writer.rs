@@ -1,11 +1,13 @@
-use std::io::Write;
+use std::io::{BufWriter, StdoutLock, Write};
 
 use byteorder_slice::{BigEndian, LittleEndian};
 
 use super::RawPcapPacket;
+use crate::common::{preallocate, truncate};
 use crate::errors::*;
 use crate::pcap::{PcapHeader, PcapPacket};
-use crate::{Endianness, TsResolution};
+use crate::write_buffer::WriteBuffer;
+use crate::{Endianness, FileWriter, IoStats, TsResolution};
 
 
 /// Writes a pcap to a writer.
@@ -31,12 +33,22 @@ use crate::{Endianness, TsResolution};
 ///     pcap_writer.write_packet(&pkt).unwrap();
 /// }
 /// ```
+///
+/// The writer never seeks, so the output can be a pipe or a socket.
+/// Call [`flush()`](Self::flush) if the underlying writer is buffered.
+///
+/// Each packet is written with a single vectored write of its header and data. To also batch the small packets, the writer
+/// can keep them in an internal buffer, see [`set_buffer_capacity()`](Self::set_buffer_capacity).
+///
+/// When the writer is dropped, the internal buffer is written on a best-effort basis, ignoring the errors.
+/// Call [`finish()`](Self::finish) to check them.
 #[derive(Debug)]
 pub struct PcapWriter<W: Write> {
     endianness: Endianness,
     snaplen: u32,
     ts_resolution: TsResolution,
-    writer: W,
+    writer: WriteBuffer<W>,
+    stats: IoStats,
 }
 
 impl<W: Write> PcapWriter<W> {
@@ -73,35 +85,89 @@ impl<W: Write> PcapWriter<W> {
     /// # Errors
     /// The writer can't be written to.
     pub fn with_header(mut writer: W, header: PcapHeader) -> PcapResult<PcapWriter<W>> {
-        header.write_to(&mut writer)?;
+        let len = header.write_to(&mut writer)?;
 
         Ok(PcapWriter {
             endianness: header.endianness,
             snaplen: header.snaplen,
             ts_resolution: header.ts_resolution,
-            writer,
+            writer: WriteBuffer::new(writer),
+            stats: IoStats { bytes: len as u64, ..Default::default() },
         })
     }
 
     /// Consumes [`Self`], returning the wrapped writer.
+    ///
+    /// The content of the internal buffer is written first, ignoring the errors: call [`flush()`](Self::flush) before to check them.
     pub fn into_writer(self) -> W {
-        self.writer
+        self.writer.into_inner()
+    }
+
+    /// Consumes [`Self`], returning the wrapped writer, like [`Self::into_writer()`].
+    ///
+    /// The content of the internal buffer is written first, ignoring the errors: call [`flush()`](Self::flush) before to check them.
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+
+    /// Flushes the writer and returns the wrapped writer.
+    ///
+    /// Unlike [`into_inner()`](Self::into_inner) and the drop of the writer, the errors are returned.
+    pub fn finish(mut self) -> PcapResult<W> {
+        self.flush()?;
+        Ok(self.into_inner())
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        self.writer.get_ref()
+    }
+
+    /// Gets a mutable reference to the underlying writer.
+    ///
+    /// You should not write to it unless you really know what you're doing, the internal buffer being bypassed.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.writer.get_mut()
+    }
+
+    /// Writes the content of the internal buffer and flushes the underlying writer.
+    pub fn flush(&mut self) -> PcapResult<()> {
+        self.writer.flush().map_err(PcapError::IoError)
+    }
+
+    /// Sets the capacity of the internal buffer, 0 by default.
+    ///
+    /// With a capacity of 0, each packet is written to the underlying writer as soon as it is given. Otherwise the packets
+    /// are kept in the buffer until it is full, which saves a lot of calls for the small packets when the underlying writer
+    /// is an unbuffered file or socket, but delays them: call [`flush()`](Self::flush) to write them. The buffer is also
+    /// written by [`into_writer()`](Self::into_writer) and when the writer is dropped, ignoring the errors.
+    ///
+    /// The content of the buffer is written first if it doesn't fit in the new capacity.
+    pub fn set_buffer_capacity(&mut self, capacity: usize) -> PcapResult<()> {
+        self.writer.set_capacity(capacity).map_err(PcapError::IoError)
+    }
+
+    /// Returns the capacity of the internal buffer.
+    pub fn buffer_capacity(&self) -> usize {
+        self.writer.capacity()
     }
 
     /// Writes a [`PcapPacket`].
     pub fn write_packet(&mut self, packet: &PcapPacket) -> PcapResult<usize> {
-        match self.endianness {
+        let res = match self.endianness {
             Endianness::Big => packet.write_to::<_, BigEndian>(&mut self.writer, self.ts_resolution, self.snaplen),
             Endianness::Little => packet.write_to::<_, LittleEndian>(&mut self.writer, self.ts_resolution, self.snaplen),
-        }
+        };
+        self.stats.record(res.map(|len| (len, len as u64)), |_| true)
     }
 
     /// Writes a [`RawPcapPacket`].
     pub fn write_raw_packet(&mut self, packet: &RawPcapPacket) -> PcapResult<usize> {
-        match self.endianness {
+        let res = match self.endianness {
             Endianness::Big => packet.write_to::<_, BigEndian>(&mut self.writer),
             Endianness::Little => packet.write_to::<_, LittleEndian>(&mut self.writer),
-        }
+        };
+        self.stats.record(res.map(|len| (len, len as u64)), |_| true)
     }
 
     /// Returns the endianess used by the writer.
@@ -119,4 +185,55 @@ impl<W: Write> PcapWriter<W> {
     pub fn ts_resolution(&self) -> TsResolution {
         self.ts_resolution
     }
+
+    /// Returns the counters of the packets written since the creation of the writer, the bytes including the global header.
+    ///
+    /// The packets kept in the internal buffer are counted as written.
+    pub fn stats(&self) -> IoStats {
+        self.stats
+    }
+}
+
+impl<W: FileWriter> PcapWriter<W> {
+    /// Flushes the writer then syncs the data of the file to the disk, see [`File::sync_data()`](std::fs::File::sync_data).
+    pub fn sync_data(&mut self) -> PcapResult<()> {
+        self.flush()?;
+        self.writer.get_ref().file().sync_data().map_err(PcapError::IoError)
+    }
+
+    /// Flushes the writer then syncs the data and the metadata of the file to the disk, see [`File::sync_all()`](std::fs::File::sync_all).
+    pub fn sync_all(&mut self) -> PcapResult<()> {
+        self.flush()?;
+        self.writer.get_ref().file().sync_all().map_err(PcapError::IoError)
+    }
+
+    /// Flushes the writer then extends the file so that it holds `len` more bytes, e.g. the expected size of the capture,
+    /// to reduce its fragmentation on the disk during a long capture.
+    ///
+    /// The disk space is reserved with `posix_fallocate()`, so that a full disk is reported here rather than in the middle of
+    /// the capture. Where it isn't supported, the file is only extended with [`File::set_len()`](std::fs::File::set_len),
+    /// which reserves nothing. The preallocated space must be removed by [`finish_truncated()`](Self::finish_truncated),
+    /// otherwise the file ends with zeros.
+    pub fn preallocate(&mut self, len: u64) -> PcapResult<()> {
+        self.flush()?;
+        preallocate(self.writer.get_ref(), len)
+    }
+
+    /// Like [`finish()`](Self::finish), then truncates the file to the written data, removing the space preallocated by
+    /// [`preallocate()`](Self::preallocate).
+    pub fn finish_truncated(self) -> PcapResult<W> {
+        let writer = self.finish()?;
+        truncate(&writer)?;
+        Ok(writer)
+    }
+}
+
+impl PcapWriter<BufWriter<StdoutLock<'static>>> {
+    /// Creates a new [`PcapWriter`] writing to the locked and buffered standard output, with the default header.
+    ///
+    /// # Errors
+    /// The standard output can't be written to.
+    pub fn to_stdout() -> PcapResult<Self> {
+        Self::new(BufWriter::new(std::io::stdout().lock()))
+    }
 }
@@ -0,0 +1,70 @@
+/// Reusable storage of the batches of blocks read with [`PcapNgReader::read_blocks_in()`](super::PcapNgReader::read_blocks_in).
+///
+/// Each block of a batch is copied into its own buffer of the arena and parsed from it, borrowing its data. Reusing the
+/// same arena for all the batches keeps its buffers allocated, so that reading a block doesn't allocate its data, only
+/// the vectors of its options. This saves most of the allocations of the jobs which parse and discard a lot of blocks.
+///
+/// The blocks of a batch borrow the arena until the next one is read. Each buffer keeps the allocation of the largest
+/// block it held.
+///
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+///
+/// use pcap_file::pcapng::{BlockArena, PcapNgReader};
+///
+/// let file_in = File::open("test.pcapng").expect("Error opening file");
+/// let mut pcapng_reader = PcapNgReader::new(file_in).unwrap();
+///
+/// let mut arena = BlockArena::new();
+/// loop {
+///     let blocks = pcapng_reader.read_blocks_in(&mut arena, 1024).unwrap();
+///     if blocks.is_empty() {
+///         break;
+///     }
+///
+///     for block in blocks {
+///         // Do something
+///     }
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct BlockArena {
+    /// Copy of each block, the first `len` ones being the last batch
+    pub(crate) buffers: Vec<Vec<u8>>,
+    /// Number of blocks of the last batch
+    pub(crate) len: usize,
+}
+
+impl BlockArena {
+    /// Creates a new empty [`BlockArena`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new empty [`BlockArena`] holding `blocks` blocks of up to `block_len` bytes without reallocating.
+    pub fn with_capacity(blocks: usize, block_len: usize) -> Self {
+        BlockArena { buffers: (0..blocks).map(|_| Vec::with_capacity(block_len)).collect(), len: 0 }
+    }
+
+    /// Returns the number of blocks of the last batch.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the last batch is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the length of the data of the last batch.
+    pub fn data_len(&self) -> usize {
+        self.buffers[..self.len].iter().map(Vec::len).sum()
+    }
+
+    /// Removes the blocks of the last batch, keeping the allocated buffers.
+    pub fn clear(&mut self) {
+        self.buffers[..self.len].iter_mut().for_each(Vec::clear);
+        self.len = 0;
+    }
+}
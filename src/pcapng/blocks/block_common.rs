@@ -1,24 +1,32 @@
 //! Common block types.
 
 use std::borrow::Cow;
-use std::io::{Result as IoResult, Write};
+use std::io::{Error, ErrorKind, Result as IoResult, Write};
 
 use byteorder_slice::byteorder::WriteBytesExt;
 use byteorder_slice::result::ReadSlice;
 use byteorder_slice::{BigEndian, ByteOrder, LittleEndian};
 use derive_into_owned::IntoOwned;
 
+use super::custom::CustomBlock;
+use super::decryption_secrets::DecryptionSecretsBlock;
 use super::enhanced_packet::EnhancedPacketBlock;
+use super::hone_connection_event::HoneConnectionEventBlock;
+use super::hone_machine_info::HoneMachineInfoBlock;
 use super::interface_description::InterfaceDescriptionBlock;
 use super::interface_statistics::InterfaceStatisticsBlock;
 use super::name_resolution::NameResolutionBlock;
+use super::opt_common::PcapNgOption;
 use super::packet::PacketBlock;
+use super::registered::RegisteredBlock;
 use super::section_header::SectionHeaderBlock;
 use super::simple_packet::SimplePacketBlock;
 use super::systemd_journal_export::SystemdJournalExportBlock;
 use super::unknown::UnknownBlock;
+use crate::common::{read_u32_fields, write_all_vectored};
 use crate::errors::PcapError;
-use crate::PcapResult;
+use crate::pcapng::Utf8Mode;
+use crate::{Endianness, PcapResult};
 
 
 /// Section header block type
@@ -37,6 +45,16 @@ pub const INTERFACE_STATISTIC_BLOCK: u32 = 0x00000005;
 pub const ENHANCED_PACKET_BLOCK: u32 = 0x00000006;
 /// Systemd journal export block type
 pub const SYSTEMD_JOURNAL_EXPORT_BLOCK: u32 = 0x00000009;
+/// Decryption secrets block type
+pub const DECRYPTION_SECRETS_BLOCK: u32 = 0x0000000A;
+/// Hone Project machine info block type
+pub const HONE_MACHINE_INFO_BLOCK: u32 = 0x00000101;
+/// Hone Project connection event block type
+pub const HONE_CONNECTION_EVENT_BLOCK: u32 = 0x00000102;
+/// Custom block type, for the blocks which may be copied to a modified capture
+pub const CUSTOM_BLOCK: u32 = 0x00000BAD;
+/// Custom block type, for the blocks which must not be copied to a modified capture
+pub const CUSTOM_BLOCK_NO_COPY: u32 = 0x40000BAD;
 
 //   0               1               2               3
 //   0 1 2 3 4 5 6 7 0 1 2 3 4 5 6 7 0 1 2 3 4 5 6 7 0 1 2 3 4 5 6 7
@@ -51,7 +69,7 @@ pub const SYSTEMD_JOURNAL_EXPORT_BLOCK: u32 = 0x00000009;
 //  |                      Block Total Length                       |
 //  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// PcapNg Block
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
 pub struct RawBlock<'a> {
     /// Type field
     pub type_: u32,
@@ -65,35 +83,48 @@ pub struct RawBlock<'a> {
 
 impl<'a> RawBlock<'a> {
     /// Parses a borrowed [`RawBlock`] from a slice.
-    pub fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+    pub fn from_slice<B: ByteOrder>(slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+        Self::from_slice_inner::<B>(slice, false)
+    }
+
+    /// Parses a borrowed [`RawBlock`] from a slice, tolerating the defects of some broken writers.
+    ///
+    /// A trailer length which differs from the initial length by less than 4 bytes (i.e. by the padding amount)
+    /// is accepted, the initial length being authoritative.
+    pub fn from_slice_lenient<B: ByteOrder>(slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+        Self::from_slice_inner::<B>(slice, true)
+    }
+
+    /// Inner function to parse a [`RawBlock`].
+    fn from_slice_inner<B: ByteOrder>(mut slice: &'a [u8], lenient: bool) -> Result<(&'a [u8], Self), PcapError> {
         if slice.len() < 12 {
             return Err(PcapError::IncompleteBuffer);
         }
 
-        let type_ = slice.read_u32::<B>().unwrap();
+        let [type_, initial_len] = read_u32_fields::<B, 2>(slice);
 
         // Special case for the section header because we don't know the endianness yet
         if type_ == SECTION_HEADER_BLOCK {
+            slice = &slice[4..];
             let initial_len = slice.read_u32::<BigEndian>().unwrap();
 
             // Check the first field of the Section header to find the endianness
             let mut tmp_slice = slice;
             let magic = tmp_slice.read_u32::<BigEndian>().unwrap();
             let res = match magic {
-                0x1A2B3C4D => inner_parse::<BigEndian>(slice, type_, initial_len),
-                0x4D3C2B1A => inner_parse::<LittleEndian>(slice, type_, initial_len.swap_bytes()),
+                0x1A2B3C4D => inner_parse::<BigEndian>(slice, type_, initial_len, lenient),
+                0x4D3C2B1A => inner_parse::<LittleEndian>(slice, type_, initial_len.swap_bytes(), lenient),
                 _ => Err(PcapError::InvalidField("SectionHeaderBlock: invalid magic number")),
             };
 
             return res;
         }
         else {
-            let initial_len = slice.read_u32::<B>().map_err(|_| PcapError::IncompleteBuffer)?;
-            return inner_parse::<B>(slice, type_, initial_len);
+            return inner_parse::<B>(&slice[8..], type_, initial_len, lenient);
         };
 
         // Section Header parsing
-        fn inner_parse<B: ByteOrder>(slice: &[u8], type_: u32, initial_len: u32) -> Result<(&[u8], RawBlock<'_>), PcapError> {
+        fn inner_parse<B: ByteOrder>(slice: &[u8], type_: u32, initial_len: u32, lenient: bool) -> Result<(&[u8], RawBlock<'_>), PcapError> {
             if (initial_len % 4) != 0 {
                 return Err(PcapError::InvalidField("Block: (initial_len % 4) != 0"));
             }
@@ -114,7 +145,8 @@ impl<'a> RawBlock<'a> {
 
             let trailer_len = rem.read_u32::<B>().unwrap();
 
-            if initial_len != trailer_len {
+            let tolerated = lenient && initial_len.abs_diff(trailer_len) < 4;
+            if initial_len != trailer_len && !tolerated {
                 return Err(PcapError::InvalidField("Block: initial_length != trailer_length"));
             }
 
@@ -133,17 +165,122 @@ impl<'a> RawBlock<'a> {
         writer.write_all(&self.body[..])?;
         writer.write_u32::<B>(self.trailer_len)?;
 
-        Ok(self.body.len() + 6)
+        Ok(self.body.len() + 12)
     }
 
     /// Tries to convert a [`RawBlock`] into a [`Block`]
     pub fn try_into_block<B: ByteOrder>(self) -> PcapResult<Block<'a>> {
-        Block::try_from_raw_block::<B>(self)
+        self.try_into_block_with::<B>(Utf8Mode::Strict)
+    }
+
+    /// Tries to convert a [`RawBlock`] into a [`Block`], the string options being parsed with the given [`Utf8Mode`]
+    pub(crate) fn try_into_block_with<B: ByteOrder>(self, mode: Utf8Mode) -> PcapResult<Block<'a>> {
+        Block::try_from_raw_block_with::<B>(self, mode)
+    }
+
+    /// Parses the body of the block into a [`Block`] borrowing it.
+    ///
+    /// Unlike [`Self::try_into_block()`], the packet data and the options are never copied, even if the body is owned:
+    /// the returned block lives as long as the borrow of `self`. Use [`Self::parsed_owned()`] to keep it longer.
+    pub fn parsed<B: ByteOrder>(&self) -> PcapResult<Block<'_>> {
+        Block::from_body::<B>(self.type_, self.initial_len, &self.body, Utf8Mode::Strict)
+    }
+
+    /// Parses the body of the block into an owned [`Block`], which copies its packet data and options.
+    pub fn parsed_owned<B: ByteOrder>(&self) -> PcapResult<Block<'static>> {
+        self.parsed::<B>().map(Block::into_owned)
+    }
+
+    /// Returns a copy of the block whose packet data and option values are padded to 32 bits,
+    /// or `None` if the lengths declared in the block are inconsistent.
+    ///
+    /// Only the blocks whose layout is known (SHB, IDB, PB, ISB and EPB) are repaired.
+    pub(crate) fn with_canonical_padding<B: ByteOrder>(&self) -> Option<RawBlock<'static>> {
+        let body = &self.body[..];
+
+        let body = match self.type_ {
+            // The section header has its own endianness
+            SECTION_HEADER_BLOCK => match body.get(..4)? {
+                [0x1A, 0x2B, 0x3C, 0x4D] => canonical_padding::<BigEndian>(body, 16, 0)?,
+                [0x4D, 0x3C, 0x2B, 0x1A] => canonical_padding::<LittleEndian>(body, 16, 0)?,
+                _ => return None,
+            },
+            INTERFACE_DESCRIPTION_BLOCK => canonical_padding::<B>(body, 8, 0)?,
+            INTERFACE_STATISTIC_BLOCK => canonical_padding::<B>(body, 12, 0)?,
+            PACKET_BLOCK | ENHANCED_PACKET_BLOCK => {
+                let captured_len = B::read_u32(body.get(12..16)?) as usize;
+                canonical_padding::<B>(body, 20, captured_len)?
+            },
+            _ => return None,
+        };
+
+        let len = body.len() as u32 + 12;
+        Some(RawBlock { type_: self.type_, initial_len: len, body: Cow::Owned(body), trailer_len: len })
+    }
+}
+
+/// Rebuilds a block body made of `fixed_len` bytes of fields, `data_len` bytes of packet data and options,
+/// adding the missing padding after the data and the option values.
+///
+/// For each padding, the canonical layout is preferred and the unpadded one is only used if the rest
+/// of the body can't be parsed otherwise.
+fn canonical_padding<B: ByteOrder>(body: &[u8], fixed_len: usize, data_len: usize) -> Option<Vec<u8>> {
+    let data_end = fixed_len.checked_add(data_len)?;
+    if body.len() < data_end {
+        return None;
+    }
+
+    // parsable[offset] is true if the options starting at offset can be parsed until the end of the body
+    let mut parsable = vec![false; body.len() + 1];
+    parsable[body.len()] = true;
+    for offset in (data_end..body.len()).rev() {
+        parsable[offset] = match opt_header::<B>(&body[offset..]) {
+            Some((0, _)) => true,
+            Some((_, len)) => next_opt_offset(&parsable, offset, len).is_some(),
+            None => false,
+        };
+    }
+
+    let data_pad_len = (4 - data_len % 4) % 4;
+    let mut offset = [data_end + data_pad_len, data_end].into_iter().find(|&offset| parsable.get(offset) == Some(&true))?;
+
+    let mut padded = body[..data_end].to_vec();
+    padded.resize(data_end + data_pad_len, 0);
+
+    while offset < body.len() {
+        let (code, len) = opt_header::<B>(&body[offset..])?;
+        if code == 0 {
+            break;
+        }
+
+        padded.extend_from_slice(&body[offset..offset + 4 + len]);
+        padded.resize(padded.len() + (4 - len % 4) % 4, 0);
+        offset = next_opt_offset(&parsable, offset, len)?;
+    }
+
+    // opt_endofopt
+    if padded.len() > data_end + data_pad_len {
+        padded.extend_from_slice(&[0; 4]);
+    }
+
+    return Some(padded);
+
+    fn opt_header<B: ByteOrder>(slice: &[u8]) -> Option<(u16, usize)> {
+        let code = B::read_u16(slice.get(..2)?);
+        let len = B::read_u16(slice.get(2..4)?) as usize;
+        Some((code, len))
+    }
+
+    fn next_opt_offset(parsable: &[bool], offset: usize, len: usize) -> Option<usize> {
+        let unpadded = offset + 4 + len;
+        let padded = unpadded + (4 - len % 4) % 4;
+        [padded, unpadded].into_iter().find(|&next| parsable.get(next) == Some(&true))
     }
 }
 
 /// PcapNg parsed blocks
 #[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum Block<'a> {
     /// Section Header block
     SectionHeader(SectionHeaderBlock<'a>),
@@ -161,6 +298,16 @@ pub enum Block<'a> {
     EnhancedPacket(EnhancedPacketBlock<'a>),
     /// Systemd Journal Export block
     SystemdJournalExport(SystemdJournalExportBlock<'a>),
+    /// Decryption Secrets block
+    DecryptionSecrets(DecryptionSecretsBlock<'a>),
+    /// Hone Project Machine Info block
+    HoneMachineInfo(HoneMachineInfoBlock<'a>),
+    /// Hone Project Connection Event block
+    HoneConnectionEvent(HoneConnectionEventBlock<'a>),
+    /// Custom block
+    Custom(CustomBlock<'a>),
+    /// User-defined block, parsed by the [`PcapNgBlock`] registered for its type
+    Registered(RegisteredBlock),
     /// Unknown block
     Unknown(UnknownBlock<'a>),
 }
@@ -176,55 +323,97 @@ impl<'a> Block<'a> {
 
     /// Writes a [`Block`] to a writer.
     pub fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
-        return match self {
-            Self::SectionHeader(b) => inner_write_to::<B, _, W>(b, SECTION_HEADER_BLOCK, writer),
-            Self::InterfaceDescription(b) => inner_write_to::<B, _, W>(b, INTERFACE_DESCRIPTION_BLOCK, writer),
-            Self::Packet(b) => inner_write_to::<B, _, W>(b, PACKET_BLOCK, writer),
-            Self::SimplePacket(b) => inner_write_to::<B, _, W>(b, SIMPLE_PACKET_BLOCK, writer),
-            Self::NameResolution(b) => inner_write_to::<B, _, W>(b, NAME_RESOLUTION_BLOCK, writer),
-            Self::InterfaceStatistics(b) => inner_write_to::<B, _, W>(b, INTERFACE_STATISTIC_BLOCK, writer),
-            Self::EnhancedPacket(b) => inner_write_to::<B, _, W>(b, ENHANCED_PACKET_BLOCK, writer),
-            Self::SystemdJournalExport(b) => inner_write_to::<B, _, W>(b, SYSTEMD_JOURNAL_EXPORT_BLOCK, writer),
-            Self::Unknown(b) => inner_write_to::<B, _, W>(b, b.type_, writer),
-        };
+        match self {
+            Self::SectionHeader(b) => write_block_to::<B, _, W>(b, writer),
+            Self::InterfaceDescription(b) => write_block_to::<B, _, W>(b, writer),
+            Self::Packet(b) => write_block_to::<B, _, W>(b, writer),
+            Self::SimplePacket(b) => write_block_to::<B, _, W>(b, writer),
+            Self::NameResolution(b) => write_block_to::<B, _, W>(b, writer),
+            Self::InterfaceStatistics(b) => write_block_to::<B, _, W>(b, writer),
+            Self::EnhancedPacket(b) => write_block_to::<B, _, W>(b, writer),
+            Self::SystemdJournalExport(b) => write_block_to::<B, _, W>(b, writer),
+            Self::DecryptionSecrets(b) => write_block_to::<B, _, W>(b, writer),
+            Self::HoneMachineInfo(b) => write_block_to::<B, _, W>(b, writer),
+            Self::HoneConnectionEvent(b) => write_block_to::<B, _, W>(b, writer),
+            Self::Custom(b) => write_block_to::<B, _, W>(b, writer),
+            Self::Registered(b) => write_body_to::<B, W>(b.block_type(), writer, |w| b.write_to(Endianness::from_byteorder::<B>(), w)),
+            Self::Unknown(b) => write_body_to::<B, W>(b.type_, writer, |w| w.write_all(&b.value).map(|_| b.value.len())),
+        }
+    }
 
-        fn inner_write_to<'a, B: ByteOrder, BL: PcapNgBlock<'a>, W: Write>(block: &BL, block_code: u32, writer: &mut W) -> IoResult<usize> {
-            // Fake write to compute the data length
-            let data_len = block.write_to::<B, _>(&mut std::io::sink()).unwrap();
-            let pad_len = (4 - (data_len % 4)) % 4;
+    /// Returns the codes of the options of the block, in the order of the block.
+    ///
+    /// The options are parsed in the order of the file and the repeated options are all kept, so a code may appear
+    /// several times. The accessors of the blocks, like [`EnhancedPacketBlock::flags()`], return the first one.
+    pub fn option_codes(&self) -> Vec<u16> {
+        match self {
+            Self::SectionHeader(b) => b.options.iter().map(PcapNgOption::code).collect(),
+            Self::InterfaceDescription(b) => b.options.iter().map(PcapNgOption::code).collect(),
+            Self::Packet(b) => b.options.iter().map(PcapNgOption::code).collect(),
+            Self::NameResolution(b) => b.options.iter().map(PcapNgOption::code).collect(),
+            Self::InterfaceStatistics(b) => b.options.iter().map(PcapNgOption::code).collect(),
+            Self::EnhancedPacket(b) => b.options.iter().map(PcapNgOption::code).collect(),
+            Self::DecryptionSecrets(b) => b.options.iter().map(PcapNgOption::code).collect(),
+            Self::HoneMachineInfo(b) => b.options.iter().map(PcapNgOption::code).collect(),
+            Self::HoneConnectionEvent(b) => b.options.iter().map(PcapNgOption::code).collect(),
+            Self::SimplePacket(_) | Self::SystemdJournalExport(_) | Self::Custom(_) | Self::Registered(_) | Self::Unknown(_) => vec![],
+        }
+    }
 
-            let block_len = data_len + pad_len + 12;
+    /// Returns the codes of the options which appear several times in the block, in ascending order.
+    pub fn duplicate_option_codes(&self) -> Vec<u16> {
+        let mut codes = self.option_codes();
+        codes.sort_unstable();
 
-            writer.write_u32::<B>(block_code)?;
-            writer.write_u32::<B>(block_len as u32)?;
-            block.write_to::<B, _>(writer)?;
-            writer.write_all(&[0_u8; 3][..pad_len])?;
-            writer.write_u32::<B>(block_len as u32)?;
+        let mut duplicates: Vec<u16> = codes.windows(2).filter(|pair| pair[0] == pair[1]).map(|pair| pair[0]).collect();
+        duplicates.dedup();
+        duplicates
+    }
 
-            Ok(block_len)
+    /// Sorts the options of the block by ascending code, the options of the same code keeping their order.
+    pub fn sort_options(&mut self) {
+        match self {
+            Self::SectionHeader(b) => b.options.sort_by_cached_key(PcapNgOption::code),
+            Self::InterfaceDescription(b) => b.options.sort_by_cached_key(PcapNgOption::code),
+            Self::Packet(b) => b.options.sort_by_cached_key(PcapNgOption::code),
+            Self::NameResolution(b) => b.options.sort_by_cached_key(PcapNgOption::code),
+            Self::InterfaceStatistics(b) => b.options.sort_by_cached_key(PcapNgOption::code),
+            Self::EnhancedPacket(b) => b.options.sort_by_cached_key(PcapNgOption::code),
+            Self::DecryptionSecrets(b) => b.options.sort_by_cached_key(PcapNgOption::code),
+            Self::HoneMachineInfo(b) => b.options.sort_by_cached_key(PcapNgOption::code),
+            Self::HoneConnectionEvent(b) => b.options.sort_by_cached_key(PcapNgOption::code),
+            Self::SimplePacket(_) | Self::SystemdJournalExport(_) | Self::Custom(_) | Self::Registered(_) | Self::Unknown(_) => (),
         }
     }
 
     /// Tries to create a [`Block`] from a [`RawBlock`].
     ///
-    /// The RawBlock must be Borrowed.
+    /// If the body of the RawBlock is owned, the returned block is owned too.
     pub fn try_from_raw_block<B: ByteOrder>(raw_block: RawBlock<'a>) -> Result<Block<'a>, PcapError> {
-        let body = match raw_block.body {
-            Cow::Borrowed(b) => b,
-            _ => panic!("The raw block is not borrowed"),
-        };
+        Self::try_from_raw_block_with::<B>(raw_block, Utf8Mode::Strict)
+    }
 
-        match raw_block.type_ {
+    /// Tries to create a [`Block`] from a [`RawBlock`], the string options being parsed with the given [`Utf8Mode`]
+    pub(crate) fn try_from_raw_block_with<B: ByteOrder>(raw_block: RawBlock<'a>, mode: Utf8Mode) -> Result<Block<'a>, PcapError> {
+        match raw_block.body {
+            Cow::Borrowed(body) => Self::from_body::<B>(raw_block.type_, raw_block.initial_len, body, mode),
+            Cow::Owned(body) => Block::from_body::<B>(raw_block.type_, raw_block.initial_len, &body, mode).map(Block::into_owned),
+        }
+    }
+
+    /// Parses the body of a block of the given type.
+    fn from_body<B: ByteOrder>(type_: u32, initial_len: u32, body: &'a [u8], mode: Utf8Mode) -> Result<Block<'a>, PcapError> {
+        match type_ {
             SECTION_HEADER_BLOCK => {
-                let (_, block) = SectionHeaderBlock::from_slice::<BigEndian>(body)?;
+                let (_, block) = SectionHeaderBlock::from_slice_with(body, mode)?;
                 Ok(Block::SectionHeader(block))
             },
             INTERFACE_DESCRIPTION_BLOCK => {
-                let (_, block) = InterfaceDescriptionBlock::from_slice::<B>(body)?;
+                let (_, block) = InterfaceDescriptionBlock::from_slice_with::<B>(body, mode)?;
                 Ok(Block::InterfaceDescription(block))
             },
             PACKET_BLOCK => {
-                let (_, block) = PacketBlock::from_slice::<B>(body)?;
+                let (_, block) = PacketBlock::from_slice_with::<B>(body, mode)?;
                 Ok(Block::Packet(block))
             },
             SIMPLE_PACKET_BLOCK => {
@@ -232,22 +421,41 @@ impl<'a> Block<'a> {
                 Ok(Block::SimplePacket(block))
             },
             NAME_RESOLUTION_BLOCK => {
-                let (_, block) = NameResolutionBlock::from_slice::<B>(body)?;
+                let (_, block) = NameResolutionBlock::from_slice_with::<B>(body, mode)?;
                 Ok(Block::NameResolution(block))
             },
             INTERFACE_STATISTIC_BLOCK => {
-                let (_, block) = InterfaceStatisticsBlock::from_slice::<B>(body)?;
+                let (_, block) = InterfaceStatisticsBlock::from_slice_with::<B>(body, mode)?;
                 Ok(Block::InterfaceStatistics(block))
             },
             ENHANCED_PACKET_BLOCK => {
-                let (_, block) = EnhancedPacketBlock::from_slice::<B>(body)?;
+                let (_, block) = EnhancedPacketBlock::from_slice_with::<B>(body, mode)?;
                 Ok(Block::EnhancedPacket(block))
             },
             SYSTEMD_JOURNAL_EXPORT_BLOCK => {
                 let (_, block) = SystemdJournalExportBlock::from_slice::<B>(body)?;
                 Ok(Block::SystemdJournalExport(block))
             },
-            type_ => Ok(Block::Unknown(UnknownBlock::new(type_, raw_block.initial_len, body))),
+            DECRYPTION_SECRETS_BLOCK => {
+                let (_, block) = DecryptionSecretsBlock::from_slice_with::<B>(body, mode)?;
+                Ok(Block::DecryptionSecrets(block))
+            },
+            HONE_MACHINE_INFO_BLOCK => {
+                let (_, block) = HoneMachineInfoBlock::from_slice_with::<B>(body, mode)?;
+                Ok(Block::HoneMachineInfo(block))
+            },
+            HONE_CONNECTION_EVENT_BLOCK => {
+                let (_, block) = HoneConnectionEventBlock::from_slice_with::<B>(body, mode)?;
+                Ok(Block::HoneConnectionEvent(block))
+            },
+            CUSTOM_BLOCK | CUSTOM_BLOCK_NO_COPY => {
+                let (_, block) = CustomBlock::from_slice::<B>(body)?;
+                Ok(Block::Custom(CustomBlock { copiable: type_ == CUSTOM_BLOCK, ..block }))
+            },
+            type_ => {
+                let block = UnknownBlock::new(type_, initial_len, body).with_endianness(Endianness::from_byteorder::<B>());
+                Ok(Block::Unknown(block))
+            },
         }
     }
 
@@ -314,19 +522,692 @@ impl<'a> Block<'a> {
             _ => None,
         }
     }
+
+    /// Tries to downcast the current block into an [`DecryptionSecretsBlock`], if possible
+    pub fn into_decryption_secrets(self) -> Option<DecryptionSecretsBlock<'a>> {
+        match self {
+            Block::DecryptionSecrets(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Tries to downcast the current block into an [`HoneMachineInfoBlock`], if possible
+    pub fn into_hone_machine_info(self) -> Option<HoneMachineInfoBlock<'a>> {
+        match self {
+            Block::HoneMachineInfo(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Tries to downcast the current block into an [`HoneConnectionEventBlock`], if possible
+    pub fn into_hone_connection_event(self) -> Option<HoneConnectionEventBlock<'a>> {
+        match self {
+            Block::HoneConnectionEvent(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Tries to downcast the current block into a [`CustomBlock`], if possible
+    pub fn into_custom(self) -> Option<CustomBlock<'a>> {
+        match self {
+            Block::Custom(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Tries to downcast the current block into a [`RegisteredBlock`], if possible
+    pub fn into_registered(self) -> Option<RegisteredBlock> {
+        match self {
+            Block::Registered(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Tries to downcast the current block into an [`UnknownBlock`], if possible
+    pub fn into_unknown(self) -> Option<UnknownBlock<'a>> {
+        match self {
+            Block::Unknown(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the inner [`EnhancedPacketBlock`], if the current block is an [`EnhancedPacketBlock`]
+    pub fn as_enhanced_packet(&self) -> Option<&EnhancedPacketBlock<'a>> {
+        match self {
+            Block::EnhancedPacket(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the inner [`InterfaceDescriptionBlock`], if the current block is an [`InterfaceDescriptionBlock`]
+    pub fn as_interface_description(&self) -> Option<&InterfaceDescriptionBlock<'a>> {
+        match self {
+            Block::InterfaceDescription(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the inner [`InterfaceStatisticsBlock`], if the current block is an [`InterfaceStatisticsBlock`]
+    pub fn as_interface_statistics(&self) -> Option<&InterfaceStatisticsBlock<'a>> {
+        match self {
+            Block::InterfaceStatistics(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the inner [`NameResolutionBlock`], if the current block is a [`NameResolutionBlock`]
+    pub fn as_name_resolution(&self) -> Option<&NameResolutionBlock<'a>> {
+        match self {
+            Block::NameResolution(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the inner [`PacketBlock`], if the current block is a [`PacketBlock`]
+    pub fn as_packet(&self) -> Option<&PacketBlock<'a>> {
+        match self {
+            Block::Packet(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the inner [`SectionHeaderBlock`], if the current block is a [`SectionHeaderBlock`]
+    pub fn as_section_header(&self) -> Option<&SectionHeaderBlock<'a>> {
+        match self {
+            Block::SectionHeader(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the inner [`SimplePacketBlock`], if the current block is a [`SimplePacketBlock`]
+    pub fn as_simple_packet(&self) -> Option<&SimplePacketBlock<'a>> {
+        match self {
+            Block::SimplePacket(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the inner [`SystemdJournalExportBlock`], if the current block is a [`SystemdJournalExportBlock`]
+    pub fn as_systemd_journal_export(&self) -> Option<&SystemdJournalExportBlock<'a>> {
+        match self {
+            Block::SystemdJournalExport(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the inner [`DecryptionSecretsBlock`], if the current block is a [`DecryptionSecretsBlock`]
+    pub fn as_decryption_secrets(&self) -> Option<&DecryptionSecretsBlock<'a>> {
+        match self {
+            Block::DecryptionSecrets(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the inner [`HoneMachineInfoBlock`], if the current block is a [`HoneMachineInfoBlock`]
+    pub fn as_hone_machine_info(&self) -> Option<&HoneMachineInfoBlock<'a>> {
+        match self {
+            Block::HoneMachineInfo(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the inner [`HoneConnectionEventBlock`], if the current block is a [`HoneConnectionEventBlock`]
+    pub fn as_hone_connection_event(&self) -> Option<&HoneConnectionEventBlock<'a>> {
+        match self {
+            Block::HoneConnectionEvent(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the inner [`CustomBlock`], if the current block is a [`CustomBlock`]
+    pub fn as_custom(&self) -> Option<&CustomBlock<'a>> {
+        match self {
+            Block::Custom(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the inner [`RegisteredBlock`], if the current block is a [`RegisteredBlock`]
+    pub fn as_registered(&self) -> Option<&RegisteredBlock> {
+        match self {
+            Block::Registered(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the inner [`UnknownBlock`], if the current block is an [`UnknownBlock`]
+    pub fn as_unknown(&self) -> Option<&UnknownBlock<'a>> {
+        match self {
+            Block::Unknown(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the inner [`EnhancedPacketBlock`], if the current block is an [`EnhancedPacketBlock`]
+    pub fn as_enhanced_packet_mut(&mut self) -> Option<&mut EnhancedPacketBlock<'a>> {
+        match self {
+            Block::EnhancedPacket(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the inner [`InterfaceDescriptionBlock`], if the current block is an [`InterfaceDescriptionBlock`]
+    pub fn as_interface_description_mut(&mut self) -> Option<&mut InterfaceDescriptionBlock<'a>> {
+        match self {
+            Block::InterfaceDescription(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the inner [`InterfaceStatisticsBlock`], if the current block is an [`InterfaceStatisticsBlock`]
+    pub fn as_interface_statistics_mut(&mut self) -> Option<&mut InterfaceStatisticsBlock<'a>> {
+        match self {
+            Block::InterfaceStatistics(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the inner [`NameResolutionBlock`], if the current block is a [`NameResolutionBlock`]
+    pub fn as_name_resolution_mut(&mut self) -> Option<&mut NameResolutionBlock<'a>> {
+        match self {
+            Block::NameResolution(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the inner [`PacketBlock`], if the current block is a [`PacketBlock`]
+    pub fn as_packet_mut(&mut self) -> Option<&mut PacketBlock<'a>> {
+        match self {
+            Block::Packet(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the inner [`SectionHeaderBlock`], if the current block is a [`SectionHeaderBlock`]
+    pub fn as_section_header_mut(&mut self) -> Option<&mut SectionHeaderBlock<'a>> {
+        match self {
+            Block::SectionHeader(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the inner [`SimplePacketBlock`], if the current block is a [`SimplePacketBlock`]
+    pub fn as_simple_packet_mut(&mut self) -> Option<&mut SimplePacketBlock<'a>> {
+        match self {
+            Block::SimplePacket(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the inner [`SystemdJournalExportBlock`], if the current block is a [`SystemdJournalExportBlock`]
+    pub fn as_systemd_journal_export_mut(&mut self) -> Option<&mut SystemdJournalExportBlock<'a>> {
+        match self {
+            Block::SystemdJournalExport(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the inner [`DecryptionSecretsBlock`], if the current block is a [`DecryptionSecretsBlock`]
+    pub fn as_decryption_secrets_mut(&mut self) -> Option<&mut DecryptionSecretsBlock<'a>> {
+        match self {
+            Block::DecryptionSecrets(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the inner [`HoneMachineInfoBlock`], if the current block is a [`HoneMachineInfoBlock`]
+    pub fn as_hone_machine_info_mut(&mut self) -> Option<&mut HoneMachineInfoBlock<'a>> {
+        match self {
+            Block::HoneMachineInfo(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the inner [`HoneConnectionEventBlock`], if the current block is a [`HoneConnectionEventBlock`]
+    pub fn as_hone_connection_event_mut(&mut self) -> Option<&mut HoneConnectionEventBlock<'a>> {
+        match self {
+            Block::HoneConnectionEvent(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the inner [`CustomBlock`], if the current block is a [`CustomBlock`]
+    pub fn as_custom_mut(&mut self) -> Option<&mut CustomBlock<'a>> {
+        match self {
+            Block::Custom(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the inner [`RegisteredBlock`], if the current block is a [`RegisteredBlock`]
+    pub fn as_registered_mut(&mut self) -> Option<&mut RegisteredBlock> {
+        match self {
+            Block::Registered(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the inner [`UnknownBlock`], if the current block is an [`UnknownBlock`]
+    pub fn as_unknown_mut(&mut self) -> Option<&mut UnknownBlock<'a>> {
+        match self {
+            Block::Unknown(a) => Some(a),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> From<EnhancedPacketBlock<'a>> for Block<'a> {
+    fn from(block: EnhancedPacketBlock<'a>) -> Self {
+        Block::EnhancedPacket(block)
+    }
+}
+
+impl<'a> TryFrom<Block<'a>> for EnhancedPacketBlock<'a> {
+    /// The block, returned unchanged when it is of another type
+    type Error = Block<'a>;
+
+    fn try_from(block: Block<'a>) -> Result<Self, Self::Error> {
+        match block {
+            Block::EnhancedPacket(a) => Ok(a),
+            block => Err(block),
+        }
+    }
+}
+
+impl<'a> From<InterfaceDescriptionBlock<'a>> for Block<'a> {
+    fn from(block: InterfaceDescriptionBlock<'a>) -> Self {
+        Block::InterfaceDescription(block)
+    }
+}
+
+impl<'a> TryFrom<Block<'a>> for InterfaceDescriptionBlock<'a> {
+    /// The block, returned unchanged when it is of another type
+    type Error = Block<'a>;
+
+    fn try_from(block: Block<'a>) -> Result<Self, Self::Error> {
+        match block {
+            Block::InterfaceDescription(a) => Ok(a),
+            block => Err(block),
+        }
+    }
+}
+
+impl<'a> From<InterfaceStatisticsBlock<'a>> for Block<'a> {
+    fn from(block: InterfaceStatisticsBlock<'a>) -> Self {
+        Block::InterfaceStatistics(block)
+    }
+}
+
+impl<'a> TryFrom<Block<'a>> for InterfaceStatisticsBlock<'a> {
+    /// The block, returned unchanged when it is of another type
+    type Error = Block<'a>;
+
+    fn try_from(block: Block<'a>) -> Result<Self, Self::Error> {
+        match block {
+            Block::InterfaceStatistics(a) => Ok(a),
+            block => Err(block),
+        }
+    }
+}
+
+impl<'a> From<NameResolutionBlock<'a>> for Block<'a> {
+    fn from(block: NameResolutionBlock<'a>) -> Self {
+        Block::NameResolution(block)
+    }
+}
+
+impl<'a> TryFrom<Block<'a>> for NameResolutionBlock<'a> {
+    /// The block, returned unchanged when it is of another type
+    type Error = Block<'a>;
+
+    fn try_from(block: Block<'a>) -> Result<Self, Self::Error> {
+        match block {
+            Block::NameResolution(a) => Ok(a),
+            block => Err(block),
+        }
+    }
+}
+
+impl<'a> From<PacketBlock<'a>> for Block<'a> {
+    fn from(block: PacketBlock<'a>) -> Self {
+        Block::Packet(block)
+    }
+}
+
+impl<'a> TryFrom<Block<'a>> for PacketBlock<'a> {
+    /// The block, returned unchanged when it is of another type
+    type Error = Block<'a>;
+
+    fn try_from(block: Block<'a>) -> Result<Self, Self::Error> {
+        match block {
+            Block::Packet(a) => Ok(a),
+            block => Err(block),
+        }
+    }
+}
+
+impl<'a> From<SectionHeaderBlock<'a>> for Block<'a> {
+    fn from(block: SectionHeaderBlock<'a>) -> Self {
+        Block::SectionHeader(block)
+    }
+}
+
+impl<'a> TryFrom<Block<'a>> for SectionHeaderBlock<'a> {
+    /// The block, returned unchanged when it is of another type
+    type Error = Block<'a>;
+
+    fn try_from(block: Block<'a>) -> Result<Self, Self::Error> {
+        match block {
+            Block::SectionHeader(a) => Ok(a),
+            block => Err(block),
+        }
+    }
+}
+
+impl<'a> From<SimplePacketBlock<'a>> for Block<'a> {
+    fn from(block: SimplePacketBlock<'a>) -> Self {
+        Block::SimplePacket(block)
+    }
+}
+
+impl<'a> TryFrom<Block<'a>> for SimplePacketBlock<'a> {
+    /// The block, returned unchanged when it is of another type
+    type Error = Block<'a>;
+
+    fn try_from(block: Block<'a>) -> Result<Self, Self::Error> {
+        match block {
+            Block::SimplePacket(a) => Ok(a),
+            block => Err(block),
+        }
+    }
+}
+
+impl<'a> From<SystemdJournalExportBlock<'a>> for Block<'a> {
+    fn from(block: SystemdJournalExportBlock<'a>) -> Self {
+        Block::SystemdJournalExport(block)
+    }
+}
+
+impl<'a> TryFrom<Block<'a>> for SystemdJournalExportBlock<'a> {
+    /// The block, returned unchanged when it is of another type
+    type Error = Block<'a>;
+
+    fn try_from(block: Block<'a>) -> Result<Self, Self::Error> {
+        match block {
+            Block::SystemdJournalExport(a) => Ok(a),
+            block => Err(block),
+        }
+    }
+}
+
+impl<'a> From<DecryptionSecretsBlock<'a>> for Block<'a> {
+    fn from(block: DecryptionSecretsBlock<'a>) -> Self {
+        Block::DecryptionSecrets(block)
+    }
+}
+
+impl<'a> TryFrom<Block<'a>> for DecryptionSecretsBlock<'a> {
+    /// The block, returned unchanged when it is of another type
+    type Error = Block<'a>;
+
+    fn try_from(block: Block<'a>) -> Result<Self, Self::Error> {
+        match block {
+            Block::DecryptionSecrets(a) => Ok(a),
+            block => Err(block),
+        }
+    }
+}
+
+impl<'a> From<HoneMachineInfoBlock<'a>> for Block<'a> {
+    fn from(block: HoneMachineInfoBlock<'a>) -> Self {
+        Block::HoneMachineInfo(block)
+    }
+}
+
+impl<'a> TryFrom<Block<'a>> for HoneMachineInfoBlock<'a> {
+    /// The block, returned unchanged when it is of another type
+    type Error = Block<'a>;
+
+    fn try_from(block: Block<'a>) -> Result<Self, Self::Error> {
+        match block {
+            Block::HoneMachineInfo(a) => Ok(a),
+            block => Err(block),
+        }
+    }
+}
+
+impl<'a> From<HoneConnectionEventBlock<'a>> for Block<'a> {
+    fn from(block: HoneConnectionEventBlock<'a>) -> Self {
+        Block::HoneConnectionEvent(block)
+    }
+}
+
+impl<'a> TryFrom<Block<'a>> for HoneConnectionEventBlock<'a> {
+    /// The block, returned unchanged when it is of another type
+    type Error = Block<'a>;
+
+    fn try_from(block: Block<'a>) -> Result<Self, Self::Error> {
+        match block {
+            Block::HoneConnectionEvent(a) => Ok(a),
+            block => Err(block),
+        }
+    }
+}
+
+impl<'a> From<CustomBlock<'a>> for Block<'a> {
+    fn from(block: CustomBlock<'a>) -> Self {
+        Block::Custom(block)
+    }
+}
+
+impl<'a> TryFrom<Block<'a>> for CustomBlock<'a> {
+    /// The block, returned unchanged when it is of another type
+    type Error = Block<'a>;
+
+    fn try_from(block: Block<'a>) -> Result<Self, Self::Error> {
+        match block {
+            Block::Custom(a) => Ok(a),
+            block => Err(block),
+        }
+    }
+}
+
+impl<'a> From<RegisteredBlock> for Block<'a> {
+    fn from(block: RegisteredBlock) -> Self {
+        Block::Registered(block)
+    }
+}
+
+impl<'a> TryFrom<Block<'a>> for RegisteredBlock {
+    /// The block, returned unchanged when it is of another type
+    type Error = Block<'a>;
+
+    fn try_from(block: Block<'a>) -> Result<Self, Self::Error> {
+        match block {
+            Block::Registered(a) => Ok(a),
+            block => Err(block),
+        }
+    }
+}
+
+impl<'a> From<UnknownBlock<'a>> for Block<'a> {
+    fn from(block: UnknownBlock<'a>) -> Self {
+        Block::Unknown(block)
+    }
+}
+
+impl<'a> TryFrom<Block<'a>> for UnknownBlock<'a> {
+    /// The block, returned unchanged when it is of another type
+    type Error = Block<'a>;
+
+    fn try_from(block: Block<'a>) -> Result<Self, Self::Error> {
+        match block {
+            Block::Unknown(a) => Ok(a),
+            block => Err(block),
+        }
+    }
+}
+
+
+/// Writes a [`PcapNgBlock`] to a writer, with its block type, lengths and padding.
+pub(crate) fn write_block_to<'a, B: ByteOrder, BL: PcapNgBlock<'a>, W: Write>(block: &BL, writer: &mut W) -> IoResult<usize> {
+    write_body_to::<B, W>(block.block_type(), writer, |mut w| block.write_to::<B, _>(&mut w))
+}
+
+/// Writes the body of a block to a writer, with the given block type, lengths and padding.
+pub(crate) fn write_body_to<B: ByteOrder, W: Write>(
+    block_type: u32,
+    writer: &mut W,
+    write_body: impl Fn(&mut dyn Write) -> IoResult<usize>,
+) -> IoResult<usize> {
+    // The length of the body is needed by the header, it is measured by a first pass which writes nothing
+    let mut counter = ByteCounter(0);
+    write_body(&mut counter)?;
+    let data_len = counter.0;
+    let pad_len = (4 - (data_len % 4)) % 4;
+
+    let block_len = data_len + pad_len + 12;
+
+    let mut pending = [0_u8; GATHER_LEN];
+    B::write_u32(&mut pending[0..4], block_type);
+    B::write_u32(&mut pending[4..8], block_len as u32);
+
+    let mut tail = [0_u8; 7];
+    B::write_u32(&mut tail[pad_len..pad_len + 4], block_len as u32);
+
+    let mut out = GatherWriter { writer, pending, pending_len: 8, remaining: data_len, tail: &tail[..pad_len + 4] };
+    write_body(&mut out)?;
+    // The length of the block was written with the first pass, a body of another length would corrupt the output
+    if out.remaining != 0 {
+        return Err(Error::new(ErrorKind::InvalidData, "PcapNgBlock: write_to() wrote less data than on its first call"));
+    }
+    out.finish()?;
+
+    Ok(block_len)
+}
+
+/// Counts the bytes written without storing them.
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        Ok(())
+    }
+}
+
+/// Size of the stack buffer of a [`GatherWriter`]
+const GATHER_LEN: usize = 256;
+
+/// Gathers the small writes of a block in a stack buffer.
+///
+/// A write which doesn't fit in the buffer, like the data of a packet, is written along with the pending bytes in a single
+/// vectored write, and with the padding and the trailer if it ends the body. A block is so written without copying its data,
+/// in a single call unless options follow a large packet.
+struct GatherWriter<'w, 't, W: Write> {
+    writer: &'w mut W,
+    pending: [u8; GATHER_LEN],
+    pending_len: usize,
+    /// Length of the body still to be written
+    remaining: usize,
+    /// Padding and trailer, empty once written
+    tail: &'t [u8],
+}
+
+impl<W: Write> GatherWriter<'_, '_, W> {
+    /// Writes the pending bytes followed by the padding and the trailer, if not done yet.
+    fn finish(mut self) -> IoResult<()> {
+        if self.tail.is_empty() {
+            return Ok(());
+        }
+
+        let tail_start = self.pending_len;
+        if let Some(dst) = self.pending.get_mut(tail_start..tail_start + self.tail.len()) {
+            dst.copy_from_slice(self.tail);
+            self.writer.write_all(&self.pending[..tail_start + self.tail.len()])
+        }
+        else {
+            write_all_vectored(self.writer, [&self.pending[..self.pending_len], self.tail])
+        }
+    }
+}
+
+impl<W: Write> Write for GatherWriter<'_, '_, W> {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        self.remaining = self
+            .remaining
+            .checked_sub(buf.len())
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "PcapNgBlock: write_to() wrote more data than on its first call"))?;
+
+        if buf.len() <= GATHER_LEN - self.pending_len {
+            self.pending[self.pending_len..self.pending_len + buf.len()].copy_from_slice(buf);
+            self.pending_len += buf.len();
+        }
+        else if self.remaining == 0 {
+            write_all_vectored(self.writer, [&self.pending[..self.pending_len], buf, self.tail])?;
+            self.pending_len = 0;
+            self.tail = &[];
+        }
+        else {
+            write_all_vectored(self.writer, [&self.pending[..self.pending_len], buf])?;
+            self.pending_len = 0;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        Ok(())
+    }
 }
 
 
 /// Common interface for the PcapNg blocks
+///
+/// Can be implemented for user-defined blocks, which can then be written with
+/// [`PcapNgWriter::write_pcapng_block()`](crate::pcapng::PcapNgWriter::write_pcapng_block),
+/// registered with [`PcapNgParser::register_block()`](crate::pcapng::PcapNgParser::register_block) to be read as
+/// [`Block::Registered`], or decoded from an [`UnknownBlock`] with [`UnknownBlock::parse_as()`].
 pub trait PcapNgBlock<'a> {
+    /// Type code of the block
+    const BLOCK_TYPE: u32;
+
     /// Parse a new block from a slice
     fn from_slice<B: ByteOrder>(slice: &'a [u8]) -> Result<(&[u8], Self), PcapError>
     where
         Self: std::marker::Sized;
 
     /// Write the content of a block into a writer
+    ///
+    /// It is called twice for each written block, first to measure its length then to write it: both calls must write
+    /// the same bytes, otherwise the block is refused with an [`ErrorKind::InvalidData`] error.
     fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize>;
 
+    /// Returns the type code of the block
+    fn block_type(&self) -> u32 {
+        Self::BLOCK_TYPE
+    }
+
+    /// Returns a copy of the block as a [`Block`], `None` for the user-defined blocks which have no variant.
+    ///
+    /// Used by the [`PcapNgWriter`](crate::pcapng::PcapNgWriter) to check the standard blocks before writing them.
+    fn to_block(&self) -> Option<Block<'_>> {
+        None
+    }
+
     /// Convert a block into the [`Block`] enumeration
-    fn into_block(self) -> Block<'a>;
+    ///
+    /// Only available for the blocks which have a [`Block`] variant: the user-defined blocks are
+    /// written with [`PcapNgWriter::write_pcapng_block()`](crate::pcapng::PcapNgWriter::write_pcapng_block)
+    /// and wrapped in a [`RegisteredBlock`].
+    fn into_block(self) -> Block<'a>
+    where
+        Self: Sized + Into<Block<'a>>,
+    {
+        self.into()
+    }
 }
@@ -0,0 +1,74 @@
+//! Custom Block (CB).
+
+use std::borrow::Cow;
+use std::io::{Result as IoResult, Write};
+
+use byteorder_slice::byteorder::WriteBytesExt;
+use byteorder_slice::result::ReadSlice;
+use byteorder_slice::ByteOrder;
+use derive_into_owned::IntoOwned;
+
+use super::block_common::{Block, PcapNgBlock, CUSTOM_BLOCK, CUSTOM_BLOCK_NO_COPY};
+use crate::errors::PcapError;
+
+
+/// A Custom Block (CB) stores data in a format defined by the organization identified by its Private Enterprise Number.
+///
+/// The block has no length field for its data, so the data read from a file includes the padding bytes, and any
+/// options must be encoded by the organization inside the data.
+#[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+pub struct CustomBlock<'a> {
+    /// IANA Private Enterprise Number of the organization which defines the format of the data.
+    pub pen: u32,
+
+    /// The block may be copied to a new file by the tools which don't understand it, even if they modify the capture.
+    ///
+    /// A block describing the capture itself, like a digest, should not be copiable.
+    pub copiable: bool,
+
+    /// Custom data.
+    pub data: Cow<'a, [u8]>,
+}
+
+impl<'a> CustomBlock<'a> {
+    /// Creates a new copiable [`CustomBlock`].
+    pub fn new(pen: u32, data: &'a [u8]) -> Self {
+        CustomBlock { pen, copiable: true, data: Cow::Borrowed(data) }
+    }
+}
+
+impl<'a> PcapNgBlock<'a> for CustomBlock<'a> {
+    /// Type of the copiable custom blocks, see [`PcapNgBlock::block_type()`] for the actual type of the block.
+    const BLOCK_TYPE: u32 = CUSTOM_BLOCK;
+
+    fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+        if slice.len() < 4 {
+            return Err(PcapError::InvalidField("CustomBlock: block length < 4"));
+        }
+
+        let pen = slice.read_u32::<B>().unwrap();
+        let block = CustomBlock { pen, copiable: true, data: Cow::Borrowed(slice) };
+
+        Ok((&[], block))
+    }
+
+    fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
+        writer.write_u32::<B>(self.pen)?;
+        writer.write_all(&self.data)?;
+
+        Ok(4 + self.data.len())
+    }
+
+    fn block_type(&self) -> u32 {
+        if self.copiable {
+            CUSTOM_BLOCK
+        }
+        else {
+            CUSTOM_BLOCK_NO_COPY
+        }
+    }
+
+    fn to_block(&self) -> Option<Block<'_>> {
+        Some(Block::Custom(self.clone()))
+    }
+}
@@ -0,0 +1,321 @@
+//! Decryption Secrets Block (DSB).
+
+use std::borrow::Cow;
+use std::io::{Read, Result as IoResult, Write};
+
+use byteorder_slice::byteorder::WriteBytesExt;
+use byteorder_slice::result::ReadSlice;
+use byteorder_slice::ByteOrder;
+use derive_into_owned::IntoOwned;
+
+use super::block_common::{Block, PcapNgBlock, DECRYPTION_SECRETS_BLOCK};
+use super::opt_common::{utf8_str, CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
+use crate::errors::PcapError;
+use crate::pcapng::Utf8Mode;
+
+
+/// A Decryption Secrets Block (DSB) stores the secrets needed to decrypt the traffic of the capture.
+#[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+pub struct DecryptionSecretsBlock<'a> {
+    /// Format of the secrets.
+    pub secrets_type: SecretsType,
+
+    /// The secrets, in the format given by `secrets_type`.
+    pub secrets_data: Cow<'a, [u8]>,
+
+    /// Options
+    pub options: Vec<DecryptionSecretsOption<'a>>,
+}
+
+impl<'a> DecryptionSecretsBlock<'a> {
+    /// Parses the block from a slice, the string options being parsed with the given [`Utf8Mode`]
+    pub(crate) fn from_slice_with<B: ByteOrder>(mut slice: &'a [u8], mode: Utf8Mode) -> Result<(&'a [u8], Self), PcapError> {
+        if slice.len() < 8 {
+            return Err(PcapError::InvalidField("DecryptionSecretsBlock: block length < 8"));
+        }
+
+        let secrets_type = slice.read_u32::<B>().unwrap().into();
+        let secrets_len = slice.read_u32::<B>().unwrap() as usize;
+
+        let pad_len = (4 - (secrets_len % 4)) % 4;
+        let tot_len = secrets_len
+            .checked_add(pad_len)
+            .ok_or(PcapError::InvalidField("DecryptionSecretsBlock: secrets_len + padding > usize::MAX"))?;
+
+        if slice.len() < tot_len {
+            return Err(PcapError::InvalidField("DecryptionSecretsBlock: secrets_len + padding > block length"));
+        }
+
+        let secrets_data = &slice[..secrets_len];
+        slice = &slice[tot_len..];
+
+        let (slice, options) = DecryptionSecretsOption::opts_from_slice::<B>(slice, mode)?;
+        let block = DecryptionSecretsBlock { secrets_type, secrets_data: Cow::Borrowed(secrets_data), options };
+
+        Ok((slice, block))
+    }
+}
+
+
+impl<'a> PcapNgBlock<'a> for DecryptionSecretsBlock<'a> {
+    const BLOCK_TYPE: u32 = DECRYPTION_SECRETS_BLOCK;
+
+    fn from_slice<B: ByteOrder>(slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+        Self::from_slice_with::<B>(slice, Utf8Mode::Strict)
+    }
+
+    fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
+        let pad_len = (4 - (self.secrets_data.len() % 4)) % 4;
+
+        writer.write_u32::<B>(self.secrets_type.into())?;
+        writer.write_u32::<B>(self.secrets_data.len() as u32)?;
+        writer.write_all(&self.secrets_data)?;
+        writer.write_all(&[0_u8; 3][..pad_len])?;
+
+        let opt_len = DecryptionSecretsOption::write_opts_to::<B, W>(&self.options, writer)?;
+
+        Ok(8 + self.secrets_data.len() + pad_len + opt_len)
+    }
+
+    fn to_block(&self) -> Option<Block<'_>> {
+        Some(Block::DecryptionSecrets(self.clone()))
+    }
+}
+
+impl DecryptionSecretsBlock<'static> {
+    /// Creates a new [`DecryptionSecretsBlock`] containing the content of a TLS key log file, as written by
+    /// the applications honoring the `SSLKEYLOGFILE` environment variable.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use std::fs::File;
+    ///
+    /// use pcap_file::pcapng::blocks::decryption_secrets::DecryptionSecretsBlock;
+    /// use pcap_file::pcapng::PcapNgWriter;
+    ///
+    /// let keylog = File::open("keys.log").expect("Error opening file");
+    /// let secrets = DecryptionSecretsBlock::from_tls_keylog(keylog).unwrap();
+    ///
+    /// let file_out = File::create("out.pcapng").expect("Error creating file");
+    /// let mut pcapng_writer = PcapNgWriter::new(file_out).unwrap();
+    /// pcapng_writer.write_pcapng_block(&secrets).unwrap();
+    /// ```
+    pub fn from_tls_keylog<R: Read>(mut reader: R) -> Result<Self, PcapError> {
+        let mut secrets_data = vec![];
+        reader.read_to_end(&mut secrets_data).map_err(PcapError::IoError)?;
+
+        Ok(DecryptionSecretsBlock { secrets_type: SecretsType::TlsKeyLog, secrets_data: Cow::Owned(secrets_data), options: vec![] })
+    }
+
+    /// Creates a new [`DecryptionSecretsBlock`] containing the content of a WireGuard key log file, as written by
+    /// the `extract-handshakes.sh` script of WireGuard.
+    pub fn from_wireguard_keylog<R: Read>(mut reader: R) -> Result<Self, PcapError> {
+        let mut secrets_data = vec![];
+        reader.read_to_end(&mut secrets_data).map_err(PcapError::IoError)?;
+
+        Ok(DecryptionSecretsBlock { secrets_type: SecretsType::WireGuardKeyLog, secrets_data: Cow::Owned(secrets_data), options: vec![] })
+    }
+
+    /// Creates a new [`DecryptionSecretsBlock`] containing a ZigBee network key.
+    ///
+    /// # Example
+    /// ```rust
+    /// use pcap_file::pcapng::blocks::decryption_secrets::{DecryptionSecretsBlock, ZigBeeNwkKey};
+    ///
+    /// let key = ZigBeeNwkKey { key: [0xAB; 16], pan_id: 0x1A62 };
+    /// let secrets = DecryptionSecretsBlock::from_zigbee_nwk_key(&key);
+    ///
+    /// assert_eq!(secrets.zigbee_nwk_key(), Some(key));
+    /// ```
+    pub fn from_zigbee_nwk_key(key: &ZigBeeNwkKey) -> Self {
+        let mut secrets_data = key.key.to_vec();
+        secrets_data.extend_from_slice(&key.pan_id.to_le_bytes());
+
+        DecryptionSecretsBlock { secrets_type: SecretsType::ZigBeeNwkKey, secrets_data: Cow::Owned(secrets_data), options: vec![] }
+    }
+
+    /// Creates a new [`DecryptionSecretsBlock`] containing a ZigBee application support key.
+    pub fn from_zigbee_aps_key(key: &ZigBeeApsKey) -> Self {
+        let mut secrets_data = key.key.to_vec();
+        secrets_data.extend_from_slice(&key.pan_id.to_le_bytes());
+        secrets_data.extend_from_slice(&key.low_short_address.to_le_bytes());
+        secrets_data.extend_from_slice(&key.high_short_address.to_le_bytes());
+
+        DecryptionSecretsBlock { secrets_type: SecretsType::ZigBeeApsKey, secrets_data: Cow::Owned(secrets_data), options: vec![] }
+    }
+}
+
+impl<'a> DecryptionSecretsBlock<'a> {
+    /// Returns the content of the key log if the block contains a TLS or a WireGuard key log in UTF-8.
+    pub fn key_log(&self) -> Option<&str> {
+        match self.secrets_type {
+            SecretsType::TlsKeyLog | SecretsType::WireGuardKeyLog => std::str::from_utf8(&self.secrets_data).ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the ZigBee network key if the block contains a valid one.
+    pub fn zigbee_nwk_key(&self) -> Option<ZigBeeNwkKey> {
+        if self.secrets_type != SecretsType::ZigBeeNwkKey || self.secrets_data.len() != 18 {
+            return None;
+        }
+
+        let data = &self.secrets_data;
+        Some(ZigBeeNwkKey { key: data[..16].try_into().unwrap(), pan_id: u16::from_le_bytes([data[16], data[17]]) })
+    }
+
+    /// Returns the ZigBee application support key if the block contains a valid one.
+    pub fn zigbee_aps_key(&self) -> Option<ZigBeeApsKey> {
+        if self.secrets_type != SecretsType::ZigBeeApsKey || self.secrets_data.len() != 22 {
+            return None;
+        }
+
+        let data = &self.secrets_data;
+        Some(ZigBeeApsKey {
+            key: data[..16].try_into().unwrap(),
+            pan_id: u16::from_le_bytes([data[16], data[17]]),
+            low_short_address: u16::from_le_bytes([data[18], data[19]]),
+            high_short_address: u16::from_le_bytes([data[20], data[21]]),
+        })
+    }
+}
+
+
+/// ZigBee network (NWK) key, stored in a [`DecryptionSecretsBlock`] of type [`SecretsType::ZigBeeNwkKey`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct ZigBeeNwkKey {
+    /// AES-128-CCM key
+    pub key: [u8; 16],
+    /// PAN ID of the network using the key
+    pub pan_id: u16,
+}
+
+/// ZigBee application support (APS) key, stored in a [`DecryptionSecretsBlock`] of type [`SecretsType::ZigBeeApsKey`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct ZigBeeApsKey {
+    /// AES-128-CCM key
+    pub key: [u8; 16],
+    /// PAN ID of the network using the key
+    pub pan_id: u16,
+    /// Lowest short address of the two nodes using the key
+    pub low_short_address: u16,
+    /// Highest short address of the two nodes using the key
+    pub high_short_address: u16,
+}
+
+
+/// Format of the secrets stored in a [`DecryptionSecretsBlock`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum SecretsType {
+    /// TLS Key Log, in the NSS key log format
+    TlsKeyLog,
+    /// WireGuard Key Log
+    WireGuardKeyLog,
+    /// ZigBee NWK Key and ZigBee PAN ID, in little endian
+    ZigBeeNwkKey,
+    /// ZigBee APS Key, ZigBee PAN ID and short addresses, in little endian
+    ZigBeeApsKey,
+    /// Unknown secrets type
+    Unknown(u32),
+}
+
+impl From<u32> for SecretsType {
+    fn from(value: u32) -> Self {
+        match value {
+            0x544C534B => SecretsType::TlsKeyLog,
+            0x57474B4C => SecretsType::WireGuardKeyLog,
+            0x5A4E574B => SecretsType::ZigBeeNwkKey,
+            0x5A415053 => SecretsType::ZigBeeApsKey,
+            n => SecretsType::Unknown(n),
+        }
+    }
+}
+
+impl From<SecretsType> for u32 {
+    fn from(secrets_type: SecretsType) -> Self {
+        match secrets_type {
+            SecretsType::TlsKeyLog => 0x544C534B,
+            SecretsType::WireGuardKeyLog => 0x57474B4C,
+            SecretsType::ZigBeeNwkKey => 0x5A4E574B,
+            SecretsType::ZigBeeApsKey => 0x5A415053,
+            SecretsType::Unknown(n) => n,
+        }
+    }
+}
+
+
+/// The Decryption Secrets Block options
+#[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum DecryptionSecretsOption<'a> {
+    /// Comment associated with the current block
+    Comment(Cow<'a, str>),
+
+    /// Custom option containing binary octets in the Custom Data portion
+    CustomBinary(CustomBinaryOption<'a>),
+
+    /// Custom option containing a UTF-8 string in the Custom Data portion
+    CustomUtf8(CustomUtf8Option<'a>),
+
+    /// Unknown option
+    Unknown(UnknownOption<'a>),
+}
+
+impl<'a> DecryptionSecretsOption<'a> {
+    /// Returns the value of a string option, custom ones included, see [`Utf8Mode`].
+    pub fn as_str(&self) -> Option<Cow<'_, str>> {
+        match self {
+            DecryptionSecretsOption::Comment(value) => Some(Cow::Borrowed(value)),
+            DecryptionSecretsOption::CustomUtf8(custom) => Some(Cow::Borrowed(&custom.value)),
+            DecryptionSecretsOption::Unknown(unknown) => unknown.lossy_str(),
+            _ => None,
+        }
+    }
+
+    /// Returns the exact bytes of a string option, custom ones included, see [`Utf8Mode`].
+    pub fn as_str_raw(&self) -> Option<&[u8]> {
+        match self {
+            DecryptionSecretsOption::Comment(value) => Some(value.as_bytes()),
+            DecryptionSecretsOption::CustomUtf8(custom) => Some(custom.value.as_bytes()),
+            DecryptionSecretsOption::Unknown(unknown) => unknown.str_raw(),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> PcapNgOption<'a> for DecryptionSecretsOption<'a> {
+    fn from_slice<B: ByteOrder>(code: u16, length: u16, slice: &'a [u8]) -> Result<Self, PcapError> {
+        let opt = match code {
+            1 => DecryptionSecretsOption::Comment(utf8_str(slice)?),
+
+            2988 | 19372 => DecryptionSecretsOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice)?),
+            2989 | 19373 => DecryptionSecretsOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice)?),
+
+            _ => DecryptionSecretsOption::Unknown(UnknownOption::new(code, length, slice)),
+        };
+
+        Ok(opt)
+    }
+
+    fn unknown(option: UnknownOption<'a>) -> Self {
+        DecryptionSecretsOption::Unknown(option)
+    }
+
+    fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
+        match self {
+            DecryptionSecretsOption::Comment(a) => a.write_opt_to::<B, W>(1, writer),
+            DecryptionSecretsOption::CustomBinary(a) => a.write_opt_to::<B, W>(a.code, writer),
+            DecryptionSecretsOption::CustomUtf8(a) => a.write_opt_to::<B, W>(a.code, writer),
+            DecryptionSecretsOption::Unknown(a) => a.write_opt_to::<B, W>(a.code, writer),
+        }
+    }
+
+    fn code(&self) -> u16 {
+        match self {
+            DecryptionSecretsOption::Comment(_) => 1,
+            DecryptionSecretsOption::CustomBinary(a) => a.code,
+            DecryptionSecretsOption::CustomUtf8(a) => a.code,
+            DecryptionSecretsOption::Unknown(a) => a.code,
+        }
+    }
+}
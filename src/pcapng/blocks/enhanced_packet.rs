@@ -9,9 +9,14 @@ use byteorder_slice::result::ReadSlice;
 use byteorder_slice::ByteOrder;
 use derive_into_owned::IntoOwned;
 
-use super::block_common::{Block, PcapNgBlock};
-use super::opt_common::{CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
+use super::block_common::{Block, PcapNgBlock, ENHANCED_PACKET_BLOCK};
+use super::interface_description::{InterfaceDescriptionBlock, InterfaceId};
+use super::opt_common::{utf8_str, CustomBinaryOption, CustomUtf8Option, OptionIter, PcapNgOption, UnknownOption, WriteOptTo};
+use crate::common::read_u32_fields;
 use crate::errors::PcapError;
+use crate::pcapng::Utf8Mode;
+use crate::timestamp::{IntoTimestamp, Timestamp};
+use crate::SnapLen;
 
 
 /// An Enhanced Packet Block (EPB) is the standard container for storing the packets coming from the network.
@@ -36,18 +41,63 @@ pub struct EnhancedPacketBlock<'a> {
     pub options: Vec<EnhancedPacketOption<'a>>,
 }
 
-impl<'a> PcapNgBlock<'a> for EnhancedPacketBlock<'a> {
-    fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+impl<'a> EnhancedPacketBlock<'a> {
+    /// Parses the block from a slice, the string options being parsed with the given [`Utf8Mode`]
+    pub(crate) fn from_slice_with<B: ByteOrder>(slice: &'a [u8], mode: Utf8Mode) -> Result<(&'a [u8], Self), PcapError> {
+        let (slice, mut block) = Self::fields_from_slice::<B>(slice)?;
+        let (slice, options) = EnhancedPacketOption::opts_from_slice::<B>(slice, mode)?;
+        block.options = options;
+
+        Ok((slice, block))
+    }
+
+    /// Parses the body of a block like [`PcapNgBlock::from_slice()`], except for its options which are returned as an
+    /// [`OptionIter`] parsing them lazily.
+    ///
+    /// The `options` of the returned block stay empty, so that reading a packet with options doesn't allocate.
+    ///
+    /// # Example
+    /// ```rust
+    /// use byteorder_slice::BigEndian;
+    /// use pcap_file::pcapng::blocks::enhanced_packet::{EnhancedPacketBlock, EnhancedPacketOption, PacketFlags};
+    /// use pcap_file::pcapng::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceId};
+    /// use pcap_file::pcapng::blocks::ENHANCED_PACKET_BLOCK;
+    /// use pcap_file::pcapng::{PcapNgReader, PcapNgWriter};
+    /// use pcap_file::{DataLink, Endianness};
+    ///
+    /// let interface = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0);
+    /// let packet = EnhancedPacketBlock::builder(InterfaceId(0), &[0_u8; 64]).flags(PacketFlags::from_bits(1)).build(&interface).unwrap();
+    ///
+    /// let mut pcapng_writer = PcapNgWriter::with_endianness(Vec::new(), Endianness::Big).unwrap();
+    /// pcapng_writer.write_pcapng_block(&interface).unwrap();
+    /// pcapng_writer.write_pcapng_block(&packet).unwrap();
+    /// let data = pcapng_writer.into_inner();
+    ///
+    /// let mut pcapng_reader = PcapNgReader::new(&data[..]).unwrap();
+    /// while let Some(block) = pcapng_reader.next_raw_block() {
+    ///     let block = block.unwrap();
+    ///     if block.type_ == ENHANCED_PACKET_BLOCK {
+    ///         let (packet, mut options) = EnhancedPacketBlock::from_slice_lazy::<BigEndian>(&block.body).unwrap();
+    ///         assert!(packet.options.is_empty());
+    ///         assert_eq!(options.next().unwrap().unwrap(), EnhancedPacketOption::Flags(PacketFlags::from_bits(1)));
+    ///         assert!(options.next().is_none());
+    ///     }
+    /// }
+    /// ```
+    pub fn from_slice_lazy<B: ByteOrder>(slice: &'a [u8]) -> Result<(Self, OptionIter<'a, EnhancedPacketOption<'a>>), PcapError> {
+        let (slice, block) = Self::fields_from_slice::<B>(slice)?;
+        Ok((block, OptionIter::new::<B>(slice, Utf8Mode::Strict)))
+    }
+
+    /// Parses the fields of the block, returning it without options along with the bytes of its options
+    fn fields_from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
         if slice.len() < 20 {
             return Err(PcapError::InvalidField("EnhancedPacketBlock: block length length < 20"));
         }
 
-        let interface_id = slice.read_u32::<B>().unwrap();
-        let timestamp_high = slice.read_u32::<B>().unwrap() as u64;
-        let timestamp_low = slice.read_u32::<B>().unwrap() as u64;
-        let timestamp = (timestamp_high << 32) + timestamp_low;
-        let captured_len = slice.read_u32::<B>().unwrap();
-        let original_len = slice.read_u32::<B>().unwrap();
+        let [interface_id, timestamp_high, timestamp_low, captured_len, original_len] = read_u32_fields::<B, 5>(slice);
+        slice = &slice[20..];
+        let timestamp = ((timestamp_high as u64) << 32) + timestamp_low as u64;
 
         let pad_len = (4 - (captured_len as usize % 4)) % 4;
         let tot_len = captured_len as usize + pad_len;
@@ -59,17 +109,25 @@ impl<'a> PcapNgBlock<'a> for EnhancedPacketBlock<'a> {
         let data = &slice[..captured_len as usize];
         slice = &slice[tot_len..];
 
-        let (slice, options) = EnhancedPacketOption::opts_from_slice::<B>(slice)?;
         let block = EnhancedPacketBlock {
             interface_id,
             timestamp: Duration::from_nanos(timestamp),
             original_len,
             data: Cow::Borrowed(data),
-            options,
+            options: vec![],
         };
 
         Ok((slice, block))
     }
+}
+
+
+impl<'a> PcapNgBlock<'a> for EnhancedPacketBlock<'a> {
+    const BLOCK_TYPE: u32 = ENHANCED_PACKET_BLOCK;
+
+    fn from_slice<B: ByteOrder>(slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+        Self::from_slice_with::<B>(slice, Utf8Mode::Strict)
+    }
 
     fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
         let pad_len = (4 - (&self.data.len() % 4)) % 4;
@@ -92,19 +150,303 @@ impl<'a> PcapNgBlock<'a> for EnhancedPacketBlock<'a> {
         Ok(20 + &self.data.len() + pad_len + opt_len)
     }
 
-    fn into_block(self) -> Block<'a> {
-        Block::EnhancedPacket(self)
+    fn to_block(&self) -> Option<Block<'_>> {
+        Some(Block::EnhancedPacket(self.clone()))
+    }
+}
+
+impl<'a> EnhancedPacketBlock<'a> {
+    /// Creates a new [`EnhancedPacketBlockBuilder`] of a packet captured on the given interface.
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::time::{Duration, UNIX_EPOCH};
+    ///
+    /// use pcap_file::pcapng::blocks::enhanced_packet::EnhancedPacketBlock;
+    /// use pcap_file::pcapng::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceId};
+    ///
+    /// let interface = InterfaceDescriptionBlock::builder().tsresol_nanos().snaplen(4).build();
+    ///
+    /// let packet = EnhancedPacketBlock::builder(InterfaceId(0), &[1, 2, 3, 4, 5, 6])
+    ///     .time(UNIX_EPOCH + Duration::from_secs(1))
+    ///     .comment("truncated")
+    ///     .build(&interface)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(packet.timestamp.as_nanos(), 1_000_000_000);
+    /// assert_eq!(&packet.data[..], &[1, 2, 3, 4]);
+    /// assert_eq!(packet.original_len, 6);
+    /// ```
+    pub fn builder(interface_id: InterfaceId, data: &'a [u8]) -> EnhancedPacketBlockBuilder<'a> {
+        EnhancedPacketBlockBuilder {
+            interface_id,
+            since_epoch: Some(Duration::ZERO),
+            original_len: None,
+            data,
+            options: vec![],
+        }
+    }
+
+    /// Splits the packet data into the payload and the trailing Frame Check Sequence of `fcs_len` bytes.
+    ///
+    /// If the packet was truncated, the returned FCS contains only the captured part of it and can be empty.
+    pub fn split_fcs(&self, fcs_len: usize) -> (&[u8], &[u8]) {
+        let payload_len = self.payload_len(fcs_len);
+        self.data.split_at(payload_len)
+    }
+
+    /// Removes the trailing Frame Check Sequence of `fcs_len` bytes from the packet data.
+    ///
+    /// The `original_len` is adjusted accordingly.
+    pub fn strip_fcs(&mut self, fcs_len: usize) {
+        let payload_len = self.payload_len(fcs_len);
+
+        match &mut self.data {
+            Cow::Borrowed(data) => *data = &data[..payload_len],
+            Cow::Owned(data) => data.truncate(payload_len),
+        }
+
+        self.original_len = self.original_len.saturating_sub(fcs_len as u32);
+    }
+
+    /// Appends a Frame Check Sequence to the packet data.
+    ///
+    /// The `original_len` is adjusted accordingly.
+    pub fn append_fcs(&mut self, fcs: &[u8]) {
+        self.data.to_mut().extend_from_slice(fcs);
+        self.original_len = self.original_len.saturating_add(fcs.len() as u32);
+    }
+
+    /// Returns the timestamp of the packet as any [`Timestamp`], converted from the units of its interface.
+    pub fn timestamp_as<T: Timestamp>(&self, interface: &InterfaceDescriptionBlock) -> Option<T> {
+        interface.timestamp_as(self.timestamp.as_nanos() as u64)
+    }
+
+    /// Returns the timestamp of the packet as a `chrono::DateTime<Utc>`, converted from the units of its interface.
+    #[cfg(feature = "chrono")]
+    pub fn timestamp_utc(&self, interface: &InterfaceDescriptionBlock) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.timestamp_as(interface)
+    }
+
+    /// Returns the timestamp of the packet as a `time::OffsetDateTime`, converted from the units of its interface.
+    #[cfg(feature = "time")]
+    pub fn timestamp_offset_date_time(&self, interface: &InterfaceDescriptionBlock) -> Option<time::OffsetDateTime> {
+        self.timestamp_as(interface)
+    }
+
+    /// Sets the timestamp of the packet from any [`IntoTimestamp`], encoded in the units of its interface.
+    pub fn set_timestamp<T: IntoTimestamp>(&mut self, interface: &InterfaceDescriptionBlock, time: T) -> Result<(), PcapError> {
+        let timestamp = interface
+            .time_to_timestamp(time)
+            .ok_or(PcapError::InvalidField("EnhancedPacketBlock: timestamp not representable by the interface"))?;
+
+        self.timestamp = Duration::from_nanos(timestamp);
+        Ok(())
+    }
+
+    /// Returns the value of the `epb_flags` option, if any.
+    pub fn flags(&self) -> Option<PacketFlags> {
+        self.options.iter().find_map(|opt| match opt {
+            EnhancedPacketOption::Flags(flags) => Some(*flags),
+            _ => None,
+        })
+    }
+
+    /// Returns the value of the `epb_packetid` option, if any.
+    pub fn packet_id(&self) -> Option<u64> {
+        self.options.iter().find_map(|opt| match opt {
+            EnhancedPacketOption::PacketId(id) => Some(*id),
+            _ => None,
+        })
+    }
+
+    /// Sets the `epb_packetid` option, replacing any existing one.
+    pub fn set_packet_id(&mut self, packet_id: u64) {
+        self.options.retain(|opt| !matches!(opt, EnhancedPacketOption::PacketId(_)));
+        self.options.push(EnhancedPacketOption::PacketId(packet_id));
+    }
+
+    /// Sets the `epb_packetid` option and returns the block.
+    pub fn with_packet_id(mut self, packet_id: u64) -> Self {
+        self.set_packet_id(packet_id);
+        self
+    }
+
+    /// Returns the value of the `epb_queue` option, if any.
+    pub fn queue(&self) -> Option<u32> {
+        self.options.iter().find_map(|opt| match opt {
+            EnhancedPacketOption::Queue(queue) => Some(*queue),
+            _ => None,
+        })
+    }
+
+    /// Sets the `epb_queue` option, replacing any existing one.
+    pub fn set_queue(&mut self, queue: u32) {
+        self.options.retain(|opt| !matches!(opt, EnhancedPacketOption::Queue(_)));
+        self.options.push(EnhancedPacketOption::Queue(queue));
+    }
+
+    /// Sets the `epb_queue` option and returns the block.
+    pub fn with_queue(mut self, queue: u32) -> Self {
+        self.set_queue(queue);
+        self
+    }
+
+    /// Returns an iterator over the `epb_hash` options of the packet, as pairs of algorithm and hash value.
+    pub fn hashes(&self) -> impl Iterator<Item = (HashAlgorithm, &[u8])> {
+        self.options.iter().filter_map(|opt| match opt {
+            EnhancedPacketOption::Hash(hash) if !hash.is_empty() => Some((HashAlgorithm::from(hash[0]), &hash[1..])),
+            _ => None,
+        })
+    }
+
+    /// Computes the hash of the packet data with the given algorithm and appends it as an `epb_hash` option.
+    ///
+    /// Only [`HashAlgorithm::Crc32`], [`HashAlgorithm::Md5`] and [`HashAlgorithm::Sha1`] are supported.
+    #[cfg(feature = "hash")]
+    pub fn add_hash(&mut self, algorithm: HashAlgorithm) -> Result<(), PcapError> {
+        let hash = algorithm
+            .compute(&self.data)
+            .ok_or(PcapError::InvalidField("EnhancedPacketBlock: unsupported hash algorithm"))?;
+
+        let mut value = Vec::with_capacity(1 + hash.len());
+        value.push(algorithm.into());
+        value.extend_from_slice(&hash);
+        self.options.push(EnhancedPacketOption::Hash(Cow::Owned(value)));
+
+        Ok(())
+    }
+
+    /// Verifies the `epb_hash` options of the packet against its data.
+    ///
+    /// Returns the hashes which don't match. Hashes using an unsupported algorithm are ignored.
+    #[cfg(feature = "hash")]
+    pub fn verify_hashes(&self) -> Vec<HashMismatch> {
+        self.hashes()
+            .filter_map(|(algorithm, expected)| {
+                let actual = algorithm.compute(&self.data)?;
+                if actual == expected {
+                    return None;
+                }
+
+                Some(HashMismatch { algorithm, expected: expected.to_vec(), actual })
+            })
+            .collect()
+    }
+
+    /// Length of the captured data which is not part of the Frame Check Sequence.
+    fn payload_len(&self, fcs_len: usize) -> usize {
+        let wire_payload_len = (self.original_len as usize).saturating_sub(fcs_len);
+        self.data.len().min(wire_payload_len)
+    }
+}
+
+/// Builder of an [`EnhancedPacketBlock`].
+///
+/// The timestamp is given as a time since the Unix epoch and encoded according to the interface,
+/// the data is truncated to the snaplen of the interface and the `original_len` is computed from the data.
+#[derive(Clone, Debug)]
+pub struct EnhancedPacketBlockBuilder<'a> {
+    interface_id: InterfaceId,
+    /// `None` if the time is before the Unix epoch.
+    since_epoch: Option<Duration>,
+    original_len: Option<u32>,
+    data: &'a [u8],
+    options: Vec<EnhancedPacketOption<'a>>,
+}
+
+impl<'a> EnhancedPacketBlockBuilder<'a> {
+    /// Sets the capture time of the packet as a duration since the Unix epoch.
+    pub fn timestamp(mut self, since_epoch: Duration) -> Self {
+        self.since_epoch = Some(since_epoch);
+        self
+    }
+
+    /// Sets the capture time of the packet, from any [`IntoTimestamp`] like a `SystemTime`.
+    pub fn time<T: IntoTimestamp>(mut self, time: T) -> Self {
+        self.since_epoch = time.since_epoch();
+        self
+    }
+
+    /// Sets the length of the packet on the wire, when the given data was already truncated.
+    ///
+    /// Defaults to the length of the data.
+    pub fn original_len(mut self, original_len: u32) -> Self {
+        self.original_len = Some(original_len);
+        self
+    }
+
+    /// Adds a comment.
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.options.push(EnhancedPacketOption::Comment(Cow::Owned(comment.into())));
+        self
+    }
+
+    /// Sets the `epb_flags` option, replacing any existing one.
+    pub fn flags(mut self, flags: PacketFlags) -> Self {
+        self.options.retain(|opt| !matches!(opt, EnhancedPacketOption::Flags(_)));
+        self.options.push(EnhancedPacketOption::Flags(flags));
+        self
+    }
+
+    /// Adds an `epb_hash` option with an already computed hash of the data.
+    pub fn hash(mut self, algorithm: HashAlgorithm, hash: &[u8]) -> Self {
+        let mut value = Vec::with_capacity(1 + hash.len());
+        value.push(algorithm.into());
+        value.extend_from_slice(hash);
+        self.options.push(EnhancedPacketOption::Hash(Cow::Owned(value)));
+        self
+    }
+
+    /// Sets the `epb_dropcount` option, replacing any existing one.
+    pub fn drop_count(mut self, drop_count: u64) -> Self {
+        self.options.retain(|opt| !matches!(opt, EnhancedPacketOption::DropCount(_)));
+        self.options.push(EnhancedPacketOption::DropCount(drop_count));
+        self
+    }
+
+    /// Adds an arbitrary option.
+    pub fn option(mut self, option: EnhancedPacketOption<'a>) -> Self {
+        self.options.push(option);
+        self
+    }
+
+    /// Builds the [`EnhancedPacketBlock`], using the [`InterfaceDescriptionBlock`] of its interface
+    /// to encode the timestamp and truncate the data.
+    pub fn build(self, interface: &InterfaceDescriptionBlock) -> Result<EnhancedPacketBlock<'a>, PcapError> {
+        let since_epoch = self
+            .since_epoch
+            .ok_or(PcapError::InvalidField("EnhancedPacketBlock: timestamp before the Unix epoch"))?;
+        let timestamp = interface
+            .duration_to_timestamp(since_epoch)
+            .ok_or(PcapError::InvalidField("EnhancedPacketBlock: timestamp not representable by the interface"))?;
+
+        let captured_len = SnapLen(interface.snaplen).captured_len(self.data.len());
+
+        let original_len = self.original_len.unwrap_or(self.data.len() as u32);
+        if (original_len as usize) < captured_len {
+            return Err(PcapError::InvalidField("EnhancedPacketBlock: original_len < captured_len"));
+        }
+
+        Ok(EnhancedPacketBlock {
+            interface_id: self.interface_id.0,
+            timestamp: Duration::from_nanos(timestamp),
+            original_len,
+            data: Cow::Borrowed(&self.data[..captured_len]),
+            options: self.options,
+        })
     }
 }
 
 /// The Enhanced Packet Block (EPB) options
 #[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum EnhancedPacketOption<'a> {
     /// Comment associated with the current block
     Comment(Cow<'a, str>),
 
     /// 32-bit flags word containing link-layer information.
-    Flags(u32),
+    Flags(PacketFlags),
 
     /// Contains a hash of the packet.
     Hash(Cow<'a, [u8]>),
@@ -115,6 +457,15 @@ pub enum EnhancedPacketOption<'a> {
     /// and the start of the capture process.
     DropCount(u64),
 
+    /// 64-bit unsigned integer identifying the packet, unique across all the interfaces of the capture.
+    PacketId(u64),
+
+    /// 32-bit unsigned integer identifying the queue of the interface on which the packet was received.
+    Queue(u32),
+
+    /// Verdict of the packet, as decided by the hardware or a filtering program.
+    Verdict(PacketVerdict<'a>),
+
     /// Custom option containing binary octets in the Custom Data portion
     CustomBinary(CustomBinaryOption<'a>),
 
@@ -125,15 +476,37 @@ pub enum EnhancedPacketOption<'a> {
     Unknown(UnknownOption<'a>),
 }
 
+impl<'a> EnhancedPacketOption<'a> {
+    /// Returns the value of a string option, custom ones included, see [`Utf8Mode`].
+    pub fn as_str(&self) -> Option<Cow<'_, str>> {
+        match self {
+            EnhancedPacketOption::Comment(value) => Some(Cow::Borrowed(value)),
+            EnhancedPacketOption::CustomUtf8(custom) => Some(Cow::Borrowed(&custom.value)),
+            EnhancedPacketOption::Unknown(unknown) => unknown.lossy_str(),
+            _ => None,
+        }
+    }
+
+    /// Returns the exact bytes of a string option, custom ones included, see [`Utf8Mode`].
+    pub fn as_str_raw(&self) -> Option<&[u8]> {
+        match self {
+            EnhancedPacketOption::Comment(value) => Some(value.as_bytes()),
+            EnhancedPacketOption::CustomUtf8(custom) => Some(custom.value.as_bytes()),
+            EnhancedPacketOption::Unknown(unknown) => unknown.str_raw(),
+            _ => None,
+        }
+    }
+}
+
 impl<'a> PcapNgOption<'a> for EnhancedPacketOption<'a> {
     fn from_slice<B: ByteOrder>(code: u16, length: u16, mut slice: &'a [u8]) -> Result<Self, PcapError> {
         let opt = match code {
-            1 => EnhancedPacketOption::Comment(Cow::Borrowed(std::str::from_utf8(slice)?)),
+            1 => EnhancedPacketOption::Comment(utf8_str(slice)?),
             2 => {
                 if slice.len() != 4 {
                     return Err(PcapError::InvalidField("EnhancedPacketOption: Flags length != 4"));
                 }
-                EnhancedPacketOption::Flags(slice.read_u32::<B>().map_err(|_| PcapError::IncompleteBuffer)?)
+                EnhancedPacketOption::Flags(slice.read_u32::<B>().map_err(|_| PcapError::IncompleteBuffer)?.into())
             },
             3 => EnhancedPacketOption::Hash(Cow::Borrowed(slice)),
             4 => {
@@ -142,6 +515,19 @@ impl<'a> PcapNgOption<'a> for EnhancedPacketOption<'a> {
                 }
                 EnhancedPacketOption::DropCount(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?)
             },
+            5 => {
+                if slice.len() != 8 {
+                    return Err(PcapError::InvalidField("EnhancedPacketOption: PacketId length != 8"));
+                }
+                EnhancedPacketOption::PacketId(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?)
+            },
+            6 => {
+                if slice.len() != 4 {
+                    return Err(PcapError::InvalidField("EnhancedPacketOption: Queue length != 4"));
+                }
+                EnhancedPacketOption::Queue(slice.read_u32::<B>().map_err(|_| PcapError::IncompleteBuffer)?)
+            },
+            7 => EnhancedPacketOption::Verdict(PacketVerdict::from_slice::<B>(slice)?),
 
             2988 | 19372 => EnhancedPacketOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice)?),
             2989 | 19373 => EnhancedPacketOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice)?),
@@ -152,15 +538,407 @@ impl<'a> PcapNgOption<'a> for EnhancedPacketOption<'a> {
         Ok(opt)
     }
 
+    fn unknown(option: UnknownOption<'a>) -> Self {
+        EnhancedPacketOption::Unknown(option)
+    }
+
     fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
         match self {
             EnhancedPacketOption::Comment(a) => a.write_opt_to::<B, W>(1, writer),
-            EnhancedPacketOption::Flags(a) => a.write_opt_to::<B, W>(2, writer),
+            EnhancedPacketOption::Flags(a) => a.bits().write_opt_to::<B, W>(2, writer),
             EnhancedPacketOption::Hash(a) => a.write_opt_to::<B, W>(3, writer),
             EnhancedPacketOption::DropCount(a) => a.write_opt_to::<B, W>(4, writer),
+            EnhancedPacketOption::PacketId(a) => a.write_opt_to::<B, W>(5, writer),
+            EnhancedPacketOption::Queue(a) => a.write_opt_to::<B, W>(6, writer),
+            EnhancedPacketOption::Verdict(a) => a.write_opt_to::<B, W>(7, writer),
             EnhancedPacketOption::CustomBinary(a) => a.write_opt_to::<B, W>(a.code, writer),
             EnhancedPacketOption::CustomUtf8(a) => a.write_opt_to::<B, W>(a.code, writer),
             EnhancedPacketOption::Unknown(a) => a.write_opt_to::<B, W>(a.code, writer),
         }
     }
+
+    fn code(&self) -> u16 {
+        match self {
+            EnhancedPacketOption::Comment(_) => 1,
+            EnhancedPacketOption::Flags(_) => 2,
+            EnhancedPacketOption::Hash(_) => 3,
+            EnhancedPacketOption::DropCount(_) => 4,
+            EnhancedPacketOption::PacketId(_) => 5,
+            EnhancedPacketOption::Queue(_) => 6,
+            EnhancedPacketOption::Verdict(_) => 7,
+            EnhancedPacketOption::CustomBinary(a) => a.code,
+            EnhancedPacketOption::CustomUtf8(a) => a.code,
+            EnhancedPacketOption::Unknown(a) => a.code,
+        }
+    }
+}
+
+
+/// Verdict stored in the `epb_verdict` option.
+#[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+pub enum PacketVerdict<'a> {
+    /// Verdict of the hardware, in a format specific to the hardware.
+    Hardware(Cow<'a, [u8]>),
+
+    /// Return value of a Linux eBPF TC program.
+    LinuxEbpfTc(u64),
+
+    /// Return value of a Linux eBPF XDP program.
+    LinuxEbpfXdp(u64),
+
+    /// Unknown verdict type
+    Unknown {
+        /// Type of the verdict
+        verdict_type: u8,
+        /// Value of the verdict
+        value: Cow<'a, [u8]>,
+    },
+}
+
+impl<'a> PacketVerdict<'a> {
+    fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> Result<Self, PcapError> {
+        let verdict_type = slice.read_u8().map_err(|_| PcapError::InvalidField("EnhancedPacketOption: Verdict length < 1"))?;
+
+        let verdict = match verdict_type {
+            0 => PacketVerdict::Hardware(Cow::Borrowed(slice)),
+            1 | 2 => {
+                if slice.len() != 8 {
+                    return Err(PcapError::InvalidField("EnhancedPacketOption: eBPF Verdict length != 9"));
+                }
+
+                let value = slice.read_u64::<B>().unwrap();
+                if verdict_type == 1 {
+                    PacketVerdict::LinuxEbpfTc(value)
+                }
+                else {
+                    PacketVerdict::LinuxEbpfXdp(value)
+                }
+            },
+            _ => PacketVerdict::Unknown { verdict_type, value: Cow::Borrowed(slice) },
+        };
+
+        Ok(verdict)
+    }
+}
+
+impl<'a> WriteOptTo for PacketVerdict<'a> {
+    fn write_opt_to<B: ByteOrder, W: Write>(&self, code: u16, writer: &mut W) -> IoResult<usize> {
+        let (verdict_type, len) = match self {
+            PacketVerdict::Hardware(value) => (0, value.len()),
+            PacketVerdict::LinuxEbpfTc(_) => (1, 8),
+            PacketVerdict::LinuxEbpfXdp(_) => (2, 8),
+            PacketVerdict::Unknown { verdict_type, value } => (*verdict_type, value.len()),
+        };
+
+        let len = 1 + len;
+        let pad_len = (4 - len % 4) % 4;
+
+        writer.write_u16::<B>(code)?;
+        writer.write_u16::<B>(len as u16)?;
+        writer.write_u8(verdict_type)?;
+
+        match self {
+            PacketVerdict::Hardware(value) => writer.write_all(value)?,
+            PacketVerdict::LinuxEbpfTc(value) => writer.write_u64::<B>(*value)?,
+            PacketVerdict::LinuxEbpfXdp(value) => writer.write_u64::<B>(*value)?,
+            PacketVerdict::Unknown { value, .. } => writer.write_all(value)?,
+        }
+
+        writer.write_all(&[0_u8; 3][..pad_len])?;
+
+        Ok(4 + len + pad_len)
+    }
+}
+
+/// The 32-bit flags word of the `epb_flags` option.
+///
+/// Bits 0-1 hold the [`PacketDirection`], bits 2-4 the [`ReceptionType`], bits 5-8 the FCS length in bytes
+/// and bits 16-31 the link-layer dependent errors.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct PacketFlags(u32);
+
+impl PacketFlags {
+    const DIRECTION_MASK: u32 = 0b11;
+    const RECEPTION_TYPE_SHIFT: u32 = 2;
+    const RECEPTION_TYPE_MASK: u32 = 0b111 << Self::RECEPTION_TYPE_SHIFT;
+    const FCS_LEN_SHIFT: u32 = 5;
+    const FCS_LEN_MASK: u32 = 0b1111 << Self::FCS_LEN_SHIFT;
+    const LINK_LAYER_ERRORS_SHIFT: u32 = 16;
+
+    /// Creates a new [`PacketFlags`] from its raw 32-bit value.
+    pub fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the raw 32-bit value of the flags.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns the direction of the packet.
+    pub fn direction(&self) -> PacketDirection {
+        match self.0 & Self::DIRECTION_MASK {
+            0 => PacketDirection::NotAvailable,
+            1 => PacketDirection::Inbound,
+            2 => PacketDirection::Outbound,
+            _ => PacketDirection::Reserved,
+        }
+    }
+
+    /// Sets the direction of the packet.
+    pub fn set_direction(&mut self, direction: PacketDirection) {
+        let bits = match direction {
+            PacketDirection::NotAvailable => 0,
+            PacketDirection::Inbound => 1,
+            PacketDirection::Outbound => 2,
+            PacketDirection::Reserved => 3,
+        };
+
+        self.0 = (self.0 & !Self::DIRECTION_MASK) | bits;
+    }
+
+    /// Returns the reception type of the packet.
+    pub fn reception_type(&self) -> ReceptionType {
+        match (self.0 & Self::RECEPTION_TYPE_MASK) >> Self::RECEPTION_TYPE_SHIFT {
+            0 => ReceptionType::NotSpecified,
+            1 => ReceptionType::Unicast,
+            2 => ReceptionType::Multicast,
+            3 => ReceptionType::Broadcast,
+            4 => ReceptionType::Promiscuous,
+            n => ReceptionType::Reserved(ReservedReceptionType(n as u8)),
+        }
+    }
+
+    /// Sets the reception type of the packet.
+    pub fn set_reception_type(&mut self, reception_type: ReceptionType) {
+        let bits = match reception_type {
+            ReceptionType::NotSpecified => 0,
+            ReceptionType::Unicast => 1,
+            ReceptionType::Multicast => 2,
+            ReceptionType::Broadcast => 3,
+            ReceptionType::Promiscuous => 4,
+            ReceptionType::Reserved(n) => n.0 as u32,
+        };
+
+        self.0 = (self.0 & !Self::RECEPTION_TYPE_MASK) | ((bits << Self::RECEPTION_TYPE_SHIFT) & Self::RECEPTION_TYPE_MASK);
+    }
+
+    /// Returns the length in bytes of the Frame Check Sequence at the end of the packet.
+    ///
+    /// Returns `None` if the information is not available.
+    pub fn fcs_len(&self) -> Option<usize> {
+        match (self.0 & Self::FCS_LEN_MASK) >> Self::FCS_LEN_SHIFT {
+            0 => None,
+            n => Some(n as usize),
+        }
+    }
+
+    /// Sets the length in bytes of the Frame Check Sequence at the end of the packet.
+    ///
+    /// Only the 4 lower bits of `fcs_len` are kept, 0 meaning that the information is not available.
+    pub fn set_fcs_len(&mut self, fcs_len: u8) {
+        self.0 = (self.0 & !Self::FCS_LEN_MASK) | (((fcs_len as u32) << Self::FCS_LEN_SHIFT) & Self::FCS_LEN_MASK);
+    }
+
+    /// Returns the raw 16 bits of the link-layer dependent errors.
+    pub fn link_layer_errors(&self) -> u16 {
+        (self.0 >> Self::LINK_LAYER_ERRORS_SHIFT) as u16
+    }
+
+    /// Returns true if the given link-layer error is set.
+    pub fn has_error(&self, error: LinkLayerError) -> bool {
+        self.0 & error.mask() != 0
+    }
+
+    /// Sets or clears the given link-layer error.
+    pub fn set_error(&mut self, error: LinkLayerError, value: bool) {
+        if value {
+            self.0 |= error.mask();
+        }
+        else {
+            self.0 &= !error.mask();
+        }
+    }
+}
+
+impl From<u32> for PacketFlags {
+    fn from(bits: u32) -> Self {
+        Self(bits)
+    }
+}
+
+impl From<PacketFlags> for u32 {
+    fn from(flags: PacketFlags) -> Self {
+        flags.0
+    }
+}
+
+/// Direction of a packet, as stored in the `epb_flags` option.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum PacketDirection {
+    /// Information not available
+    #[default]
+    NotAvailable,
+    /// Inbound packet
+    Inbound,
+    /// Outbound packet
+    Outbound,
+    /// Reserved value
+    Reserved,
+}
+
+/// Reception type of a packet, as stored in the `epb_flags` option.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum ReceptionType {
+    /// Not specified
+    #[default]
+    NotSpecified,
+    /// Unicast packet
+    Unicast,
+    /// Multicast packet
+    Multicast,
+    /// Broadcast packet
+    Broadcast,
+    /// Packet received in promiscuous mode
+    Promiscuous,
+    /// Reserved value
+    Reserved(ReservedReceptionType),
+}
+
+/// Reserved value of a [`ReceptionType`], between 5 and 7.
+///
+/// The values below 5 are the named reception types, so they are refused to keep the flags round-tripping.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct ReservedReceptionType(u8);
+
+impl ReservedReceptionType {
+    /// Creates a reserved reception type, `None` if the value is a named reception type or doesn't fit in 3 bits.
+    pub fn new(value: u8) -> Option<Self> {
+        match value {
+            5..=7 => Some(Self(value)),
+            _ => None,
+        }
+    }
+
+    /// Returns the raw value.
+    pub fn get(&self) -> u8 {
+        self.0
+    }
+}
+
+/// Link-layer dependent errors of the `epb_flags` option.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum LinkLayerError {
+    /// CRC error
+    Crc,
+    /// Packet too long error
+    PacketTooLong,
+    /// Packet too short error
+    PacketTooShort,
+    /// Wrong Inter Frame Gap error
+    WrongInterFrameGap,
+    /// Unaligned frame error
+    UnalignedFrame,
+    /// Start Frame Delimiter error
+    StartFrameDelimiter,
+    /// Preamble error
+    Preamble,
+    /// Symbol error
+    Symbol,
+}
+
+impl LinkLayerError {
+    fn mask(self) -> u32 {
+        let bit = match self {
+            LinkLayerError::Crc => 24,
+            LinkLayerError::PacketTooLong => 25,
+            LinkLayerError::PacketTooShort => 26,
+            LinkLayerError::WrongInterFrameGap => 27,
+            LinkLayerError::UnalignedFrame => 28,
+            LinkLayerError::StartFrameDelimiter => 29,
+            LinkLayerError::Preamble => 30,
+            LinkLayerError::Symbol => 31,
+        };
+
+        1 << bit
+    }
+}
+
+
+/// Algorithm used to compute the value of an `epb_hash` option.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum HashAlgorithm {
+    /// 2's complement
+    TwosComplement,
+    /// XOR
+    Xor,
+    /// CRC32, stored in big endian
+    Crc32,
+    /// MD-5
+    Md5,
+    /// SHA-1
+    Sha1,
+    /// Toeplitz
+    Toeplitz,
+    /// Unknown algorithm
+    Unknown(u8),
+}
+
+impl HashAlgorithm {
+    /// Computes the hash of `data`.
+    ///
+    /// Returns `None` if the algorithm is not supported.
+    #[cfg(feature = "hash")]
+    pub fn compute(&self, data: &[u8]) -> Option<Vec<u8>> {
+        use md5::Digest;
+
+        match self {
+            HashAlgorithm::Crc32 => Some(crc32fast::hash(data).to_be_bytes().to_vec()),
+            HashAlgorithm::Md5 => Some(md5::Md5::digest(data).to_vec()),
+            HashAlgorithm::Sha1 => Some(sha1::Sha1::digest(data).to_vec()),
+            _ => None,
+        }
+    }
+}
+
+impl From<u8> for HashAlgorithm {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => HashAlgorithm::TwosComplement,
+            1 => HashAlgorithm::Xor,
+            2 => HashAlgorithm::Crc32,
+            3 => HashAlgorithm::Md5,
+            4 => HashAlgorithm::Sha1,
+            5 => HashAlgorithm::Toeplitz,
+            n => HashAlgorithm::Unknown(n),
+        }
+    }
+}
+
+impl From<HashAlgorithm> for u8 {
+    fn from(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::TwosComplement => 0,
+            HashAlgorithm::Xor => 1,
+            HashAlgorithm::Crc32 => 2,
+            HashAlgorithm::Md5 => 3,
+            HashAlgorithm::Sha1 => 4,
+            HashAlgorithm::Toeplitz => 5,
+            HashAlgorithm::Unknown(n) => n,
+        }
+    }
+}
+
+/// An `epb_hash` option which doesn't match the data of its packet.
+#[cfg(feature = "hash")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HashMismatch {
+    /// Algorithm of the hash
+    pub algorithm: HashAlgorithm,
+
+    /// Hash stored in the option
+    pub expected: Vec<u8>,
+
+    /// Hash computed from the packet data
+    pub actual: Vec<u8>,
 }
@@ -0,0 +1,203 @@
+//! Hone Project Connection Event Block.
+
+use std::borrow::Cow;
+use std::io::{Result as IoResult, Write};
+
+use byteorder_slice::byteorder::WriteBytesExt;
+use byteorder_slice::result::ReadSlice;
+use byteorder_slice::ByteOrder;
+use derive_into_owned::IntoOwned;
+
+use super::block_common::{Block, PcapNgBlock, HONE_CONNECTION_EVENT_BLOCK};
+use super::opt_common::{utf8_str, CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
+use crate::errors::PcapError;
+use crate::pcapng::Utf8Mode;
+
+
+/// The Connection Event Block of the Hone Project records the opening or the closing of a connection by a process.
+#[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+pub struct HoneConnectionEventBlock<'a> {
+    /// Identifier of the connection.
+    pub connection_id: u32,
+
+    /// Identifier of the process owning the connection.
+    pub process_id: u32,
+
+    /// Number of units of time that have elapsed since 1970-01-01 00:00:00 UTC, stored like the one of the Enhanced Packet Block.
+    pub timestamp: u64,
+
+    /// Options
+    pub options: Vec<HoneConnectionEventOption<'a>>,
+}
+
+impl<'a> HoneConnectionEventBlock<'a> {
+    /// Parses the block from a slice, the string options being parsed with the given [`Utf8Mode`]
+    pub(crate) fn from_slice_with<B: ByteOrder>(mut slice: &'a [u8], mode: Utf8Mode) -> Result<(&'a [u8], Self), PcapError> {
+        if slice.len() < 16 {
+            return Err(PcapError::InvalidField("HoneConnectionEventBlock: block length < 16"));
+        }
+
+        let connection_id = slice.read_u32::<B>().unwrap();
+        let process_id = slice.read_u32::<B>().unwrap();
+        let timestamp_high = slice.read_u32::<B>().unwrap() as u64;
+        let timestamp_low = slice.read_u32::<B>().unwrap() as u64;
+        let (slice, options) = HoneConnectionEventOption::opts_from_slice::<B>(slice, mode)?;
+
+        let block = HoneConnectionEventBlock { connection_id, process_id, timestamp: (timestamp_high << 32) + timestamp_low, options };
+
+        Ok((slice, block))
+    }
+}
+
+
+impl<'a> PcapNgBlock<'a> for HoneConnectionEventBlock<'a> {
+    const BLOCK_TYPE: u32 = HONE_CONNECTION_EVENT_BLOCK;
+
+    fn from_slice<B: ByteOrder>(slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+        Self::from_slice_with::<B>(slice, Utf8Mode::Strict)
+    }
+
+    fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
+        writer.write_u32::<B>(self.connection_id)?;
+        writer.write_u32::<B>(self.process_id)?;
+        writer.write_u32::<B>((self.timestamp >> 32) as u32)?;
+        writer.write_u32::<B>((self.timestamp & 0xFFFFFFFF) as u32)?;
+
+        let opt_len = HoneConnectionEventOption::write_opts_to::<B, _>(&self.options, writer)?;
+        Ok(16 + opt_len)
+    }
+
+    fn to_block(&self) -> Option<Block<'_>> {
+        Some(Block::HoneConnectionEvent(self.clone()))
+    }
+}
+
+impl<'a> HoneConnectionEventBlock<'a> {
+    /// Returns the value of the `ev_event` option, if any.
+    pub fn event(&self) -> Option<HoneEvent> {
+        self.options.iter().find_map(|opt| match opt {
+            HoneConnectionEventOption::Event(event) => Some(*event),
+            _ => None,
+        })
+    }
+}
+
+
+/// Event recorded by a Hone Project Connection Event Block.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum HoneEvent {
+    /// The connection was opened
+    Start,
+    /// The connection was closed
+    End,
+    /// Unknown event
+    Unknown(u32),
+}
+
+impl From<u32> for HoneEvent {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => HoneEvent::Start,
+            0xFFFFFFFF => HoneEvent::End,
+            n => HoneEvent::Unknown(n),
+        }
+    }
+}
+
+impl From<HoneEvent> for u32 {
+    fn from(event: HoneEvent) -> Self {
+        match event {
+            HoneEvent::Start => 0,
+            HoneEvent::End => 0xFFFFFFFF,
+            HoneEvent::Unknown(n) => n,
+        }
+    }
+}
+
+
+/// The Hone Project Connection Event Block options
+#[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum HoneConnectionEventOption<'a> {
+    /// Comment associated with the current block
+    Comment(Cow<'a, str>),
+
+    /// Whether the connection was opened or closed.
+    Event(HoneEvent),
+
+    /// Custom option containing binary octets in the Custom Data portion
+    CustomBinary(CustomBinaryOption<'a>),
+
+    /// Custom option containing a UTF-8 string in the Custom Data portion
+    CustomUtf8(CustomUtf8Option<'a>),
+
+    /// Unknown option
+    Unknown(UnknownOption<'a>),
+}
+
+impl<'a> HoneConnectionEventOption<'a> {
+    /// Returns the value of a string option, custom ones included, see [`Utf8Mode`].
+    pub fn as_str(&self) -> Option<Cow<'_, str>> {
+        match self {
+            HoneConnectionEventOption::Comment(value) => Some(Cow::Borrowed(value)),
+            HoneConnectionEventOption::CustomUtf8(custom) => Some(Cow::Borrowed(&custom.value)),
+            HoneConnectionEventOption::Unknown(unknown) => unknown.lossy_str(),
+            _ => None,
+        }
+    }
+
+    /// Returns the exact bytes of a string option, custom ones included, see [`Utf8Mode`].
+    pub fn as_str_raw(&self) -> Option<&[u8]> {
+        match self {
+            HoneConnectionEventOption::Comment(value) => Some(value.as_bytes()),
+            HoneConnectionEventOption::CustomUtf8(custom) => Some(custom.value.as_bytes()),
+            HoneConnectionEventOption::Unknown(unknown) => unknown.str_raw(),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> PcapNgOption<'a> for HoneConnectionEventOption<'a> {
+    fn from_slice<B: ByteOrder>(code: u16, length: u16, mut slice: &'a [u8]) -> Result<Self, PcapError> {
+        let opt = match code {
+            1 => HoneConnectionEventOption::Comment(utf8_str(slice)?),
+            2 => {
+                if slice.len() != 4 {
+                    return Err(PcapError::InvalidField("HoneConnectionEventOption: Event length != 4"));
+                }
+                HoneConnectionEventOption::Event(slice.read_u32::<B>().map_err(|_| PcapError::IncompleteBuffer)?.into())
+            },
+
+            2988 | 19372 => HoneConnectionEventOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice)?),
+            2989 | 19373 => HoneConnectionEventOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice)?),
+
+            _ => HoneConnectionEventOption::Unknown(UnknownOption::new(code, length, slice)),
+        };
+
+        Ok(opt)
+    }
+
+    fn unknown(option: UnknownOption<'a>) -> Self {
+        HoneConnectionEventOption::Unknown(option)
+    }
+
+    fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
+        match self {
+            HoneConnectionEventOption::Comment(a) => a.write_opt_to::<B, W>(1, writer),
+            HoneConnectionEventOption::Event(a) => u32::from(*a).write_opt_to::<B, W>(2, writer),
+            HoneConnectionEventOption::CustomBinary(a) => a.write_opt_to::<B, W>(a.code, writer),
+            HoneConnectionEventOption::CustomUtf8(a) => a.write_opt_to::<B, W>(a.code, writer),
+            HoneConnectionEventOption::Unknown(a) => a.write_opt_to::<B, W>(a.code, writer),
+        }
+    }
+
+    fn code(&self) -> u16 {
+        match self {
+            HoneConnectionEventOption::Comment(_) => 1,
+            HoneConnectionEventOption::Event(_) => 2,
+            HoneConnectionEventOption::CustomBinary(a) => a.code,
+            HoneConnectionEventOption::CustomUtf8(a) => a.code,
+            HoneConnectionEventOption::Unknown(a) => a.code,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Hone Project Machine Info Block.
+
+use std::borrow::Cow;
+use std::io::{Result as IoResult, Write};
+
+use byteorder_slice::ByteOrder;
+use derive_into_owned::IntoOwned;
+
+use super::block_common::{Block, PcapNgBlock, HONE_MACHINE_INFO_BLOCK};
+use super::opt_common::{utf8_str, CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
+use crate::errors::PcapError;
+use crate::pcapng::Utf8Mode;
+
+
+/// The Machine Info Block of the Hone Project describes the host on which the capture was made.
+///
+/// The block only holds options: the ones this crate doesn't model are kept as [`HoneMachineInfoOption::Unknown`],
+/// so the block is written back unchanged.
+#[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+pub struct HoneMachineInfoBlock<'a> {
+    /// Options
+    pub options: Vec<HoneMachineInfoOption<'a>>,
+}
+
+impl<'a> HoneMachineInfoBlock<'a> {
+    /// Parses the block from a slice, the string options being parsed with the given [`Utf8Mode`]
+    pub(crate) fn from_slice_with<B: ByteOrder>(slice: &'a [u8], mode: Utf8Mode) -> Result<(&'a [u8], Self), PcapError> {
+        let (slice, options) = HoneMachineInfoOption::opts_from_slice::<B>(slice, mode)?;
+
+        Ok((slice, HoneMachineInfoBlock { options }))
+    }
+}
+
+
+impl<'a> PcapNgBlock<'a> for HoneMachineInfoBlock<'a> {
+    const BLOCK_TYPE: u32 = HONE_MACHINE_INFO_BLOCK;
+
+    fn from_slice<B: ByteOrder>(slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+        Self::from_slice_with::<B>(slice, Utf8Mode::Strict)
+    }
+
+    fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
+        HoneMachineInfoOption::write_opts_to::<B, _>(&self.options, writer)
+    }
+
+    fn to_block(&self) -> Option<Block<'_>> {
+        Some(Block::HoneMachineInfo(self.clone()))
+    }
+}
+
+
+/// The Hone Project Machine Info Block options
+#[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum HoneMachineInfoOption<'a> {
+    /// Comment associated with the current block
+    Comment(Cow<'a, str>),
+
+    /// Custom option containing binary octets in the Custom Data portion
+    CustomBinary(CustomBinaryOption<'a>),
+
+    /// Custom option containing a UTF-8 string in the Custom Data portion
+    CustomUtf8(CustomUtf8Option<'a>),
+
+    /// Unknown option
+    Unknown(UnknownOption<'a>),
+}
+
+impl<'a> HoneMachineInfoOption<'a> {
+    /// Returns the value of a string option, custom ones included, see [`Utf8Mode`].
+    pub fn as_str(&self) -> Option<Cow<'_, str>> {
+        match self {
+            HoneMachineInfoOption::Comment(value) => Some(Cow::Borrowed(value)),
+            HoneMachineInfoOption::CustomUtf8(custom) => Some(Cow::Borrowed(&custom.value)),
+            HoneMachineInfoOption::Unknown(unknown) => unknown.lossy_str(),
+            _ => None,
+        }
+    }
+
+    /// Returns the exact bytes of a string option, custom ones included, see [`Utf8Mode`].
+    pub fn as_str_raw(&self) -> Option<&[u8]> {
+        match self {
+            HoneMachineInfoOption::Comment(value) => Some(value.as_bytes()),
+            HoneMachineInfoOption::CustomUtf8(custom) => Some(custom.value.as_bytes()),
+            HoneMachineInfoOption::Unknown(unknown) => unknown.str_raw(),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> PcapNgOption<'a> for HoneMachineInfoOption<'a> {
+    fn from_slice<B: ByteOrder>(code: u16, length: u16, slice: &'a [u8]) -> Result<Self, PcapError> {
+        let opt = match code {
+            1 => HoneMachineInfoOption::Comment(utf8_str(slice)?),
+
+            2988 | 19372 => HoneMachineInfoOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice)?),
+            2989 | 19373 => HoneMachineInfoOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice)?),
+
+            _ => HoneMachineInfoOption::Unknown(UnknownOption::new(code, length, slice)),
+        };
+
+        Ok(opt)
+    }
+
+    fn unknown(option: UnknownOption<'a>) -> Self {
+        HoneMachineInfoOption::Unknown(option)
+    }
+
+    fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
+        match self {
+            HoneMachineInfoOption::Comment(a) => a.write_opt_to::<B, W>(1, writer),
+            HoneMachineInfoOption::CustomBinary(a) => a.write_opt_to::<B, W>(a.code, writer),
+            HoneMachineInfoOption::CustomUtf8(a) => a.write_opt_to::<B, W>(a.code, writer),
+            HoneMachineInfoOption::Unknown(a) => a.write_opt_to::<B, W>(a.code, writer),
+        }
+    }
+
+    fn code(&self) -> u16 {
+        match self {
+            HoneMachineInfoOption::Comment(_) => 1,
+            HoneMachineInfoOption::CustomBinary(a) => a.code,
+            HoneMachineInfoOption::CustomUtf8(a) => a.code,
+            HoneMachineInfoOption::Unknown(a) => a.code,
+        }
+    }
+}
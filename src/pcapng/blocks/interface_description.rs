@@ -3,16 +3,20 @@
 //! Interface Description Block (IDB).
 
 use std::borrow::Cow;
-use std::io::{Result as IoResult, Write};
+use std::io::{Error as IoError, ErrorKind, Result as IoResult, Write};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
 
 use byteorder_slice::byteorder::WriteBytesExt;
 use byteorder_slice::result::ReadSlice;
-use byteorder_slice::ByteOrder;
+use byteorder_slice::{BigEndian, ByteOrder};
 use derive_into_owned::IntoOwned;
 
-use super::block_common::{Block, PcapNgBlock};
-use super::opt_common::{CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
+use super::block_common::{Block, PcapNgBlock, INTERFACE_DESCRIPTION_BLOCK};
+use super::opt_common::{utf8_str, CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
 use crate::errors::PcapError;
+use crate::pcapng::Utf8Mode;
+use crate::timestamp::{IntoTimestamp, Timestamp};
 use crate::DataLink;
 
 
@@ -36,8 +40,9 @@ pub struct InterfaceDescriptionBlock<'a> {
     pub options: Vec<InterfaceDescriptionOption<'a>>,
 }
 
-impl<'a> PcapNgBlock<'a> for InterfaceDescriptionBlock<'a> {
-    fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+impl<'a> InterfaceDescriptionBlock<'a> {
+    /// Parses the block from a slice, the string options being parsed with the given [`Utf8Mode`]
+    pub(crate) fn from_slice_with<B: ByteOrder>(mut slice: &'a [u8], mode: Utf8Mode) -> Result<(&'a [u8], Self), PcapError> {
         if slice.len() < 8 {
             return Err(PcapError::InvalidField("InterfaceDescriptionBlock: block length < 8"));
         }
@@ -50,15 +55,26 @@ impl<'a> PcapNgBlock<'a> for InterfaceDescriptionBlock<'a> {
         }
 
         let snaplen = slice.read_u32::<B>().unwrap();
-        let (slice, options) = InterfaceDescriptionOption::opts_from_slice::<B>(slice)?;
+        let (slice, options) = InterfaceDescriptionOption::opts_from_slice::<B>(slice, mode)?;
 
         let block = InterfaceDescriptionBlock { linktype, snaplen, options };
 
         Ok((slice, block))
     }
+}
+
+
+impl<'a> PcapNgBlock<'a> for InterfaceDescriptionBlock<'a> {
+    const BLOCK_TYPE: u32 = INTERFACE_DESCRIPTION_BLOCK;
+
+    fn from_slice<B: ByteOrder>(slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+        Self::from_slice_with::<B>(slice, Utf8Mode::Strict)
+    }
 
     fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
-        writer.write_u16::<B>(u32::from(self.linktype) as u16)?;
+        let linktype = u16::try_from(u32::from(self.linktype))
+            .map_err(|_| IoError::new(ErrorKind::InvalidInput, "InterfaceDescriptionBlock: linktype > u16::MAX"))?;
+        writer.write_u16::<B>(linktype)?;
         writer.write_u16::<B>(0)?;
         writer.write_u32::<B>(self.snaplen)?;
 
@@ -66,8 +82,26 @@ impl<'a> PcapNgBlock<'a> for InterfaceDescriptionBlock<'a> {
         Ok(8 + opt_len)
     }
 
-    fn into_block(self) -> Block<'a> {
-        Block::InterfaceDescription(self)
+    fn to_block(&self) -> Option<Block<'_>> {
+        Some(Block::InterfaceDescription(self.clone()))
+    }
+}
+
+/// Index of an [`InterfaceDescriptionBlock`] within its section.
+///
+/// Interfaces are numbered in the order in which their blocks appear, starting at 0.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct InterfaceId(pub u32);
+
+impl From<u32> for InterfaceId {
+    fn from(id: u32) -> Self {
+        InterfaceId(id)
+    }
+}
+
+impl From<InterfaceId> for u32 {
+    fn from(id: InterfaceId) -> Self {
+        id.0
     }
 }
 
@@ -76,10 +110,274 @@ impl InterfaceDescriptionBlock<'static> {
     pub fn new(linktype: DataLink, snaplen: u32) -> Self {
         Self { linktype, snaplen, options: vec![] }
     }
+
+    /// Creates a new [`InterfaceDescriptionBlockBuilder`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use pcap_file::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+    /// use pcap_file::DataLink;
+    ///
+    /// let interface = InterfaceDescriptionBlock::builder()
+    ///     .linktype(DataLink::ETHERNET)
+    ///     .name("eth0")
+    ///     .tsresol_nanos()
+    ///     .snaplen(0xFFFF)
+    ///     .build();
+    ///
+    /// assert_eq!(interface.ts_resol(), 9);
+    /// ```
+    pub fn builder() -> InterfaceDescriptionBlockBuilder {
+        InterfaceDescriptionBlockBuilder::default()
+    }
+}
+
+impl<'a> InterfaceDescriptionBlock<'a> {
+    /// Returns the length in bytes of the Frame Check Sequence at the end of the packets of this interface,
+    /// as declared by the `if_fcslen` option.
+    ///
+    /// The option itself is expressed in bits. Returns `None` if the option is absent.
+    pub fn fcs_len(&self) -> Option<usize> {
+        self.options.iter().find_map(|opt| match opt {
+            InterfaceDescriptionOption::IfFcsLen(bits) => Some(*bits as usize / 8),
+            _ => None,
+        })
+    }
+
+    /// Returns the value of the `if_tsresol` option, defaulting to 6 (microseconds) if absent.
+    pub fn ts_resol(&self) -> u8 {
+        self.options
+            .iter()
+            .find_map(|opt| match opt {
+                InterfaceDescriptionOption::IfTsResol(resol) => Some(*resol),
+                _ => None,
+            })
+            .unwrap_or(6)
+    }
+
+    /// Returns the value of the `if_tsoffset` option in seconds, defaulting to 0 if absent.
+    pub fn ts_offset(&self) -> u64 {
+        self.options
+            .iter()
+            .find_map(|opt| match opt {
+                InterfaceDescriptionOption::IfTsOffset(offset) => Some(*offset),
+                _ => None,
+            })
+            .unwrap_or(0)
+    }
+
+    /// Converts a duration since the Unix epoch into a timestamp expressed in the units of this interface,
+    /// taking the `if_tsresol` and `if_tsoffset` options into account.
+    ///
+    /// Returns `None` if the duration is before the `if_tsoffset` or doesn't fit in 64 bits.
+    pub fn duration_to_timestamp(&self, duration: Duration) -> Option<u64> {
+        let duration = duration.checked_sub(Duration::from_secs(self.ts_offset()))?;
+        let units_per_sec = self.units_per_sec()?;
+
+        let ticks = duration.as_secs() as u128 * units_per_sec + duration.subsec_nanos() as u128 * units_per_sec / 1_000_000_000;
+        u64::try_from(ticks).ok()
+    }
+
+    /// Converts any [`IntoTimestamp`] into a timestamp expressed in the units of this interface.
+    ///
+    /// Returns `None` if the time is before the Unix epoch or can't be represented by the interface.
+    pub fn time_to_timestamp<T: IntoTimestamp>(&self, time: T) -> Option<u64> {
+        self.duration_to_timestamp(time.since_epoch()?)
+    }
+
+    /// Converts a timestamp expressed in the units of this interface into a duration since the Unix epoch,
+    /// taking the `if_tsresol` and `if_tsoffset` options into account.
+    pub fn timestamp_to_duration(&self, timestamp: u64) -> Duration {
+        let duration = match self.units_per_sec() {
+            Some(units_per_sec) => {
+                let secs = timestamp as u128 / units_per_sec;
+                let nanos = (timestamp as u128 % units_per_sec) * 1_000_000_000 / units_per_sec;
+                Duration::new(secs as u64, nanos as u32)
+            },
+            None => Duration::ZERO,
+        };
+
+        duration.saturating_add(Duration::from_secs(self.ts_offset()))
+    }
+
+    /// Converts a timestamp expressed in the units of this interface into any [`Timestamp`], taking the `if_tsresol` and
+    /// `if_tsoffset` options into account.
+    ///
+    /// Returns `None` if the timestamp can't be represented, or if the resolution doesn't fit in 64 bits.
+    pub fn timestamp_as<T: Timestamp>(&self, timestamp: u64) -> Option<T> {
+        T::from_ticks(timestamp, self.units_per_sec()? as u64, self.ts_offset())
+    }
+
+    /// Number of timestamp units per second, `None` if it doesn't fit in 64 bits.
+    fn units_per_sec(&self) -> Option<u128> {
+        let resol = self.ts_resol();
+        let exp = (resol & 0x7F) as u32;
+
+        let units_per_sec = if resol & 0x80 == 0 { 10_u64.checked_pow(exp)? } else { 2_u64.checked_pow(exp)? };
+
+        Some(units_per_sec as u128)
+    }
+
+    /// Declares the length in bytes of the Frame Check Sequence at the end of the packets of this interface.
+    ///
+    /// Replaces any existing `if_fcslen` option.
+    ///
+    /// # Errors
+    /// The length in bits, stored by the option, doesn't fit in 8 bits.
+    pub fn set_fcs_len(&mut self, fcs_len: u8) -> Result<(), PcapError> {
+        let bits = fcs_len.checked_mul(8).ok_or(PcapError::InvalidField("InterfaceDescriptionBlock: if_fcslen > 255 bits"))?;
+        self.options.retain(|opt| !matches!(opt, InterfaceDescriptionOption::IfFcsLen(_)));
+        self.options.push(InterfaceDescriptionOption::IfFcsLen(bits));
+        Ok(())
+    }
+}
+
+/// Builder of an [`InterfaceDescriptionBlock`] with typed options.
+///
+/// Defaults to a [`DataLink::ETHERNET`] interface with no snaplen limit and no options.
+#[derive(Clone, Debug)]
+pub struct InterfaceDescriptionBlockBuilder {
+    block: InterfaceDescriptionBlock<'static>,
+}
+
+impl Default for InterfaceDescriptionBlockBuilder {
+    fn default() -> Self {
+        Self { block: InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0) }
+    }
+}
+
+impl InterfaceDescriptionBlockBuilder {
+    /// Sets the link type of the interface.
+    pub fn linktype(mut self, linktype: DataLink) -> Self {
+        self.block.linktype = linktype;
+        self
+    }
+
+    /// Sets the maximum number of bytes captured from each packet, 0 meaning no limit.
+    pub fn snaplen(mut self, snaplen: u32) -> Self {
+        self.block.snaplen = snaplen;
+        self
+    }
+
+    /// Adds a comment.
+    pub fn comment(self, comment: impl Into<String>) -> Self {
+        self.option(InterfaceDescriptionOption::Comment(Cow::Owned(comment.into())))
+    }
+
+    /// Sets the name of the device used to capture data (`if_name`).
+    pub fn name(self, name: impl Into<String>) -> Self {
+        self.option(InterfaceDescriptionOption::IfName(Cow::Owned(name.into())))
+    }
+
+    /// Sets the description of the device used to capture data (`if_description`).
+    pub fn description(self, description: impl Into<String>) -> Self {
+        self.option(InterfaceDescriptionOption::IfDescription(Cow::Owned(description.into())))
+    }
+
+    /// Adds an IPv4 address and its netmask (`if_IPv4addr`).
+    pub fn ipv4_addr(self, addr: Ipv4Addr, netmask: Ipv4Addr) -> Self {
+        let mut value = addr.octets().to_vec();
+        value.extend_from_slice(&netmask.octets());
+        self.option(InterfaceDescriptionOption::IfIpv4Addr(Cow::Owned(value)))
+    }
+
+    /// Adds an IPv6 address and its prefix length (`if_IPv6addr`).
+    pub fn ipv6_addr(self, addr: Ipv6Addr, prefix_len: u8) -> Self {
+        let mut value = addr.octets().to_vec();
+        value.push(prefix_len);
+        self.option(InterfaceDescriptionOption::IfIpv6Addr(Cow::Owned(value)))
+    }
+
+    /// Sets the MAC address of the interface (`if_MACaddr`).
+    pub fn mac_addr(self, addr: [u8; 6]) -> Self {
+        self.option(InterfaceDescriptionOption::IfMacAddr(Cow::Owned(addr.to_vec())))
+    }
+
+    /// Sets the speed of the interface in bits per second (`if_speed`).
+    pub fn speed(self, speed: u64) -> Self {
+        self.option(InterfaceDescriptionOption::IfSpeed(speed))
+    }
+
+    /// Sets the raw resolution of the timestamps (`if_tsresol`).
+    ///
+    /// If the most significant bit is 0, the resolution is 10^-value seconds, else 2^-(value & 0x7F) seconds.
+    pub fn tsresol(self, tsresol: u8) -> Self {
+        self.option(InterfaceDescriptionOption::IfTsResol(tsresol))
+    }
+
+    /// Sets the resolution of the timestamps to microseconds, which is also the default when the option is absent.
+    pub fn tsresol_micros(self) -> Self {
+        self.tsresol(6)
+    }
+
+    /// Sets the resolution of the timestamps to nanoseconds.
+    pub fn tsresol_nanos(self) -> Self {
+        self.tsresol(9)
+    }
+
+    /// Sets the offset in seconds added to the timestamps of the packets (`if_tsoffset`).
+    pub fn tsoffset(self, tsoffset: u64) -> Self {
+        self.option(InterfaceDescriptionOption::IfTsOffset(tsoffset))
+    }
+
+    /// Sets the libpcap filter string used to capture traffic (`if_filter`).
+    pub fn filter(self, filter: &str) -> Self {
+        let mut value = vec![0];
+        value.extend_from_slice(filter.as_bytes());
+        self.option(InterfaceDescriptionOption::IfFilter(Cow::Owned(value)))
+    }
+
+    /// Sets the name of the operating system of the machine of the interface (`if_os`).
+    pub fn os(self, os: impl Into<String>) -> Self {
+        self.option(InterfaceDescriptionOption::IfOs(Cow::Owned(os.into())))
+    }
+
+    /// Sets the length in bytes of the Frame Check Sequence of the packets (`if_fcslen`).
+    ///
+    /// # Errors
+    /// The length in bits, stored by the option, doesn't fit in 8 bits.
+    pub fn fcs_len(mut self, fcs_len: u8) -> Result<Self, PcapError> {
+        self.block.set_fcs_len(fcs_len)?;
+        Ok(self)
+    }
+
+    /// Sets the description of the interface hardware (`if_hardware`).
+    pub fn hardware(self, hardware: impl Into<String>) -> Self {
+        self.option(InterfaceDescriptionOption::IfHardware(Cow::Owned(hardware.into())))
+    }
+
+    /// Adds an arbitrary option.
+    ///
+    /// The options which can appear only once replace the existing one.
+    pub fn option(mut self, option: InterfaceDescriptionOption<'static>) -> Self {
+        let unique = !matches!(
+            option,
+            InterfaceDescriptionOption::Comment(_)
+                | InterfaceDescriptionOption::IfIpv4Addr(_)
+                | InterfaceDescriptionOption::IfIpv6Addr(_)
+                | InterfaceDescriptionOption::CustomBinary(_)
+                | InterfaceDescriptionOption::CustomUtf8(_)
+                | InterfaceDescriptionOption::Unknown(_)
+        );
+
+        if unique {
+            let discriminant = std::mem::discriminant(&option);
+            self.block.options.retain(|opt| std::mem::discriminant(opt) != discriminant);
+        }
+
+        self.block.options.push(option);
+        self
+    }
+
+    /// Builds the [`InterfaceDescriptionBlock`].
+    pub fn build(self) -> InterfaceDescriptionBlock<'static> {
+        self.block
+    }
 }
 
 /// The Interface Description Block (IDB) options
 #[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum InterfaceDescriptionOption<'a> {
     /// The opt_comment option is a UTF-8 string containing human-readable comment text
     /// that is associated to the current block.
@@ -101,6 +399,8 @@ pub enum InterfaceDescriptionOption<'a> {
     IfMacAddr(Cow<'a, [u8]>),
 
     /// The if_EUIaddr option is the Interface Hardware EUI address (64 bits), if available.
+    ///
+    /// The address is a byte string, read as a big-endian number whatever the endianness of the section.
     IfEuIAddr(u64),
 
     /// The if_speed option is a 64-bit number for the Interface speed (in bits per second).
@@ -140,12 +440,42 @@ pub enum InterfaceDescriptionOption<'a> {
     Unknown(UnknownOption<'a>),
 }
 
+impl<'a> InterfaceDescriptionOption<'a> {
+    /// Returns the value of a string option, custom ones included, see [`Utf8Mode`].
+    pub fn as_str(&self) -> Option<Cow<'_, str>> {
+        match self {
+            InterfaceDescriptionOption::Comment(value)
+            | InterfaceDescriptionOption::IfName(value)
+            | InterfaceDescriptionOption::IfDescription(value)
+            | InterfaceDescriptionOption::IfOs(value)
+            | InterfaceDescriptionOption::IfHardware(value) => Some(Cow::Borrowed(value)),
+            InterfaceDescriptionOption::CustomUtf8(custom) => Some(Cow::Borrowed(&custom.value)),
+            InterfaceDescriptionOption::Unknown(unknown) => unknown.lossy_str(),
+            _ => None,
+        }
+    }
+
+    /// Returns the exact bytes of a string option, custom ones included, see [`Utf8Mode`].
+    pub fn as_str_raw(&self) -> Option<&[u8]> {
+        match self {
+            InterfaceDescriptionOption::Comment(value)
+            | InterfaceDescriptionOption::IfName(value)
+            | InterfaceDescriptionOption::IfDescription(value)
+            | InterfaceDescriptionOption::IfOs(value)
+            | InterfaceDescriptionOption::IfHardware(value) => Some(value.as_bytes()),
+            InterfaceDescriptionOption::CustomUtf8(custom) => Some(custom.value.as_bytes()),
+            InterfaceDescriptionOption::Unknown(unknown) => unknown.str_raw(),
+            _ => None,
+        }
+    }
+}
+
 impl<'a> PcapNgOption<'a> for InterfaceDescriptionOption<'a> {
     fn from_slice<B: ByteOrder>(code: u16, length: u16, mut slice: &'a [u8]) -> Result<Self, PcapError> {
         let opt = match code {
-            1 => InterfaceDescriptionOption::Comment(Cow::Borrowed(std::str::from_utf8(slice)?)),
-            2 => InterfaceDescriptionOption::IfName(Cow::Borrowed(std::str::from_utf8(slice)?)),
-            3 => InterfaceDescriptionOption::IfDescription(Cow::Borrowed(std::str::from_utf8(slice)?)),
+            1 => InterfaceDescriptionOption::Comment(utf8_str(slice)?),
+            2 => InterfaceDescriptionOption::IfName(utf8_str(slice)?),
+            3 => InterfaceDescriptionOption::IfDescription(utf8_str(slice)?),
             4 => {
                 if slice.len() != 8 {
                     return Err(PcapError::InvalidField("InterfaceDescriptionOption: IfIpv4Addr length != 8"));
@@ -168,7 +498,7 @@ impl<'a> PcapNgOption<'a> for InterfaceDescriptionOption<'a> {
                 if slice.len() != 8 {
                     return Err(PcapError::InvalidField("InterfaceDescriptionOption: IfEuIAddr length != 8"));
                 }
-                InterfaceDescriptionOption::IfEuIAddr(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?)
+                InterfaceDescriptionOption::IfEuIAddr(slice.read_u64::<BigEndian>().map_err(|_| PcapError::IncompleteBuffer)?)
             },
             8 => {
                 if slice.len() != 8 {
@@ -194,7 +524,7 @@ impl<'a> PcapNgOption<'a> for InterfaceDescriptionOption<'a> {
                 }
                 InterfaceDescriptionOption::IfFilter(Cow::Borrowed(slice))
             },
-            12 => InterfaceDescriptionOption::IfOs(Cow::Borrowed(std::str::from_utf8(slice)?)),
+            12 => InterfaceDescriptionOption::IfOs(utf8_str(slice)?),
             13 => {
                 if slice.len() != 1 {
                     return Err(PcapError::InvalidField("InterfaceDescriptionOption: IfFcsLen length != 1"));
@@ -207,7 +537,7 @@ impl<'a> PcapNgOption<'a> for InterfaceDescriptionOption<'a> {
                 }
                 InterfaceDescriptionOption::IfTsOffset(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?)
             },
-            15 => InterfaceDescriptionOption::IfHardware(Cow::Borrowed(std::str::from_utf8(slice)?)),
+            15 => InterfaceDescriptionOption::IfHardware(utf8_str(slice)?),
 
             2988 | 19372 => InterfaceDescriptionOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice)?),
             2989 | 19373 => InterfaceDescriptionOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice)?),
@@ -218,6 +548,10 @@ impl<'a> PcapNgOption<'a> for InterfaceDescriptionOption<'a> {
         Ok(opt)
     }
 
+    fn unknown(option: UnknownOption<'a>) -> Self {
+        InterfaceDescriptionOption::Unknown(option)
+    }
+
     fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
         match self {
             InterfaceDescriptionOption::Comment(a) => a.write_opt_to::<B, W>(1, writer),
@@ -226,7 +560,7 @@ impl<'a> PcapNgOption<'a> for InterfaceDescriptionOption<'a> {
             InterfaceDescriptionOption::IfIpv4Addr(a) => a.write_opt_to::<B, W>(4, writer),
             InterfaceDescriptionOption::IfIpv6Addr(a) => a.write_opt_to::<B, W>(5, writer),
             InterfaceDescriptionOption::IfMacAddr(a) => a.write_opt_to::<B, W>(6, writer),
-            InterfaceDescriptionOption::IfEuIAddr(a) => a.write_opt_to::<B, W>(7, writer),
+            InterfaceDescriptionOption::IfEuIAddr(a) => Cow::Borrowed(&a.to_be_bytes()[..]).write_opt_to::<B, W>(7, writer),
             InterfaceDescriptionOption::IfSpeed(a) => a.write_opt_to::<B, W>(8, writer),
             InterfaceDescriptionOption::IfTsResol(a) => a.write_opt_to::<B, W>(9, writer),
             InterfaceDescriptionOption::IfTzone(a) => a.write_opt_to::<B, W>(10, writer),
@@ -240,4 +574,27 @@ impl<'a> PcapNgOption<'a> for InterfaceDescriptionOption<'a> {
             InterfaceDescriptionOption::Unknown(a) => a.write_opt_to::<B, W>(a.code, writer),
         }
     }
+
+    fn code(&self) -> u16 {
+        match self {
+            InterfaceDescriptionOption::Comment(_) => 1,
+            InterfaceDescriptionOption::IfName(_) => 2,
+            InterfaceDescriptionOption::IfDescription(_) => 3,
+            InterfaceDescriptionOption::IfIpv4Addr(_) => 4,
+            InterfaceDescriptionOption::IfIpv6Addr(_) => 5,
+            InterfaceDescriptionOption::IfMacAddr(_) => 6,
+            InterfaceDescriptionOption::IfEuIAddr(_) => 7,
+            InterfaceDescriptionOption::IfSpeed(_) => 8,
+            InterfaceDescriptionOption::IfTsResol(_) => 9,
+            InterfaceDescriptionOption::IfTzone(_) => 10,
+            InterfaceDescriptionOption::IfFilter(_) => 11,
+            InterfaceDescriptionOption::IfOs(_) => 12,
+            InterfaceDescriptionOption::IfFcsLen(_) => 13,
+            InterfaceDescriptionOption::IfTsOffset(_) => 14,
+            InterfaceDescriptionOption::IfHardware(_) => 15,
+            InterfaceDescriptionOption::CustomBinary(a) => a.code,
+            InterfaceDescriptionOption::CustomUtf8(a) => a.code,
+            InterfaceDescriptionOption::Unknown(a) => a.code,
+        }
+    }
 }
@@ -8,9 +8,12 @@ use byteorder_slice::result::ReadSlice;
 use byteorder_slice::ByteOrder;
 use derive_into_owned::IntoOwned;
 
-use super::block_common::{Block, PcapNgBlock};
-use super::opt_common::{CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
+use super::block_common::{Block, PcapNgBlock, INTERFACE_STATISTIC_BLOCK};
+use super::interface_description::InterfaceDescriptionBlock;
+use super::opt_common::{utf8_str, CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
 use crate::errors::PcapError;
+use crate::pcapng::Utf8Mode;
+use crate::timestamp::{IntoTimestamp, Timestamp};
 
 
 /// The Interface Statistics Block contains the capture statistics for a given interface and it is optional.
@@ -32,37 +35,147 @@ pub struct InterfaceStatisticsBlock<'a> {
     pub options: Vec<InterfaceStatisticsOption<'a>>,
 }
 
-impl<'a> PcapNgBlock<'a> for InterfaceStatisticsBlock<'a> {
-    fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> Result<(&[u8], Self), PcapError> {
+impl<'a> InterfaceStatisticsBlock<'a> {
+    /// Parses the block from a slice, the string options being parsed with the given [`Utf8Mode`]
+    pub(crate) fn from_slice_with<B: ByteOrder>(mut slice: &'a [u8], mode: Utf8Mode) -> Result<(&[u8], Self), PcapError> {
         if slice.len() < 12 {
             return Err(PcapError::InvalidField("InterfaceStatisticsBlock: block length < 12"));
         }
 
         let interface_id = slice.read_u32::<B>().unwrap();
-        let timestamp = slice.read_u64::<B>().unwrap();
-        let (slice, options) = InterfaceStatisticsOption::opts_from_slice::<B>(slice)?;
+        let timestamp = read_timestamp::<B>(&mut slice)?;
+        let (slice, options) = InterfaceStatisticsOption::opts_from_slice::<B>(slice, mode)?;
 
         let block = InterfaceStatisticsBlock { interface_id, timestamp, options };
 
         Ok((slice, block))
     }
+}
+
+
+impl<'a> PcapNgBlock<'a> for InterfaceStatisticsBlock<'a> {
+    const BLOCK_TYPE: u32 = INTERFACE_STATISTIC_BLOCK;
+
+    fn from_slice<B: ByteOrder>(slice: &'a [u8]) -> Result<(&[u8], Self), PcapError> {
+        Self::from_slice_with::<B>(slice, Utf8Mode::Strict)
+    }
 
     fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
         writer.write_u32::<B>(self.interface_id)?;
-        writer.write_u64::<B>(self.timestamp)?;
+        writer.write_u32::<B>((self.timestamp >> 32) as u32)?;
+        writer.write_u32::<B>(self.timestamp as u32)?;
 
         let opt_len = InterfaceStatisticsOption::write_opts_to::<B, _>(&self.options, writer)?;
         Ok(12 + opt_len)
     }
 
-    fn into_block(self) -> Block<'a> {
-        Block::InterfaceStatistics(self)
+    fn to_block(&self) -> Option<Block<'_>> {
+        Some(Block::InterfaceStatistics(self.clone()))
     }
 }
 
+impl<'a> InterfaceStatisticsBlock<'a> {
+    /// Returns the time these statistics refer to as any [`Timestamp`], converted from the units of its interface.
+    pub fn timestamp_as<T: Timestamp>(&self, interface: &InterfaceDescriptionBlock) -> Option<T> {
+        interface.timestamp_as(self.timestamp)
+    }
+
+    /// Returns the value of the `isb_starttime` option, in the units of the interface, if any.
+    pub fn start_time(&self) -> Option<u64> {
+        self.options.iter().find_map(|opt| match opt {
+            InterfaceStatisticsOption::IsbStartTime(time) => Some(*time),
+            _ => None,
+        })
+    }
+
+    /// Returns the value of the `isb_starttime` option as any [`Timestamp`], converted from the units of its interface.
+    pub fn start_time_as<T: Timestamp>(&self, interface: &InterfaceDescriptionBlock) -> Option<T> {
+        interface.timestamp_as(self.start_time()?)
+    }
+
+    /// Returns the value of the `isb_endtime` option, in the units of the interface, if any.
+    pub fn end_time(&self) -> Option<u64> {
+        self.options.iter().find_map(|opt| match opt {
+            InterfaceStatisticsOption::IsbEndTime(time) => Some(*time),
+            _ => None,
+        })
+    }
+
+    /// Returns the value of the `isb_endtime` option as any [`Timestamp`], converted from the units of its interface.
+    pub fn end_time_as<T: Timestamp>(&self, interface: &InterfaceDescriptionBlock) -> Option<T> {
+        interface.timestamp_as(self.end_time()?)
+    }
+
+    /// Returns the time these statistics refer to as a `chrono::DateTime<Utc>`.
+    #[cfg(feature = "chrono")]
+    pub fn timestamp_utc(&self, interface: &InterfaceDescriptionBlock) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.timestamp_as(interface)
+    }
+
+    /// Returns the value of the `isb_starttime` option as a `chrono::DateTime<Utc>`.
+    #[cfg(feature = "chrono")]
+    pub fn start_time_utc(&self, interface: &InterfaceDescriptionBlock) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.start_time_as(interface)
+    }
+
+    /// Returns the value of the `isb_endtime` option as a `chrono::DateTime<Utc>`.
+    #[cfg(feature = "chrono")]
+    pub fn end_time_utc(&self, interface: &InterfaceDescriptionBlock) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.end_time_as(interface)
+    }
+
+    /// Returns the time these statistics refer to as a `time::OffsetDateTime`.
+    #[cfg(feature = "time")]
+    pub fn timestamp_offset_date_time(&self, interface: &InterfaceDescriptionBlock) -> Option<time::OffsetDateTime> {
+        self.timestamp_as(interface)
+    }
+
+    /// Returns the value of the `isb_starttime` option as a `time::OffsetDateTime`.
+    #[cfg(feature = "time")]
+    pub fn start_time_offset_date_time(&self, interface: &InterfaceDescriptionBlock) -> Option<time::OffsetDateTime> {
+        self.start_time_as(interface)
+    }
+
+    /// Returns the value of the `isb_endtime` option as a `time::OffsetDateTime`.
+    #[cfg(feature = "time")]
+    pub fn end_time_offset_date_time(&self, interface: &InterfaceDescriptionBlock) -> Option<time::OffsetDateTime> {
+        self.end_time_as(interface)
+    }
+
+    /// Sets the time these statistics refer to from any [`IntoTimestamp`], encoded in the units of its interface.
+    pub fn set_timestamp<T: IntoTimestamp>(&mut self, interface: &InterfaceDescriptionBlock, time: T) -> Result<(), PcapError> {
+        self.timestamp = encode_time(interface, time)?;
+        Ok(())
+    }
+
+    /// Sets the `isb_starttime` option from any [`IntoTimestamp`], encoded in the units of its interface.
+    pub fn set_start_time<T: IntoTimestamp>(&mut self, interface: &InterfaceDescriptionBlock, time: T) -> Result<(), PcapError> {
+        let time = encode_time(interface, time)?;
+        self.options.retain(|opt| !matches!(opt, InterfaceStatisticsOption::IsbStartTime(_)));
+        self.options.push(InterfaceStatisticsOption::IsbStartTime(time));
+        Ok(())
+    }
+
+    /// Sets the `isb_endtime` option from any [`IntoTimestamp`], encoded in the units of its interface.
+    pub fn set_end_time<T: IntoTimestamp>(&mut self, interface: &InterfaceDescriptionBlock, time: T) -> Result<(), PcapError> {
+        let time = encode_time(interface, time)?;
+        self.options.retain(|opt| !matches!(opt, InterfaceStatisticsOption::IsbEndTime(_)));
+        self.options.push(InterfaceStatisticsOption::IsbEndTime(time));
+        Ok(())
+    }
+}
+
+/// Encodes a time in the units of the interface
+fn encode_time<T: IntoTimestamp>(interface: &InterfaceDescriptionBlock, time: T) -> Result<u64, PcapError> {
+    interface
+        .time_to_timestamp(time)
+        .ok_or(PcapError::InvalidField("InterfaceStatisticsBlock: timestamp not representable by the interface"))
+}
+
 
 /// The Interface Statistics Block options
 #[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum InterfaceStatisticsOption<'a> {
     /// The opt_comment option is a UTF-8 string containing human-readable comment text
     /// that is associated to the current block.
@@ -104,12 +217,34 @@ pub enum InterfaceStatisticsOption<'a> {
     Unknown(UnknownOption<'a>),
 }
 
+impl<'a> InterfaceStatisticsOption<'a> {
+    /// Returns the value of a string option, custom ones included, see [`Utf8Mode`].
+    pub fn as_str(&self) -> Option<Cow<'_, str>> {
+        match self {
+            InterfaceStatisticsOption::Comment(value) => Some(Cow::Borrowed(value)),
+            InterfaceStatisticsOption::CustomUtf8(custom) => Some(Cow::Borrowed(&custom.value)),
+            InterfaceStatisticsOption::Unknown(unknown) => unknown.lossy_str(),
+            _ => None,
+        }
+    }
+
+    /// Returns the exact bytes of a string option, custom ones included, see [`Utf8Mode`].
+    pub fn as_str_raw(&self) -> Option<&[u8]> {
+        match self {
+            InterfaceStatisticsOption::Comment(value) => Some(value.as_bytes()),
+            InterfaceStatisticsOption::CustomUtf8(custom) => Some(custom.value.as_bytes()),
+            InterfaceStatisticsOption::Unknown(unknown) => unknown.str_raw(),
+            _ => None,
+        }
+    }
+}
+
 impl<'a> PcapNgOption<'a> for InterfaceStatisticsOption<'a> {
     fn from_slice<B: ByteOrder>(code: u16, length: u16, mut slice: &'a [u8]) -> Result<Self, PcapError> {
         let opt = match code {
-            1 => InterfaceStatisticsOption::Comment(Cow::Borrowed(std::str::from_utf8(slice)?)),
-            2 => InterfaceStatisticsOption::IsbStartTime(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?),
-            3 => InterfaceStatisticsOption::IsbEndTime(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?),
+            1 => InterfaceStatisticsOption::Comment(utf8_str(slice)?),
+            2 => InterfaceStatisticsOption::IsbStartTime(read_timestamp::<B>(&mut slice)?),
+            3 => InterfaceStatisticsOption::IsbEndTime(read_timestamp::<B>(&mut slice)?),
             4 => InterfaceStatisticsOption::IsbIfRecv(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?),
             5 => InterfaceStatisticsOption::IsbIfDrop(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?),
             6 => InterfaceStatisticsOption::IsbFilterAccept(slice.read_u64::<B>().map_err(|_| PcapError::IncompleteBuffer)?),
@@ -125,11 +260,15 @@ impl<'a> PcapNgOption<'a> for InterfaceStatisticsOption<'a> {
         Ok(opt)
     }
 
+    fn unknown(option: UnknownOption<'a>) -> Self {
+        InterfaceStatisticsOption::Unknown(option)
+    }
+
     fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
         match self {
             InterfaceStatisticsOption::Comment(a) => a.write_opt_to::<B, W>(1, writer),
-            InterfaceStatisticsOption::IsbStartTime(a) => a.write_opt_to::<B, W>(2, writer),
-            InterfaceStatisticsOption::IsbEndTime(a) => a.write_opt_to::<B, W>(3, writer),
+            InterfaceStatisticsOption::IsbStartTime(a) => write_timestamp_opt_to::<B, W>(2, *a, writer),
+            InterfaceStatisticsOption::IsbEndTime(a) => write_timestamp_opt_to::<B, W>(3, *a, writer),
             InterfaceStatisticsOption::IsbIfRecv(a) => a.write_opt_to::<B, W>(4, writer),
             InterfaceStatisticsOption::IsbIfDrop(a) => a.write_opt_to::<B, W>(5, writer),
             InterfaceStatisticsOption::IsbFilterAccept(a) => a.write_opt_to::<B, W>(6, writer),
@@ -140,4 +279,38 @@ impl<'a> PcapNgOption<'a> for InterfaceStatisticsOption<'a> {
             InterfaceStatisticsOption::Unknown(a) => a.write_opt_to::<B, W>(a.code, writer),
         }
     }
+
+    fn code(&self) -> u16 {
+        match self {
+            InterfaceStatisticsOption::Comment(_) => 1,
+            InterfaceStatisticsOption::IsbStartTime(_) => 2,
+            InterfaceStatisticsOption::IsbEndTime(_) => 3,
+            InterfaceStatisticsOption::IsbIfRecv(_) => 4,
+            InterfaceStatisticsOption::IsbIfDrop(_) => 5,
+            InterfaceStatisticsOption::IsbFilterAccept(_) => 6,
+            InterfaceStatisticsOption::IsbOsDrop(_) => 7,
+            InterfaceStatisticsOption::IsbUsrDeliv(_) => 8,
+            InterfaceStatisticsOption::CustomBinary(a) => a.code,
+            InterfaceStatisticsOption::CustomUtf8(a) => a.code,
+            InterfaceStatisticsOption::Unknown(a) => a.code,
+        }
+    }
+}
+
+/// Reads a timestamp, stored as its high and low 32 bits halves.
+fn read_timestamp<B: ByteOrder>(slice: &mut &[u8]) -> Result<u64, PcapError> {
+    let high = slice.read_u32::<B>().map_err(|_| PcapError::IncompleteBuffer)?;
+    let low = slice.read_u32::<B>().map_err(|_| PcapError::IncompleteBuffer)?;
+
+    Ok(((high as u64) << 32) + low as u64)
+}
+
+/// Writes a timestamp option, stored as its high and low 32 bits halves.
+fn write_timestamp_opt_to<B: ByteOrder, W: Write>(code: u16, timestamp: u64, writer: &mut W) -> IoResult<usize> {
+    writer.write_u16::<B>(code)?;
+    writer.write_u16::<B>(8)?;
+    writer.write_u32::<B>((timestamp >> 32) as u32)?;
+    writer.write_u32::<B>(timestamp as u32)?;
+
+    Ok(12)
 }
@@ -1,15 +1,21 @@
 //! Contains the PcapNg blocks.
 
 pub(crate) mod block_common;
+pub mod custom;
+pub mod decryption_secrets;
 pub mod enhanced_packet;
+pub mod hone_connection_event;
+pub mod hone_machine_info;
 pub mod interface_description;
 pub mod interface_statistics;
 pub mod name_resolution;
 pub(crate) mod opt_common;
 pub mod packet;
+pub mod registered;
 pub mod section_header;
 pub mod simple_packet;
 pub mod systemd_journal_export;
 pub mod unknown;
 
 pub use block_common::*;
+pub use opt_common::OptionIter;
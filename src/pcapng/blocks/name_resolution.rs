@@ -8,9 +8,10 @@ use byteorder_slice::result::ReadSlice;
 use byteorder_slice::ByteOrder;
 use derive_into_owned::IntoOwned;
 
-use super::block_common::{Block, PcapNgBlock};
-use super::opt_common::{CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
+use super::block_common::{Block, PcapNgBlock, NAME_RESOLUTION_BLOCK};
+use super::opt_common::{utf8_str, CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
 use crate::errors::PcapError;
+use crate::pcapng::Utf8Mode;
 
 
 /// The Name Resolution Block (NRB) is used to support the correlation of numeric addresses
@@ -23,8 +24,9 @@ pub struct NameResolutionBlock<'a> {
     pub options: Vec<NameResolutionOption<'a>>,
 }
 
-impl<'a> PcapNgBlock<'a> for NameResolutionBlock<'a> {
-    fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+impl<'a> NameResolutionBlock<'a> {
+    /// Parses the block from a slice, the string options being parsed with the given [`Utf8Mode`]
+    pub(crate) fn from_slice_with<B: ByteOrder>(mut slice: &'a [u8], mode: Utf8Mode) -> Result<(&'a [u8], Self), PcapError> {
         let mut records = Vec::new();
 
         loop {
@@ -37,12 +39,21 @@ impl<'a> PcapNgBlock<'a> for NameResolutionBlock<'a> {
             }
         }
 
-        let (rem, options) = NameResolutionOption::opts_from_slice::<B>(slice)?;
+        let (rem, options) = NameResolutionOption::opts_from_slice::<B>(slice, mode)?;
 
         let block = NameResolutionBlock { records, options };
 
         Ok((rem, block))
     }
+}
+
+
+impl<'a> PcapNgBlock<'a> for NameResolutionBlock<'a> {
+    const BLOCK_TYPE: u32 = NAME_RESOLUTION_BLOCK;
+
+    fn from_slice<B: ByteOrder>(slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+        Self::from_slice_with::<B>(slice, Utf8Mode::Strict)
+    }
 
     fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
         let mut len = 0;
@@ -57,8 +68,8 @@ impl<'a> PcapNgBlock<'a> for NameResolutionBlock<'a> {
         Ok(len)
     }
 
-    fn into_block(self) -> Block<'a> {
-        Block::NameResolution(self)
+    fn to_block(&self) -> Option<Block<'_>> {
+        Some(Block::NameResolution(self.clone()))
     }
 }
 
@@ -294,6 +305,7 @@ impl<'a> UnknownRecord<'a> {
 
 /// The Name Resolution Block (NRB) options
 #[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum NameResolutionOption<'a> {
     /// The opt_comment option is a UTF-8 string containing human-readable comment text
     /// that is associated to the current block.
@@ -318,11 +330,33 @@ pub enum NameResolutionOption<'a> {
     Unknown(UnknownOption<'a>),
 }
 
+impl<'a> NameResolutionOption<'a> {
+    /// Returns the value of a string option, custom ones included, see [`Utf8Mode`].
+    pub fn as_str(&self) -> Option<Cow<'_, str>> {
+        match self {
+            NameResolutionOption::Comment(value) | NameResolutionOption::NsDnsName(value) => Some(Cow::Borrowed(value)),
+            NameResolutionOption::CustomUtf8(custom) => Some(Cow::Borrowed(&custom.value)),
+            NameResolutionOption::Unknown(unknown) => unknown.lossy_str(),
+            _ => None,
+        }
+    }
+
+    /// Returns the exact bytes of a string option, custom ones included, see [`Utf8Mode`].
+    pub fn as_str_raw(&self) -> Option<&[u8]> {
+        match self {
+            NameResolutionOption::Comment(value) | NameResolutionOption::NsDnsName(value) => Some(value.as_bytes()),
+            NameResolutionOption::CustomUtf8(custom) => Some(custom.value.as_bytes()),
+            NameResolutionOption::Unknown(unknown) => unknown.str_raw(),
+            _ => None,
+        }
+    }
+}
+
 impl<'a> PcapNgOption<'a> for NameResolutionOption<'a> {
     fn from_slice<B: ByteOrder>(code: u16, length: u16, slice: &'a [u8]) -> Result<Self, PcapError> {
         let opt = match code {
-            1 => NameResolutionOption::Comment(Cow::Borrowed(std::str::from_utf8(slice)?)),
-            2 => NameResolutionOption::NsDnsName(Cow::Borrowed(std::str::from_utf8(slice)?)),
+            1 => NameResolutionOption::Comment(utf8_str(slice)?),
+            2 => NameResolutionOption::NsDnsName(utf8_str(slice)?),
             3 => {
                 if slice.len() != 4 {
                     return Err(PcapError::InvalidField("NameResolutionOption: NsDnsIpv4Addr length != 4"));
@@ -345,6 +379,10 @@ impl<'a> PcapNgOption<'a> for NameResolutionOption<'a> {
         Ok(opt)
     }
 
+    fn unknown(option: UnknownOption<'a>) -> Self {
+        NameResolutionOption::Unknown(option)
+    }
+
     fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
         match self {
             NameResolutionOption::Comment(a) => a.write_opt_to::<B, W>(1, writer),
@@ -356,4 +394,16 @@ impl<'a> PcapNgOption<'a> for NameResolutionOption<'a> {
             NameResolutionOption::Unknown(a) => a.write_opt_to::<B, W>(a.code, writer),
         }
     }
+
+    fn code(&self) -> u16 {
+        match self {
+            NameResolutionOption::Comment(_) => 1,
+            NameResolutionOption::NsDnsName(_) => 2,
+            NameResolutionOption::NsDnsIpv4Addr(_) => 3,
+            NameResolutionOption::NsDnsIpv6Addr(_) => 4,
+            NameResolutionOption::CustomBinary(a) => a.code,
+            NameResolutionOption::CustomUtf8(a) => a.code,
+            NameResolutionOption::Unknown(a) => a.code,
+        }
+    }
 }
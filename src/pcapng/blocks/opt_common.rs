@@ -1,5 +1,6 @@
 use std::borrow::Cow;
 use std::io::{Result as IoResult, Write};
+use std::iter::FusedIterator;
 
 use byteorder_slice::byteorder::WriteBytesExt;
 use byteorder_slice::result::ReadSlice;
@@ -7,8 +8,15 @@ use byteorder_slice::ByteOrder;
 use derive_into_owned::IntoOwned;
 
 use crate::errors::PcapError;
+use crate::pcapng::Utf8Mode;
 
 
+/// Parses the value of a string option, which is kept as an [`UnknownOption`] by [`PcapNgOption::opts_from_slice()`]
+/// if it isn't valid UTF-8 and the [`Utf8Mode`] allows it.
+pub(crate) fn utf8_str(slice: &[u8]) -> Result<Cow<'_, str>, PcapError> {
+    Ok(Cow::Borrowed(std::str::from_utf8(slice)?))
+}
+
 /// Common fonctions of the PcapNg options
 pub(crate) trait PcapNgOption<'a> {
     /// Parse an option from a slice
@@ -16,8 +24,12 @@ pub(crate) trait PcapNgOption<'a> {
     where
         Self: std::marker::Sized;
 
-    /// Parse all options in a block
-    fn opts_from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> Result<(&'a [u8], Vec<Self>), PcapError>
+    /// Parse all options in a block, the string options which aren't valid UTF-8 being handled according to the [`Utf8Mode`]
+    ///
+    /// The options are kept in a `Vec` which only allocates once the first option is found,
+    /// so the blocks without options, which are most of the packets, don't allocate.
+    /// [`OptionIter`] parses them lazily without any allocation.
+    fn opts_from_slice<B: ByteOrder>(mut slice: &'a [u8], mode: Utf8Mode) -> Result<(&'a [u8], Vec<Self>), PcapError>
     where
         Self: std::marker::Sized,
     {
@@ -28,38 +40,64 @@ pub(crate) trait PcapNgOption<'a> {
             return Ok((slice, options));
         }
 
-        while !slice.is_empty() {
-            if slice.len() < 4 {
-                return Err(PcapError::InvalidField("Option: slice.len() < 4"));
+        loop {
+            let (rem, opt) = Self::next_opt::<B>(slice, mode)?;
+            slice = rem;
+            match opt {
+                Some(opt) => options.push(opt),
+                None => return Ok((slice, options)),
             }
+        }
+    }
 
-            let code = slice.read_u16::<B>().unwrap();
-            let length = slice.read_u16::<B>().unwrap() as usize;
-            let pad_len = (4 - (length % 4)) % 4;
-
-            if code == 0 {
-                return Ok((slice, options));
-            }
+    /// Parse the next option of a block, `None` at the end of the options
+    fn next_opt<B: ByteOrder>(mut slice: &'a [u8], mode: Utf8Mode) -> Result<(&'a [u8], Option<Self>), PcapError>
+    where
+        Self: std::marker::Sized,
+    {
+        // The options always end with an end-of-options marker
+        if slice.is_empty() {
+            return Err(PcapError::InvalidField("Invalid option"));
+        }
 
-            if slice.len() < length + pad_len {
-                return Err(PcapError::InvalidField("Option: length + pad.len() > slice.len()"));
-            }
+        if slice.len() < 4 {
+            return Err(PcapError::InvalidField("Option: slice.len() < 4"));
+        }
 
-            let tmp_slice = &slice[..length];
-            let opt = Self::from_slice::<B>(code, length as u16, tmp_slice)?;
+        let code = slice.read_u16::<B>().unwrap();
+        let length = slice.read_u16::<B>().unwrap() as usize;
+        let pad_len = (4 - (length % 4)) % 4;
 
-            // Jump over the padding
-            slice = &slice[length + pad_len..];
+        if code == 0 {
+            return Ok((slice, None));
+        }
 
-            options.push(opt);
+        if slice.len() < length + pad_len {
+            return Err(PcapError::InvalidField("Option: length + pad.len() > slice.len()"));
         }
 
-        Err(PcapError::InvalidField("Invalid option"))
+        let tmp_slice = &slice[..length];
+        let opt = match Self::from_slice::<B>(code, length as u16, tmp_slice) {
+            // The string option is kept with its raw bytes
+            Err(PcapError::Utf8Error(_)) if mode != Utf8Mode::Strict => {
+                Self::unknown(UnknownOption::new(code, length as u16, tmp_slice).with_kept_by(mode))
+            },
+            res => res?,
+        };
+
+        // Jump over the padding
+        Ok((&slice[length + pad_len..], Some(opt)))
     }
 
+    /// Create an unknown option of the type
+    fn unknown(option: UnknownOption<'a>) -> Self;
+
     /// Write the option to a writer
     fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize>;
 
+    /// Returns the code of the option
+    fn code(&self) -> u16;
+
     /// Write all options in a block
     fn write_opts_to<B: ByteOrder, W: Write>(opts: &[Self], writer: &mut W) -> IoResult<usize>
     where
@@ -82,8 +120,54 @@ pub(crate) trait PcapNgOption<'a> {
     }
 }
 
+/// Lazy view over the options of a block, each option being parsed when it is reached.
+///
+/// It borrows the option bytes of the block, so that reading its options doesn't allocate the `Vec` of its `options`
+/// field, see e.g. [`EnhancedPacketBlock::from_slice_lazy()`](super::enhanced_packet::EnhancedPacketBlock::from_slice_lazy).
+/// It ends at the end-of-options marker, or after the first invalid option.
+#[derive(Clone, Debug)]
+pub struct OptionIter<'a, O> {
+    /// Options left to parse, `None` once the end or an error is reached
+    slice: Option<&'a [u8]>,
+    mode: Utf8Mode,
+    next_opt: NextOpt<'a, O>,
+}
+
+/// Parser of the next option of an [`OptionIter`], see [`PcapNgOption::next_opt()`]
+type NextOpt<'a, O> = fn(&'a [u8], Utf8Mode) -> Result<(&'a [u8], Option<O>), PcapError>;
+
+impl<'a, O> OptionIter<'a, O> {
+    /// Creates a new [`OptionIter`] over the option bytes of a block, which may be empty
+    pub(crate) fn new<B: ByteOrder>(slice: &'a [u8], mode: Utf8Mode) -> Self
+    where
+        O: PcapNgOption<'a>,
+    {
+        OptionIter { slice: Some(slice).filter(|slice| !slice.is_empty()), mode, next_opt: O::next_opt::<B> }
+    }
+}
+
+impl<'a, O> Iterator for OptionIter<'a, O> {
+    type Item = Result<O, PcapError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let slice = self.slice.take()?;
+        match (self.next_opt)(slice, self.mode) {
+            Ok((rem, Some(opt))) => {
+                self.slice = Some(rem);
+                Some(Ok(opt))
+            },
+            Ok((_, None)) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+impl<'a, O> FusedIterator for OptionIter<'a, O> {}
+
 /// Unknown options
-#[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+///
+/// Two options are equal if their code, length and value are equal, whatever [`Self::kept_by`].
+#[derive(Clone, Debug, IntoOwned)]
 pub struct UnknownOption<'a> {
     /// Option code
     pub code: u16,
@@ -91,15 +175,54 @@ pub struct UnknownOption<'a> {
     pub length: u16,
     /// Option value
     pub value: Cow<'a, [u8]>,
+    /// Mode which kept a string option which isn't valid UTF-8, `None` for a genuinely unknown option or one built by
+    /// the user
+    ///
+    /// Set by the parser, it lets the `as_str()` and `as_str_raw()` methods of the option enums return the string.
+    pub kept_by: Option<Utf8Mode>,
 }
 
 impl<'a> UnknownOption<'a> {
     /// Creates a new [`UnknownOption`]
     pub fn new(code: u16, length: u16, value: &'a [u8]) -> Self {
-        UnknownOption { code, length, value: Cow::Borrowed(value) }
+        UnknownOption { code, length, value: Cow::Borrowed(value), kept_by: None }
+    }
+
+    /// Marks the option as a string option kept by the given [`Utf8Mode`]
+    pub(crate) fn with_kept_by(self, mode: Utf8Mode) -> Self {
+        UnknownOption { kept_by: Some(mode), ..self }
+    }
+
+    /// Returns the raw bytes of a string option kept by [`Utf8Mode::Raw`] or [`Utf8Mode::Lossy`].
+    ///
+    /// The options decode their strings lazily through this option: `as_str_raw()` returns these bytes whatever the
+    /// mode, and `as_str()` replaces their invalid sequences by U+FFFD only if the mode is [`Utf8Mode::Lossy`].
+    /// The PEN of a custom string option is skipped, like in [`CustomUtf8Option::value`].
+    pub(crate) fn str_raw(&self) -> Option<&[u8]> {
+        self.kept_by?;
+        match self.code {
+            2988 | 19372 => self.value.get(4..),
+            _ => Some(&self.value),
+        }
+    }
+
+    /// Returns the string of an option kept by [`Utf8Mode::Lossy`], see [`Self::str_raw()`]
+    pub(crate) fn lossy_str(&self) -> Option<Cow<'_, str>> {
+        match self.kept_by {
+            Some(Utf8Mode::Lossy) => self.str_raw().map(String::from_utf8_lossy),
+            _ => None,
+        }
     }
 }
 
+impl PartialEq for UnknownOption<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.code == other.code && self.length == other.length && self.value == other.value
+    }
+}
+
+impl Eq for UnknownOption<'_> {}
+
 /// Custom binary option
 #[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
 pub struct CustomBinaryOption<'a> {
@@ -135,7 +258,7 @@ impl<'a> CustomUtf8Option<'a> {
     /// Parse a [`CustomUtf8Option`] from a slice
     pub fn from_slice<B: ByteOrder>(code: u16, mut src: &'a [u8]) -> Result<Self, PcapError> {
         let pen = src.read_u32::<B>().map_err(|_| PcapError::IncompleteBuffer)?;
-        let opt = CustomUtf8Option { code, pen, value: Cow::Borrowed(std::str::from_utf8(src)?) };
+        let opt = CustomUtf8Option { code, pen, value: utf8_str(src)? };
         Ok(opt)
     }
 }
@@ -2,15 +2,18 @@
 
 use std::borrow::Cow;
 use std::io::{Result as IoResult, Write};
+use std::time::Duration;
 
 use byteorder_slice::byteorder::WriteBytesExt;
 use byteorder_slice::result::ReadSlice;
 use byteorder_slice::ByteOrder;
 use derive_into_owned::IntoOwned;
 
-use super::block_common::{Block, PcapNgBlock};
-use super::opt_common::{CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
+use super::block_common::{Block, PcapNgBlock, PACKET_BLOCK};
+use super::enhanced_packet::{EnhancedPacketBlock, EnhancedPacketOption, PacketFlags};
+use super::opt_common::{utf8_str, CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
 use crate::errors::PcapError;
+use crate::pcapng::Utf8Mode;
 
 /// The Packet Block is obsolete, and MUST NOT be used in new files.
 /// Use the Enhanced Packet Block or Simple Packet Block instead.
@@ -42,8 +45,9 @@ pub struct PacketBlock<'a> {
     pub options: Vec<PacketOption<'a>>,
 }
 
-impl<'a> PcapNgBlock<'a> for PacketBlock<'a> {
-    fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+impl<'a> PacketBlock<'a> {
+    /// Parses the block from a slice, the string options being parsed with the given [`Utf8Mode`]
+    pub(crate) fn from_slice_with<B: ByteOrder>(mut slice: &'a [u8], mode: Utf8Mode) -> Result<(&'a [u8], Self), PcapError> {
         if slice.len() < 20 {
             return Err(PcapError::InvalidField("EnhancedPacketBlock: block length length < 20"));
         }
@@ -64,7 +68,7 @@ impl<'a> PcapNgBlock<'a> for PacketBlock<'a> {
         let data = &slice[..captured_len as usize];
         slice = &slice[tot_len..];
 
-        let (slice, options) = PacketOption::opts_from_slice::<B>(slice)?;
+        let (slice, options) = PacketOption::opts_from_slice::<B>(slice, mode)?;
         let block = PacketBlock {
             interface_id,
             drop_count,
@@ -77,6 +81,15 @@ impl<'a> PcapNgBlock<'a> for PacketBlock<'a> {
 
         Ok((slice, block))
     }
+}
+
+
+impl<'a> PcapNgBlock<'a> for PacketBlock<'a> {
+    const BLOCK_TYPE: u32 = PACKET_BLOCK;
+
+    fn from_slice<B: ByteOrder>(slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+        Self::from_slice_with::<B>(slice, Utf8Mode::Strict)
+    }
 
     fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
         writer.write_u16::<B>(self.interface_id)?;
@@ -94,19 +107,52 @@ impl<'a> PcapNgBlock<'a> for PacketBlock<'a> {
         Ok(20 + self.data.len() + pad_len + opt_len)
     }
 
-    fn into_block(self) -> Block<'a> {
-        Block::Packet(self)
+    fn to_block(&self) -> Option<Block<'_>> {
+        Some(Block::Packet(self.clone()))
+    }
+}
+
+impl<'a> From<PacketBlock<'a>> for EnhancedPacketBlock<'a> {
+    /// Upgrades an obsolete [`PacketBlock`] to an [`EnhancedPacketBlock`].
+    ///
+    /// The drops count is stored in an `epb_dropcount` option, unless it is 0xFFFF (not available).
+    fn from(block: PacketBlock<'a>) -> Self {
+        let mut options: Vec<EnhancedPacketOption<'a>> = block
+            .options
+            .into_iter()
+            .map(|opt| match opt {
+                PacketOption::Comment(a) => EnhancedPacketOption::Comment(a),
+                PacketOption::Flags(a) => EnhancedPacketOption::Flags(a),
+                PacketOption::Hash(a) => EnhancedPacketOption::Hash(a),
+                PacketOption::CustomBinary(a) => EnhancedPacketOption::CustomBinary(a),
+                PacketOption::CustomUtf8(a) => EnhancedPacketOption::CustomUtf8(a),
+                PacketOption::Unknown(a) => EnhancedPacketOption::Unknown(a),
+            })
+            .collect();
+
+        if block.drop_count != 0xFFFF {
+            options.push(EnhancedPacketOption::DropCount(block.drop_count as u64));
+        }
+
+        EnhancedPacketBlock {
+            interface_id: block.interface_id as u32,
+            timestamp: Duration::from_nanos(block.timestamp),
+            original_len: block.original_len,
+            data: block.data,
+            options,
+        }
     }
 }
 
 /// Packet Block option
 #[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum PacketOption<'a> {
     /// Comment associated with the current block
     Comment(Cow<'a, str>),
 
     /// 32-bit flags word containing link-layer information.
-    Flags(u32),
+    Flags(PacketFlags),
 
     /// Contains a hash of the packet.
     Hash(Cow<'a, [u8]>),
@@ -121,15 +167,37 @@ pub enum PacketOption<'a> {
     Unknown(UnknownOption<'a>),
 }
 
+impl<'a> PacketOption<'a> {
+    /// Returns the value of a string option, custom ones included, see [`Utf8Mode`].
+    pub fn as_str(&self) -> Option<Cow<'_, str>> {
+        match self {
+            PacketOption::Comment(value) => Some(Cow::Borrowed(value)),
+            PacketOption::CustomUtf8(custom) => Some(Cow::Borrowed(&custom.value)),
+            PacketOption::Unknown(unknown) => unknown.lossy_str(),
+            _ => None,
+        }
+    }
+
+    /// Returns the exact bytes of a string option, custom ones included, see [`Utf8Mode`].
+    pub fn as_str_raw(&self) -> Option<&[u8]> {
+        match self {
+            PacketOption::Comment(value) => Some(value.as_bytes()),
+            PacketOption::CustomUtf8(custom) => Some(custom.value.as_bytes()),
+            PacketOption::Unknown(unknown) => unknown.str_raw(),
+            _ => None,
+        }
+    }
+}
+
 impl<'a> PcapNgOption<'a> for PacketOption<'a> {
     fn from_slice<B: ByteOrder>(code: u16, length: u16, mut slice: &'a [u8]) -> Result<Self, PcapError> {
         let opt = match code {
-            1 => PacketOption::Comment(Cow::Borrowed(std::str::from_utf8(slice)?)),
+            1 => PacketOption::Comment(utf8_str(slice)?),
             2 => {
                 if slice.len() != 4 {
                     return Err(PcapError::InvalidField("PacketOption: Flags length != 4"));
                 }
-                PacketOption::Flags(slice.read_u32::<B>().map_err(|_| PcapError::IncompleteBuffer)?)
+                PacketOption::Flags(slice.read_u32::<B>().map_err(|_| PcapError::IncompleteBuffer)?.into())
             },
             3 => PacketOption::Hash(Cow::Borrowed(slice)),
 
@@ -142,14 +210,29 @@ impl<'a> PcapNgOption<'a> for PacketOption<'a> {
         Ok(opt)
     }
 
+    fn unknown(option: UnknownOption<'a>) -> Self {
+        PacketOption::Unknown(option)
+    }
+
     fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
         match self {
             PacketOption::Comment(a) => a.write_opt_to::<B, W>(1, writer),
-            PacketOption::Flags(a) => a.write_opt_to::<B, W>(2, writer),
+            PacketOption::Flags(a) => a.bits().write_opt_to::<B, W>(2, writer),
             PacketOption::Hash(a) => a.write_opt_to::<B, W>(3, writer),
             PacketOption::CustomBinary(a) => a.write_opt_to::<B, W>(a.code, writer),
             PacketOption::CustomUtf8(a) => a.write_opt_to::<B, W>(a.code, writer),
             PacketOption::Unknown(a) => a.write_opt_to::<B, W>(a.code, writer),
         }
     }
+
+    fn code(&self) -> u16 {
+        match self {
+            PacketOption::Comment(_) => 1,
+            PacketOption::Flags(_) => 2,
+            PacketOption::Hash(_) => 3,
+            PacketOption::CustomBinary(a) => a.code,
+            PacketOption::CustomUtf8(a) => a.code,
+            PacketOption::Unknown(a) => a.code,
+        }
+    }
 }
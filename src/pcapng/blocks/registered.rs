@@ -0,0 +1,111 @@
+//! Registered Block.
+
+use std::any::Any;
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::io::{Result as IoResult, Write};
+
+use byteorder_slice::{BigEndian, LittleEndian};
+
+use super::block_common::PcapNgBlock;
+use crate::Endianness;
+
+
+/// User-defined block, parsed by the [`PcapNgBlock`] registered for its type
+///
+/// See [`PcapNgParser::register_block()`](crate::pcapng::PcapNgParser::register_block).
+pub struct RegisteredBlock {
+    inner: Box<dyn DynBlock>,
+}
+
+impl RegisteredBlock {
+    /// Creates a new [`RegisteredBlock`]
+    pub fn new<T>(block: T) -> Self
+    where
+        T: for<'b> PcapNgBlock<'b> + Clone + Debug + Eq + Send + Sync + 'static,
+    {
+        RegisteredBlock { inner: Box::new(block) }
+    }
+
+    /// Returns the type code of the block
+    pub fn block_type(&self) -> u32 {
+        self.inner.block_type()
+    }
+
+    /// Returns a reference to the inner block, if it is a `T`
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        self.inner.as_any().downcast_ref()
+    }
+
+    /// Returns a mutable reference to the inner block, if it is a `T`
+    pub fn downcast_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.inner.as_any_mut().downcast_mut()
+    }
+
+    /// Writes the content of the block in the given endianness
+    pub(crate) fn write_to(&self, endianness: Endianness, writer: &mut dyn Write) -> IoResult<usize> {
+        self.inner.write_to(endianness, writer)
+    }
+}
+
+impl Clone for RegisteredBlock {
+    fn clone(&self) -> Self {
+        RegisteredBlock { inner: self.inner.clone_box() }
+    }
+}
+
+impl PartialEq for RegisteredBlock {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.eq_dyn(other.inner.as_any())
+    }
+}
+
+impl Eq for RegisteredBlock {}
+
+impl Debug for RegisteredBlock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        self.inner.fmt(f)
+    }
+}
+
+
+/// Object-safe part of a registered [`PcapNgBlock`]
+trait DynBlock: Debug + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn clone_box(&self) -> Box<dyn DynBlock>;
+    fn eq_dyn(&self, other: &dyn Any) -> bool;
+    fn block_type(&self) -> u32;
+    fn write_to(&self, endianness: Endianness, writer: &mut dyn Write) -> IoResult<usize>;
+}
+
+impl<T> DynBlock for T
+where
+    T: for<'b> PcapNgBlock<'b> + Clone + Debug + Eq + Send + Sync + 'static,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn DynBlock> {
+        Box::new(self.clone())
+    }
+
+    fn eq_dyn(&self, other: &dyn Any) -> bool {
+        other.downcast_ref::<T>() == Some(self)
+    }
+
+    fn block_type(&self) -> u32 {
+        <T as PcapNgBlock<'static>>::block_type(self)
+    }
+
+    fn write_to(&self, endianness: Endianness, mut writer: &mut dyn Write) -> IoResult<usize> {
+        match endianness {
+            Endianness::Big => <T as PcapNgBlock<'static>>::write_to::<BigEndian, _>(self, &mut writer),
+            Endianness::Little => <T as PcapNgBlock<'static>>::write_to::<LittleEndian, _>(self, &mut writer),
+        }
+    }
+}
@@ -8,9 +8,10 @@ use byteorder_slice::result::ReadSlice;
 use byteorder_slice::{BigEndian, ByteOrder, LittleEndian};
 use derive_into_owned::IntoOwned;
 
-use super::block_common::{Block, PcapNgBlock};
-use super::opt_common::{CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
+use super::block_common::{Block, PcapNgBlock, SECTION_HEADER_BLOCK};
+use super::opt_common::{utf8_str, CustomBinaryOption, CustomUtf8Option, PcapNgOption, UnknownOption, WriteOptTo};
 use crate::errors::PcapError;
+use crate::pcapng::Utf8Mode;
 use crate::Endianness;
 
 
@@ -38,8 +39,9 @@ pub struct SectionHeaderBlock<'a> {
     pub options: Vec<SectionHeaderOption<'a>>,
 }
 
-impl<'a> PcapNgBlock<'a> for SectionHeaderBlock<'a> {
-    fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+impl<'a> SectionHeaderBlock<'a> {
+    /// Parses the block from a slice, the string options being parsed with the given [`Utf8Mode`]
+    pub(crate) fn from_slice_with(mut slice: &'a [u8], mode: Utf8Mode) -> Result<(&'a [u8], Self), PcapError> {
         if slice.len() < 16 {
             return Err(PcapError::InvalidField("SectionHeaderBlock: block length < 16"));
         }
@@ -52,8 +54,8 @@ impl<'a> PcapNgBlock<'a> for SectionHeaderBlock<'a> {
         };
 
         let (rem, major_version, minor_version, section_length, options) = match endianness {
-            Endianness::Big => parse_inner::<BigEndian>(slice)?,
-            Endianness::Little => parse_inner::<LittleEndian>(slice)?,
+            Endianness::Big => parse_inner::<BigEndian>(slice, mode)?,
+            Endianness::Little => parse_inner::<LittleEndian>(slice, mode)?,
         };
 
         let block = SectionHeaderBlock { endianness, major_version, minor_version, section_length, options };
@@ -61,15 +63,24 @@ impl<'a> PcapNgBlock<'a> for SectionHeaderBlock<'a> {
         return Ok((rem, block));
 
         #[allow(clippy::type_complexity)]
-        fn parse_inner<B: ByteOrder>(mut slice: &[u8]) -> Result<(&[u8], u16, u16, i64, Vec<SectionHeaderOption>), PcapError> {
+        fn parse_inner<B: ByteOrder>(mut slice: &[u8], mode: Utf8Mode) -> Result<(&[u8], u16, u16, i64, Vec<SectionHeaderOption>), PcapError> {
             let maj_ver = slice.read_u16::<B>().unwrap();
             let min_ver = slice.read_u16::<B>().unwrap();
             let sec_len = slice.read_i64::<B>().unwrap();
-            let (rem, opts) = SectionHeaderOption::opts_from_slice::<B>(slice)?;
+            let (rem, opts) = SectionHeaderOption::opts_from_slice::<B>(slice, mode)?;
 
             Ok((rem, maj_ver, min_ver, sec_len, opts))
         }
     }
+}
+
+
+impl<'a> PcapNgBlock<'a> for SectionHeaderBlock<'a> {
+    const BLOCK_TYPE: u32 = SECTION_HEADER_BLOCK;
+
+    fn from_slice<B: ByteOrder>(slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+        Self::from_slice_with(slice, Utf8Mode::Strict)
+    }
 
     fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
         match self.endianness {
@@ -86,8 +97,17 @@ impl<'a> PcapNgBlock<'a> for SectionHeaderBlock<'a> {
         Ok(16 + opt_len)
     }
 
-    fn into_block(self) -> Block<'a> {
-        Block::SectionHeader(self)
+    fn to_block(&self) -> Option<Block<'_>> {
+        Some(Block::SectionHeader(self.clone()))
+    }
+}
+
+impl SectionHeaderBlock<'_> {
+    /// Returns the declared length of the section, excluding this block, or `None` if it is unspecified.
+    ///
+    /// A negative length other than -1 is invalid and also returns `None`.
+    pub fn declared_section_length(&self) -> Option<u64> {
+        u64::try_from(self.section_length).ok()
     }
 }
 
@@ -103,9 +123,116 @@ impl Default for SectionHeaderBlock<'static> {
     }
 }
 
+impl SectionHeaderBlock<'static> {
+    /// Creates a new [`SectionHeaderBlockBuilder`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use pcap_file::pcapng::blocks::section_header::SectionHeaderBlock;
+    /// use pcap_file::Endianness;
+    ///
+    /// let section = SectionHeaderBlock::builder()
+    ///     .endianness(Endianness::Little)
+    ///     .user_application("my-capture-tool")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(section.section_length, -1);
+    /// ```
+    pub fn builder() -> SectionHeaderBlockBuilder {
+        SectionHeaderBlockBuilder::default()
+    }
+}
+
+
+/// Builder of a [`SectionHeaderBlock`].
+///
+/// Defaults to a big endian 1.0 section of unspecified length without options.
+#[derive(Clone, Debug, Default)]
+pub struct SectionHeaderBlockBuilder {
+    block: SectionHeaderBlock<'static>,
+}
+
+impl SectionHeaderBlockBuilder {
+    /// Sets the endianness of the section, which determines its magic number.
+    pub fn endianness(mut self, endianness: Endianness) -> Self {
+        self.block.endianness = endianness;
+        self
+    }
+
+    /// Sets the version of the format.
+    pub fn version(mut self, major_version: u16, minor_version: u16) -> Self {
+        self.block.major_version = major_version;
+        self.block.minor_version = minor_version;
+        self
+    }
+
+    /// Sets the length in bytes of the section excluding the Section Header Block, -1 meaning unspecified.
+    pub fn section_length(mut self, section_length: i64) -> Self {
+        self.block.section_length = section_length;
+        self
+    }
+
+    /// Adds a comment.
+    pub fn comment(self, comment: impl Into<String>) -> Self {
+        self.option(SectionHeaderOption::Comment(Cow::Owned(comment.into())))
+    }
+
+    /// Sets the description of the hardware used to create the section (`shb_hardware`).
+    pub fn hardware(self, hardware: impl Into<String>) -> Self {
+        self.option(SectionHeaderOption::Hardware(Cow::Owned(hardware.into())))
+    }
+
+    /// Sets the name of the operating system used to create the section (`shb_os`).
+    pub fn os(self, os: impl Into<String>) -> Self {
+        self.option(SectionHeaderOption::OS(Cow::Owned(os.into())))
+    }
+
+    /// Sets the name of the application used to create the section (`shb_userappl`).
+    pub fn user_application(self, user_application: impl Into<String>) -> Self {
+        self.option(SectionHeaderOption::UserApplication(Cow::Owned(user_application.into())))
+    }
+
+    /// Adds an arbitrary option.
+    ///
+    /// The options which can appear only once replace the existing one.
+    pub fn option(mut self, option: SectionHeaderOption<'static>) -> Self {
+        let unique = matches!(
+            option,
+            SectionHeaderOption::Hardware(_) | SectionHeaderOption::OS(_) | SectionHeaderOption::UserApplication(_)
+        );
+
+        if unique {
+            let discriminant = std::mem::discriminant(&option);
+            self.block.options.retain(|opt| std::mem::discriminant(opt) != discriminant);
+        }
+
+        self.block.options.push(option);
+        self
+    }
+
+    /// Builds the [`SectionHeaderBlock`].
+    ///
+    /// Returns an error if the major version is not 1 or if the section length is neither -1
+    /// nor a positive multiple of 4.
+    pub fn build(self) -> Result<SectionHeaderBlock<'static>, PcapError> {
+        if self.block.major_version != 1 {
+            return Err(PcapError::InvalidField("SectionHeaderBlock: major version != 1"));
+        }
+
+        let section_length = self.block.section_length;
+        if section_length != -1 && (section_length < 0 || section_length % 4 != 0) {
+            return Err(PcapError::InvalidField("SectionHeaderBlock: section length not -1 or a positive multiple of 4"));
+        }
+
+        Ok(self.block)
+    }
+}
+
 
 /// Section Header Block options
 #[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum SectionHeaderOption<'a> {
     /// Comment associated with the current block
     Comment(Cow<'a, str>),
@@ -129,13 +256,41 @@ pub enum SectionHeaderOption<'a> {
     Unknown(UnknownOption<'a>),
 }
 
+impl<'a> SectionHeaderOption<'a> {
+    /// Returns the value of a string option, custom ones included, see [`Utf8Mode`].
+    pub fn as_str(&self) -> Option<Cow<'_, str>> {
+        match self {
+            SectionHeaderOption::Comment(value)
+            | SectionHeaderOption::Hardware(value)
+            | SectionHeaderOption::OS(value)
+            | SectionHeaderOption::UserApplication(value) => Some(Cow::Borrowed(value)),
+            SectionHeaderOption::CustomUtf8(custom) => Some(Cow::Borrowed(&custom.value)),
+            SectionHeaderOption::Unknown(unknown) => unknown.lossy_str(),
+            _ => None,
+        }
+    }
+
+    /// Returns the exact bytes of a string option, custom ones included, see [`Utf8Mode`].
+    pub fn as_str_raw(&self) -> Option<&[u8]> {
+        match self {
+            SectionHeaderOption::Comment(value)
+            | SectionHeaderOption::Hardware(value)
+            | SectionHeaderOption::OS(value)
+            | SectionHeaderOption::UserApplication(value) => Some(value.as_bytes()),
+            SectionHeaderOption::CustomUtf8(custom) => Some(custom.value.as_bytes()),
+            SectionHeaderOption::Unknown(unknown) => unknown.str_raw(),
+            _ => None,
+        }
+    }
+}
+
 impl<'a> PcapNgOption<'a> for SectionHeaderOption<'a> {
     fn from_slice<B: ByteOrder>(code: u16, length: u16, slice: &'a [u8]) -> Result<Self, PcapError> {
         let opt = match code {
-            1 => SectionHeaderOption::Comment(Cow::Borrowed(std::str::from_utf8(slice)?)),
-            2 => SectionHeaderOption::Hardware(Cow::Borrowed(std::str::from_utf8(slice)?)),
-            3 => SectionHeaderOption::OS(Cow::Borrowed(std::str::from_utf8(slice)?)),
-            4 => SectionHeaderOption::UserApplication(Cow::Borrowed(std::str::from_utf8(slice)?)),
+            1 => SectionHeaderOption::Comment(utf8_str(slice)?),
+            2 => SectionHeaderOption::Hardware(utf8_str(slice)?),
+            3 => SectionHeaderOption::OS(utf8_str(slice)?),
+            4 => SectionHeaderOption::UserApplication(utf8_str(slice)?),
 
             2988 | 19372 => SectionHeaderOption::CustomUtf8(CustomUtf8Option::from_slice::<B>(code, slice)?),
             2989 | 19373 => SectionHeaderOption::CustomBinary(CustomBinaryOption::from_slice::<B>(code, slice)?),
@@ -146,6 +301,10 @@ impl<'a> PcapNgOption<'a> for SectionHeaderOption<'a> {
         Ok(opt)
     }
 
+    fn unknown(option: UnknownOption<'a>) -> Self {
+        SectionHeaderOption::Unknown(option)
+    }
+
     fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
         match self {
             SectionHeaderOption::Comment(a) => a.write_opt_to::<B, W>(1, writer),
@@ -157,4 +316,16 @@ impl<'a> PcapNgOption<'a> for SectionHeaderOption<'a> {
             SectionHeaderOption::Unknown(a) => a.write_opt_to::<B, W>(a.code, writer),
         }
     }
+
+    fn code(&self) -> u16 {
+        match self {
+            SectionHeaderOption::Comment(_) => 1,
+            SectionHeaderOption::Hardware(_) => 2,
+            SectionHeaderOption::OS(_) => 3,
+            SectionHeaderOption::UserApplication(_) => 4,
+            SectionHeaderOption::CustomBinary(a) => a.code,
+            SectionHeaderOption::CustomUtf8(a) => a.code,
+            SectionHeaderOption::Unknown(a) => a.code,
+        }
+    }
 }
@@ -8,8 +8,9 @@ use byteorder_slice::result::ReadSlice;
 use byteorder_slice::ByteOrder;
 use derive_into_owned::IntoOwned;
 
-use super::block_common::{Block, PcapNgBlock};
+use super::block_common::{Block, PcapNgBlock, SIMPLE_PACKET_BLOCK};
 use crate::errors::PcapError;
+use crate::SnapLen;
 
 
 /// The Simple Packet Block (SPB) is a lightweight container for storing the packets coming from the network.
@@ -25,6 +26,8 @@ pub struct SimplePacketBlock<'a> {
 }
 
 impl<'a> PcapNgBlock<'a> for SimplePacketBlock<'a> {
+    const BLOCK_TYPE: u32 = SIMPLE_PACKET_BLOCK;
+
     fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
         if slice.len() < 4 {
             return Err(PcapError::InvalidField("SimplePacketBlock: block length < 4"));
@@ -46,7 +49,29 @@ impl<'a> PcapNgBlock<'a> for SimplePacketBlock<'a> {
         Ok(4 + self.data.len() + pad_len)
     }
 
-    fn into_block(self) -> Block<'a> {
-        Block::SimplePacket(self)
+    fn to_block(&self) -> Option<Block<'_>> {
+        Some(Block::SimplePacket(self.clone()))
+    }
+}
+
+impl<'a> SimplePacketBlock<'a> {
+    /// Returns the captured length of the packet given the snaplen of the first interface of the section.
+    ///
+    /// A snaplen of 0 means no limit.
+    pub fn captured_len(&self, snaplen: u32) -> usize {
+        SnapLen(snaplen).captured_len(self.original_len as usize)
+    }
+
+    /// Truncates the data to the captured length of the packet, removing the padding read along with it.
+    pub fn apply_snaplen(&mut self, snaplen: u32) {
+        let captured_len = self.captured_len(snaplen);
+        if self.data.len() <= captured_len {
+            return;
+        }
+
+        match &mut self.data {
+            Cow::Borrowed(data) => *data = &data[..captured_len],
+            Cow::Owned(data) => data.truncate(captured_len),
+        }
     }
 }
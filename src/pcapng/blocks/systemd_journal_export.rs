@@ -6,7 +6,7 @@ use std::io::{Result as IoResult, Write};
 use byteorder_slice::ByteOrder;
 use derive_into_owned::IntoOwned;
 
-use super::block_common::{Block, PcapNgBlock};
+use super::block_common::{Block, PcapNgBlock, SYSTEMD_JOURNAL_EXPORT_BLOCK};
 use crate::errors::PcapError;
 
 
@@ -18,6 +18,8 @@ pub struct SystemdJournalExportBlock<'a> {
 }
 
 impl<'a> PcapNgBlock<'a> for SystemdJournalExportBlock<'a> {
+    const BLOCK_TYPE: u32 = SYSTEMD_JOURNAL_EXPORT_BLOCK;
+
     fn from_slice<B: ByteOrder>(slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
         let packet = SystemdJournalExportBlock { journal_entry: Cow::Borrowed(slice) };
         Ok((&[], packet))
@@ -32,7 +34,199 @@ impl<'a> PcapNgBlock<'a> for SystemdJournalExportBlock<'a> {
         Ok(self.journal_entry.len() + pad_len)
     }
 
-    fn into_block(self) -> Block<'a> {
-        Block::SystemdJournalExport(self)
+    fn to_block(&self) -> Option<Block<'_>> {
+        Some(Block::SystemdJournalExport(self.clone()))
+    }
+}
+
+impl SystemdJournalExportBlock<'static> {
+    /// Creates a new [`SystemdJournalExportBlock`] containing the given entries, in the Journal Export Format.
+    ///
+    /// The values containing control characters, such as newlines, are written in the binary form.
+    ///
+    /// # Example
+    /// ```rust
+    /// use pcap_file::pcapng::blocks::systemd_journal_export::{JournalEntry, SystemdJournalExportBlock};
+    ///
+    /// let entry = JournalEntry::new().with_field("MESSAGE", "Hello").with_field("PRIORITY", "6");
+    /// let block = SystemdJournalExportBlock::from_entries(&[entry]).unwrap();
+    ///
+    /// assert_eq!(&block.journal_entry[..], b"MESSAGE=Hello\nPRIORITY=6\n");
+    /// ```
+    pub fn from_entries(entries: &[JournalEntry]) -> Result<Self, PcapError> {
+        let mut journal_entry = vec![];
+
+        for (idx, entry) in entries.iter().enumerate() {
+            if idx > 0 {
+                journal_entry.push(b'\n');
+            }
+            entry.write_to(&mut journal_entry)?;
+        }
+
+        Ok(SystemdJournalExportBlock { journal_entry: Cow::Owned(journal_entry) })
+    }
+}
+
+impl<'a> SystemdJournalExportBlock<'a> {
+    /// Returns an iterator over the entries of the block, parsed from the Journal Export Format.
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::borrow::Cow;
+    ///
+    /// use pcap_file::pcapng::blocks::systemd_journal_export::SystemdJournalExportBlock;
+    ///
+    /// let block = SystemdJournalExportBlock { journal_entry: Cow::Borrowed(b"MESSAGE=Hello\nPRIORITY=6\n") };
+    ///
+    /// let entry = block.entries().next().unwrap().unwrap();
+    /// assert_eq!(entry.get("MESSAGE"), Some(&b"Hello"[..]));
+    /// ```
+    pub fn entries(&self) -> JournalEntries<'_> {
+        // Strip the padding of the block, an entry always ends with a newline
+        let len = self.journal_entry.iter().rposition(|&b| b != 0).map_or(0, |pos| pos + 1);
+        JournalEntries { slice: &self.journal_entry[..len] }
+    }
+}
+
+
+/// Iterator over the [`JournalEntry`]s of a [`SystemdJournalExportBlock`].
+#[derive(Clone, Debug)]
+pub struct JournalEntries<'a> {
+    slice: &'a [u8],
+}
+
+impl<'a> Iterator for JournalEntries<'a> {
+    type Item = Result<JournalEntry<'a>, PcapError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.slice.is_empty() {
+            return None;
+        }
+
+        match JournalEntry::from_slice(self.slice) {
+            Ok((rem, entry)) => {
+                self.slice = rem;
+                Some(Ok(entry))
+            },
+            Err(e) => {
+                self.slice = &[];
+                Some(Err(e))
+            },
+        }
     }
 }
+
+
+/// An entry of the systemd Journal Export Format.
+#[derive(Clone, Debug, Default, IntoOwned, Eq, PartialEq)]
+pub struct JournalEntry<'a> {
+    /// Fields of the entry, in order of appearance
+    pub fields: Vec<JournalField<'a>>,
+}
+
+impl<'a> JournalEntry<'a> {
+    /// Parses a [`JournalEntry`] from a slice, up to the empty line which separates the entries.
+    ///
+    /// Both the text (`NAME=value`) and the binary (length-prefixed) forms of the fields are supported.
+    pub fn from_slice(mut slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+        let mut fields = vec![];
+
+        while let Some((&first, rem)) = slice.split_first() {
+            if first == b'\n' {
+                slice = rem;
+                break;
+            }
+
+            let line_len = slice
+                .iter()
+                .position(|&b| b == b'\n')
+                .ok_or(PcapError::InvalidField("SystemdJournalExportBlock: field without trailing newline"))?;
+            let line = &slice[..line_len];
+            slice = &slice[line_len + 1..];
+
+            let field = match line.iter().position(|&b| b == b'=') {
+                Some(eq_pos) => {
+                    let name = std::str::from_utf8(&line[..eq_pos])?;
+                    JournalField { name: Cow::Borrowed(name), value: Cow::Borrowed(&line[eq_pos + 1..]) }
+                },
+                None => {
+                    if slice.len() < 8 {
+                        return Err(PcapError::InvalidField("SystemdJournalExportBlock: binary field without length"));
+                    }
+                    let value_len = u64::from_le_bytes(slice[..8].try_into().unwrap());
+                    slice = &slice[8..];
+
+                    let value_len = usize::try_from(value_len)
+                        .ok()
+                        .filter(|&len| len < slice.len() && slice[len] == b'\n')
+                        .ok_or(PcapError::InvalidField("SystemdJournalExportBlock: invalid binary field length"))?;
+                    let value = &slice[..value_len];
+                    slice = &slice[value_len + 1..];
+
+                    JournalField { name: Cow::Borrowed(std::str::from_utf8(line)?), value: Cow::Borrowed(value) }
+                },
+            };
+
+            fields.push(field);
+        }
+
+        Ok((slice, JournalEntry { fields }))
+    }
+
+    /// Creates a new empty [`JournalEntry`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a field to the entry and returns it.
+    pub fn with_field(mut self, name: impl Into<String>, value: impl AsRef<[u8]>) -> Self {
+        self.fields.push(JournalField { name: Cow::Owned(name.into()), value: Cow::Owned(value.as_ref().to_vec()) });
+        self
+    }
+
+    /// Writes the entry in the Journal Export Format, without the empty line which separates the entries.
+    ///
+    /// Returns an error if a field name is empty or contains a `=` or a control character.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<usize, PcapError> {
+        let mut len = 0;
+
+        for field in &self.fields {
+            if field.name.is_empty() || field.name.bytes().any(|b| b == b'=' || b.is_ascii_control()) {
+                return Err(PcapError::InvalidField("SystemdJournalExportBlock: invalid field name"));
+            }
+
+            writer.write_all(field.name.as_bytes()).map_err(PcapError::IoError)?;
+            if field.value.iter().any(|b| b.is_ascii_control()) {
+                writer.write_all(b"\n").map_err(PcapError::IoError)?;
+                writer.write_all(&(field.value.len() as u64).to_le_bytes()).map_err(PcapError::IoError)?;
+                len += 9;
+            }
+            else {
+                writer.write_all(b"=").map_err(PcapError::IoError)?;
+                len += 1;
+            }
+            writer.write_all(&field.value).map_err(PcapError::IoError)?;
+            writer.write_all(b"\n").map_err(PcapError::IoError)?;
+
+            len += field.name.len() + field.value.len() + 1;
+        }
+
+        Ok(len)
+    }
+
+    /// Returns the value of the first field with the given name, if any.
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.fields.iter().find(|field| field.name == name).map(|field| &field.value[..])
+    }
+}
+
+
+/// A field of a [`JournalEntry`].
+#[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+pub struct JournalField<'a> {
+    /// Name of the field, e.g. `MESSAGE`
+    pub name: Cow<'a, str>,
+
+    /// Value of the field, which is usually but not necessarily UTF-8
+    pub value: Cow<'a, [u8]>,
+}
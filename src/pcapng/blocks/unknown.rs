@@ -1,17 +1,18 @@
 //! Unknown Block.
 
 use std::borrow::Cow;
-use std::io::{Result as IoResult, Write};
 
 use byteorder_slice::ByteOrder;
 use derive_into_owned::IntoOwned;
 
 use super::block_common::{Block, PcapNgBlock};
-use crate::PcapError;
+use crate::{Endianness, PcapError};
 
 
 /// Unknown block
-#[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+///
+/// Two blocks are equal if their type, length and value are equal, whatever [`Self::endianness`].
+#[derive(Clone, Debug, IntoOwned)]
 pub struct UnknownBlock<'a> {
     /// Block type
     pub type_: u32,
@@ -19,29 +20,51 @@ pub struct UnknownBlock<'a> {
     pub length: u32,
     /// Block value
     pub value: Cow<'a, [u8]>,
+    /// Endianness of the value, `None` when built by the user, see [`Self::endianness()`]
+    pub endianness: Option<Endianness>,
 }
 
 impl<'a> UnknownBlock<'a> {
     /// Creates a new [`UnknownBlock`]
     pub fn new(type_: u32, length: u32, value: &'a [u8]) -> Self {
-        UnknownBlock { type_, length, value: Cow::Borrowed(value) }
+        UnknownBlock { type_, length, value: Cow::Borrowed(value), endianness: None }
+    }
+
+    /// Sets the endianness of the value, see [`Self::endianness()`]
+    pub fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = Some(endianness);
+        self
     }
-}
 
-impl<'a> PcapNgBlock<'a> for UnknownBlock<'a> {
-    fn from_slice<B: ByteOrder>(_slice: &'a [u8]) -> Result<(&[u8], Self), PcapError>
-    where
-        Self: Sized,
-    {
-        unimplemented!("UnkknownBlock::<as PcapNgBlock>::From_slice shouldn't be called")
+    /// Returns the endianness of the value, set when the block is read from a section
+    ///
+    /// A [`PcapNgWriter`](crate::pcapng::PcapNgWriter) re-encodes the value in the endianness of its section if the type of the
+    /// block is registered, see [`PcapNgWriter::register_block()`](crate::pcapng::PcapNgWriter::register_block), and refuses it
+    /// otherwise. A value without endianness is written unchanged.
+    pub fn endianness(&self) -> Option<Endianness> {
+        self.endianness
     }
 
-    fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> IoResult<usize> {
-        writer.write_all(&self.value)?;
-        Ok(self.value.len())
+    /// Parses the value of the block as a user-defined [`PcapNgBlock`].
+    pub fn parse_as<'b, B: ByteOrder, T: PcapNgBlock<'b>>(&'b self) -> Result<T, PcapError> {
+        if self.type_ != T::BLOCK_TYPE {
+            return Err(PcapError::InvalidField("UnknownBlock: type_ != BLOCK_TYPE"));
+        }
+
+        let (_, block) = T::from_slice::<B>(&self.value)?;
+        Ok(block)
     }
 
-    fn into_block(self) -> Block<'a> {
+    /// Converts the block into the [`Block`] enumeration
+    pub fn into_block(self) -> Block<'a> {
         Block::Unknown(self)
     }
 }
+
+impl PartialEq for UnknownBlock<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.type_ == other.type_ && self.length == other.length && self.value == other.value
+    }
+}
+
+impl Eq for UnknownBlock<'_> {}
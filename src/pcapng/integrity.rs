@@ -0,0 +1,372 @@
+use std::borrow::Cow;
+use std::io::{Read, Result as IoResult, Write};
+
+use byteorder_slice::byteorder::WriteBytesExt;
+use byteorder_slice::result::ReadSlice;
+use byteorder_slice::BigEndian;
+
+use super::blocks::block_common::Block;
+use super::blocks::custom::CustomBlock;
+use super::blocks::interface_description::InterfaceDescriptionBlock;
+use super::blocks::section_header::SectionHeaderBlock;
+use super::{PcapNgParser, PcapNgWriter};
+use crate::digest::{DigestAlgorithm, DigestHasher, PacketDigest};
+use crate::read_buffer::ReadBuffer;
+use crate::{Endianness, PcapError, PcapResult};
+
+
+/// Tag at the start of the data of a manifest, after the PEN
+const MANIFEST_TAG: [u8; 4] = *b"IMNF";
+/// Version of the format of a manifest
+const MANIFEST_VERSION: u8 = 1;
+/// Identifier of SHA-256, the only algorithm of the digests of a manifest
+const MANIFEST_SHA256: u8 = 2;
+
+/// SHA-256 digests of a section of a capture, stored in a [`CustomBlock`] at the end of the section.
+///
+/// The digests aren't keyed: they detect the accidental corruption of a capture, not its tampering, since whoever can
+/// modify the capture can also compute the digests again.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IntegrityManifest {
+    /// Digest of all the bytes of the section before the manifest, from its section header
+    pub section_digest: PacketDigest,
+    /// Digest of the data of each packet of the section, in order
+    pub packet_digests: Vec<PacketDigest>,
+}
+
+impl IntegrityManifest {
+    /// Encodes the manifest into a non-copiable [`CustomBlock`] of the given Private Enterprise Number.
+    ///
+    /// The data starts with the tag `IMNF`, its integers are in big endian.
+    ///
+    /// # Errors
+    /// A digest isn't a [`DigestAlgorithm::Sha256`] one, or there are more than `u32::MAX` packet digests.
+    pub fn to_custom_block(&self, pen: u32) -> PcapResult<CustomBlock<'static>> {
+        let digests = std::iter::once(&self.section_digest).chain(&self.packet_digests);
+        if digests.clone().any(|digest| digest.algorithm() != DigestAlgorithm::Sha256) {
+            return Err(PcapError::InvalidField("IntegrityManifest: digest algorithm != SHA-256"));
+        }
+        let count = u32::try_from(self.packet_digests.len()).map_err(|_| PcapError::InvalidField("IntegrityManifest: packet count > u32::MAX"))?;
+
+        let mut data = Vec::with_capacity(12 + (self.packet_digests.len() + 1) * DigestAlgorithm::Sha256.digest_len());
+        data.extend_from_slice(&MANIFEST_TAG);
+        data.push(MANIFEST_VERSION);
+        data.push(MANIFEST_SHA256);
+        data.write_u16::<BigEndian>(0).unwrap();
+        data.write_u32::<BigEndian>(count).unwrap();
+        for digest in digests {
+            data.extend_from_slice(digest.as_bytes());
+        }
+
+        Ok(CustomBlock { pen, copiable: false, data: Cow::Owned(data) })
+    }
+
+    /// Decodes a manifest from a [`CustomBlock`].
+    ///
+    /// Returns `None` if the block is not a manifest of the given Private Enterprise Number.
+    pub fn from_custom_block(block: &CustomBlock, pen: u32) -> Option<PcapResult<Self>> {
+        if block.pen != pen || !block.data.starts_with(&MANIFEST_TAG) {
+            return None;
+        }
+
+        return Some(inner(&block.data[4..]));
+
+        fn inner(mut slice: &[u8]) -> PcapResult<IntegrityManifest> {
+            if slice.len() < 8 {
+                return Err(PcapError::InvalidField("IntegrityManifest: data length < 12"));
+            }
+
+            if slice.read_u8().unwrap() != MANIFEST_VERSION {
+                return Err(PcapError::InvalidField("IntegrityManifest: unsupported version"));
+            }
+
+            if slice.read_u8().unwrap() != MANIFEST_SHA256 {
+                return Err(PcapError::InvalidField("IntegrityManifest: digest algorithm != SHA-256"));
+            }
+            let algorithm = DigestAlgorithm::Sha256;
+            let _reserved = slice.read_u16::<BigEndian>().unwrap();
+            let count = slice.read_u32::<BigEndian>().unwrap() as usize;
+            let digests_count = count.checked_add(1).ok_or(PcapError::InvalidField("IntegrityManifest: packet count > usize::MAX - 1"))?;
+
+            // The data may be followed by the padding of the block
+            let digest_len = algorithm.digest_len();
+            if slice.len() / digest_len < digests_count {
+                return Err(PcapError::InvalidField("IntegrityManifest: data length < length of the digests"));
+            }
+
+            let mut digests = slice.chunks_exact(digest_len).take(digests_count).map(|bytes| PacketDigest::new(algorithm, bytes));
+            let section_digest = digests.next().unwrap();
+            let packet_digests = digests.collect();
+
+            Ok(IntegrityManifest { section_digest, packet_digests })
+        }
+    }
+}
+
+/// Writer digesting the bytes written to the inner writer.
+struct DigestWriter<W: Write> {
+    writer: W,
+    hasher: DigestHasher,
+}
+
+impl<W: Write> Write for DigestWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        let len = self.writer.write(buf)?;
+        self.hasher.update(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes a PcapNg closing each section with an [`IntegrityManifest`], to detect the corruption of archived captures.
+///
+/// An [`IntegrityManifestWriter`] closes each section of the capture with a non-copiable [`CustomBlock`] holding an
+/// [`IntegrityManifest`]: the SHA-256 digest of the data of each packet of the section, and the one of all the bytes of
+/// the section before the manifest. [`verify_integrity()`] reads the capture back and checks each section against its
+/// manifest. The digests aren't keyed, so they don't protect against a deliberate modification of the capture.
+///
+/// Requires the `digest` feature.
+///
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+///
+/// use pcap_file::pcapng::{verify_integrity, IntegrityManifestWriter, PcapNgReader};
+///
+/// const PEN: u32 = 32473;
+///
+/// let mut pcapng_reader = PcapNgReader::new(File::open("test.pcapng").unwrap()).unwrap();
+/// let file_out = File::create("archive.pcapng").unwrap();
+/// let section = pcapng_reader.section().clone();
+/// let mut writer = IntegrityManifestWriter::with_section_header(file_out, PEN, section).unwrap();
+///
+/// while let Some(block) = pcapng_reader.next_block() {
+///     writer.write_block(&block.unwrap()).unwrap();
+/// }
+/// writer.finish().unwrap();
+///
+/// // Later
+/// let sections = verify_integrity(File::open("archive.pcapng").unwrap(), PEN).unwrap();
+/// assert!(sections.iter().all(|section| section.is_valid()));
+/// ```
+///
+/// The manifest of a section is written before the header of the next section and by [`finish()`](Self::finish).
+/// No manifest is written for the last section if the writer is dropped without being finished.
+pub struct IntegrityManifestWriter<W: Write> {
+    writer: PcapNgWriter<DigestWriter<W>>,
+    pen: u32,
+    packet_digests: Vec<PacketDigest>,
+}
+
+impl<W: Write> IntegrityManifestWriter<W> {
+    /// Creates a new [`IntegrityManifestWriter`] writing its manifests under the given Private Enterprise Number.
+    ///
+    /// The section header is written in the native endianness, like [`PcapNgWriter::new()`].
+    pub fn new(writer: W, pen: u32) -> PcapResult<Self> {
+        let section = SectionHeaderBlock { endianness: Endianness::native(), ..Default::default() };
+        Self::with_section_header(writer, pen, section)
+    }
+
+    /// Creates a new [`IntegrityManifestWriter`] with the given section header.
+    pub fn with_section_header(writer: W, pen: u32, section: SectionHeaderBlock<'static>) -> PcapResult<Self> {
+        let writer = DigestWriter { writer, hasher: DigestAlgorithm::Sha256.hasher() };
+        let writer = PcapNgWriter::with_section_header(writer, section)?;
+
+        Ok(Self { writer, pen, packet_digests: vec![] })
+    }
+
+    /// Writes a [`Block`], see [`PcapNgWriter::write_block()`].
+    ///
+    /// A [`SectionHeaderBlock`] closes the current section, whose manifest is written first.
+    pub fn write_block(&mut self, block: &Block) -> PcapResult<usize> {
+        if let Block::SectionHeader(_) = block {
+            self.write_manifest()?;
+        }
+
+        let len = self.writer.write_block(block)?;
+        match block {
+            Block::EnhancedPacket(packet) => self.packet_digests.push(DigestAlgorithm::Sha256.compute(&packet.data)),
+            Block::Packet(packet) => self.packet_digests.push(DigestAlgorithm::Sha256.compute(&packet.data)),
+            Block::SimplePacket(packet) => self.packet_digests.push(DigestAlgorithm::Sha256.compute(&packet.data)),
+            _ => (),
+        }
+
+        Ok(len)
+    }
+
+    /// Writes the manifest of the current section and resets the digests.
+    fn write_manifest(&mut self) -> PcapResult<()> {
+        // The buffered blocks must be digested
+        self.writer.flush()?;
+
+        let section_digest = self.writer.get_ref().hasher.clone().finish();
+        let manifest = IntegrityManifest { section_digest, packet_digests: std::mem::take(&mut self.packet_digests) };
+        self.writer.write_block(&Block::Custom(manifest.to_custom_block(self.pen)?))?;
+        self.writer.flush()?;
+
+        self.writer.get_mut().hasher = DigestAlgorithm::Sha256.hasher();
+        Ok(())
+    }
+
+    /// Writes the manifest of the current section, flushes the writer and returns the wrapped writer.
+    pub fn finish(mut self) -> PcapResult<W> {
+        self.write_manifest()?;
+        Ok(self.writer.into_inner().writer)
+    }
+
+    /// Sets the capacity of the internal buffer, see [`PcapNgWriter::set_buffer_capacity()`].
+    pub fn set_buffer_capacity(&mut self, capacity: usize) -> PcapResult<()> {
+        self.writer.set_buffer_capacity(capacity)
+    }
+
+    /// Returns the Private Enterprise Number of the manifests.
+    pub fn pen(&self) -> u32 {
+        self.pen
+    }
+
+    /// Returns the current [`SectionHeaderBlock`].
+    pub fn section(&self) -> &SectionHeaderBlock<'static> {
+        self.writer.section()
+    }
+
+    /// Returns all the current [`InterfaceDescriptionBlock`].
+    pub fn interfaces(&self) -> &[InterfaceDescriptionBlock<'static>] {
+        self.writer.interfaces()
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer.get_ref().writer
+    }
+}
+
+/// Result of the verification of a section by [`verify_integrity()`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SectionIntegrity {
+    /// Manifest of the section, if found
+    pub manifest: Option<IntegrityManifest>,
+    /// The digest of the section matches the manifest
+    pub section_matches: bool,
+    /// Number of packets before the manifest
+    pub packet_count: usize,
+    /// Indexes in the section of the packets whose digest doesn't match the manifest
+    pub mismatched_packets: Vec<usize>,
+    /// Number of blocks after the manifest, which are not covered by it
+    pub unverified_blocks: usize,
+}
+
+impl SectionIntegrity {
+    /// Returns true if the section has a manifest, matches it entirely and has no block after it.
+    pub fn is_valid(&self) -> bool {
+        match &self.manifest {
+            Some(manifest) => {
+                self.section_matches &&
+                    self.mismatched_packets.is_empty() &&
+                    manifest.packet_digests.len() == self.packet_count &&
+                    self.unverified_blocks == 0
+            },
+            None => false,
+        }
+    }
+}
+
+/// Verifies each section of a PcapNg against the [`IntegrityManifest`] of the given Private Enterprise Number.
+///
+/// # Errors
+/// The PcapNg or a manifest can't be parsed.
+pub fn verify_integrity<R: Read>(reader: R, pen: u32) -> PcapResult<Vec<SectionIntegrity>> {
+    let algorithm = DigestAlgorithm::Sha256;
+    let mut reader = ReadBuffer::new(reader);
+    let mut hasher = algorithm.hasher();
+    let mut parser = reader.parse_with(|src| {
+        let (rem, parser) = PcapNgParser::new(src)?;
+        hasher.update(&src[..src.len() - rem.len()]);
+        Ok((rem, parser))
+    })?;
+
+    let mut sections = vec![];
+    let mut current = SectionIntegrity::default();
+    let mut packet_digests = vec![];
+
+    while reader.has_data_left().map_err(PcapError::IoError)? {
+        let verified = reader.parse_with(|src| {
+            let (rem, block) = parser.next_block(src)?;
+            let bytes = &src[..src.len() - rem.len()];
+
+            let verified = match block {
+                Block::SectionHeader(_) => {
+                    hasher = algorithm.hasher();
+                    hasher.update(bytes);
+                    Verified::Section
+                },
+                Block::Custom(custom) => match IntegrityManifest::from_custom_block(&custom, pen) {
+                    Some(manifest) => Verified::Manifest(manifest?, hasher.clone().finish()),
+                    None => {
+                        hasher.update(bytes);
+                        Verified::Other
+                    },
+                },
+                Block::EnhancedPacket(packet) => {
+                    hasher.update(bytes);
+                    Verified::Packet(algorithm.compute(&packet.data))
+                },
+                Block::Packet(packet) => {
+                    hasher.update(bytes);
+                    Verified::Packet(algorithm.compute(&packet.data))
+                },
+                Block::SimplePacket(packet) => {
+                    hasher.update(bytes);
+                    Verified::Packet(algorithm.compute(&packet.data))
+                },
+                _ => {
+                    hasher.update(bytes);
+                    Verified::Other
+                },
+            };
+
+            Ok((rem, verified))
+        })?;
+
+        match verified {
+            Verified::Section => {
+                sections.push(std::mem::take(&mut current));
+                packet_digests.clear();
+            },
+            Verified::Manifest(manifest, section_digest) if current.manifest.is_none() => {
+                current.section_matches = manifest.section_digest == section_digest;
+                current.mismatched_packets = packet_digests
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, digest)| manifest.packet_digests.get(i) != Some(digest))
+                    .map(|(i, _)| i)
+                    .collect();
+                current.manifest = Some(manifest);
+            },
+            Verified::Packet(digest) if current.manifest.is_none() => {
+                packet_digests.push(digest);
+                current.packet_count += 1;
+            },
+            _ => {
+                if current.manifest.is_some() {
+                    current.unverified_blocks += 1;
+                }
+            },
+        }
+    }
+
+    parser.end_of_input()?;
+    sections.push(current);
+
+    Ok(sections)
+}
+
+/// Block read by [`verify_integrity()`].
+enum Verified {
+    Section,
+    Manifest(IntegrityManifest, PacketDigest),
+    Packet(PacketDigest),
+    Other,
+}
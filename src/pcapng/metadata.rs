@@ -0,0 +1,175 @@
+use std::borrow::Cow;
+use std::io::Read;
+
+use byteorder_slice::byteorder::WriteBytesExt;
+use byteorder_slice::result::ReadSlice;
+use byteorder_slice::BigEndian;
+use derive_into_owned::IntoOwned;
+
+use super::blocks::block_common::Block;
+use super::blocks::custom::CustomBlock;
+use super::PcapNgReader;
+use crate::{PcapError, PcapResult};
+
+
+/// Tag at the start of the data of a metadata block, after the PEN
+const METADATA_TAG: [u8; 4] = *b"AMTD";
+
+/// Point of the capture at which an [`ApplicationMetadata`] was written.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum MetadataPoint {
+    /// Right after a section header
+    AfterSectionHeader,
+    /// After a given number of packets
+    Periodic,
+    /// At the end of the capture
+    AtEnd,
+    /// Written explicitly with [`PcapNgWriter::write_metadata()`](super::PcapNgWriter::write_metadata)
+    Explicit,
+}
+
+/// Opaque metadata of an application, stored in a [`CustomBlock`] instead of a file next to the capture.
+///
+/// The block is copiable and belongs to the Private Enterprise Number of the application. The [`PcapNgWriter`](super::PcapNgWriter) writes it on demand with
+/// [`write_metadata()`](super::PcapNgWriter::write_metadata), or automatically at the points enabled in a
+/// [`MetadataConfig`]: after each section header, every given number of packets, and at the end of the capture.
+///
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+///
+/// use pcap_file::pcapng::{read_metadata, MetadataConfig, PcapNgWriter};
+///
+/// const PEN: u32 = 32473;
+///
+/// let mut pcapng_writer = PcapNgWriter::new(File::create("out.pcapng").unwrap()).unwrap();
+/// pcapng_writer.set_metadata(Some(MetadataConfig::new(PEN, b"{\"case\": 42}".to_vec()))).unwrap();
+/// // Write the packets
+/// pcapng_writer.finish().unwrap();
+///
+/// for metadata in read_metadata(File::open("out.pcapng").unwrap(), PEN).unwrap() {
+///     println!("{:?}: {} bytes", metadata.point, metadata.data.len());
+/// }
+/// ```
+#[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+pub struct ApplicationMetadata<'a> {
+    /// Point at which the metadata was written
+    pub point: MetadataPoint,
+    /// Metadata serialized by the application
+    pub data: Cow<'a, [u8]>,
+}
+
+impl<'a> ApplicationMetadata<'a> {
+    /// Creates a new [`ApplicationMetadata`].
+    pub fn new(point: MetadataPoint, data: &'a [u8]) -> Self {
+        ApplicationMetadata { point, data: Cow::Borrowed(data) }
+    }
+
+    /// Encodes the metadata into a copiable [`CustomBlock`] of the given Private Enterprise Number.
+    ///
+    /// The data starts with the tag `AMTD`, the point and the length of the metadata in big endian, so that the
+    /// padding of the block is not mistaken for metadata.
+    pub fn to_custom_block(&self, pen: u32) -> CustomBlock<'static> {
+        let mut data = Vec::with_capacity(12 + self.data.len());
+
+        data.extend_from_slice(&METADATA_TAG);
+        data.push(match self.point {
+            MetadataPoint::AfterSectionHeader => 0,
+            MetadataPoint::Periodic => 1,
+            MetadataPoint::AtEnd => 2,
+            MetadataPoint::Explicit => 3,
+        });
+        data.extend_from_slice(&[0_u8; 3]);
+        data.write_u32::<BigEndian>(self.data.len() as u32).unwrap();
+        data.extend_from_slice(&self.data);
+
+        CustomBlock { pen, copiable: true, data: Cow::Owned(data) }
+    }
+
+    /// Decodes the metadata from a [`CustomBlock`].
+    ///
+    /// Returns `None` if the block is not metadata of the given Private Enterprise Number.
+    pub fn from_custom_block(block: &'a CustomBlock, pen: u32) -> Option<PcapResult<Self>> {
+        if block.pen != pen || !block.data.starts_with(&METADATA_TAG) {
+            return None;
+        }
+
+        return Some(inner(&block.data[4..]));
+
+        fn inner(mut slice: &[u8]) -> PcapResult<ApplicationMetadata> {
+            if slice.len() < 8 {
+                return Err(PcapError::InvalidField("ApplicationMetadata: data length < 12"));
+            }
+
+            let point = match slice.read_u8().unwrap() {
+                0 => MetadataPoint::AfterSectionHeader,
+                1 => MetadataPoint::Periodic,
+                2 => MetadataPoint::AtEnd,
+                3 => MetadataPoint::Explicit,
+                _ => return Err(PcapError::InvalidField("ApplicationMetadata: unknown point")),
+            };
+            slice = &slice[3..];
+
+            let len = slice.read_u32::<BigEndian>().unwrap() as usize;
+            if slice.len() < len {
+                return Err(PcapError::InvalidField("ApplicationMetadata: data length < metadata length"));
+            }
+
+            Ok(ApplicationMetadata { point, data: Cow::Borrowed(&slice[..len]) })
+        }
+    }
+}
+
+/// Metadata written automatically by a [`PcapNgWriter`](super::PcapNgWriter), see
+/// [`set_metadata()`](super::PcapNgWriter::set_metadata).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MetadataConfig {
+    /// Private Enterprise Number of the application
+    pub pen: u32,
+    /// Metadata serialized by the application
+    pub data: Vec<u8>,
+    /// Writes the metadata after each section header
+    pub after_section_header: bool,
+    /// Writes the metadata every `interval` packets, never if 0
+    pub interval: u64,
+    /// Writes the metadata at the end of the capture, by [`finish()`](super::PcapNgWriter::finish)
+    pub at_end: bool,
+}
+
+impl MetadataConfig {
+    /// Creates a new [`MetadataConfig`] writing the metadata after each section header and at the end of the capture.
+    pub fn new(pen: u32, data: Vec<u8>) -> Self {
+        MetadataConfig { pen, data, after_section_header: true, interval: 0, at_end: true }
+    }
+
+    /// Returns the block to write at the given point, if enabled.
+    pub(crate) fn block_at(&self, point: MetadataPoint) -> Option<Block<'static>> {
+        let enabled = match point {
+            MetadataPoint::AfterSectionHeader => self.after_section_header,
+            MetadataPoint::Periodic => self.interval > 0,
+            MetadataPoint::AtEnd => self.at_end,
+            MetadataPoint::Explicit => true,
+        };
+
+        enabled.then(|| Block::Custom(ApplicationMetadata::new(point, &self.data).to_custom_block(self.pen)))
+    }
+}
+
+/// Reads all the [`ApplicationMetadata`] of the given Private Enterprise Number in a PcapNg, in order.
+///
+/// # Errors
+/// The PcapNg or a metadata block can't be parsed.
+pub fn read_metadata<R: Read>(reader: R, pen: u32) -> PcapResult<Vec<ApplicationMetadata<'static>>> {
+    let mut reader = PcapNgReader::new(reader)?;
+    let mut metadata = vec![];
+
+    while let Some(block) = reader.next_block() {
+        if let Block::Custom(custom) = block? {
+            if let Some(res) = ApplicationMetadata::from_custom_block(&custom, pen) {
+                metadata.push(res?.into_owned());
+            }
+        }
+    }
+
+    Ok(metadata)
+}
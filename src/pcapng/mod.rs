@@ -3,11 +3,49 @@
 pub mod blocks;
 pub use blocks::{Block, PcapNgBlock, RawBlock};
 
+pub(crate) mod arena;
+pub use arena::*;
+
+#[cfg(feature = "digest")]
+pub(crate) mod integrity;
+#[cfg(feature = "digest")]
+pub use integrity::*;
+
+pub(crate) mod metadata;
+pub use metadata::*;
+
+pub(crate) mod packet;
+pub use packet::*;
+
 pub(crate) mod parser;
 pub use parser::*;
 
+pub(crate) mod preserve;
+pub use preserve::*;
+
+pub(crate) mod push_parser;
+pub use push_parser::*;
+
 pub(crate) mod reader;
 pub use reader::*;
 
+pub(crate) mod reorder;
+pub use reorder::*;
+
+pub(crate) mod rotate;
+pub use rotate::*;
+
+pub(crate) mod scan;
+pub use scan::*;
+
+pub(crate) mod statistics;
+pub use statistics::*;
+
+pub(crate) mod threaded;
+pub use threaded::*;
+
+pub(crate) mod transcode;
+pub use transcode::*;
+
 pub(crate) mod writer;
 pub use writer::*;
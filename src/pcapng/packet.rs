@@ -0,0 +1,141 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use derive_into_owned::IntoOwned;
+
+use super::blocks::enhanced_packet::PacketFlags;
+use super::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption};
+use super::blocks::packet::PacketOption;
+use super::Block;
+use crate::errors::PcapError;
+use crate::timestamp::{from_duration, IntoTimestamp, Timestamp};
+use crate::DataLink;
+
+
+/// Packet of a PcapNg stream, resolved against its [`InterfaceDescriptionBlock`].
+///
+/// Bundles what most consumers need from an [`EnhancedPacketBlock`](super::blocks::enhanced_packet::EnhancedPacketBlock),
+/// a [`PacketBlock`](super::blocks::packet::PacketBlock) or a [`SimplePacketBlock`](super::blocks::simple_packet::SimplePacketBlock)
+/// and its interface, as returned by [`PcapNgReader::next_packet()`](super::PcapNgReader::next_packet).
+#[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+pub struct PcapNgPacket<'a> {
+    /// Index of the interface of the packet within its section
+    pub interface_id: u32,
+
+    /// Link type of the interface
+    pub linktype: DataLink,
+
+    /// Name of the interface, from its `if_name` option
+    pub interface_name: Option<Cow<'a, str>>,
+
+    /// Snaplen of the interface, 0 meaning no limit
+    pub snaplen: u32,
+
+    /// Time since the Unix epoch, with the resolution and offset of the interface applied,
+    /// `None` for a simple packet which has no timestamp
+    pub timestamp: Option<Duration>,
+
+    /// Flags of the packet, from its `epb_flags` or `pack_flags` option
+    pub flags: Option<PacketFlags>,
+
+    /// Actual length of the packet when it was transmitted on the network
+    pub original_len: u32,
+
+    /// Data of the packet, including the link-layer headers
+    pub data: Cow<'a, [u8]>,
+}
+
+impl<'a> PcapNgPacket<'a> {
+    /// Returns the timestamp of the packet as any [`Timestamp`], `None` for a simple packet.
+    ///
+    /// The ticks of the timestamp, e.g. returned as a `u64`, are nanoseconds since the Unix epoch.
+    pub fn timestamp_as<T: Timestamp>(&self) -> Option<T> {
+        from_duration(self.timestamp?)
+    }
+
+    /// Returns the timestamp of the packet as a `chrono::DateTime<Utc>`, `None` for a simple packet.
+    #[cfg(feature = "chrono")]
+    pub fn timestamp_utc(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.timestamp_as()
+    }
+
+    /// Returns the timestamp of the packet as a `time::OffsetDateTime`, `None` for a simple packet.
+    #[cfg(feature = "time")]
+    pub fn timestamp_offset_date_time(&self) -> Option<time::OffsetDateTime> {
+        self.timestamp_as()
+    }
+
+    /// Sets the timestamp of the packet from any [`IntoTimestamp`].
+    ///
+    /// Fails for a simple packet, which can't store a timestamp.
+    pub fn set_timestamp<T: IntoTimestamp>(&mut self, time: T) -> Result<(), PcapError> {
+        if self.timestamp.is_none() {
+            return Err(PcapError::InvalidField("PcapNgPacket: simple packet without timestamp"));
+        }
+
+        let timestamp = time.since_epoch().ok_or(PcapError::InvalidField("PcapNgPacket: timestamp before the Unix epoch"))?;
+        self.timestamp = Some(timestamp);
+        Ok(())
+    }
+
+    /// Resolves a packet block against the interfaces of its section, borrowing the name of its interface.
+    ///
+    /// Returns `None` if the block doesn't carry a packet, or an error if its interface doesn't exist.
+    pub fn from_block(block: Block<'a>, interfaces: &'a [InterfaceDescriptionBlock<'_>]) -> Option<Result<Self, PcapError>> {
+        Self::resolve(block, interfaces, Cow::Borrowed)
+    }
+
+    /// Resolves a packet block like [`Self::from_block()`], copying the name of its interface so that the packet
+    /// doesn't borrow the interfaces.
+    pub(crate) fn from_block_copied(block: Block<'a>, interfaces: &[InterfaceDescriptionBlock<'_>]) -> Option<Result<Self, PcapError>> {
+        Self::resolve(block, interfaces, |name| Cow::Owned(name.to_owned()))
+    }
+
+    fn resolve<'i>(
+        block: Block<'a>,
+        interfaces: &'i [InterfaceDescriptionBlock<'_>],
+        name: impl FnOnce(&'i str) -> Cow<'a, str>,
+    ) -> Option<Result<Self, PcapError>> {
+        let (interface_id, timestamp, flags, original_len, data) = match block {
+            Block::EnhancedPacket(epb) => {
+                let flags = epb.flags();
+                (epb.interface_id, Some(epb.timestamp.as_nanos() as u64), flags, epb.original_len, epb.data)
+            },
+            Block::Packet(pb) => {
+                let flags = pb.options.iter().find_map(|opt| match opt {
+                    PacketOption::Flags(flags) => Some(*flags),
+                    _ => None,
+                });
+                (pb.interface_id as u32, Some(pb.timestamp), flags, pb.original_len, pb.data)
+            },
+            Block::SimplePacket(spb) => (0, None, None, spb.original_len, spb.data),
+            _ => return None,
+        };
+
+        let interface = match interfaces.get(interface_id as usize) {
+            Some(interface) => interface,
+            None => return Some(Err(PcapError::InvalidInterfaceId(interface_id))),
+        };
+
+        // An invalid name kept by Utf8Mode::Lossy is decoded, and so owned
+        let interface_name = interface.options.iter().find_map(|opt| match opt {
+            InterfaceDescriptionOption::IfName(_) => opt.as_str(),
+            InterfaceDescriptionOption::Unknown(unknown) if unknown.code == 2 => opt.as_str(),
+            _ => None,
+        });
+
+        Some(Ok(PcapNgPacket {
+            interface_id,
+            linktype: interface.linktype,
+            interface_name: interface_name.map(|interface_name| match interface_name {
+                Cow::Borrowed(interface_name) => name(interface_name),
+                Cow::Owned(interface_name) => Cow::Owned(interface_name),
+            }),
+            snaplen: interface.snaplen,
+            timestamp: timestamp.map(|ts| interface.timestamp_to_duration(ts)),
+            flags,
+            original_len,
+            data,
+        }))
+    }
+}
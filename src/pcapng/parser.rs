@@ -1,14 +1,80 @@
+use std::fmt::Debug;
+
 use byteorder_slice::{BigEndian, ByteOrder, LittleEndian};
 
-use super::blocks::block_common::{Block, RawBlock};
+use super::blocks::block_common::{Block, PcapNgBlock, RawBlock};
 use super::blocks::enhanced_packet::EnhancedPacketBlock;
 use super::blocks::interface_description::InterfaceDescriptionBlock;
+use super::blocks::registered::RegisteredBlock;
 use super::blocks::section_header::SectionHeaderBlock;
 use super::blocks::{INTERFACE_DESCRIPTION_BLOCK, SECTION_HEADER_BLOCK};
+use super::PreservedBlock;
 use crate::errors::PcapError;
 use crate::Endianness;
 
 
+/// Configuration of a [`PcapNgParser`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PcapNgParserConfig {
+    /// Strip the trailing Frame Check Sequence from the packet data of the [`EnhancedPacketBlock`]s
+    /// whose `epb_flags` option or interface `if_fcslen` option declares an FCS length.
+    ///
+    /// The `original_len` of the packets is adjusted accordingly.
+    pub strip_fcs: bool,
+
+    /// Convert the obsolete [`PacketBlock`](super::blocks::packet::PacketBlock)s into [`EnhancedPacketBlock`]s,
+    /// so that packets are only returned as [`Block::EnhancedPacket`] or [`Block::SimplePacket`].
+    pub upgrade_packet_blocks: bool,
+
+    /// Tolerate the defects of some broken writers instead of refusing the whole file.
+    ///
+    /// A trailer length which differs from the initial length by the padding amount is accepted,
+    /// see [`RawBlock::from_slice_lenient()`].
+    ///
+    /// Packet data and option values which are not padded to 32 bits are accepted when the lengths
+    /// declared in the block are otherwise consistent.
+    pub lenient: bool,
+
+    /// Types of the blocks which are skipped, e.g. [`SYSTEMD_JOURNAL_EXPORT_BLOCK`](super::blocks::SYSTEMD_JOURNAL_EXPORT_BLOCK).
+    ///
+    /// Only the framing of these blocks is read and they are never returned as a [`Block`], which speeds up the
+    /// reading of the blocks of interest. The section headers and interface descriptions are never skipped because
+    /// the following blocks depend on them.
+    pub skip_block_types: Vec<u32>,
+
+    /// Check that the blocks of each section fill exactly the section length declared in its header, if any.
+    ///
+    /// The check is made when the next section header or the end of the input is reached, and detects the truncated
+    /// captures and the sections whose blocks were altered without updating their header.
+    pub check_section_length: bool,
+
+    /// Handling of the string options, like the comments, which are not valid UTF-8.
+    pub utf8_mode: Utf8Mode,
+}
+
+/// Handling of the string options which are not valid UTF-8, see [`PcapNgParserConfig::utf8_mode`].
+///
+/// The raw bytes of an option which is kept are returned by its `as_str_raw()`, e.g.
+/// [`InterfaceDescriptionOption::as_str_raw()`](super::blocks::interface_description::InterfaceDescriptionOption::as_str_raw).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Utf8Mode {
+    /// The block is refused with a [`PcapError::Utf8Error`]
+    #[default]
+    Strict,
+    /// The option is kept like with [`Utf8Mode::Raw`], its `as_str()` replacing the invalid sequences by U+FFFD,
+    /// the replacement character
+    Lossy,
+    /// The option is kept as an unknown option of the same code, holding the raw bytes of its value
+    Raw,
+}
+
+impl PcapNgParserConfig {
+    /// Returns true if the blocks of this type are skipped.
+    pub fn skips(&self, type_: u32) -> bool {
+        type_ != SECTION_HEADER_BLOCK && type_ != INTERFACE_DESCRIPTION_BLOCK && self.skip_block_types.contains(&type_)
+    }
+}
+
 /// Parses a PcapNg from a slice of bytes.
 ///
 /// You can match on [`PcapError::IncompleteBuffer`] to know if the parser need more data.
@@ -46,40 +112,145 @@ use crate::Endianness;
 pub struct PcapNgParser {
     section: SectionHeaderBlock<'static>,
     interfaces: Vec<InterfaceDescriptionBlock<'static>>,
+    config: PcapNgParserConfig,
+    registered_blocks: Vec<(u32, BlockParser)>,
+    /// Number of blocks accepted after a repair in lenient mode
+    repaired_blocks: u64,
+    /// Number of bytes parsed in the current section, after its header
+    section_offset: u64,
+    /// Length of the header of the current section
+    section_header_len: u64,
+    /// Original bytes of the header of the current section
+    raw_section: RawBlock<'static>,
 }
 
+/// Parses the body of a registered block.
+type BlockParser = fn(&[u8], Endianness) -> Result<RegisteredBlock, PcapError>;
+
 impl PcapNgParser {
     /// Creates a new [`PcapNgParser`].
     ///
     /// Parses the first block which must be a valid SectionHeaderBlock.
     pub fn new(src: &[u8]) -> Result<(&[u8], Self), PcapError> {
+        Self::with_config(src, PcapNgParserConfig::default())
+    }
+
+    /// Creates a new [`PcapNgParser`] with the given [`PcapNgParserConfig`].
+    ///
+    /// Parses the first block which must be a valid SectionHeaderBlock.
+    pub fn with_config(src: &[u8], config: PcapNgParserConfig) -> Result<(&[u8], Self), PcapError> {
         // Always use BigEndian here because we can't know the SectionHeaderBlock endianness
-        let (rem, section) = Block::from_slice::<BigEndian>(src)?;
-        let section = match section {
-            Block::SectionHeader(section) => section.into_owned(),
+        let (rem, raw_block) = if config.lenient {
+            RawBlock::from_slice_lenient::<BigEndian>(src)?
+        }
+        else {
+            RawBlock::from_slice::<BigEndian>(src)?
+        };
+        let raw_section = raw_block.clone().into_owned();
+        let trailer_repaired = raw_block.initial_len != raw_block.trailer_len;
+        let (section, padding_repaired) = match try_into_block_repaired::<BigEndian>(raw_block, &config)? {
+            (Block::SectionHeader(section), repaired) => (section.into_owned(), repaired),
             _ => return Err(PcapError::InvalidField("PcapNg: SectionHeader invalid or missing")),
         };
 
-        let parser = PcapNgParser { section, interfaces: vec![] };
+        let section_header_len = (src.len() - rem.len()) as u64;
+        let parser = PcapNgParser {
+            section,
+            interfaces: vec![],
+            config,
+            registered_blocks: vec![],
+            repaired_blocks: (trailer_repaired || padding_repaired) as u64,
+            section_offset: 0,
+            section_header_len,
+            raw_section,
+        };
 
         Ok((rem, parser))
     }
 
     /// Returns the remainder and the next [`Block`].
-    pub fn next_block<'a>(&mut self, src: &'a [u8]) -> Result<(&'a [u8], Block<'a>), PcapError> {
+    ///
+    /// The blocks skipped by the [`PcapNgParserConfig`] are consumed before it.
+    pub fn next_block<'a>(&mut self, mut src: &'a [u8]) -> Result<(&'a [u8], Block<'a>), PcapError> {
         // Read next Block
-        match self.section.endianness {
-            Endianness::Big => {
-                let (rem, raw_block) = self.next_raw_block_inner::<BigEndian>(src)?;
-                let block = raw_block.try_into_block::<BigEndian>()?;
-                Ok((rem, block))
-            },
-            Endianness::Little => {
-                let (rem, raw_block) = self.next_raw_block_inner::<LittleEndian>(src)?;
-                let block = raw_block.try_into_block::<LittleEndian>()?;
-                Ok((rem, block))
-            },
+        let (rem, raw_block) = loop {
+            let (rem, raw_block) = self.next_raw_block(src)?;
+            if !self.config.skips(raw_block.type_) {
+                break (rem, raw_block);
+            }
+            src = rem;
+        };
+        // A block whose trailer was repaired is already counted by `next_raw_block()`
+        let trailer_repaired = raw_block.initial_len != raw_block.trailer_len;
+        let (block, padding_repaired) = self.decode_block(raw_block)?;
+        if padding_repaired && !trailer_repaired {
+            self.repaired_blocks += 1;
         }
+
+        Ok((rem, block))
+    }
+
+    /// Returns the remainder and the next [`Block`], without updating the current section and interfaces.
+    ///
+    /// The same block is returned by the next call to [`Self::next_block()`].
+    pub fn peek_block<'a>(&self, mut src: &'a [u8]) -> Result<(&'a [u8], Block<'a>), PcapError> {
+        let (rem, raw_block) = loop {
+            let (rem, raw_block) = match (self.section.endianness, self.config.lenient) {
+                (Endianness::Big, false) => RawBlock::from_slice::<BigEndian>(src)?,
+                (Endianness::Big, true) => RawBlock::from_slice_lenient::<BigEndian>(src)?,
+                (Endianness::Little, false) => RawBlock::from_slice::<LittleEndian>(src)?,
+                (Endianness::Little, true) => RawBlock::from_slice_lenient::<LittleEndian>(src)?,
+            };
+            if !self.config.skips(raw_block.type_) {
+                break (rem, raw_block);
+            }
+            src = rem;
+        };
+        let (block, _) = self.decode_block(raw_block)?;
+
+        Ok((rem, block))
+    }
+
+    /// Converts a [`RawBlock`] into a [`Block`], applying the configuration of the parser.
+    ///
+    /// Also returns true if the padding of the block had to be repaired.
+    fn decode_block<'a>(&self, raw_block: RawBlock<'a>) -> Result<(Block<'a>, bool), PcapError> {
+        let (mut block, repaired) = match self.section.endianness {
+            Endianness::Big => try_into_block_repaired::<BigEndian>(raw_block, &self.config)?,
+            Endianness::Little => try_into_block_repaired::<LittleEndian>(raw_block, &self.config)?,
+        };
+
+        if let Block::Unknown(unknown) = &block {
+            if let Some((_, parse)) = self.registered_blocks.iter().find(|(type_, _)| *type_ == unknown.type_) {
+                block = Block::Registered(parse(&unknown.value, self.section.endianness)?);
+            }
+        }
+
+        if self.config.upgrade_packet_blocks {
+            if let Block::Packet(packet) = block {
+                block = Block::EnhancedPacket(packet.into());
+            }
+        }
+
+        if let Block::SimplePacket(packet) = &mut block {
+            if let Some(interface) = self.interfaces.first() {
+                packet.apply_snaplen(interface.snaplen);
+            }
+        }
+
+        if self.config.strip_fcs {
+            if let Block::EnhancedPacket(packet) = &mut block {
+                let fcs_len = packet
+                    .flags()
+                    .and_then(|flags| flags.fcs_len())
+                    .or_else(|| self.interfaces.get(packet.interface_id as usize).and_then(|interface| interface.fcs_len()));
+                if let Some(fcs_len) = fcs_len {
+                    packet.strip_fcs(fcs_len);
+                }
+            }
+        }
+
+        Ok((block, repaired))
     }
 
     /// Returns the remainder and the next [`RawBlock`].
@@ -93,28 +264,105 @@ impl PcapNgParser {
 
     /// Inner function to parse the next raw block.
     fn next_raw_block_inner<'a, B: ByteOrder>(&mut self, src: &'a [u8]) -> Result<(&'a [u8], RawBlock<'a>), PcapError> {
-        let (rem, raw_block) = RawBlock::from_slice::<B>(src)?;
+        let (rem, raw_block) = if self.config.lenient {
+            RawBlock::from_slice_lenient::<B>(src)?
+        }
+        else {
+            RawBlock::from_slice::<B>(src)?
+        };
+        if raw_block.initial_len != raw_block.trailer_len {
+            self.repaired_blocks += 1;
+        }
 
         match raw_block.type_ {
             SECTION_HEADER_BLOCK => {
-                self.section = raw_block.clone().try_into_block::<B>()?.into_owned().into_section_header().unwrap();
+                let section = try_into_block::<B>(raw_block.clone(), &self.config)?.into_owned().into_section_header().unwrap();
+                self.check_section_length()?;
+                self.section = section;
                 self.interfaces.clear();
+                self.section_offset = 0;
+                self.section_header_len = (src.len() - rem.len()) as u64;
+                self.raw_section = raw_block.clone().into_owned();
+                return Ok((rem, raw_block));
             },
             INTERFACE_DESCRIPTION_BLOCK => {
-                let interface = raw_block.clone().try_into_block::<B>()?.into_owned().into_interface_description().unwrap();
+                let interface = try_into_block::<B>(raw_block.clone(), &self.config)?.into_owned().into_interface_description().unwrap();
                 self.interfaces.push(interface);
             },
             _ => {},
         }
 
+        self.section_offset += (src.len() - rem.len()) as u64;
+
         Ok((rem, raw_block))
     }
 
+    /// Returns the remainder and the next [`PreservedBlock`], keeping the original bytes of the block.
+    ///
+    /// Unlike [`next_block()`](Self::next_block), the blocks are not transformed nor skipped by the [`PcapNgParserConfig`].
+    pub fn next_preserved_block<'a>(&mut self, src: &'a [u8]) -> Result<(&'a [u8], PreservedBlock<'a>), PcapError> {
+        let (rem, raw_block) = self.next_raw_block(src)?;
+
+        // The endianness of a section header is the one of its new section
+        let endianness = self.section.endianness;
+        let block = match endianness {
+            Endianness::Big => try_into_block::<BigEndian>(raw_block.clone(), &self.config)?,
+            Endianness::Little => try_into_block::<LittleEndian>(raw_block.clone(), &self.config)?,
+        };
+
+        Ok((rem, PreservedBlock::new(raw_block, endianness, block)))
+    }
+
     /// Returns the current [`SectionHeaderBlock`].
     pub fn section(&self) -> &SectionHeaderBlock<'static> {
         &self.section
     }
 
+    /// Returns the original bytes of the current [`SectionHeaderBlock`].
+    pub fn raw_section(&self) -> &RawBlock<'static> {
+        &self.raw_section
+    }
+
+    /// Returns the number of bytes parsed in the current section, after its header.
+    pub fn section_offset(&self) -> u64 {
+        self.section_offset
+    }
+
+    /// Checks that the current section is complete, at the end of the input.
+    ///
+    /// Only fails if [`PcapNgParserConfig::check_section_length`] is set. The section is then considered complete,
+    /// so an error is only returned once.
+    pub fn end_of_input(&mut self) -> Result<(), PcapError> {
+        let res = self.check_section_length();
+        if let Some(len) = self.section.declared_section_length() {
+            self.section_offset = len;
+        }
+
+        res
+    }
+
+    /// Checks that the length of the current section matches its declared length, if enabled.
+    fn check_section_length(&self) -> Result<(), PcapError> {
+        if !self.config.check_section_length {
+            return Ok(());
+        }
+
+        match self.section.declared_section_length() {
+            Some(len) if len != self.section_offset => Err(PcapError::InvalidField("SectionHeaderBlock: section_length != length of the section")),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the length of the header of the current section.
+    pub(crate) fn section_header_len(&self) -> u64 {
+        self.section_header_len
+    }
+
+    /// Accounts for `len` bytes of the current section skipped without being parsed.
+    pub(crate) fn skip_section_bytes(&mut self, len: u64) {
+        self.section_offset += len;
+    }
+
     /// Returns all the current [`InterfaceDescriptionBlock`].
     pub fn interfaces(&self) -> &[InterfaceDescriptionBlock<'static>] {
         &self.interfaces[..]
@@ -124,4 +372,67 @@ impl PcapNgParser {
     pub fn packet_interface(&self, packet: &EnhancedPacketBlock) -> Option<&InterfaceDescriptionBlock> {
         self.interfaces.get(packet.interface_id as usize)
     }
+
+    /// Registers a user-defined [`PcapNgBlock`].
+    ///
+    /// The blocks of this type are parsed with [`PcapNgBlock::from_slice()`] and returned as [`Block::Registered`],
+    /// an error being returned if they are invalid. The types which have their own [`Block`] variant can't be replaced.
+    pub fn register_block<T>(&mut self)
+    where
+        T: for<'b> PcapNgBlock<'b> + Clone + Debug + Eq + Send + Sync + 'static,
+    {
+        self.registered_blocks.push((T::BLOCK_TYPE, parse::<T>));
+
+        fn parse<T>(body: &[u8], endianness: Endianness) -> Result<RegisteredBlock, PcapError>
+        where
+            T: for<'b> PcapNgBlock<'b> + Clone + Debug + Eq + Send + Sync + 'static,
+        {
+            let (_, block) = match endianness {
+                Endianness::Big => T::from_slice::<BigEndian>(body)?,
+                Endianness::Little => T::from_slice::<LittleEndian>(body)?,
+            };
+            Ok(RegisteredBlock::new(block))
+        }
+    }
+
+    /// Returns the [`PcapNgParserConfig`] of the parser.
+    pub fn config(&self) -> &PcapNgParserConfig {
+        &self.config
+    }
+
+    /// Returns the number of blocks accepted after a repair, see [`PcapNgParserConfig::lenient`].
+    ///
+    /// The blocks are counted when parsed by [`Self::next_block()`] or [`Self::next_raw_block()`], a block being counted once.
+    pub fn repaired_blocks(&self) -> u64 {
+        self.repaired_blocks
+    }
+
+    /// Counts a block repaired outside of the parser, e.g. a streamed block whose trailer was tolerated
+    pub(crate) fn count_repaired_block(&mut self) {
+        self.repaired_blocks += 1;
+    }
+}
+
+/// Converts a [`RawBlock`] into a [`Block`].
+///
+/// The string options are parsed according to the [`Utf8Mode`] of the config. In lenient mode, a block which can't
+/// be parsed is parsed again with its padding fixed.
+fn try_into_block<'a, B: ByteOrder>(raw_block: RawBlock<'a>, config: &PcapNgParserConfig) -> Result<Block<'a>, PcapError> {
+    try_into_block_repaired::<B>(raw_block, config).map(|(block, _)| block)
+}
+
+/// Converts a [`RawBlock`] into a [`Block`] like [`try_into_block()`], also returning true if its padding was fixed.
+fn try_into_block_repaired<'a, B: ByteOrder>(raw_block: RawBlock<'a>, config: &PcapNgParserConfig) -> Result<(Block<'a>, bool), PcapError> {
+    let mode = config.utf8_mode;
+    if !config.lenient {
+        return raw_block.try_into_block_with::<B>(mode).map(|block| (block, false));
+    }
+
+    match raw_block.clone().try_into_block_with::<B>(mode) {
+        Ok(block) => Ok((block, false)),
+        Err(err) => match raw_block.with_canonical_padding::<B>() {
+            Some(repaired) => repaired.try_into_block_with::<B>(mode).map(|block| (block, true)).map_err(|_| err),
+            None => Err(err),
+        },
+    }
 }
@@ -0,0 +1,91 @@
+use derive_into_owned::IntoOwned;
+
+use super::blocks::block_common::{Block, RawBlock};
+use crate::Endianness;
+
+
+/// Block read with its original bytes, for the preserving mode where reading a PcapNg and writing it back produces
+/// byte-identical output for the untouched blocks.
+///
+/// The original bytes are kept next to the decoded block. The blocks are read with
+/// [`PcapNgReader::next_preserved_block()`](super::PcapNgReader::next_preserved_block) and written with
+/// [`PcapNgWriter::write_preserved_block()`](super::PcapNgWriter::write_preserved_block): a block which was not
+/// modified through [`PreservedBlock::block_mut()`] is written from its original bytes, with its unknown options,
+/// the order of its options and its padding bytes intact. A modified block is encoded again.
+///
+/// The writer must be created with [`PcapNgWriter::with_raw_section_header()`](super::PcapNgWriter::with_raw_section_header)
+/// from [`PcapNgReader::raw_section()`](super::PcapNgReader::raw_section) so that the first section header is preserved too.
+///
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+///
+/// use pcap_file::pcapng::blocks::Block;
+/// use pcap_file::pcapng::{PcapNgReader, PcapNgWriter};
+///
+/// let mut pcapng_reader = PcapNgReader::new(File::open("test.pcapng").unwrap()).unwrap();
+/// let file_out = File::create("out.pcapng").unwrap();
+/// let mut pcapng_writer = PcapNgWriter::with_raw_section_header(file_out, pcapng_reader.raw_section()).unwrap();
+///
+/// while let Some(block) = pcapng_reader.next_preserved_block() {
+///     let mut block = block.unwrap();
+///
+///     // Only the modified packets are encoded again
+///     if let Block::EnhancedPacket(packet) = block.block() {
+///         if packet.data.len() > 64 {
+///             if let Block::EnhancedPacket(packet) = block.block_mut() {
+///                 packet.data.to_mut().truncate(64);
+///             }
+///         }
+///     }
+///
+///     pcapng_writer.write_preserved_block(&block).unwrap();
+/// }
+/// ```
+#[derive(Clone, Debug, IntoOwned, Eq, PartialEq)]
+pub struct PreservedBlock<'a> {
+    raw: RawBlock<'a>,
+    endianness: Endianness,
+    block: Block<'a>,
+    modified: bool,
+}
+
+impl<'a> PreservedBlock<'a> {
+    /// Creates a new [`PreservedBlock`] from a block and its original bytes, read in the given endianness.
+    pub fn new(raw: RawBlock<'a>, endianness: Endianness, block: Block<'a>) -> Self {
+        PreservedBlock { raw, endianness, block, modified: false }
+    }
+
+    /// Returns the original bytes of the block.
+    pub fn raw(&self) -> &RawBlock<'a> {
+        &self.raw
+    }
+
+    /// Returns the endianness of the original bytes.
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// Returns the decoded block.
+    pub fn block(&self) -> &Block<'a> {
+        &self.block
+    }
+
+    /// Returns the decoded block to modify it.
+    ///
+    /// The block is then marked as modified and will be encoded again instead of being written from its original bytes.
+    pub fn block_mut(&mut self) -> &mut Block<'a> {
+        self.modified = true;
+        &mut self.block
+    }
+
+    /// Returns true if the block was accessed through [`block_mut()`](Self::block_mut).
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Consumes [`Self`], returning the decoded block.
+    pub fn into_block(self) -> Block<'a> {
+        self.block
+    }
+}
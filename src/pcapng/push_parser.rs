@@ -0,0 +1,168 @@
+use byteorder_slice::{BigEndian, ByteOrder, LittleEndian};
+
+use super::blocks::SECTION_HEADER_BLOCK;
+use super::{Block, PcapNgParser, PcapNgParserConfig};
+use crate::errors::PcapError;
+use crate::Endianness;
+
+
+/// Event returned by a [`PcapNgPushParser`].
+#[derive(Debug)]
+pub enum PcapNgEvent {
+    /// A complete block has been parsed.
+    Block(Block<'static>),
+
+    /// The buffered data doesn't contain a complete block.
+    ///
+    /// Contains the minimum number of bytes needed to make progress, which is the remaining length of
+    /// the next block once its header has been received.
+    NeedMoreData(usize),
+
+    /// The data is invalid.
+    ///
+    /// The invalid bytes are kept in the parser, so feeding more data returns the same error again.
+    Error(PcapError),
+}
+
+/// Parses a PcapNg from chunks of bytes pushed by the caller, without any I/O.
+///
+/// Useful when the capture is received from a transport which doesn't implement [`std::io::Read`].
+///
+/// # Example
+/// ```rust
+/// use pcap_file::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+/// use pcap_file::pcapng::{PcapNgEvent, PcapNgPushParser, PcapNgWriter};
+/// use pcap_file::DataLink;
+///
+/// let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+/// pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0)).unwrap();
+/// let data = pcapng_writer.into_inner();
+///
+/// let mut parser = PcapNgPushParser::new();
+/// let mut blocks = vec![];
+///
+/// // Receive the capture in chunks of 10 bytes
+/// for chunk in data.chunks(10) {
+///     for event in parser.feed(chunk) {
+///         match event {
+///             PcapNgEvent::Block(block) => blocks.push(block),
+///             PcapNgEvent::NeedMoreData(_) => {},
+///             PcapNgEvent::Error(err) => panic!("{err}"),
+///         }
+///     }
+/// }
+///
+/// // The section header and the interface description
+/// assert_eq!(blocks.len(), 2);
+/// assert!(parser.finish().is_ok());
+/// ```
+pub struct PcapNgPushParser {
+    parser: Option<PcapNgParser>,
+    config: PcapNgParserConfig,
+    buffer: Vec<u8>,
+}
+
+impl PcapNgPushParser {
+    /// Creates a new [`PcapNgPushParser`].
+    pub fn new() -> Self {
+        Self::with_config(PcapNgParserConfig::default())
+    }
+
+    /// Creates a new [`PcapNgPushParser`] with the given [`PcapNgParserConfig`].
+    pub fn with_config(config: PcapNgParserConfig) -> Self {
+        PcapNgPushParser { parser: None, config, buffer: vec![] }
+    }
+
+    /// Pushes data into the parser and returns the events it produced.
+    ///
+    /// All the blocks are returned, including the first [`SectionHeaderBlock`](super::blocks::section_header::SectionHeaderBlock).
+    /// The last event is always [`PcapNgEvent::NeedMoreData`] or [`PcapNgEvent::Error`].
+    pub fn feed(&mut self, data: &[u8]) -> Vec<PcapNgEvent> {
+        self.buffer.extend_from_slice(data);
+
+        let mut events = vec![];
+        let mut src = &self.buffer[..];
+
+        loop {
+            let res = match &mut self.parser {
+                Some(parser) => parser.next_block(src).map(|(rem, block)| (rem, block.into_owned())),
+                None => PcapNgParser::with_config(src, self.config.clone()).map(|(rem, parser)| {
+                    let section = parser.section().clone();
+                    self.parser = Some(parser);
+                    (rem, Block::SectionHeader(section))
+                }),
+            };
+
+            match res {
+                Ok((rem, block)) => {
+                    events.push(PcapNgEvent::Block(block));
+                    src = rem;
+                },
+                Err(PcapError::IncompleteBuffer) => {
+                    events.push(PcapNgEvent::NeedMoreData(needed_len(src, self.parser.as_ref())));
+                    break;
+                },
+                Err(err) => {
+                    events.push(PcapNgEvent::Error(err));
+                    break;
+                },
+            }
+        }
+
+        let consumed = self.buffer.len() - src.len();
+        self.buffer.drain(..consumed);
+
+        return events;
+
+        fn needed_len(src: &[u8], parser: Option<&PcapNgParser>) -> usize {
+            // The section header length can only be read after its magic
+            let header_len = match (parser, src.get(..4)) {
+                (Some(_), Some(type_)) if type_ != SECTION_HEADER_BLOCK.to_be_bytes() => 8,
+                (Some(_), None) => 8,
+                _ => 12,
+            };
+            if src.len() < header_len {
+                return header_len - src.len();
+            }
+
+            let endianness = match parser {
+                Some(parser) if header_len == 8 => parser.section().endianness,
+                _ if src[8..12] == [0x1A, 0x2B, 0x3C, 0x4D] => Endianness::Big,
+                _ => Endianness::Little,
+            };
+            let block_len = match endianness {
+                Endianness::Big => BigEndian::read_u32(&src[4..8]),
+                Endianness::Little => LittleEndian::read_u32(&src[4..8]),
+            } as usize;
+
+            block_len.saturating_sub(src.len()).max(1)
+        }
+    }
+
+    /// Returns the inner [`PcapNgParser`], once the first section header has been parsed.
+    pub fn parser(&self) -> Option<&PcapNgParser> {
+        self.parser.as_ref()
+    }
+
+    /// Returns the number of bytes which have been pushed but not parsed yet.
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Checks that the stream ended on a block boundary.
+    ///
+    /// Returns [`PcapError::IncompleteBuffer`] if some bytes are still buffered, or if no section header was received.
+    pub fn finish(&self) -> Result<(), PcapError> {
+        if self.parser.is_none() || !self.buffer.is_empty() {
+            return Err(PcapError::IncompleteBuffer);
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for PcapNgPushParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
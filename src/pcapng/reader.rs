@@ -1,12 +1,22 @@
-use std::io::Read;
+use std::borrow::BorrowMut;
+use std::fmt::Debug;
+use std::io::{ErrorKind, Read, Seek, SeekFrom, StdinLock};
+use std::marker::PhantomData;
+use std::time::Duration;
 
-use super::blocks::block_common::{Block, RawBlock};
+use byteorder_slice::{BigEndian, ByteOrder, LittleEndian};
+
+use super::blocks::block_common::{Block, PcapNgBlock, RawBlock};
 use super::blocks::enhanced_packet::EnhancedPacketBlock;
 use super::blocks::interface_description::InterfaceDescriptionBlock;
 use super::blocks::section_header::SectionHeaderBlock;
-use super::PcapNgParser;
-use crate::errors::PcapError;
+use super::blocks::{ENHANCED_PACKET_BLOCK, INTERFACE_DESCRIPTION_BLOCK, PACKET_BLOCK, SECTION_HEADER_BLOCK, SIMPLE_PACKET_BLOCK};
+use super::{BlockArena, PcapNgPacket, PcapNgParser, PcapNgParserConfig, PreservedBlock};
+use crate::errors::{PcapError, PcapResult};
 use crate::read_buffer::ReadBuffer;
+use crate::common::{with_len, ProgressCallback};
+use crate::index::{IndexEntry, TimeIndex};
+use crate::{DataLink, Endianness, IoStats, Progress, SnapLen};
 
 
 /// Reads a PcapNg from a reader.
@@ -28,9 +38,18 @@ use crate::read_buffer::ReadBuffer;
 ///     //Do something
 /// }
 /// ```
+///
+/// The reader never seeks, so it can read from a pipe or a socket.
+///
+/// The data is read in large chunks into an internal buffer and the blocks are parsed from it, so the reader
+/// doesn't need to be wrapped in a [`BufReader`](std::io::BufReader).
 pub struct PcapNgReader<R: Read> {
     parser: PcapNgParser,
     reader: ReadBuffer<R>,
+    stats: IoStats,
+    progress: Option<ProgressCallback>,
+    /// Error of a dropped [`StreamedBlock`], returned by the next read
+    pending_error: Option<PcapError>,
 }
 
 impl<R: Read> PcapNgReader<R> {
@@ -40,15 +59,69 @@ impl<R: Read> PcapNgReader<R> {
     pub fn new(reader: R) -> Result<PcapNgReader<R>, PcapError> {
         let mut reader = ReadBuffer::new(reader);
         let parser = reader.parse_with(PcapNgParser::new)?;
-        Ok(Self { parser, reader })
+        Ok(Self::from_parts(parser, reader))
+    }
+
+    /// Creates a new [`PcapNgReader`] from a reader with the given [`PcapNgParserConfig`].
+    ///
+    /// Parses the first block which must be a valid SectionHeaderBlock.
+    pub fn with_config(reader: R, config: PcapNgParserConfig) -> Result<PcapNgReader<R>, PcapError> {
+        let mut reader = ReadBuffer::new(reader);
+        let parser = reader.parse_with(|src| PcapNgParser::with_config(src, config.clone()))?;
+        Ok(Self::from_parts(parser, reader))
+    }
+
+    /// Creates a new [`PcapNgReader`] from a reader, with an internal buffer of `capacity` bytes instead of 8 MB.
+    ///
+    /// The capacity must be larger than the biggest block of the capture, which otherwise can't be read.
+    ///
+    /// Parses the first block which must be a valid SectionHeaderBlock.
+    pub fn with_capacity(reader: R, capacity: usize) -> Result<PcapNgReader<R>, PcapError> {
+        let mut reader = ReadBuffer::with_capacity(reader, capacity);
+        let parser = reader.parse_with(PcapNgParser::new)?;
+        Ok(Self::from_parts(parser, reader))
+    }
+
+    fn from_parts(parser: PcapNgParser, reader: ReadBuffer<R>) -> Self {
+        let stats = IoStats { blocks: 1, bytes: reader.position(), ..Default::default() };
+        Self { parser, reader, stats, progress: None, pending_error: None }
     }
 
     /// Returns the next [`Block`].
     pub fn next_block(&mut self) -> Option<Result<Block, PcapError>> {
+        if let Err(err) = self.skip_blocks() {
+            return Some(Err(err));
+        }
+
+        match self.reader.has_data_left() {
+            Ok(has_data) => {
+                if has_data {
+                    let res = self.reader.parse_with(|src| with_repaired(with_len(src, self.parser.next_block(src)), &self.parser));
+                    let res = self.stats.record_repaired(res, is_packet);
+                    ProgressCallback::update(&mut self.progress, &self.stats);
+                    Some(res)
+                }
+                else {
+                    self.end_of_input()
+                }
+            },
+            Err(e) => Some(Err(PcapError::IoError(e))),
+        }
+    }
+
+    /// Returns the next [`Block`] without consuming it.
+    ///
+    /// The same block is returned by the next call to [`Self::next_block()`], and the current section and interfaces
+    /// are only updated then. An invalid block is not consumed either.
+    pub fn peek_block(&mut self) -> Option<Result<Block, PcapError>> {
+        if let Err(err) = self.skip_blocks() {
+            return Some(Err(err));
+        }
+
         match self.reader.has_data_left() {
             Ok(has_data) => {
                 if has_data {
-                    Some(self.reader.parse_with(|src| self.parser.next_block(src)))
+                    Some(self.reader.peek_with(|src| self.parser.peek_block(src)))
                 }
                 else {
                     None
@@ -58,26 +131,550 @@ impl<R: Read> PcapNgReader<R> {
         }
     }
 
+    /// Copies the next block into `buf` and returns the [`Block`] borrowing it.
+    ///
+    /// The content of `buf` is replaced and its capacity is reused, so recycling the same buffer avoids the allocation
+    /// needed to keep an owned block once the next one is read.
+    ///
+    /// On error, the invalid block has been consumed and is left in `buf`.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use std::fs::File;
+    ///
+    /// use pcap_file::pcapng::PcapNgReader;
+    ///
+    /// let file_in = File::open("test.pcapng").expect("Error opening file");
+    /// let mut pcapng_reader = PcapNgReader::new(file_in).unwrap();
+    ///
+    /// let mut buf = Vec::with_capacity(65536);
+    /// while let Some(block) = pcapng_reader.read_block_into(&mut buf) {
+    ///     let block = block.unwrap();
+    /// }
+    /// ```
+    pub fn read_block_into<'b>(&mut self, buf: &'b mut Vec<u8>) -> Option<Result<Block<'b>, PcapError>> {
+        buf.clear();
+        if let Err(err) = self.copy_next_block(buf)? {
+            return Some(Err(err));
+        }
+
+        let len = buf.len() as u64;
+        let res = self.parser.next_block(buf).map(|(_, block)| (block, len));
+
+        let res = self.stats.record(res, is_packet);
+        self.stats.repaired = self.parser.repaired_blocks();
+        ProgressCallback::update(&mut self.progress, &self.stats);
+
+        Some(res)
+    }
+
+    /// Reads up to `n` blocks into `arena` and returns them, borrowing its data, see [`BlockArena`].
+    ///
+    /// The previous blocks of the arena are removed. An empty batch is returned at the end of the input.
+    ///
+    /// Each block is parsed as soon as it is copied and the batch stops at the first invalid one, the next blocks being left
+    /// unread. The error is returned by the next call if some blocks were read before it.
+    pub fn read_blocks_in<'a>(&mut self, arena: &'a mut BlockArena, n: usize) -> Result<Vec<Block<'a>>, PcapError> {
+        arena.clear();
+        if arena.buffers.len() < n {
+            arena.buffers.resize_with(n, Vec::new);
+        }
+
+        let BlockArena { buffers, len } = arena;
+        let mut blocks = Vec::with_capacity(n);
+        for buf in &mut buffers[..n] {
+            let res = match self.copy_next_block(buf) {
+                Some(Ok(())) => {
+                    *len += 1;
+                    let data: &'a [u8] = buf;
+                    let res = self.parser.next_block(data).map(|(_, block)| (block, data.len() as u64));
+                    let res = self.stats.record(res, is_packet);
+                    self.stats.repaired = self.parser.repaired_blocks();
+                    ProgressCallback::update(&mut self.progress, &self.stats);
+                    res
+                },
+                Some(Err(err)) => Err(err),
+                None => break,
+            };
+
+            match res {
+                Ok(block) => blocks.push(block),
+                Err(err) if blocks.is_empty() => return Err(err),
+                Err(err) => {
+                    self.pending_error = Some(err);
+                    break;
+                },
+            }
+        }
+
+        Ok(blocks)
+    }
+
+    /// Appends a copy of the next block to `buf`.
+    fn copy_next_block(&mut self, buf: &mut Vec<u8>) -> Option<Result<(), PcapError>> {
+        if let Err(err) = self.skip_blocks() {
+            return Some(Err(err));
+        }
+
+        match self.reader.has_data_left() {
+            Ok(true) => {},
+            Ok(false) => return self.end_of_input(),
+            Err(e) => return Some(Err(PcapError::IoError(e))),
+        }
+
+        let lenient = self.parser.config().lenient;
+        let res = match self.parser.section().endianness {
+            Endianness::Big => self.reader.parse_with(|src| copy_block::<BigEndian>(src, lenient, buf)),
+            Endianness::Little => self.reader.parse_with(|src| copy_block::<LittleEndian>(src, lenient, buf)),
+        };
+        if res.is_err() {
+            self.stats.errors += 1;
+        }
+
+        return Some(res);
+
+        fn copy_block<'a, B: ByteOrder>(src: &'a [u8], lenient: bool, buf: &mut Vec<u8>) -> Result<(&'a [u8], ()), PcapError> {
+            let (rem, _) = if lenient {
+                RawBlock::from_slice_lenient::<B>(src)?
+            }
+            else {
+                RawBlock::from_slice::<B>(src)?
+            };
+
+            buf.extend_from_slice(&src[..src.len() - rem.len()]);
+            Ok((rem, ()))
+        }
+    }
+
+    /// Returns the next [`Block`] along with its [`BlockPosition`] in the stream.
+    ///
+    /// On error, the offset of the invalid block is given by [`PcapNgReader::position()`].
+    pub fn next_block_with_position(&mut self) -> Option<Result<(Block, BlockPosition), PcapError>> {
+        if let Err(err) = self.skip_blocks() {
+            return Some(Err(err));
+        }
+
+        let offset = self.reader.position();
+        let parser = &mut self.parser;
+
+        match self.reader.has_data_left() {
+            Ok(has_data) => {
+                if has_data {
+                    let res = self.reader.parse_with(move |src| {
+                        let (rem, block) = parser.next_block(src)?;
+                        let position = BlockPosition { offset, len: (src.len() - rem.len()) as u64 };
+                        Ok((rem, (((block, position), parser.repaired_blocks()), position.len)))
+                    });
+                    let res = self.stats.record_repaired(res, |(block, _)| is_packet(block));
+                    ProgressCallback::update(&mut self.progress, &self.stats);
+                    Some(res)
+                }
+                else {
+                    parser.end_of_input().err().map(Err)
+                }
+            },
+            Err(e) => Some(Err(PcapError::IoError(e))),
+        }
+    }
+
+    /// Returns an iterator over the remaining blocks, yielding owned blocks.
+    ///
+    /// Unlike [`Self::into_iter()`], the reader is only borrowed, so it can still be used once the iterator is dropped.
+    pub fn blocks(&mut self) -> Blocks<'_, R> {
+        PcapNgIntoIter { reader: self, _reader: PhantomData }
+    }
+
+    /// Reads up to `n` blocks and appends them to `blocks`, returning the number of blocks read, 0 at the end of the input.
+    ///
+    /// The blocks are owned, so that the batch can be handed to a worker thread. The capacity of `blocks` is reused, so
+    /// clearing the same vector between the calls avoids its reallocations.
+    ///
+    /// On error, the blocks read before the invalid one are kept in `blocks`.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use std::fs::File;
+    ///
+    /// use pcap_file::pcapng::PcapNgReader;
+    ///
+    /// let file_in = File::open("test.pcapng").expect("Error opening file");
+    /// let mut pcapng_reader = PcapNgReader::new(file_in).unwrap();
+    ///
+    /// let mut batch = Vec::with_capacity(256);
+    /// while pcapng_reader.read_blocks(&mut batch, 256).unwrap() > 0 {
+    ///     // Hand the batch to a worker thread
+    ///     batch.clear();
+    /// }
+    /// ```
+    pub fn read_blocks(&mut self, blocks: &mut Vec<Block<'static>>, n: usize) -> Result<usize, PcapError> {
+        for count in 0..n {
+            match self.next_block() {
+                Some(Ok(block)) => blocks.push(block.into_owned()),
+                Some(Err(err)) => return Err(err),
+                None => return Ok(count),
+            }
+        }
+
+        Ok(n)
+    }
+
+    /// Returns the next packet, resolved against its interface.
+    ///
+    /// The other blocks are consumed silently, the section headers and the interfaces still being tracked.
+    /// Use [`Self::next_block()`] to get them too.
+    pub fn next_packet(&mut self) -> Option<Result<PcapNgPacket<'_>, PcapError>> {
+        // The type of the next block is peeked, because a block borrowing the reader can't be returned from a loop
+        loop {
+            if let Err(err) = self.skip_blocks() {
+                return Some(Err(err));
+            }
+
+            match self.reader.has_data_left() {
+                Ok(true) => {},
+                Ok(false) => return self.end_of_input(),
+                Err(e) => return Some(Err(PcapError::IoError(e))),
+            }
+
+            let endianness = self.parser.section().endianness;
+            match self.reader.peek_with(|src| peek_block_type(src, endianness)) {
+                Ok(ENHANCED_PACKET_BLOCK | SIMPLE_PACKET_BLOCK | PACKET_BLOCK) => break,
+                Ok(_) => {},
+                Err(err) => return Some(Err(err)),
+            }
+
+            if let Err(err) = self.next_block()? {
+                return Some(Err(err));
+            }
+        }
+
+        // The block is consumed even if its interface doesn't exist
+        let parser = &mut self.parser;
+        let res = self.reader.parse_with(|src| {
+            let (rem, block) = parser.next_block(src)?;
+            let packet = PcapNgPacket::from_block_copied(block, parser.interfaces())
+                .unwrap_or(Err(PcapError::InvalidField("PcapNgReader: packet block decoded as another block")));
+            Ok((rem, ((packet, parser.repaired_blocks()), (src.len() - rem.len()) as u64)))
+        });
+        let res = self.stats.record_repaired(res, |_| true);
+        ProgressCallback::update(&mut self.progress, &self.stats);
+
+        Some(res.and_then(|packet| packet))
+    }
+
+    /// Consumes the next blocks skipped by the [`PcapNgParserConfig`], only reading their framing.
+    ///
+    /// They are skipped here rather than by the parser, so they never need to fit in the buffer along with the next block.
+    /// The error left by a dropped [`StreamedBlock`] is returned first.
+    fn skip_blocks(&mut self) -> Result<(), PcapError> {
+        self.take_pending_error()?;
+
+        if self.parser.config().skip_block_types.is_empty() {
+            return Ok(());
+        }
+
+        while self.reader.has_data_left().map_err(PcapError::IoError)? {
+            let endianness = self.parser.section().endianness;
+            let type_ = self.reader.peek_with(|src| peek_block_type(src, endianness))?;
+            if !self.parser.config().skips(type_) {
+                break;
+            }
+
+            let res = self.reader.parse_with(|src| with_len(src, self.parser.next_raw_block(src)));
+            let is_packet = |block: &RawBlock| matches!(block.type_, ENHANCED_PACKET_BLOCK | SIMPLE_PACKET_BLOCK | PACKET_BLOCK);
+            self.stats.record(res, is_packet)?;
+            self.stats.skipped += 1;
+            self.stats.repaired = self.parser.repaired_blocks();
+            ProgressCallback::update(&mut self.progress, &self.stats);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the error left by a dropped [`StreamedBlock`], once.
+    fn take_pending_error(&mut self) -> Result<(), PcapError> {
+        self.pending_error.take().map_or(Ok(()), Err)
+    }
+
+    /// Checks the length of the last section at the end of the input, see [`PcapNgParserConfig::check_section_length`].
+    fn end_of_input<T>(&mut self) -> Option<Result<T, PcapError>> {
+        self.parser.end_of_input().err().map(Err)
+    }
+
+    /// Returns an iterator over the remaining packets, resolved against their interface, yielding owned packets.
+    ///
+    /// The other blocks are consumed silently, like with [`Self::next_packet()`]. Use [`Self::blocks()`] to get them too.
+    pub fn packets(&mut self) -> Packets<'_, R> {
+        Packets { reader: self }
+    }
+
+    /// Returns the offset in the stream of the next block, relative to the start of the reader.
+    pub fn position(&self) -> u64 {
+        self.reader.position()
+    }
+
+    /// Reads all the remaining blocks and returns a [`TimeIndex`] of one packet every `interval` packets.
+    ///
+    /// The [`SimplePacketBlock`](super::blocks::simple_packet::SimplePacketBlock)s, which have no timestamp, are counted
+    /// but never indexed. The reader must have been created at the start of the capture for the index to be used by
+    /// [`Self::seek_to_time()`].
+    pub fn build_index(&mut self, interval: u32) -> Result<TimeIndex, PcapError> {
+        let mut index = TimeIndex::new(interval);
+        let mut count = 0;
+        let mut section = self.position() - self.parser.section_offset() - self.parser.section_header_len();
+
+        loop {
+            self.skip_blocks()?;
+            let offset = self.position();
+            let block = match self.next_block() {
+                Some(block) => block?,
+                None => break,
+            };
+
+            let timestamp = match block {
+                Block::SectionHeader(_) => {
+                    section = offset;
+                    continue;
+                },
+                Block::EnhancedPacket(packet) => Some((packet.interface_id, packet.timestamp.as_nanos() as u64)),
+                Block::Packet(packet) => Some((packet.interface_id as u32, packet.timestamp)),
+                Block::SimplePacket(_) => None,
+                _ => continue,
+            };
+
+            let timestamp = timestamp.and_then(|(id, ts)| self.interfaces().get(id as usize).map(|idb| idb.timestamp_to_duration(ts)));
+            if let Some(timestamp) = timestamp {
+                index.add_packet(count, IndexEntry { timestamp, offset, section });
+            }
+            count += 1;
+        }
+        index.set_capture_len(self.position());
+
+        Ok(index)
+    }
+
     /// Returns the next [`RawBlock`].
     pub fn next_raw_block(&mut self) -> Option<Result<RawBlock, PcapError>> {
+        if let Err(err) = self.take_pending_error() {
+            return Some(Err(err));
+        }
+
         match self.reader.has_data_left() {
             Ok(has_data) => {
                 if has_data {
-                    Some(self.reader.parse_with(|src| self.parser.next_raw_block(src)))
+                    let res = self.reader.parse_with(|src| with_repaired(with_len(src, self.parser.next_raw_block(src)), &self.parser));
+                    let is_packet = |block: &RawBlock| matches!(block.type_, ENHANCED_PACKET_BLOCK | SIMPLE_PACKET_BLOCK | PACKET_BLOCK);
+                    let res = self.stats.record_repaired(res, is_packet);
+                    ProgressCallback::update(&mut self.progress, &self.stats);
+                    Some(res)
                 }
                 else {
-                    None
+                    self.end_of_input()
+                }
+            },
+            Err(e) => Some(Err(PcapError::IoError(e))),
+        }
+    }
+
+    /// Returns the next [`PreservedBlock`], keeping the original bytes of the block, see [`PcapNgParser::next_preserved_block()`].
+    pub fn next_preserved_block(&mut self) -> Option<Result<PreservedBlock, PcapError>> {
+        if let Err(err) = self.take_pending_error() {
+            return Some(Err(err));
+        }
+
+        match self.reader.has_data_left() {
+            Ok(has_data) => {
+                if has_data {
+                    let res = self.reader.parse_with(|src| with_repaired(with_len(src, self.parser.next_preserved_block(src)), &self.parser));
+                    let is_packet = |block: &PreservedBlock| matches!(block.block(), Block::EnhancedPacket(_) | Block::SimplePacket(_) | Block::Packet(_));
+                    let res = self.stats.record_repaired(res, is_packet);
+                    ProgressCallback::update(&mut self.progress, &self.stats);
+                    Some(res)
+                }
+                else {
+                    self.end_of_input()
                 }
             },
             Err(e) => Some(Err(PcapError::IoError(e))),
         }
     }
 
+    /// Returns the next block as a [`StreamedBlock`], whose body is read on demand instead of being loaded in the
+    /// internal buffer.
+    ///
+    /// It reads the blocks larger than the internal buffer, which [`Self::next_block()`] refuses, e.g. a
+    /// [`DecryptionSecretsBlock`](super::blocks::decryption_secrets::DecryptionSecretsBlock) embedding a
+    /// multi-megabyte key log. The section headers and interface descriptions, which the following blocks depend
+    /// on, are never streamed and must be read with [`Self::next_block()`]: an error is returned for them, without
+    /// consuming them.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use std::fs::File;
+    /// use std::io::Read;
+    ///
+    /// use pcap_file::pcapng::blocks::DECRYPTION_SECRETS_BLOCK;
+    /// use pcap_file::pcapng::PcapNgReader;
+    ///
+    /// let file_in = File::open("test.pcapng").expect("Error opening file");
+    /// let mut pcapng_reader = PcapNgReader::new(file_in).unwrap();
+    ///
+    /// let mut block = pcapng_reader.next_streamed_block().unwrap().unwrap();
+    /// if block.type_() == DECRYPTION_SECRETS_BLOCK {
+    ///     // Skip the secrets type and length, then copy the secrets by chunks
+    ///     let mut header = [0_u8; 8];
+    ///     block.read_exact(&mut header).unwrap();
+    ///     std::io::copy(&mut block, &mut File::create("secrets.bin").unwrap()).unwrap();
+    /// }
+    /// block.finish().unwrap();
+    /// ```
+    pub fn next_streamed_block(&mut self) -> Option<Result<StreamedBlock<'_, R>, PcapError>> {
+        if let Err(err) = self.skip_blocks() {
+            return Some(Err(err));
+        }
+
+        match self.reader.has_data_left() {
+            Ok(true) => {},
+            Ok(false) => return self.end_of_input(),
+            Err(e) => return Some(Err(PcapError::IoError(e))),
+        }
+
+        let endianness = self.parser.section().endianness;
+        let (type_, initial_len) = match self.reader.peek_with(|src| peek_block_header(src, endianness)) {
+            Ok(header) => header,
+            Err(err) => return Some(Err(err)),
+        };
+
+        if type_ == SECTION_HEADER_BLOCK || type_ == INTERFACE_DESCRIPTION_BLOCK {
+            return Some(Err(PcapError::InvalidField("PcapNgReader: section headers and interface descriptions can't be streamed")));
+        }
+        if initial_len % 4 != 0 {
+            self.stats.errors += 1;
+            return Some(Err(PcapError::InvalidField("Block: (initial_len % 4) != 0")));
+        }
+        if initial_len < 12 {
+            self.stats.errors += 1;
+            return Some(Err(PcapError::InvalidField("Block: initial_len < 12")));
+        }
+
+        // Consumes the header, the body is read by the StreamedBlock
+        if let Err(err) = self.reader.parse_with(|src| Ok((&src[8..], ()))) {
+            return Some(Err(err));
+        }
+
+        let remaining = initial_len as u64 - 12;
+        Some(Ok(StreamedBlock { reader: self, type_, initial_len, endianness, remaining, done: false }))
+    }
+
+    /// Returns the next packet as a [`StreamedPacket`], whose data is read on demand in chunks of the size of the caller's
+    /// buffer, instead of being loaded in the internal buffer.
+    ///
+    /// It reads the packets larger than the internal buffer, e.g. the jumbo frames or the USB transfers of several
+    /// hundreds of kilobytes, without one contiguous allocation per packet. The other blocks are consumed silently like
+    /// with [`Self::next_packet()`], the ones other than the section headers and the interfaces being streamed too.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use std::fs::File;
+    /// use std::io::Read;
+    ///
+    /// use pcap_file::pcapng::PcapNgReader;
+    ///
+    /// let file_in = File::open("test.pcapng").expect("Error opening file");
+    /// let mut pcapng_reader = PcapNgReader::new(file_in).unwrap();
+    ///
+    /// let mut chunk = [0_u8; 16384];
+    /// while let Some(packet) = pcapng_reader.next_streamed_packet() {
+    ///     let mut packet = packet.unwrap();
+    ///     println!("{:?}: {} bytes", packet.timestamp(), packet.captured_len());
+    ///
+    ///     loop {
+    ///         let len = packet.read(&mut chunk).unwrap();
+    ///         if len == 0 {
+    ///             break;
+    ///         }
+    ///         // Do something with &chunk[..len]
+    ///     }
+    /// }
+    /// ```
+    pub fn next_streamed_packet(&mut self) -> Option<Result<StreamedPacket<'_, R>, PcapError>> {
+        loop {
+            if let Err(err) = self.skip_blocks() {
+                return Some(Err(err));
+            }
+
+            match self.reader.has_data_left() {
+                Ok(true) => {},
+                Ok(false) => return self.end_of_input(),
+                Err(e) => return Some(Err(PcapError::IoError(e))),
+            }
+
+            let endianness = self.parser.section().endianness;
+            let res = match self.reader.peek_with(|src| peek_block_type(src, endianness)) {
+                Ok(ENHANCED_PACKET_BLOCK | SIMPLE_PACKET_BLOCK | PACKET_BLOCK) => break,
+                Ok(SECTION_HEADER_BLOCK | INTERFACE_DESCRIPTION_BLOCK) => self.next_block()?.map(|_| ()),
+                Ok(_) => self.next_streamed_block()?.and_then(StreamedBlock::finish),
+                Err(err) => Err(err),
+            };
+
+            if let Err(err) = res {
+                return Some(Err(err));
+            }
+        }
+
+        // The block is consumed even if its interface doesn't exist
+        Some(self.next_streamed_block()?.and_then(StreamedPacket::new))
+    }
+
+    /// Registers a user-defined [`PcapNgBlock`], see [`PcapNgParser::register_block()`].
+    pub fn register_block<T>(&mut self)
+    where
+        T: for<'b> PcapNgBlock<'b> + Clone + Debug + Eq + Send + Sync + 'static,
+    {
+        self.parser.register_block::<T>()
+    }
+
+    /// Returns the counters of the blocks read since the creation of the reader, including its section header.
+    pub fn stats(&self) -> IoStats {
+        IoStats { repaired: self.parser.repaired_blocks(), ..self.stats }
+    }
+
+    /// Registers a callback called each time `interval` bytes have been consumed, to report the progress of the reader.
+    ///
+    /// The `total` size of the input, e.g. the length of the file, is given back to the callback to compute the
+    /// [`Progress::fraction()`].
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use std::fs::File;
+    ///
+    /// use pcap_file::pcapng::PcapNgReader;
+    ///
+    /// let file_in = File::open("test.pcapng").expect("Error opening file");
+    /// let total = file_in.metadata().unwrap().len();
+    ///
+    /// let mut pcapng_reader = PcapNgReader::new(file_in).unwrap();
+    /// pcapng_reader.set_progress_callback(1 << 20, Some(total), |progress| {
+    ///     println!("{:.0}%", progress.fraction().unwrap() * 100.0);
+    /// });
+    ///
+    /// while let Some(block) = pcapng_reader.next_block() {
+    ///     let block = block.unwrap();
+    /// }
+    /// ```
+    pub fn set_progress_callback<F: FnMut(Progress) + Send + 'static>(&mut self, interval: u64, total: Option<u64>, callback: F) {
+        self.progress = Some(ProgressCallback::new(&self.stats, interval, total, callback));
+    }
+
     /// Returns the current [`SectionHeaderBlock`].
     pub fn section(&self) -> &SectionHeaderBlock<'static> {
         self.parser.section()
     }
 
+    /// Returns the original bytes of the current [`SectionHeaderBlock`].
+    pub fn raw_section(&self) -> &RawBlock<'static> {
+        self.parser.raw_section()
+    }
+
     /// Returns all the current [`InterfaceDescriptionBlock`].
     pub fn interfaces(&self) -> &[InterfaceDescriptionBlock<'static>] {
         self.parser.interfaces()
@@ -89,6 +686,8 @@ impl<R: Read> PcapNgReader<R> {
     }
 
     /// Consumes the [`Self`], returning the wrapped reader.
+    ///
+    /// The data already read into the internal buffer but not returned as blocks yet is lost.
     pub fn into_inner(self) -> R {
         self.reader.into_inner()
     }
@@ -97,4 +696,679 @@ impl<R: Read> PcapNgReader<R> {
     pub fn get_ref(&self) -> &R {
         self.reader.get_ref()
     }
+
+    /// Gets a mutable reference to the wrapped reader.
+    ///
+    /// You should not read from it or seek it unless you really know what you're doing, the internal buffer being bypassed.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.reader.get_mut()
+    }
+}
+
+impl<R: Read + Seek> PcapNgReader<R> {
+    /// Returns an iterator over the type and [`BlockPosition`] of the blocks remaining after the current position,
+    /// without consuming them.
+    ///
+    /// Only the headers of the blocks are read, their bodies are skipped by seeking the inner reader, so it runs at
+    /// near-disk speed, e.g. to build an index of a capture. The blocks are not validated, so a corrupted capture
+    /// may give wrong blocks instead of an error.
+    ///
+    /// The inner reader is seeked back to its position when the iterator is dropped.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use std::fs::File;
+    ///
+    /// use pcap_file::pcapng::PcapNgReader;
+    ///
+    /// let file_in = File::open("test.pcapng").expect("Error opening file");
+    /// let mut pcapng_reader = PcapNgReader::new(file_in).unwrap();
+    ///
+    /// for block in pcapng_reader.scan_blocks().unwrap() {
+    ///     let block = block.unwrap();
+    ///     println!("Block {:#x} at {}, {} bytes", block.type_, block.position.offset, block.position.len);
+    /// }
+    /// ```
+    pub fn scan_blocks(&mut self) -> Result<ScanBlocks<'_, R>, PcapError> {
+        let start = self.reader.get_mut().stream_position().map_err(PcapError::IoError)?;
+        let endianness = self.parser.section().endianness;
+        let base = self.reader.position();
+
+        Ok(ScanBlocks { reader: self, start, base, offset: 0, endianness, done: false })
+    }
+
+    /// Skips the rest of the current section, using the section length declared in its header.
+    ///
+    /// The blocks are not read: the inner reader is seeked past the whole section in one jump, so the next block is the
+    /// section header of the next section, e.g. to only read the last section of a capture. Returns `false` and
+    /// does nothing if the section length is unspecified.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use std::fs::File;
+    ///
+    /// use pcap_file::pcapng::PcapNgReader;
+    ///
+    /// let file_in = File::open("test.pcapng").expect("Error opening file");
+    /// let mut pcapng_reader = PcapNgReader::new(file_in).unwrap();
+    ///
+    /// // Read the second section
+    /// pcapng_reader.skip_section().unwrap();
+    /// while let Some(block) = pcapng_reader.next_block() {
+    ///     let block = block.unwrap();
+    /// }
+    /// ```
+    pub fn skip_section(&mut self) -> Result<bool, PcapError> {
+        let section_length = self.parser.section().section_length;
+        if section_length == -1 {
+            return Ok(false);
+        }
+
+        let remaining = u64::try_from(section_length)
+            .ok()
+            .and_then(|len| len.checked_sub(self.parser.section_offset()))
+            .ok_or(PcapError::InvalidField("SectionHeaderBlock: section_length < length of the blocks read"))?;
+
+        self.reader.skip(remaining).map_err(PcapError::IoError)?;
+        self.parser.skip_section_bytes(remaining);
+
+        Ok(true)
+    }
+
+    /// Seeks to the last packet of the [`TimeIndex`] whose timestamp is not after `timestamp`, or to the first one.
+    ///
+    /// The section header of the packet and the interfaces which immediately follow it are read again, then the next
+    /// blocks are read from the packet, the first packet at `timestamp` being at most [`TimeIndex::interval()`] packets
+    /// further. The interfaces described later in the section, before the packet, are not known. Returns `false` and
+    /// does nothing if the index is empty, and an error if the length of the capture isn't the one of the index.
+    pub fn seek_to_time(&mut self, index: &TimeIndex, timestamp: Duration) -> Result<bool, PcapError> {
+        let entry = match index.find(timestamp) {
+            Some(entry) => *entry,
+            None => return Ok(false),
+        };
+        index.check_capture_len(self.reader.stream_len().map_err(PcapError::IoError)?)?;
+
+        // The current section is left, so its length can't be checked
+        let _ = self.parser.end_of_input();
+
+        self.reader.seek_to(entry.section).map_err(PcapError::IoError)?;
+        match self.next_raw_block() {
+            Some(Ok(block)) if block.type_ == SECTION_HEADER_BLOCK => {},
+            Some(Err(err)) => return Err(err),
+            _ => return Err(PcapError::InvalidField("TimeIndex: no section header at the indexed position")),
+        }
+
+        while self.position() < entry.offset && self.reader.has_data_left().map_err(PcapError::IoError)? {
+            let endianness = self.parser.section().endianness;
+            if self.reader.peek_with(|src| peek_block_type(src, endianness))? != INTERFACE_DESCRIPTION_BLOCK {
+                break;
+            }
+            if let Some(Err(err)) = self.next_raw_block() {
+                return Err(err);
+            }
+        }
+
+        let skipped = entry
+            .offset
+            .checked_sub(self.position())
+            .ok_or(PcapError::InvalidField("TimeIndex: indexed packet inside an interface description"))?;
+        self.parser.skip_section_bytes(skipped);
+        self.reader.seek_to(entry.offset).map_err(PcapError::IoError)?;
+
+        Ok(true)
+    }
+
+    /// Counts the packets remaining after the current position, without consuming them.
+    ///
+    /// Like [`Self::scan_blocks()`], the block bodies are skipped, so it is much faster than parsing the capture,
+    /// e.g. to size a progress bar.
+    pub fn count_packets(&mut self) -> Result<u64, PcapError> {
+        self.scan_blocks()?.try_fold(0, |count, block| {
+            let is_packet = matches!(block?.type_, ENHANCED_PACKET_BLOCK | SIMPLE_PACKET_BLOCK | PACKET_BLOCK);
+            Ok(count + is_packet as u64)
+        })
+    }
+
+    /// Reads at `offset` from the next block, first from the internal buffer then from the inner reader,
+    /// which is at `start` after the buffered data. Returns the number of bytes read.
+    fn read_at(&mut self, start: u64, offset: u64, buf: &mut [u8]) -> Result<usize, PcapError> {
+        let buffered = self.reader.buffer();
+        let buffered_len = buffered.len() as u64;
+
+        let mut filled = 0;
+        if offset < buffered_len {
+            let src = &buffered[offset as usize..];
+            filled = src.len().min(buf.len());
+            buf[..filled].copy_from_slice(&src[..filled]);
+        }
+        if filled == buf.len() {
+            return Ok(filled);
+        }
+
+        let reader = self.reader.get_mut();
+        let pos = start + (offset + filled as u64 - buffered_len);
+        reader.seek(SeekFrom::Start(pos)).map_err(PcapError::IoError)?;
+        while filled < buf.len() {
+            match reader.read(&mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(nb_read) => filled += nb_read,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {},
+                Err(err) => return Err(PcapError::IoError(err)),
+            }
+        }
+
+        Ok(filled)
+    }
+}
+
+impl PcapNgReader<StdinLock<'static>> {
+    /// Creates a new [`PcapNgReader`] reading from the locked standard input.
+    ///
+    /// Parses the first block which must be a valid SectionHeaderBlock.
+    pub fn from_stdin() -> Result<Self, PcapError> {
+        Self::new(std::io::stdin().lock())
+    }
+}
+
+impl<R: Read> IntoIterator for PcapNgReader<R> {
+    type Item = Result<Block<'static>, PcapError>;
+    type IntoIter = PcapNgIntoIter<R>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        PcapNgIntoIter { reader: self, _reader: PhantomData }
+    }
+}
+
+
+/// Iterator over the blocks of a [`PcapNgReader`], yielding owned blocks.
+///
+/// Unlike the blocks returned by [`PcapNgReader::next_block()`], they don't borrow the reader, so they can be stored
+/// or sent to other threads.
+///
+/// The iterator either owns the reader, when returned by [`PcapNgReader::into_iter()`], or borrows it, see [`Blocks`].
+///
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+///
+/// use pcap_file::pcapng::PcapNgReader;
+///
+/// let file_in = File::open("test.pcapng").expect("Error opening file");
+/// let pcapng_reader = PcapNgReader::new(file_in).unwrap();
+///
+/// let handle = std::thread::spawn(move || pcapng_reader.into_iter().filter_map(Result::ok).count());
+/// let block_count = handle.join().unwrap();
+/// ```
+pub struct PcapNgIntoIter<R: Read, Rd = PcapNgReader<R>> {
+    reader: Rd,
+    _reader: PhantomData<PcapNgReader<R>>,
+}
+
+impl<R: Read, Rd: BorrowMut<PcapNgReader<R>>> PcapNgIntoIter<R, Rd> {
+    /// Returns a reference to the [`PcapNgReader`], e.g. to get the current section and interfaces.
+    pub fn reader(&self) -> &PcapNgReader<R> {
+        self.reader.borrow()
+    }
+
+    /// Consumes [`Self`], returning the [`PcapNgReader`], or the reference to it.
+    pub fn into_reader(self) -> Rd {
+        self.reader
+    }
+}
+
+impl<R: Read, Rd: BorrowMut<PcapNgReader<R>>> Iterator for PcapNgIntoIter<R, Rd> {
+    type Item = Result<Block<'static>, PcapError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.borrow_mut().next_block().map(|res| res.map(|block| block.into_owned()))
+    }
+}
+
+/// Yields owned blocks, like [`PcapNgReader::blocks()`], with the errors separated from the items.
+#[cfg(feature = "fallible-iterator")]
+impl<R: Read> fallible_iterator::FallibleIterator for PcapNgReader<R> {
+    type Item = Block<'static>;
+    type Error = PcapError;
+
+    fn next(&mut self) -> Result<Option<Self::Item>, Self::Error> {
+        self.next_block().transpose().map(|block| block.map(Block::into_owned))
+    }
+}
+
+/// [`PcapNgIntoIter`] borrowing the [`PcapNgReader`], returned by [`PcapNgReader::blocks()`].
+///
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+///
+/// use pcap_file::pcapng::{Block, PcapNgReader};
+///
+/// let file_in = File::open("test.pcapng").expect("Error opening file");
+/// let mut pcapng_reader = PcapNgReader::new(file_in).unwrap();
+///
+/// let packets = pcapng_reader
+///     .blocks()
+///     .filter_map(Result::ok)
+///     .filter(|block| matches!(block, Block::EnhancedPacket(_)))
+///     .take(10)
+///     .collect::<Vec<_>>();
+///
+/// println!("{} interfaces", pcapng_reader.interfaces().len());
+/// ```
+pub type Blocks<'a, R> = PcapNgIntoIter<R, &'a mut PcapNgReader<R>>;
+
+
+/// Iterator over the packets of a borrowed [`PcapNgReader`], yielding owned packets, returned by [`PcapNgReader::packets()`].
+///
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+///
+/// use pcap_file::pcapng::PcapNgReader;
+///
+/// let file_in = File::open("test.pcapng").expect("Error opening file");
+/// let mut pcapng_reader = PcapNgReader::new(file_in).unwrap();
+///
+/// for packet in pcapng_reader.packets() {
+///     let packet = packet.unwrap();
+///     println!("{:?} {:?}: {} bytes", packet.timestamp, packet.linktype, packet.data.len());
+/// }
+/// ```
+pub struct Packets<'a, R: Read> {
+    reader: &'a mut PcapNgReader<R>,
+}
+
+impl<R: Read> Iterator for Packets<'_, R> {
+    type Item = Result<PcapNgPacket<'static>, PcapError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.next_packet().map(|res| res.map(|packet| packet.into_owned()))
+    }
+}
+
+
+/// Block whose body is read on demand through [`Read`], returned by [`PcapNgReader::next_streamed_block()`].
+///
+/// Reading past the body returns 0. The rest of the body and the trailer are consumed by [`Self::finish()`], which
+/// checks the trailer, or when the block is dropped, the error being then returned by the next read of the reader.
+/// The other blocks are read after it, the error being returned once.
+pub struct StreamedBlock<'a, R: Read> {
+    reader: &'a mut PcapNgReader<R>,
+    type_: u32,
+    initial_len: u32,
+    endianness: Endianness,
+    /// Number of bytes of the body not read yet
+    remaining: u64,
+    /// The rest of the block has been consumed
+    done: bool,
+}
+
+impl<R: Read> StreamedBlock<'_, R> {
+    /// Returns the type of the block.
+    pub fn type_(&self) -> u32 {
+        self.type_
+    }
+
+    /// Returns the length of the block, including its header and trailer.
+    pub fn initial_len(&self) -> u32 {
+        self.initial_len
+    }
+
+    /// Returns the length of the body of the block.
+    pub fn body_len(&self) -> u64 {
+        self.initial_len as u64 - 12
+    }
+
+    /// Returns the number of bytes of the body not read yet.
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    /// Returns the endianness of the body.
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// Consumes the rest of the block and checks its trailer.
+    pub fn finish(mut self) -> Result<(), PcapError> {
+        self.finish_inner()
+    }
+
+    fn finish_inner(&mut self) -> Result<(), PcapError> {
+        if self.done {
+            return Ok(());
+        }
+        self.done = true;
+
+        let mut buf = [0_u8; 8192];
+        while self.remaining > 0 {
+            let len = self.read(&mut buf).map_err(PcapError::IoError)?;
+            if len == 0 {
+                return Err(PcapError::IoError(ErrorKind::UnexpectedEof.into()));
+            }
+        }
+
+        let endianness = self.endianness;
+        let reader = &mut *self.reader;
+        let res = reader.reader.parse_with(|src| read_trailer_len(src, endianness));
+        let trailer_len = match res {
+            Ok(trailer_len) => trailer_len,
+            Err(err) => {
+                reader.stats.errors += 1;
+                return Err(err);
+            },
+        };
+
+        let tolerated = reader.parser.config().lenient && self.initial_len.abs_diff(trailer_len) < 4;
+        if self.initial_len != trailer_len {
+            if !tolerated {
+                reader.stats.errors += 1;
+                return Err(PcapError::InvalidField("Block: initial_length != trailer_length"));
+            }
+            reader.parser.count_repaired_block();
+        }
+
+        let len = self.initial_len as u64;
+        let is_packet = |type_: &u32| matches!(*type_, ENHANCED_PACKET_BLOCK | SIMPLE_PACKET_BLOCK | PACKET_BLOCK);
+        reader.parser.skip_section_bytes(len);
+        reader.stats.record(Ok::<_, PcapError>((self.type_, len)), is_packet)?;
+        reader.stats.repaired = reader.parser.repaired_blocks();
+        ProgressCallback::update(&mut reader.progress, &reader.stats);
+
+        return Ok(());
+
+        fn read_trailer_len(src: &[u8], endianness: Endianness) -> Result<(&[u8], u32), PcapError> {
+            if src.len() < 4 {
+                return Err(PcapError::IncompleteBuffer);
+            }
+
+            let trailer_len = match endianness {
+                Endianness::Big => BigEndian::read_u32(&src[..4]),
+                Endianness::Little => LittleEndian::read_u32(&src[..4]),
+            };
+
+            Ok((&src[4..], trailer_len))
+        }
+    }
+}
+
+impl<R: Read> Read for StreamedBlock<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.remaining == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let len = buf.len().min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        let len = self.reader.reader.read_raw(&mut buf[..len])?;
+        if len == 0 {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        self.remaining -= len as u64;
+
+        Ok(len)
+    }
+}
+
+impl<R: Read> Drop for StreamedBlock<'_, R> {
+    fn drop(&mut self) {
+        // The error can't be reported here, so it is kept for the next read
+        if let Err(err) = self.finish_inner() {
+            self.reader.pending_error = Some(err);
+        }
+    }
+}
+
+
+/// Packet whose data is read on demand through [`Read`], returned by [`PcapNgReader::next_streamed_packet()`].
+///
+/// Only the header of the packet block is decoded: the options, which follow the data, are not. Reading past the
+/// captured data returns 0. The rest of the block is consumed by [`Self::finish()`] or when the packet is dropped,
+/// like a [`StreamedBlock`].
+pub struct StreamedPacket<'a, R: Read> {
+    block: StreamedBlock<'a, R>,
+    interface_id: u32,
+    linktype: DataLink,
+    timestamp: Option<Duration>,
+    captured_len: u32,
+    original_len: u32,
+    /// Number of bytes of the data not read yet
+    remaining: u32,
+}
+
+impl<'a, R: Read> StreamedPacket<'a, R> {
+    /// Decodes the header of a streamed packet block and resolves it against its interface.
+    fn new(mut block: StreamedBlock<'a, R>) -> Result<Self, PcapError> {
+        let header_len = match block.type_ {
+            SIMPLE_PACKET_BLOCK => 4,
+            _ => 20,
+        };
+        if block.body_len() < header_len as u64 {
+            return Err(PcapError::InvalidField("StreamedPacket: block length < packet header length"));
+        }
+
+        let mut header = [0_u8; 20];
+        block.read_exact(&mut header[..header_len]).map_err(PcapError::IoError)?;
+
+        let (interface_id, timestamp, captured_len, original_len) = match block.endianness {
+            Endianness::Big => parse_header::<BigEndian>(block.type_, &header, block.body_len()),
+            Endianness::Little => parse_header::<LittleEndian>(block.type_, &header, block.body_len()),
+        };
+
+        let pad_len = (4 - (captured_len as u64 % 4)) % 4;
+        if captured_len as u64 + pad_len > block.remaining {
+            return Err(PcapError::InvalidField("StreamedPacket: captured_len + padding > block length"));
+        }
+
+        let interface = block.reader.parser.interfaces().get(interface_id as usize).ok_or(PcapError::InvalidInterfaceId(interface_id))?;
+        let linktype = interface.linktype;
+        let timestamp = timestamp.map(|ts| interface.timestamp_to_duration(ts));
+
+        // The captured length of a simple packet is bounded by the snaplen of its interface too
+        let captured_len = match block.type_ {
+            SIMPLE_PACKET_BLOCK => captured_len.min(SnapLen(interface.snaplen).captured_len(original_len as usize) as u32),
+            _ => captured_len,
+        };
+
+        return Ok(StreamedPacket { block, interface_id, linktype, timestamp, captured_len, original_len, remaining: captured_len });
+
+        fn parse_header<B: ByteOrder>(type_: u32, header: &[u8], body_len: u64) -> (u32, Option<u64>, u32, u32) {
+            match type_ {
+                SIMPLE_PACKET_BLOCK => {
+                    let original_len = B::read_u32(&header[0..4]);
+                    let captured_len = (original_len as u64).min(body_len - 4) as u32;
+                    (0, None, captured_len, original_len)
+                },
+                PACKET_BLOCK => {
+                    let interface_id = B::read_u16(&header[0..2]) as u32;
+                    let timestamp = ((B::read_u32(&header[4..8]) as u64) << 32) + B::read_u32(&header[8..12]) as u64;
+                    (interface_id, Some(timestamp), B::read_u32(&header[12..16]), B::read_u32(&header[16..20]))
+                },
+                _ => {
+                    let interface_id = B::read_u32(&header[0..4]);
+                    let timestamp = ((B::read_u32(&header[4..8]) as u64) << 32) + B::read_u32(&header[8..12]) as u64;
+                    (interface_id, Some(timestamp), B::read_u32(&header[12..16]), B::read_u32(&header[16..20]))
+                },
+            }
+        }
+    }
+
+    /// Returns the index of the interface of the packet within its section.
+    pub fn interface_id(&self) -> u32 {
+        self.interface_id
+    }
+
+    /// Returns the link type of the interface.
+    pub fn linktype(&self) -> DataLink {
+        self.linktype
+    }
+
+    /// Returns the time since the Unix epoch, with the resolution and offset of the interface applied,
+    /// `None` for a simple packet which has no timestamp.
+    pub fn timestamp(&self) -> Option<Duration> {
+        self.timestamp
+    }
+
+    /// Returns the length of the captured data.
+    pub fn captured_len(&self) -> u32 {
+        self.captured_len
+    }
+
+    /// Returns the actual length of the packet when it was transmitted on the network.
+    pub fn original_len(&self) -> u32 {
+        self.original_len
+    }
+
+    /// Returns the number of bytes of the data not read yet.
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+
+    /// Consumes the rest of the block and checks its trailer.
+    pub fn finish(self) -> Result<(), PcapError> {
+        self.block.finish()
+    }
+}
+
+impl<R: Read> Read for StreamedPacket<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(self.remaining as usize);
+        let len = self.block.read(&mut buf[..len])?;
+        self.remaining -= len as u32;
+
+        Ok(len)
+    }
+}
+
+
+/// Iterator over the block headers of a [`PcapNgReader`], returned by [`PcapNgReader::scan_blocks()`].
+///
+/// The iteration stops after the first error.
+pub struct ScanBlocks<'a, R: Read + Seek> {
+    reader: &'a mut PcapNgReader<R>,
+    /// Position of the inner reader when the iterator was created
+    start: u64,
+    /// Position of the next block relative to the start of the reader
+    base: u64,
+    /// Offset of the next scanned block from the next block of the reader
+    offset: u64,
+    endianness: Endianness,
+    done: bool,
+}
+
+impl<R: Read + Seek> Iterator for ScanBlocks<'_, R> {
+    type Item = Result<ScannedBlock, PcapError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let res = self.scan_next().transpose();
+        self.done = !matches!(res, Some(Ok(_)));
+        res
+    }
+}
+
+impl<R: Read + Seek> ScanBlocks<'_, R> {
+    fn scan_next(&mut self) -> Result<Option<ScannedBlock>, PcapError> {
+        let mut header = [0_u8; 12];
+        match self.reader.read_at(self.start, self.offset, &mut header)? {
+            0 => return Ok(None),
+            12 => {},
+            _ => return Err(PcapError::IoError(ErrorKind::UnexpectedEof.into())),
+        }
+
+        let type_ = read_u32(&header[0..4], self.endianness);
+        if type_ == SECTION_HEADER_BLOCK {
+            self.endianness = match BigEndian::read_u32(&header[8..12]) {
+                0x1A2B3C4D => Endianness::Big,
+                0x4D3C2B1A => Endianness::Little,
+                _ => return Err(PcapError::InvalidField("SectionHeaderBlock: invalid magic number")),
+            };
+        }
+
+        let len = read_u32(&header[4..8], self.endianness) as u64;
+        if len < 12 {
+            return Err(PcapError::InvalidField("PcapNgReader: invalid block length"));
+        }
+
+        let position = BlockPosition { offset: self.base + self.offset, len };
+        self.offset += len;
+
+        return Ok(Some(ScannedBlock { type_, position }));
+
+        fn read_u32(src: &[u8], endianness: Endianness) -> u32 {
+            match endianness {
+                Endianness::Big => BigEndian::read_u32(src),
+                Endianness::Little => LittleEndian::read_u32(src),
+            }
+        }
+    }
+}
+
+impl<R: Read + Seek> Drop for ScanBlocks<'_, R> {
+    fn drop(&mut self) {
+        // The errors can't be reported, the next read will fail instead
+        let _ = self.reader.reader.get_mut().seek(SeekFrom::Start(self.start));
+    }
+}
+
+
+/// Type and position of a block, returned by [`PcapNgReader::scan_blocks()`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct ScannedBlock {
+    /// Type of the block
+    pub type_: u32,
+
+    /// Position of the block in the stream
+    pub position: BlockPosition,
+}
+
+
+/// Position of a block in a PcapNg stream.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct BlockPosition {
+    /// Offset in bytes of the start of the block, relative to the start of the reader
+    pub offset: u64,
+
+    /// Length in bytes of the block, including its header and trailer
+    pub len: u64,
+}
+
+fn is_packet(block: &Block) -> bool {
+    matches!(block, Block::EnhancedPacket(_) | Block::SimplePacket(_) | Block::Packet(_))
+}
+
+/// Adds the number of blocks repaired so far by the parser to the result of [`with_len()`], see [`IoStats::record_repaired()`]
+#[allow(clippy::type_complexity)]
+fn with_repaired<'a, T>(res: PcapResult<(&'a [u8], (T, u64))>, parser: &PcapNgParser) -> PcapResult<(&'a [u8], ((T, u64), u64))> {
+    res.map(|(rem, (value, len))| (rem, ((value, parser.repaired_blocks()), len)))
+}
+
+/// Reads the type and the length of the next block without consuming it
+fn peek_block_header(src: &[u8], endianness: Endianness) -> Result<(&[u8], (u32, u32)), PcapError> {
+    if src.len() < 8 {
+        return Err(PcapError::IncompleteBuffer);
+    }
+
+    let header = match endianness {
+        Endianness::Big => (BigEndian::read_u32(&src[..4]), BigEndian::read_u32(&src[4..8])),
+        Endianness::Little => (LittleEndian::read_u32(&src[..4]), LittleEndian::read_u32(&src[4..8])),
+    };
+
+    Ok((src, header))
+}
+
+/// Reads the type of the next block without consuming it
+fn peek_block_type(src: &[u8], endianness: Endianness) -> Result<(&[u8], u32), PcapError> {
+    if src.len() < 4 {
+        return Err(PcapError::IncompleteBuffer);
+    }
+
+    let type_ = match endianness {
+        Endianness::Big => BigEndian::read_u32(&src[..4]),
+        Endianness::Little => LittleEndian::read_u32(&src[..4]),
+    };
+
+    Ok((src, type_))
 }
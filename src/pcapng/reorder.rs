@@ -0,0 +1,307 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::io::{Read, Write};
+use std::time::{Duration, SystemTime};
+
+use super::blocks::enhanced_packet::EnhancedPacketBlock;
+use super::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceId};
+use super::{Block, PcapNgBlock, PcapNgReader, PcapNgWriter};
+use crate::errors::PcapError;
+use crate::PcapResult;
+
+
+/// Wraps a [`PcapNgReader`] to detect the packets whose timestamp goes backward, and optionally to sort them.
+///
+/// The packets are kept in a buffer of `window` packets and returned in timestamp order, which fixes
+/// the small interleavings of the captures made on several queues of a NIC. A `window` of 0 disables the
+/// buffering and only detects the disorder.
+///
+/// The other blocks keep their position: the buffered packets are all returned before them.
+///
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+///
+/// use pcap_file::pcapng::{OrderedPcapNgReader, PcapNgReader};
+///
+/// let file_in = File::open("test.pcapng").expect("Error opening file");
+/// let pcapng_reader = PcapNgReader::new(file_in).unwrap();
+/// let mut ordered_reader = OrderedPcapNgReader::new(pcapng_reader, 64);
+///
+/// while let Some(block) = ordered_reader.next_block() {
+///     let block = block.unwrap();
+/// }
+///
+/// println!("{} packets still out of order", ordered_reader.out_of_order_count());
+/// ```
+pub struct OrderedPcapNgReader<R: Read> {
+    reader: PcapNgReader<R>,
+    window: usize,
+    buffer: BinaryHeap<Reverse<Entry>>,
+    /// Non-packet block returned once the buffer is empty
+    pending: Option<Block<'static>>,
+    sequence: u64,
+    last_timestamp: Option<Duration>,
+    last_out_of_order: bool,
+    out_of_order_count: u64,
+    eof: bool,
+}
+
+impl<R: Read> OrderedPcapNgReader<R> {
+    /// Creates a new [`OrderedPcapNgReader`] which sorts the packets within a window of `window` packets.
+    pub fn new(reader: PcapNgReader<R>, window: usize) -> Self {
+        OrderedPcapNgReader {
+            reader,
+            window,
+            buffer: BinaryHeap::with_capacity(window + 1),
+            pending: None,
+            sequence: 0,
+            last_timestamp: None,
+            last_out_of_order: false,
+            out_of_order_count: 0,
+            eof: false,
+        }
+    }
+
+    /// Returns the next [`Block`].
+    ///
+    /// The packets are owned because they may have been buffered.
+    pub fn next_block(&mut self) -> Option<Result<Block<'static>, PcapError>> {
+        loop {
+            if self.pending.is_some() || self.eof || self.buffer.len() > self.window {
+                if let Some(Reverse(entry)) = self.buffer.pop() {
+                    return Some(Ok(self.emit(entry)));
+                }
+                if let Some(block) = self.pending.take() {
+                    self.last_out_of_order = false;
+                    return Some(Ok(block));
+                }
+                if self.eof {
+                    return None;
+                }
+            }
+
+            let block = match self.reader.next_block() {
+                Some(Ok(block)) => block.into_owned(),
+                Some(Err(err)) => return Some(Err(err)),
+                None => {
+                    self.eof = true;
+                    continue;
+                },
+            };
+
+            let timestamp = match &block {
+                Block::EnhancedPacket(epb) => {
+                    self.reader.packet_interface(epb).map(|idb| idb.timestamp_to_duration(epb.timestamp.as_nanos() as u64))
+                },
+                Block::Packet(pb) => self.reader.interfaces().get(pb.interface_id as usize).map(|idb| idb.timestamp_to_duration(pb.timestamp)),
+                _ => None,
+            };
+
+            match timestamp {
+                Some(timestamp) => {
+                    self.buffer.push(Reverse(Entry { timestamp, sequence: self.sequence, block }));
+                    self.sequence += 1;
+                },
+                None => self.pending = Some(block),
+            }
+        }
+    }
+
+    /// Returns true if the last returned block is a packet older than a packet returned before it.
+    pub fn is_out_of_order(&self) -> bool {
+        self.last_out_of_order
+    }
+
+    /// Returns the number of packets returned out of order, because the window was too small to sort them.
+    pub fn out_of_order_count(&self) -> u64 {
+        self.out_of_order_count
+    }
+
+    /// Returns the inner [`PcapNgReader`].
+    ///
+    /// The buffered packets are lost.
+    pub fn into_inner(self) -> PcapNgReader<R> {
+        self.reader
+    }
+
+    /// Returns a reference to the inner [`PcapNgReader`].
+    pub fn get_ref(&self) -> &PcapNgReader<R> {
+        &self.reader
+    }
+
+    fn emit(&mut self, entry: Entry) -> Block<'static> {
+        self.last_out_of_order = self.last_timestamp.is_some_and(|last| entry.timestamp < last);
+        if self.last_out_of_order {
+            self.out_of_order_count += 1;
+        }
+        else {
+            self.last_timestamp = Some(entry.timestamp);
+        }
+
+        entry.block
+    }
+}
+
+
+/// Wraps a [`PcapNgWriter`] to write the packets in timestamp order.
+///
+/// The packets are kept in a buffer of `capacity` packets and the oldest one is written when it is full,
+/// which sorts the output of capture sources running on several threads as long as their disorder fits in the buffer.
+///
+/// The other blocks are written immediately, ahead of the buffered packets, except a [`SectionHeaderBlock`](super::blocks::section_header::SectionHeaderBlock)
+/// which is written after all of them.
+///
+/// The buffered packets should be written with [`Self::flush()`] or [`Self::into_inner()`] before the writer is dropped:
+/// the drop writes them on a best-effort basis, ignoring the errors.
+///
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+/// use std::time::SystemTime;
+///
+/// use pcap_file::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+/// use pcap_file::pcapng::{PcapNgWriter, SortingPcapNgWriter};
+/// use pcap_file::DataLink;
+///
+/// let file_out = File::create("out.pcapng").expect("Error creating file");
+/// let mut sorting_writer = SortingPcapNgWriter::new(PcapNgWriter::new(file_out).unwrap(), 1024);
+///
+/// let interface = sorting_writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0)).unwrap();
+/// sorting_writer.write_packet(interface, SystemTime::now(), &[0_u8; 64]).unwrap();
+///
+/// let pcapng_writer = sorting_writer.into_inner().unwrap();
+/// ```
+pub struct SortingPcapNgWriter<W: Write> {
+    /// Always `Some`, only taken by `into_inner()`
+    writer: Option<PcapNgWriter<W>>,
+    capacity: usize,
+    buffer: BinaryHeap<Reverse<Entry>>,
+    sequence: u64,
+}
+
+impl<W: Write> SortingPcapNgWriter<W> {
+    /// Creates a new [`SortingPcapNgWriter`] which buffers up to `capacity` packets.
+    pub fn new(writer: PcapNgWriter<W>, capacity: usize) -> Self {
+        SortingPcapNgWriter { writer: Some(writer), capacity, buffer: BinaryHeap::with_capacity(capacity + 1), sequence: 0 }
+    }
+
+    /// Writes a [`Block`], or buffers it if it is a packet.
+    pub fn write_block(&mut self, block: &Block) -> PcapResult<()> {
+        let interfaces = self.get_ref().interfaces();
+        let timestamp = match block {
+            Block::EnhancedPacket(epb) => {
+                interfaces.get(epb.interface_id as usize).map(|idb| idb.timestamp_to_duration(epb.timestamp.as_nanos() as u64))
+            },
+            Block::Packet(pb) => interfaces.get(pb.interface_id as usize).map(|idb| idb.timestamp_to_duration(pb.timestamp)),
+            _ => None,
+        };
+
+        match timestamp {
+            Some(timestamp) => {
+                self.buffer.push(Reverse(Entry { timestamp, sequence: self.sequence, block: block.clone().into_owned() }));
+                self.sequence += 1;
+
+                if self.buffer.len() > self.capacity {
+                    let Reverse(entry) = self.buffer.pop().unwrap();
+                    self.get_mut().write_block(&entry.block)?;
+                }
+            },
+            None => {
+                if let Block::SectionHeader(_) = block {
+                    self.write_buffered()?;
+                }
+                self.get_mut().write_block(block)?;
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Writes an [`InterfaceDescriptionBlock`] and returns the [`InterfaceId`] to use to write its packets.
+    pub fn add_interface(&mut self, interface: &InterfaceDescriptionBlock) -> PcapResult<InterfaceId> {
+        self.get_mut().add_interface(interface)
+    }
+
+    /// Buffers a packet captured on the given interface at the given time.
+    pub fn write_packet(&mut self, interface: InterfaceId, ts: SystemTime, data: &[u8]) -> PcapResult<()> {
+        let idb = self.get_ref().interfaces().get(interface.0 as usize).ok_or(PcapError::InvalidInterfaceId(interface.0))?;
+        let packet = EnhancedPacketBlock::builder(interface, data).time(ts).build(idb)?;
+
+        self.write_block(&packet.into_block())
+    }
+
+    /// Returns the number of buffered packets.
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Writes all the buffered packets in timestamp order and flushes the inner writer.
+    pub fn flush(&mut self) -> PcapResult<()> {
+        self.write_buffered()?;
+        self.get_mut().flush()
+    }
+
+    /// Writes all the buffered packets and returns the inner [`PcapNgWriter`].
+    pub fn into_inner(mut self) -> PcapResult<PcapNgWriter<W>> {
+        self.write_buffered()?;
+        Ok(self.writer.take().expect("SortingPcapNgWriter: writer already taken"))
+    }
+
+    /// Returns a reference to the inner [`PcapNgWriter`].
+    pub fn get_ref(&self) -> &PcapNgWriter<W> {
+        self.writer.as_ref().expect("SortingPcapNgWriter: writer already taken")
+    }
+
+    /// Returns a mutable reference to the inner [`PcapNgWriter`].
+    ///
+    /// The blocks written with it are written ahead of the buffered packets.
+    pub fn get_mut(&mut self) -> &mut PcapNgWriter<W> {
+        self.writer.as_mut().expect("SortingPcapNgWriter: writer already taken")
+    }
+
+    fn write_buffered(&mut self) -> PcapResult<()> {
+        while let Some(Reverse(entry)) = self.buffer.pop() {
+            self.get_mut().write_block(&entry.block)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Drop for SortingPcapNgWriter<W> {
+    fn drop(&mut self) {
+        // The errors can't be reported
+        if self.writer.is_some() {
+            let _ = self.write_buffered();
+        }
+    }
+}
+
+
+/// Buffered packet, ordered by timestamp then by arrival
+struct Entry {
+    timestamp: Duration,
+    sequence: u64,
+    block: Block<'static>,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.timestamp, self.sequence).cmp(&(other.timestamp, other.sequence))
+    }
+}
@@ -0,0 +1,371 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{sink, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use byteorder_slice::{BigEndian, ByteOrder, LittleEndian};
+
+use super::blocks::block_common::{Block, PcapNgBlock};
+use super::blocks::enhanced_packet::EnhancedPacketBlock;
+use super::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceId};
+use super::blocks::section_header::SectionHeaderBlock;
+use super::blocks::{ENHANCED_PACKET_BLOCK, PACKET_BLOCK};
+use super::PcapNgWriter;
+use crate::{Endianness, PcapError, PcapResult};
+
+
+/// Thresholds of a [`RotatingPcapNgWriter`], like the `-C`, `-G` and `-W` options of tcpdump.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RotationConfig {
+    /// Switch to a new file once the current one reaches this size in bytes
+    pub max_file_size: Option<u64>,
+
+    /// Switch to a new file once the current one has been open for this duration
+    pub max_duration: Option<Duration>,
+
+    /// Keep at most this number of files, deleting the oldest ones
+    pub max_files: Option<usize>,
+
+    /// Switch to a new file when the timestamp of a packet enters a new interval, aligned on the Unix epoch
+    ///
+    /// Unlike `max_duration`, the files only depend on the packets, e.g. one file per hour on the hour.
+    pub interval: Option<Duration>,
+}
+
+/// Writes a PcapNg into a series of files, switching to a new file when a threshold of the [`RotationConfig`] is reached.
+///
+/// Each file is a complete capture: it starts with the current section header and interfaces.
+/// The files are named after the given path with an increasing index, e.g. `capture_00000.pcapng`, `capture_00001.pcapng`...
+///
+/// If the path contains `%` conversions, it is instead a pattern expanded in UTC with the start of the current
+/// [`interval`](RotationConfig::interval), or with the opening time of the file if there is no interval:
+/// `%Y` (year), `%m` (month), `%d` (day), `%H` (hour), `%M` (minute), `%S` (second), `%s` (Unix timestamp) and `%%`.
+/// An index is appended if the name is already taken by a previous file, e.g. after a rotation on size.
+///
+/// The current file is buffered: its content is written when the writer switches to the next file, on
+/// [`flush()`](Self::flush) and when the writer is dropped.
+///
+/// # Example
+/// ```rust,no_run
+/// use std::time::{Duration, SystemTime};
+///
+/// use pcap_file::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+/// use pcap_file::pcapng::{RotatingPcapNgWriter, RotationConfig};
+/// use pcap_file::DataLink;
+///
+/// // Ring buffer of 10 files of 100 MB
+/// let config = RotationConfig { max_file_size: Some(100_000_000), max_files: Some(10), ..Default::default() };
+/// let mut writer = RotatingPcapNgWriter::new("capture.pcapng", config).unwrap();
+///
+/// let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0)).unwrap();
+/// writer.write_packet(interface, SystemTime::now(), &[0_u8; 64]).unwrap();
+///
+/// // One file per hour of packets, e.g. `capture-2024-01-31T12.pcapng`
+/// let config = RotationConfig { interval: Some(Duration::from_secs(3600)), ..Default::default() };
+/// let mut writer = RotatingPcapNgWriter::new("capture-%Y-%m-%dT%H.pcapng", config).unwrap();
+/// ```
+pub struct RotatingPcapNgWriter {
+    config: RotationConfig,
+    path: PathBuf,
+    next_index: u64,
+    files: VecDeque<PathBuf>,
+    writer: PcapNgWriter<BufWriter<File>>,
+    /// Length of the current file
+    file_len: u64,
+    /// Length of the section header and interfaces at the start of the current file
+    header_len: u64,
+    opened_at: Instant,
+    /// Start of the interval of the current file, since the Unix epoch, once a packet has been written
+    interval_start: Option<Duration>,
+}
+
+impl RotatingPcapNgWriter {
+    /// Creates a new [`RotatingPcapNgWriter`] and its first file, with a section in the native endianness.
+    pub fn new<P: AsRef<Path>>(path: P, config: RotationConfig) -> PcapResult<Self> {
+        Self::with_section_header(path, config, SectionHeaderBlock::default())
+    }
+
+    /// Creates a new [`RotatingPcapNgWriter`] and its first file, with the given section header.
+    pub fn with_section_header<P: AsRef<Path>>(path: P, config: RotationConfig, section: SectionHeaderBlock<'static>) -> PcapResult<Self> {
+        if config.max_files == Some(0) {
+            return Err(PcapError::InvalidField("RotationConfig: max_files == 0"));
+        }
+        if config.interval == Some(Duration::ZERO) {
+            return Err(PcapError::InvalidField("RotationConfig: interval == 0"));
+        }
+
+        let path = path.as_ref().to_path_buf();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let time = config.interval.map_or(now, |interval| interval_start(now, interval));
+        let file_path = file_path(&path, 0, time, &[]);
+        let file = File::create(&file_path).map_err(PcapError::IoError)?;
+        let header_len = block_len(&Block::SectionHeader(section.clone()))?;
+        let writer = PcapNgWriter::with_section_header(BufWriter::new(file), section)?;
+
+        Ok(RotatingPcapNgWriter {
+            config,
+            path,
+            next_index: 1,
+            files: VecDeque::from([file_path]),
+            writer,
+            file_len: header_len,
+            header_len,
+            opened_at: Instant::now(),
+            interval_start: None,
+        })
+    }
+
+    /// Writes a [`Block`], switching to a new file first if a threshold has been reached.
+    ///
+    /// A [`SectionHeaderBlock`] changes the section header written at the start of the next files.
+    pub fn write_block(&mut self, block: &Block) -> PcapResult<usize> {
+        self.check_interval(block)?;
+        if self.should_rotate() {
+            self.rotate()?;
+        }
+
+        let len = self.writer.write_block(block)?;
+        self.file_len += len as u64;
+
+        Ok(len)
+    }
+
+    /// Writes a [`PcapNgBlock`], switching to a new file first if a threshold has been reached.
+    pub fn write_pcapng_block<'a, B: PcapNgBlock<'a>>(&mut self, block: &B) -> PcapResult<usize> {
+        // The packets are parsed back to get their timestamp
+        if self.config.interval.is_some() && matches!(block.block_type(), ENHANCED_PACKET_BLOCK | PACKET_BLOCK) {
+            return match self.writer.section().endianness {
+                Endianness::Big => write_parsed::<BigEndian, _>(self, block),
+                Endianness::Little => write_parsed::<LittleEndian, _>(self, block),
+            };
+        }
+
+        if self.should_rotate() {
+            self.rotate()?;
+        }
+
+        let len = self.writer.write_pcapng_block(block)?;
+        self.file_len += len as u64;
+
+        return Ok(len);
+
+        fn write_parsed<'a, B: ByteOrder, T: PcapNgBlock<'a>>(writer: &mut RotatingPcapNgWriter, block: &T) -> PcapResult<usize> {
+            let mut data = vec![];
+            block.write_to::<B, _>(&mut data).map_err(PcapError::IoError)?;
+            let (_, block) = Block::from_slice::<B>(&data)?;
+
+            writer.write_block(&block)
+        }
+    }
+
+    /// Writes an [`InterfaceDescriptionBlock`] and returns the [`InterfaceId`] to use to write its packets.
+    pub fn add_interface(&mut self, interface: &InterfaceDescriptionBlock) -> PcapResult<InterfaceId> {
+        self.write_block(&Block::InterfaceDescription(interface.clone()))?;
+        Ok(InterfaceId(self.writer.interfaces().len() as u32 - 1))
+    }
+
+    /// Writes a packet captured on the given interface at the given time.
+    pub fn write_packet(&mut self, interface: InterfaceId, ts: SystemTime, data: &[u8]) -> PcapResult<usize> {
+        let idb = self.writer.interfaces().get(interface.0 as usize).ok_or(PcapError::InvalidInterfaceId(interface.0))?;
+        let packet = EnhancedPacketBlock::builder(interface, data).time(ts).build(idb)?;
+
+        self.write_block(&packet.into_block())
+    }
+
+    /// Closes the current file and starts a new one, whatever the thresholds.
+    pub fn rotate(&mut self) -> PcapResult<()> {
+        let time = match self.interval_start {
+            Some(start) => start,
+            None => SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default(),
+        };
+        let file_path = file_path(&self.path, self.next_index, time, self.files.make_contiguous());
+        let file = File::create(&file_path).map_err(PcapError::IoError)?;
+
+        let section = self.writer.section().clone();
+        let mut file_len = block_len(&Block::SectionHeader(section.clone()))?;
+        let mut writer = PcapNgWriter::with_section_header(BufWriter::new(file), section)?;
+        for interface in self.writer.interfaces() {
+            file_len += writer.write_block(&Block::InterfaceDescription(interface.clone()))? as u64;
+        }
+
+        self.writer.flush()?;
+        self.writer = writer;
+        self.file_len = file_len;
+        self.header_len = file_len;
+        self.opened_at = Instant::now();
+        self.next_index += 1;
+
+        self.files.push_back(file_path);
+        if let Some(max_files) = self.config.max_files {
+            while self.files.len() > max_files {
+                let oldest = self.files.pop_front().unwrap();
+                std::fs::remove_file(oldest).map_err(PcapError::IoError)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the current file.
+    pub fn flush(&mut self) -> PcapResult<()> {
+        self.writer.flush()
+    }
+
+    /// Flushes the current file then syncs its data to the disk, see [`PcapNgWriter::sync_data()`].
+    pub fn sync_data(&mut self) -> PcapResult<()> {
+        self.writer.sync_data()
+    }
+
+    /// Returns the path of the file currently written.
+    pub fn current_path(&self) -> &Path {
+        self.files.back().unwrap()
+    }
+
+    /// Returns the paths of the files written and not deleted, from the oldest to the current one.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|path| path.as_path())
+    }
+
+    /// Rotates the file if the block is a packet of a new interval.
+    ///
+    /// The first packet only names the current file, which is renamed if the path is a pattern.
+    fn check_interval(&mut self, block: &Block) -> PcapResult<()> {
+        let interval = match self.config.interval {
+            Some(interval) => interval,
+            None => return Ok(()),
+        };
+
+        let interfaces = self.writer.interfaces();
+        let timestamp = match block {
+            Block::EnhancedPacket(epb) => {
+                interfaces.get(epb.interface_id as usize).map(|idb| idb.timestamp_to_duration(epb.timestamp.as_nanos() as u64))
+            },
+            Block::Packet(pb) => interfaces.get(pb.interface_id as usize).map(|idb| idb.timestamp_to_duration(pb.timestamp)),
+            _ => None,
+        };
+        let start = match timestamp {
+            Some(timestamp) => interval_start(timestamp, interval),
+            None => return Ok(()),
+        };
+
+        match self.interval_start {
+            Some(current) if current == start => {},
+            Some(_) => {
+                self.interval_start = Some(start);
+                self.rotate()?;
+            },
+            None => {
+                self.interval_start = Some(start);
+
+                let current = self.files.pop_back().unwrap();
+                let file_path = file_path(&self.path, self.next_index - 1, start, self.files.make_contiguous());
+                if file_path != current {
+                    self.writer.flush()?;
+                    std::fs::rename(&current, &file_path).map_err(PcapError::IoError)?;
+                }
+                self.files.push_back(file_path);
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if the current file has reached a threshold and contains more than its header.
+    fn should_rotate(&self) -> bool {
+        if self.file_len == self.header_len {
+            return false;
+        }
+
+        self.config.max_file_size.is_some_and(|max| self.file_len >= max)
+            || self.config.max_duration.is_some_and(|max| self.opened_at.elapsed() >= max)
+    }
+}
+
+/// Returns the start of the interval containing the timestamp.
+fn interval_start(timestamp: Duration, interval: Duration) -> Duration {
+    let nanos = timestamp.as_nanos() / interval.as_nanos() * interval.as_nanos();
+    Duration::new((nanos / 1_000_000_000) as u64, (nanos % 1_000_000_000) as u32)
+}
+
+/// Returns the path of a new file.
+///
+/// A pattern path is expanded with the time, and an index is only appended if the name is already in `files`.
+fn file_path(path: &Path, index: u64, time: Duration, files: &[PathBuf]) -> PathBuf {
+    let pattern = match path.to_str() {
+        Some(pattern) if pattern.contains('%') => pattern,
+        _ => return indexed_path(path, index),
+    };
+
+    let path = PathBuf::from(expand_pattern(pattern, time.as_secs()));
+    if files.contains(&path) {
+        return indexed_path(&path, index);
+    }
+
+    path
+}
+
+/// Expands the strftime-like conversions of the pattern with the given Unix time, in UTC.
+fn expand_pattern(pattern: &str, secs: u64) -> String {
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let secs_of_day = secs % 86400;
+
+    let mut expanded = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('Y') => expanded.push_str(&format!("{year:04}")),
+            Some('m') => expanded.push_str(&format!("{month:02}")),
+            Some('d') => expanded.push_str(&format!("{day:02}")),
+            Some('H') => expanded.push_str(&format!("{:02}", secs_of_day / 3600)),
+            Some('M') => expanded.push_str(&format!("{:02}", secs_of_day / 60 % 60)),
+            Some('S') => expanded.push_str(&format!("{:02}", secs_of_day % 60)),
+            Some('s') => expanded.push_str(&secs.to_string()),
+            Some('%') => expanded.push('%'),
+            Some(other) => {
+                expanded.push('%');
+                expanded.push(other);
+            },
+            None => expanded.push('%'),
+        }
+    }
+
+    return expanded;
+
+    // Converts a number of days since the Unix epoch to a (year, month, day) of the proleptic Gregorian calendar
+    fn civil_from_days(days: i64) -> (i64, u32, u32) {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+        let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+        let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+        (year, month, day)
+    }
+}
+
+/// Returns the path of the file of the given index: `dir/name.ext` becomes `dir/name_00042.ext`.
+fn indexed_path(path: &Path, index: u64) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{stem}_{index:05}.{}", ext.to_string_lossy()),
+        None => format!("{stem}_{index:05}"),
+    };
+
+    path.with_file_name(name)
+}
+
+/// Returns the encoded length of a block, which doesn't depend on the endianness.
+fn block_len(block: &Block) -> PcapResult<u64> {
+    let len = block.write_to::<BigEndian, _>(&mut sink()).map_err(PcapError::IoError)?;
+    Ok(len as u64)
+}
@@ -0,0 +1,149 @@
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+
+use byteorder_slice::{BigEndian, ByteOrder, LittleEndian};
+use memchr::memmem;
+
+use super::blocks::*;
+use crate::errors::PcapError;
+use crate::{Endianness, PcapResult};
+
+
+/// Magic number of a [`SectionHeaderBlock`](super::blocks::section_header::SectionHeaderBlock), the same in both endiannesses
+const SECTION_HEADER_MAGIC: [u8; 4] = [0x0A, 0x0D, 0x0D, 0x0A];
+
+/// Returns the offset of the first plausible section header in the slice.
+///
+/// The candidates are located with a vectorized search of the block type, then checked: the byte-order magic must
+/// be valid and the block length consistent, including its trailer if the block is entirely in the slice.
+/// A section header may still be found in the middle of the data of a packet, but it is very unlikely.
+pub fn find_section_header(data: &[u8]) -> Option<usize> {
+    memmem::find_iter(data, &SECTION_HEADER_MAGIC).find(|&offset| {
+        let block = &data[offset..];
+        if block.len() < 28 {
+            return false;
+        }
+
+        let endianness = match block[8..12] {
+            [0x1A, 0x2B, 0x3C, 0x4D] => Endianness::Big,
+            [0x4D, 0x3C, 0x2B, 0x1A] => Endianness::Little,
+            _ => return false,
+        };
+
+        is_block_len_valid(block, endianness, 28)
+    })
+}
+
+/// Returns the offset of the first plausible block boundary in the slice, for a section of the given endianness.
+///
+/// A boundary is accepted if the block at this offset has a known type, a length consistent with its trailer and
+/// is followed by another plausible block header or by the end of the slice. Only the 4 bytes aligned offsets relative
+/// to the start of the slice are tested, so it must start at a multiple of 4 bytes from the start of the section.
+pub fn find_block_boundary(data: &[u8], endianness: Endianness) -> Option<usize> {
+    (0..data.len().saturating_sub(11)).step_by(4).find(|&offset| {
+        let block = &data[offset..];
+        if !is_block_plausible(block, endianness) {
+            return false;
+        }
+
+        let next = &block[block_len(block, endianness)..];
+        next.len() < 8 || is_block_header_plausible(next, endianness)
+    })
+}
+
+/// Seeks the reader to the first section header found after `offset` and returns its position.
+///
+/// A [`PcapNgReader`](super::PcapNgReader) created from the reader then reads the capture from this section.
+/// Returns `None` if the end of the reader is reached without finding a section header.
+pub fn seek_to_section_header<R: Read + Seek>(reader: &mut R, offset: u64) -> PcapResult<Option<u64>> {
+    const CHUNK_LEN: usize = 1 << 20;
+    // Enough to check a section header overlapping two chunks, whose length is checked without options
+    const OVERLAP: usize = 28;
+
+    let mut chunk_offset = reader.seek(SeekFrom::Start(offset)).map_err(PcapError::IoError)?;
+    let mut buf = vec![0_u8; CHUNK_LEN];
+    let mut len = 0;
+
+    loop {
+        let read_len = match reader.read(&mut buf[len..]) {
+            Ok(read_len) => read_len,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(PcapError::IoError(err)),
+        };
+        len += read_len;
+        if read_len != 0 && len < buf.len() {
+            continue;
+        }
+
+        // Before the end of the reader, a candidate too close to the end of the buffer could be rejected wrongly
+        let searched = if read_len == 0 { len } else { len.saturating_sub(OVERLAP - 1) };
+        if let Some(pos) = find_section_header(&buf[..len]).filter(|&pos| pos < searched) {
+            let pos = chunk_offset + pos as u64;
+            reader.seek(SeekFrom::Start(pos)).map_err(PcapError::IoError)?;
+            return Ok(Some(pos));
+        }
+
+        if read_len == 0 {
+            return Ok(None);
+        }
+
+        buf.copy_within(searched.., 0);
+        len -= searched;
+        chunk_offset += searched as u64;
+    }
+}
+
+fn read_u32(src: &[u8], endianness: Endianness) -> u32 {
+    match endianness {
+        Endianness::Big => BigEndian::read_u32(src),
+        Endianness::Little => LittleEndian::read_u32(src),
+    }
+}
+
+fn block_len(block: &[u8], endianness: Endianness) -> usize {
+    read_u32(&block[4..8], endianness) as usize
+}
+
+fn is_block_len_valid(block: &[u8], endianness: Endianness, min_len: usize) -> bool {
+    let len = block_len(block, endianness);
+    if len < min_len || len % 4 != 0 {
+        return false;
+    }
+
+    // The trailer can only be checked if the block is entirely in the slice
+    match block.get(len - 4..len) {
+        Some(trailer) => read_u32(trailer, endianness) as usize == len,
+        None => true,
+    }
+}
+
+fn is_block_header_plausible(block: &[u8], endianness: Endianness) -> bool {
+    let known = matches!(
+        read_u32(&block[0..4], endianness),
+        SECTION_HEADER_BLOCK |
+            INTERFACE_DESCRIPTION_BLOCK |
+            PACKET_BLOCK |
+            SIMPLE_PACKET_BLOCK |
+            NAME_RESOLUTION_BLOCK |
+            INTERFACE_STATISTIC_BLOCK |
+            ENHANCED_PACKET_BLOCK |
+            SYSTEMD_JOURNAL_EXPORT_BLOCK |
+            DECRYPTION_SECRETS_BLOCK |
+            HONE_MACHINE_INFO_BLOCK |
+            HONE_CONNECTION_EVENT_BLOCK |
+            CUSTOM_BLOCK |
+            CUSTOM_BLOCK_NO_COPY
+    );
+    let len = block_len(block, endianness);
+
+    known && len >= 12 && len % 4 == 0
+}
+
+fn is_block_plausible(block: &[u8], endianness: Endianness) -> bool {
+    if !is_block_header_plausible(block, endianness) {
+        return false;
+    }
+
+    // The trailer is required to tell a real boundary apart
+    let len = block_len(block, endianness);
+    block.len() >= len && is_block_len_valid(block, endianness, 12)
+}
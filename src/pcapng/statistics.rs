@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+
+use super::blocks::interface_statistics::{InterfaceStatisticsBlock, InterfaceStatisticsOption};
+use super::Block;
+
+
+/// Folds the Interface Statistics Blocks of a capture into per-interface totals.
+///
+/// The counters of an ISB are cumulative since the start of the capture, so the totals are the values of the latest
+/// snapshot of each interface. When a counter decreases between two snapshots, the capture is assumed to have been
+/// restarted and the previous value is carried over.
+///
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+///
+/// use pcap_file::pcapng::{PcapNgReader, StatisticsAccumulator};
+///
+/// let file_in = File::open("test.pcapng").expect("Error opening file");
+/// let mut pcapng_reader = PcapNgReader::new(file_in).unwrap();
+///
+/// let mut statistics = StatisticsAccumulator::new();
+/// while let Some(block) = pcapng_reader.next_block() {
+///     statistics.add_block(&block.unwrap());
+/// }
+///
+/// for totals in statistics.totals() {
+///     println!("{}/{}: {:?} dropped", totals.section, totals.interface_id, totals.dropped);
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StatisticsAccumulator {
+    section: usize,
+    section_has_blocks: bool,
+    interfaces: BTreeMap<(usize, u32), InterfaceCounters>,
+}
+
+impl StatisticsAccumulator {
+    /// Creates a new empty [`StatisticsAccumulator`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a block to the accumulator.
+    ///
+    /// The section headers delimit the interfaces, the statistics are folded and the other blocks are ignored.
+    pub fn add_block(&mut self, block: &Block) {
+        match block {
+            Block::SectionHeader(_) => {
+                if self.section_has_blocks {
+                    self.section += 1;
+                    self.section_has_blocks = false;
+                }
+            },
+            Block::InterfaceStatistics(isb) => self.add_statistics(isb),
+            _ => self.section_has_blocks = true,
+        }
+    }
+
+    /// Adds the statistics of an interface of the current section to the accumulator.
+    pub fn add_statistics(&mut self, isb: &InterfaceStatisticsBlock) {
+        self.section_has_blocks = true;
+
+        let counters = self.interfaces.entry((self.section, isb.interface_id)).or_default();
+        counters.snapshots += 1;
+        counters.start_time = counters.start_time.or(Some(isb.timestamp));
+        counters.end_time = Some(isb.timestamp);
+
+        for opt in &isb.options {
+            match opt {
+                InterfaceStatisticsOption::IsbStartTime(time) => {
+                    counters.start_time = Some(counters.start_time.map_or(*time, |start| start.min(*time)));
+                },
+                InterfaceStatisticsOption::IsbEndTime(time) => {
+                    counters.end_time = Some(counters.end_time.map_or(*time, |end| end.max(*time)));
+                },
+                InterfaceStatisticsOption::IsbIfRecv(value) => counters.received.update(*value),
+                InterfaceStatisticsOption::IsbIfDrop(value) => counters.dropped.update(*value),
+                InterfaceStatisticsOption::IsbFilterAccept(value) => counters.accepted.update(*value),
+                InterfaceStatisticsOption::IsbOsDrop(value) => counters.os_dropped.update(*value),
+                InterfaceStatisticsOption::IsbUsrDeliv(value) => counters.delivered.update(*value),
+                _ => {},
+            }
+        }
+    }
+
+    /// Returns the totals of each interface which has statistics, ordered by section then by interface.
+    pub fn totals(&self) -> Vec<InterfaceTotals> {
+        self.interfaces
+            .iter()
+            .map(|(&(section, interface_id), counters)| InterfaceTotals {
+                section,
+                interface_id,
+                snapshots: counters.snapshots,
+                start_time: counters.start_time,
+                end_time: counters.end_time,
+                received: counters.received.total(),
+                dropped: counters.dropped.total(),
+                accepted: counters.accepted.total(),
+                os_dropped: counters.os_dropped.total(),
+                delivered: counters.delivered.total(),
+            })
+            .collect()
+    }
+}
+
+
+/// Statistics totals of an interface, computed by a [`StatisticsAccumulator`].
+///
+/// The counters are `None` if no snapshot of the interface contained them.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct InterfaceTotals {
+    /// Index of the section of the interface, starting at 0
+    pub section: usize,
+    /// Id of the interface in its section
+    pub interface_id: u32,
+    /// Number of statistics snapshots of the interface
+    pub snapshots: usize,
+    /// Earliest start time or ISB timestamp, in the units of the interface
+    pub start_time: Option<u64>,
+    /// Latest end time or ISB timestamp, in the units of the interface
+    pub end_time: Option<u64>,
+    /// Packets received from the physical interface (`isb_ifrecv`)
+    pub received: Option<u64>,
+    /// Packets dropped by the interface (`isb_ifdrop`)
+    pub dropped: Option<u64>,
+    /// Packets accepted by the filter (`isb_filteraccept`)
+    pub accepted: Option<u64>,
+    /// Packets dropped by the operating system (`isb_osdrop`)
+    pub os_dropped: Option<u64>,
+    /// Packets delivered to the user (`isb_usrdeliv`)
+    pub delivered: Option<u64>,
+}
+
+
+#[derive(Clone, Debug, Default)]
+struct InterfaceCounters {
+    snapshots: usize,
+    start_time: Option<u64>,
+    end_time: Option<u64>,
+    received: Counter,
+    dropped: Counter,
+    accepted: Counter,
+    os_dropped: Counter,
+    delivered: Counter,
+}
+
+/// Cumulative counter which survives the restarts of the capture.
+#[derive(Clone, Copy, Debug, Default)]
+struct Counter {
+    base: u64,
+    last: Option<u64>,
+}
+
+impl Counter {
+    fn update(&mut self, value: u64) {
+        if let Some(last) = self.last {
+            if value < last {
+                self.base = self.base.saturating_add(last);
+            }
+        }
+        self.last = Some(value);
+    }
+
+    fn total(&self) -> Option<u64> {
+        self.last.map(|last| self.base.saturating_add(last))
+    }
+}
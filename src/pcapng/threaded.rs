@@ -0,0 +1,329 @@
+use std::io::{Error, ErrorKind, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use super::blocks::block_common::Block;
+use super::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceId};
+use super::{PcapNgWriter, SortingPcapNgWriter};
+use crate::{PcapError, PcapResult};
+
+
+/// Behavior of a [`ThreadedWriter`] when its channel is full.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum OverflowPolicy {
+    /// Waits for the writer thread to make room, slowing down the caller
+    #[default]
+    Block,
+    /// Drops the packet and counts it, so that the caller never waits for the disk
+    ///
+    /// The drops are reported to the [`PcapNgWriter`], see [`PcapNgWriter::report_drops()`]. The other blocks, like the
+    /// interface descriptions, are never dropped.
+    Drop,
+}
+
+/// Writes the blocks of a [`PcapNgWriter`] from a dedicated thread, fed by a bounded channel of owned blocks.
+///
+/// The serialization and the IO happen on the writer thread, so the capture loop only moves the blocks into the channel.
+/// When the channel is full, the [`OverflowPolicy`] either waits for the writer thread or drops the packet.
+///
+/// An error of the writer thread stops it and is returned by the next call.
+///
+/// Several capture threads can write to the same file through the [`PacketSink`]s returned by [`Self::sink()`], the
+/// packets being optionally sorted by timestamp, see [`Self::with_sorting()`].
+///
+/// Dropping the writer doesn't wait for its thread, which still writes the blocks sent so far in the background but
+/// can't report its errors: use [`Self::finish()`] to check them.
+///
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+/// use std::io::BufWriter;
+///
+/// use pcap_file::pcapng::blocks::enhanced_packet::EnhancedPacketBlock;
+/// use pcap_file::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+/// use pcap_file::pcapng::{OverflowPolicy, PcapNgWriter, ThreadedWriter};
+/// use pcap_file::DataLink;
+///
+/// let file_out = BufWriter::new(File::create("out.pcapng").unwrap());
+/// let pcapng_writer = PcapNgWriter::new(file_out).unwrap();
+/// let mut writer = ThreadedWriter::new(pcapng_writer, 4096, OverflowPolicy::Drop).unwrap();
+///
+/// let interface = InterfaceDescriptionBlock::builder().linktype(DataLink::ETHERNET).build();
+/// let interface_id = writer.add_interface(&interface).unwrap();
+///
+/// // In the capture loop
+/// let packet = EnhancedPacketBlock::builder(interface_id, &[0_u8; 64]).build(&interface).unwrap().into_owned();
+/// writer.write_pcapng_block(packet).unwrap();
+///
+/// println!("{} packets dropped", writer.dropped());
+/// writer.finish().unwrap();
+/// ```
+pub struct ThreadedWriter<W: Write + Send + 'static> {
+    sender: Option<SyncSender<Message>>,
+    thread: Option<JoinHandle<PcapResult<PcapNgWriter<W>>>>,
+    policy: OverflowPolicy,
+    drops: Arc<Drops>,
+}
+
+/// Message sent to the writer thread
+enum Message {
+    Block(Block<'static>),
+    Interface(InterfaceDescriptionBlock<'static>, SyncSender<PcapResult<InterfaceId>>),
+    Flush(SyncSender<PcapResult<()>>),
+    /// Stops the thread, even if some senders are still alive
+    Close,
+}
+
+/// Packets dropped by a [`ThreadedWriter`]
+#[derive(Debug, Default)]
+struct Drops {
+    total: AtomicU64,
+    /// Some drops were not reported to the writer yet
+    pending: AtomicBool,
+    /// Drops not reported to the writer yet, by interface
+    interfaces: Mutex<Vec<u64>>,
+}
+
+impl Drops {
+    /// Counts a dropped packet of the given interface.
+    fn record(&self, interface_id: u32) {
+        self.total.fetch_add(1, Ordering::Relaxed);
+
+        let mut interfaces = self.interfaces.lock().unwrap_or_else(|err| err.into_inner());
+        if interfaces.len() <= interface_id as usize {
+            interfaces.resize(interface_id as usize + 1, 0);
+        }
+        interfaces[interface_id as usize] += 1;
+        self.pending.store(true, Ordering::Release);
+    }
+
+    /// Reports the pending drops to the writer.
+    fn report<W: Write>(&self, writer: &mut PcapNgWriter<W>) {
+        if !self.pending.swap(false, Ordering::Acquire) {
+            return;
+        }
+
+        let mut interfaces = self.interfaces.lock().unwrap_or_else(|err| err.into_inner());
+        for (interface_id, count) in interfaces.iter_mut().enumerate() {
+            // The description of the interface may still be in the channel, the drops are kept until it is written
+            if *count > 0 && writer.report_drops(interface_id as u32, *count).is_ok() {
+                *count = 0;
+            }
+        }
+
+        if interfaces.iter().any(|&count| count > 0) {
+            self.pending.store(true, Ordering::Release);
+        }
+    }
+}
+
+impl<W: Write + Send + 'static> ThreadedWriter<W> {
+    /// Creates a new [`ThreadedWriter`], spawning a thread which writes the blocks to `writer`.
+    ///
+    /// The channel holds up to `capacity` blocks.
+    pub fn new(writer: PcapNgWriter<W>, capacity: usize, policy: OverflowPolicy) -> PcapResult<Self> {
+        Self::with_sorting(writer, capacity, policy, 0)
+    }
+
+    /// Creates a new [`ThreadedWriter`] whose thread sorts the packets by timestamp within a window of `window` packets,
+    /// see [`SortingPcapNgWriter`].
+    ///
+    /// A `window` of 0 writes the packets in the order they are received.
+    pub fn with_sorting(writer: PcapNgWriter<W>, capacity: usize, policy: OverflowPolicy, window: usize) -> PcapResult<Self> {
+        let (sender, receiver) = std::sync::mpsc::sync_channel(capacity);
+        let drops = Arc::new(Drops::default());
+
+        let thread_drops = drops.clone();
+        let writer = SortingPcapNgWriter::new(writer, window);
+        let thread = std::thread::Builder::new()
+            .name("pcapng-writer".to_string())
+            .spawn(move || run(writer, receiver, &thread_drops))
+            .map_err(PcapError::IoError)?;
+
+        return Ok(ThreadedWriter { sender: Some(sender), thread: Some(thread), policy, drops });
+
+        fn run<W: Write>(mut writer: SortingPcapNgWriter<W>, receiver: Receiver<Message>, drops: &Drops) -> PcapResult<PcapNgWriter<W>> {
+            for message in receiver {
+                match message {
+                    Message::Block(block) => {
+                        drops.report(writer.get_mut());
+                        writer.write_block(&block)?;
+                    },
+                    Message::Interface(interface, reply) => {
+                        drops.report(writer.get_mut());
+                        let _ = reply.send(writer.add_interface(&interface));
+                    },
+                    Message::Flush(reply) => {
+                        let _ = reply.send(writer.flush());
+                    },
+                    Message::Close => break,
+                }
+            }
+
+            drops.report(writer.get_mut());
+            writer.into_inner()
+        }
+    }
+
+    /// Sends a block to the writer thread.
+    ///
+    /// Returns `false` if the block was a packet dropped because the channel was full, see [`OverflowPolicy::Drop`].
+    pub fn write_block(&mut self, block: Block<'static>) -> PcapResult<bool> {
+        let sender = self.sender.as_ref().ok_or_else(|| PcapError::IoError(ErrorKind::BrokenPipe.into()))?;
+        send(sender, self.policy, &self.drops, block).map_err(|_| self.thread_error())
+    }
+
+    /// Sends a block to the writer thread, see [`Self::write_block()`].
+    pub fn write_pcapng_block<B: Into<Block<'static>>>(&mut self, block: B) -> PcapResult<bool> {
+        self.write_block(block.into())
+    }
+
+    /// Sends an [`InterfaceDescriptionBlock`] to the writer thread and waits for the [`InterfaceId`] to use to write its
+    /// packets, see [`PcapNgWriter::add_interface()`].
+    pub fn add_interface(&mut self, interface: &InterfaceDescriptionBlock) -> PcapResult<InterfaceId> {
+        let sender = self.sender.as_ref().ok_or_else(|| PcapError::IoError(ErrorKind::BrokenPipe.into()))?;
+        add_interface(sender, interface).unwrap_or_else(|_| Err(self.thread_error()))
+    }
+
+    /// Returns a new [`PacketSink`] sending its blocks to the writer thread, to be moved to a capture thread.
+    pub fn sink(&self) -> PcapResult<PacketSink> {
+        let sender = self.sender.clone().ok_or_else(|| PcapError::IoError(ErrorKind::BrokenPipe.into()))?;
+        Ok(PacketSink { sender, policy: self.policy, drops: self.drops.clone() })
+    }
+
+    /// Returns the number of packets dropped because the channel was full, including the ones of the [`PacketSink`]s.
+    pub fn dropped(&self) -> u64 {
+        self.drops.total.load(Ordering::Relaxed)
+    }
+
+    /// Returns the [`OverflowPolicy`] of the writer.
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Waits for the writer thread to write the blocks sent so far, then flushes the [`PcapNgWriter`].
+    pub fn flush(&mut self) -> PcapResult<()> {
+        let (reply_sender, reply_receiver) = std::sync::mpsc::sync_channel(1);
+
+        let sender = self.sender.as_ref().ok_or_else(|| PcapError::IoError(ErrorKind::BrokenPipe.into()))?;
+        if sender.send(Message::Flush(reply_sender)).is_err() {
+            return Err(self.thread_error());
+        }
+
+        match reply_receiver.recv() {
+            Ok(res) => res,
+            Err(_) => Err(self.thread_error()),
+        }
+    }
+
+    /// Waits for the writer thread to write the blocks sent so far, then returns the [`PcapNgWriter`].
+    ///
+    /// The writer thread stops even if some [`PacketSink`]s are still alive: they fail afterwards.
+    pub fn into_inner(mut self) -> PcapResult<PcapNgWriter<W>> {
+        if let Some(sender) = self.sender.take() {
+            let _ = sender.send(Message::Close);
+        }
+        join(self.thread.take())
+    }
+
+    /// Waits for the writer thread to write the blocks sent so far, then finishes the [`PcapNgWriter`], see [`PcapNgWriter::finish()`].
+    pub fn finish(self) -> PcapResult<W> {
+        self.into_inner()?.finish()
+    }
+
+    /// Stops the writer thread and returns its error.
+    fn thread_error(&mut self) -> PcapError {
+        self.sender = None;
+        match join(self.thread.take()) {
+            Ok(_) => PcapError::IoError(ErrorKind::BrokenPipe.into()),
+            Err(err) => err,
+        }
+    }
+}
+
+/// Handle sending blocks to the thread of a [`ThreadedWriter`], returned by [`ThreadedWriter::sink()`].
+///
+/// It can be cloned and moved to other threads, all the sinks writing to the same file with the policy of the
+/// [`ThreadedWriter`]. The blocks of a sink are received in order, but they are interleaved with the ones of the other
+/// sinks, unless the writer sorts them by timestamp.
+///
+/// The sinks can't report the error of the writer thread, which is returned by the [`ThreadedWriter`]: they only fail
+/// once the thread is stopped.
+#[derive(Clone, Debug)]
+pub struct PacketSink {
+    sender: SyncSender<Message>,
+    policy: OverflowPolicy,
+    drops: Arc<Drops>,
+}
+
+impl PacketSink {
+    /// Sends an [`InterfaceDescriptionBlock`] to the writer thread and waits for its [`InterfaceId`], see
+    /// [`ThreadedWriter::add_interface()`].
+    pub fn add_interface(&self, interface: &InterfaceDescriptionBlock) -> PcapResult<InterfaceId> {
+        add_interface(&self.sender, interface).unwrap_or_else(|_| Err(PcapError::IoError(ErrorKind::BrokenPipe.into())))
+    }
+
+    /// Sends a block to the writer thread, see [`ThreadedWriter::write_block()`].
+    pub fn write_block(&self, block: Block<'static>) -> PcapResult<bool> {
+        send(&self.sender, self.policy, &self.drops, block).map_err(|_| PcapError::IoError(ErrorKind::BrokenPipe.into()))
+    }
+
+    /// Sends a block to the writer thread, see [`ThreadedWriter::write_block()`].
+    pub fn write_pcapng_block<B: Into<Block<'static>>>(&self, block: B) -> PcapResult<bool> {
+        self.write_block(block.into())
+    }
+
+    /// Returns the number of packets dropped by the [`ThreadedWriter`] and all its sinks.
+    pub fn dropped(&self) -> u64 {
+        self.drops.total.load(Ordering::Relaxed)
+    }
+}
+
+/// Sends a block to the writer thread with the given policy, failing if the thread is stopped.
+fn send(sender: &SyncSender<Message>, policy: OverflowPolicy, drops: &Drops, block: Block<'static>) -> Result<bool, ()> {
+    let interface_id = match &block {
+        Block::EnhancedPacket(packet) => Some(packet.interface_id),
+        Block::Packet(packet) => Some(packet.interface_id as u32),
+        Block::SimplePacket(_) => Some(0),
+        _ => None,
+    };
+
+    match (policy, interface_id) {
+        (OverflowPolicy::Drop, Some(interface_id)) => match sender.try_send(Message::Block(block)) {
+            Ok(()) => Ok(true),
+            Err(TrySendError::Full(_)) => {
+                drops.record(interface_id);
+                Ok(false)
+            },
+            Err(TrySendError::Disconnected(_)) => Err(()),
+        },
+        _ => sender.send(Message::Block(block)).map(|_| true).map_err(|_| ()),
+    }
+}
+
+/// Sends an interface to the writer thread and waits for its id, failing if the thread is stopped.
+fn add_interface(sender: &SyncSender<Message>, interface: &InterfaceDescriptionBlock) -> Result<PcapResult<InterfaceId>, ()> {
+    let (reply_sender, reply_receiver) = std::sync::mpsc::sync_channel(1);
+    sender.send(Message::Interface(interface.clone().into_owned(), reply_sender)).map_err(|_| ())?;
+    reply_receiver.recv().map_err(|_| ())
+}
+
+/// Waits for the writer thread, turning a panic into an error.
+fn join<W: Write>(thread: Option<JoinHandle<PcapResult<PcapNgWriter<W>>>>) -> PcapResult<PcapNgWriter<W>> {
+    let thread = thread.ok_or_else(|| PcapError::IoError(ErrorKind::BrokenPipe.into()))?;
+    thread
+        .join()
+        .unwrap_or_else(|_| Err(PcapError::IoError(Error::other("ThreadedWriter: writer thread panicked"))))
+}
+
+impl<W: Write + Send + 'static> Drop for ThreadedWriter<W> {
+    fn drop(&mut self) {
+        // The thread is detached: the blocks are still written, the errors can't be reported, use finish() to check them
+        if let Some(sender) = self.sender.take() {
+            let _ = sender.try_send(Message::Close);
+        }
+        self.thread = None;
+    }
+}
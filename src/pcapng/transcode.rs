@@ -0,0 +1,211 @@
+use std::io::{Read, Write};
+
+use byteorder_slice::{BigEndian, ByteOrder, LittleEndian};
+
+use super::blocks::{
+    CUSTOM_BLOCK, CUSTOM_BLOCK_NO_COPY, DECRYPTION_SECRETS_BLOCK, ENHANCED_PACKET_BLOCK, HONE_CONNECTION_EVENT_BLOCK, HONE_MACHINE_INFO_BLOCK,
+    INTERFACE_DESCRIPTION_BLOCK, INTERFACE_STATISTIC_BLOCK, NAME_RESOLUTION_BLOCK, PACKET_BLOCK, SECTION_HEADER_BLOCK, SIMPLE_PACKET_BLOCK,
+};
+use super::RawBlock;
+use crate::errors::PcapError;
+use crate::read_buffer::ReadBuffer;
+use crate::{Endianness, PcapResult};
+
+
+/// Converts a PcapNg stream to the given endianness without parsing the content of the blocks.
+///
+/// Only the fields whose layout is known are rewritten: the block headers and trailers, the fixed fields
+/// of the standard blocks, the option headers and the numeric values of the standard options.
+/// The packet data, the name resolution records, the unknown blocks and the unknown options are copied byte-exactly.
+///
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+///
+/// use pcap_file::pcapng::transcode;
+/// use pcap_file::Endianness;
+///
+/// let file_in = File::open("test.pcapng").expect("Error opening file");
+/// let file_out = File::create("out.pcapng").expect("Error creating file");
+///
+/// transcode(file_in, file_out, Endianness::Little).unwrap();
+/// ```
+pub fn transcode<R: Read, W: Write>(reader: R, mut writer: W, endianness: Endianness) -> PcapResult<()> {
+    let mut reader = ReadBuffer::new(reader);
+
+    // Overwritten by the first block, which must be a section header
+    let mut section_endianness = Endianness::Big;
+    let mut first_block = true;
+
+    while reader.has_data_left().map_err(PcapError::IoError)? {
+        let current_endianness = section_endianness;
+        let mut block = reader.parse_with(move |src| {
+            let (rem, _) = match current_endianness {
+                Endianness::Big => RawBlock::from_slice::<BigEndian>(src)?,
+                Endianness::Little => RawBlock::from_slice::<LittleEndian>(src)?,
+            };
+            let block_len = src.len() - rem.len();
+
+            Ok((rem, src[..block_len].to_vec()))
+        })?;
+
+        // The section header type is a palindrome and its magic has already been checked
+        if block[..4] == SECTION_HEADER_BLOCK.to_be_bytes() {
+            section_endianness = if block[8..12] == [0x1A, 0x2B, 0x3C, 0x4D] { Endianness::Big } else { Endianness::Little };
+        }
+        else if first_block {
+            return Err(PcapError::InvalidField("PcapNg: SectionHeader invalid or missing"));
+        }
+        first_block = false;
+
+        if section_endianness != endianness {
+            match section_endianness {
+                Endianness::Big => swap_block::<BigEndian>(&mut block)?,
+                Endianness::Little => swap_block::<LittleEndian>(&mut block)?,
+            }
+        }
+
+        writer.write_all(&block).map_err(PcapError::IoError)?;
+    }
+
+    Ok(())
+}
+
+/// Swaps the byte order of the known fields of a whole block, header and trailer included.
+///
+/// `B` is the byte order of the block before the swap.
+fn swap_block<B: ByteOrder>(block: &mut [u8]) -> PcapResult<()> {
+    let type_ = B::read_u32(&block[..4]);
+    let block_len = block.len();
+
+    swap_fields(&mut block[..8], &[4, 4]);
+    swap_fields(&mut block[block_len - 4..], &[4]);
+
+    let body = &mut block[8..block_len - 4];
+    let opts_offset = match type_ {
+        SECTION_HEADER_BLOCK => swap_fields(body, &[4, 2, 2, 8]),
+        INTERFACE_DESCRIPTION_BLOCK => swap_fields(body, &[2, 2, 4]),
+        PACKET_BLOCK | ENHANCED_PACKET_BLOCK => {
+            let captured_len = B::read_u32(body.get(12..16).ok_or(PcapError::InvalidField("Block: block length < 20"))?) as usize;
+            let fields_len = match type_ {
+                PACKET_BLOCK => swap_fields(body, &[2, 2, 4, 4, 4, 4]),
+                _ => swap_fields(body, &[4, 4, 4, 4, 4]),
+            };
+
+            captured_len
+                .checked_add((4 - captured_len % 4) % 4)
+                .and_then(|data_len| data_len.checked_add(fields_len))
+                .filter(|&data_end| data_end <= body.len())
+                .ok_or(PcapError::InvalidField("Block: captured_len + padding > block length"))?
+        },
+        DECRYPTION_SECRETS_BLOCK => {
+            let secrets_len = B::read_u32(body.get(4..8).ok_or(PcapError::InvalidField("Block: block length < 16"))?) as usize;
+            let fields_len = swap_fields(body, &[4, 4]);
+
+            secrets_len
+                .checked_add((4 - secrets_len % 4) % 4)
+                .and_then(|data_len| data_len.checked_add(fields_len))
+                .filter(|&data_end| data_end <= body.len())
+                .ok_or(PcapError::InvalidField("Block: secrets_len + padding > block length"))?
+        },
+        SIMPLE_PACKET_BLOCK => {
+            swap_fields(body, &[4]);
+            return Ok(());
+        },
+        NAME_RESOLUTION_BLOCK => swap_records::<B>(body)?,
+        // The timestamp is stored as its high and low 32 bits halves
+        INTERFACE_STATISTIC_BLOCK => swap_fields(body, &[4, 4, 4]),
+        HONE_MACHINE_INFO_BLOCK => 0,
+        HONE_CONNECTION_EVENT_BLOCK => swap_fields(body, &[4, 4, 4, 4]),
+        // The custom data is opaque
+        CUSTOM_BLOCK | CUSTOM_BLOCK_NO_COPY => {
+            swap_fields(body, &[4]);
+            return Ok(());
+        },
+        _ => return Ok(()),
+    };
+
+    if body.len() < opts_offset {
+        return Err(PcapError::InvalidField("Block: block length too small for its fields"));
+    }
+
+    swap_options::<B>(type_, &mut body[opts_offset..])
+}
+
+/// Swaps the byte order of the consecutive fields of the given widths.
+///
+/// Returns the total length of the fields. The fields which are not entirely in the slice are left unchanged.
+fn swap_fields(slice: &mut [u8], widths: &[usize]) -> usize {
+    let mut offset = 0;
+    for width in widths {
+        if let Some(field) = slice.get_mut(offset..offset + width) {
+            field.reverse();
+        }
+        offset += width;
+    }
+
+    offset
+}
+
+/// Swaps the byte order of the record headers of a name resolution block.
+///
+/// Returns the length of the records.
+fn swap_records<B: ByteOrder>(body: &mut [u8]) -> PcapResult<usize> {
+    let mut offset = 0;
+
+    loop {
+        let header = body
+            .get_mut(offset..offset + 4)
+            .ok_or(PcapError::InvalidField("NameResolutionBlock: Record length > slice.len()"))?;
+        let type_ = B::read_u16(&header[..2]);
+        let length = B::read_u16(&header[2..]) as usize;
+        swap_fields(header, &[2, 2]);
+
+        offset += 4 + length + (4 - length % 4) % 4;
+        if type_ == 0 {
+            return Ok(offset);
+        }
+    }
+}
+
+/// Swaps the byte order of the option headers and of the numeric values of the standard options of a block.
+fn swap_options<B: ByteOrder>(block_type: u32, mut slice: &mut [u8]) -> PcapResult<()> {
+    while slice.len() >= 4 {
+        let code = B::read_u16(&slice[..2]);
+        let length = B::read_u16(&slice[2..4]) as usize;
+        swap_fields(slice, &[2, 2]);
+
+        let value = slice
+            .get_mut(4..4 + length)
+            .ok_or(PcapError::InvalidField("Option: length + pad.len() > slice.len()"))?;
+
+        let widths: &[usize] = match (block_type, code) {
+            (_, 0) => return Ok(()),
+            // Private Enterprise Number followed by custom data
+            (_, 2988 | 2989 | 19372 | 19373) => {
+                swap_fields(value, &[4]);
+                &[]
+            },
+            // if_EUIaddr (7) is a byte string, copied unchanged
+            (INTERFACE_DESCRIPTION_BLOCK, 8 | 14 | 16 | 17) => &[8],
+            (INTERFACE_DESCRIPTION_BLOCK, 10) => &[4],
+            (PACKET_BLOCK | ENHANCED_PACKET_BLOCK, 2) => &[4],
+            (ENHANCED_PACKET_BLOCK, 4 | 5) => &[8],
+            (ENHANCED_PACKET_BLOCK, 6) => &[4],
+            // eBPF verdicts
+            (ENHANCED_PACKET_BLOCK, 7) if matches!(value.first(), Some(1 | 2)) => &[1, 8],
+            (INTERFACE_STATISTIC_BLOCK, 2 | 3) => &[4, 4],
+            (INTERFACE_STATISTIC_BLOCK, 4..=8) => &[8],
+            (HONE_CONNECTION_EVENT_BLOCK, 2) => &[4],
+            _ => &[],
+        };
+        if value.len() == widths.iter().sum::<usize>() {
+            swap_fields(value, widths);
+        }
+
+        let opt_len = (4 + length + (4 - length % 4) % 4).min(slice.len());
+        slice = &mut std::mem::take(&mut slice)[opt_len..];
+    }
+
+    Ok(())
+}
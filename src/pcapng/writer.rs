@@ -1,13 +1,22 @@
-use std::io::Write;
+use std::io::{BufWriter, Seek, SeekFrom, StdoutLock, Write};
+use std::time::SystemTime;
 
 use byteorder_slice::{BigEndian, ByteOrder, LittleEndian};
 
-use super::blocks::block_common::{Block, PcapNgBlock};
-use super::blocks::interface_description::InterfaceDescriptionBlock;
+use super::blocks::block_common::{write_block_to, Block, PcapNgBlock};
+use super::blocks::enhanced_packet::{EnhancedPacketBlock, EnhancedPacketOption};
+use super::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceId};
+use super::blocks::interface_statistics::{InterfaceStatisticsBlock, InterfaceStatisticsOption};
 use super::blocks::section_header::SectionHeaderBlock;
-use super::blocks::SECTION_HEADER_BLOCK;
-use super::RawBlock;
-use crate::{Endianness, PcapError, PcapResult};
+use super::blocks::unknown::UnknownBlock;
+use super::blocks::{
+    ENHANCED_PACKET_BLOCK, INTERFACE_DESCRIPTION_BLOCK, INTERFACE_STATISTIC_BLOCK, PACKET_BLOCK, SECTION_HEADER_BLOCK, SIMPLE_PACKET_BLOCK,
+};
+use super::{ApplicationMetadata, MetadataConfig, MetadataPoint, PreservedBlock, RawBlock};
+use crate::common::{preallocate, truncate};
+use crate::timestamp::IntoTimestamp;
+use crate::write_buffer::WriteBuffer;
+use crate::{Endianness, FileWriter, IoStats, PcapError, PcapResult, SnapLen};
 
 
 /// Writes a PcapNg to a writer.
@@ -33,10 +42,102 @@ use crate::{Endianness, PcapError, PcapResult};
 ///     pcapng_writer.write_block(&block).unwrap();
 /// }
 /// ```
+///
+/// The writer never seeks: the section length is written as -1 unless set otherwise in the section header,
+/// so the output can be a pipe or a socket. If it is seekable, [`finish_with_section_lengths()`](Self::finish_with_section_lengths)
+/// writes the actual lengths at the end. The blocks are written as they come, call [`flush()`](Self::flush)
+/// if the underlying writer is buffered.
+///
+/// The header, the small fields and the trailer of a block are gathered on the stack and written along with its data
+/// with vectored writes: a block takes a single call, or two when it holds a large packet. To also batch the small
+/// blocks, the writer can keep them in an internal buffer, see [`set_buffer_capacity()`](Self::set_buffer_capacity).
+///
+/// When the writer is dropped, the internal buffer is written on a best-effort basis, ignoring the errors.
+/// Call [`finish()`](Self::finish) to check them and to write the closing statistics.
 pub struct PcapNgWriter<W: Write> {
     section: SectionHeaderBlock<'static>,
     interfaces: Vec<InterfaceDescriptionBlock<'static>>,
-    writer: W,
+    pending_drops: Vec<u64>,
+    counters: Vec<InterfaceCounters>,
+    closing_statistics: bool,
+    option_order: OptionOrder,
+    metadata: Option<MetadataConfig>,
+    /// Packets written since the last periodic metadata
+    packets_since_metadata: u64,
+    /// Position of the current section
+    current_section: SectionSpan,
+    /// Previous sections whose length must be written by [`Self::finish_with_section_lengths()`]
+    closed_sections: Vec<SectionSpan>,
+    writer: WriteBuffer<W>,
+    stats: IoStats,
+    registered_blocks: Vec<(u32, BlockTranscoder)>,
+}
+
+/// Order in which a [`PcapNgWriter`] writes the options of the blocks.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum OptionOrder {
+    /// The options are written in the order of the block
+    #[default]
+    AsProvided,
+    /// The options are written by ascending code, the options of the same code keeping their order,
+    /// see [`Block::sort_options()`]
+    Canonical,
+}
+
+/// Packet to write with [`PcapNgWriter::write_packets()`], borrowing its data.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct PacketRef<'a> {
+    /// Interface on which the packet was captured
+    pub interface: InterfaceId,
+    /// Capture time of the packet
+    pub timestamp: SystemTime,
+    /// Captured data of the packet
+    pub data: &'a [u8],
+}
+
+impl<'a> PacketRef<'a> {
+    /// Creates a new [`PacketRef`].
+    pub fn new(interface: InterfaceId, timestamp: SystemTime, data: &'a [u8]) -> Self {
+        PacketRef { interface, timestamp, data }
+    }
+}
+
+/// Position of a section, relative to the first byte written by the writer
+#[derive(Clone, Copy)]
+struct SectionSpan {
+    /// Offset of the section header
+    offset: u64,
+    /// Length of the section header
+    header_len: u64,
+    /// Length of the section, after its header, once it is closed
+    len: u64,
+    endianness: Endianness,
+    /// The section length was left unspecified in the section header
+    unspecified: bool,
+}
+
+impl SectionSpan {
+    fn new(section: &SectionHeaderBlock, offset: u64, header_len: u64) -> Self {
+        SectionSpan { offset, header_len, len: 0, endianness: section.endianness, unspecified: section.section_length == -1 }
+    }
+}
+
+/// Packets written and drops reported on an interface, for the closing statistics
+#[derive(Clone, Copy, Default)]
+struct InterfaceCounters {
+    packets: u64,
+    drops: u64,
+}
+
+/// Re-encodes the body of a registered block, read in the given endianness, in the other endianness.
+type BlockTranscoder = fn(&[u8], Endianness) -> Result<Vec<u8>, PcapError>;
+
+/// Returns true for the standard types which the writer checks against its state and can't write as user-defined blocks.
+fn is_standard_block_type(type_: u32) -> bool {
+    matches!(
+        type_,
+        SECTION_HEADER_BLOCK | INTERFACE_DESCRIPTION_BLOCK | INTERFACE_STATISTIC_BLOCK | ENHANCED_PACKET_BLOCK | PACKET_BLOCK | SIMPLE_PACKET_BLOCK
+    )
 }
 
 impl<W: Write> PcapNgWriter<W> {
@@ -71,12 +172,48 @@ impl<W: Write> PcapNgWriter<W> {
 
     /// Creates a new [`PcapNgWriter`] from an existing writer with the given section header.
     pub fn with_section_header(mut writer: W, section: SectionHeaderBlock<'static>) -> PcapResult<Self> {
-        match section.endianness {
+        let len = match section.endianness {
             Endianness::Big => section.clone().into_block().write_to::<BigEndian, _>(&mut writer).map_err(PcapError::IoError)?,
             Endianness::Little => section.clone().into_block().write_to::<LittleEndian, _>(&mut writer).map_err(PcapError::IoError)?,
         };
 
-        Ok(Self { section, interfaces: vec![], writer })
+        Ok(Self::from_parts(writer, section, len))
+    }
+
+    /// Creates a new [`PcapNgWriter`] from an existing writer, writing the original bytes of a section header,
+    /// typically from [`PcapNgReader::raw_section()`](super::PcapNgReader::raw_section).
+    pub fn with_raw_section_header(mut writer: W, block: &RawBlock) -> PcapResult<Self> {
+        let section = match block.clone().try_into_block::<BigEndian>()?.into_owned() {
+            Block::SectionHeader(section) => section,
+            _ => return Err(PcapError::InvalidField("PcapNgWriter: raw block is not a SectionHeaderBlock")),
+        };
+        let len = match section.endianness {
+            Endianness::Big => block.write_to::<BigEndian, _>(&mut writer).map_err(PcapError::IoError)?,
+            Endianness::Little => block.write_to::<LittleEndian, _>(&mut writer).map_err(PcapError::IoError)?,
+        };
+
+        Ok(Self::from_parts(writer, section, len))
+    }
+
+    fn from_parts(writer: W, section: SectionHeaderBlock<'static>, len: usize) -> Self {
+        let stats = IoStats { blocks: 1, bytes: len as u64, ..Default::default() };
+        let current_section = SectionSpan::new(&section, 0, len as u64);
+
+        Self {
+            section,
+            interfaces: vec![],
+            pending_drops: vec![],
+            counters: vec![],
+            closing_statistics: false,
+            option_order: OptionOrder::default(),
+            metadata: None,
+            packets_since_metadata: 0,
+            current_section,
+            closed_sections: vec![],
+            writer: WriteBuffer::new(writer),
+            stats,
+            registered_blocks: vec![],
+        }
     }
 
     /// Writes a [`Block`].
@@ -115,32 +252,106 @@ impl<W: Write> PcapNgWriter<W> {
     /// pcap_ng_writer.write_block(&packet.into_block()).unwrap();
     /// ```
     pub fn write_block(&mut self, block: &Block) -> PcapResult<usize> {
+        let res = self.write_block_inner(block);
+        if res.is_ok() {
+            match block {
+                Block::EnhancedPacket(a) => self.count_packet(a.interface_id),
+                Block::Packet(a) => self.count_packet(a.interface_id as u32),
+                Block::SimplePacket(_) => self.count_packet(0),
+                _ => (),
+            }
+        }
+
+        let is_packet = matches!(block, Block::EnhancedPacket(_) | Block::SimplePacket(_) | Block::Packet(_));
+        let len = self.stats.record(res.map(|len| (len, len as u64)), |_| is_packet)?;
+        if let Block::SectionHeader(_) = block {
+            self.start_section(len);
+            self.write_metadata_at(MetadataPoint::AfterSectionHeader)?;
+        }
+        else if is_packet {
+            self.tick_metadata()?;
+        }
+
+        Ok(len)
+    }
+
+    /// Checks and tracks a [`Block`] then writes it.
+    fn write_block_inner(&mut self, block: &Block) -> PcapResult<usize> {
         match block {
             Block::SectionHeader(a) => {
                 self.section = a.clone().into_owned();
                 self.interfaces.clear();
+                self.pending_drops.clear();
+                self.counters.clear();
             },
             Block::InterfaceDescription(a) => {
+                // The linktype is a 16 bits field in a pcapng file
+                if u32::from(a.linktype) > u16::MAX as u32 {
+                    return Err(PcapError::InvalidField("InterfaceDescriptionBlock: linktype > u16::MAX"));
+                }
                 self.interfaces.push(a.clone().into_owned());
+                self.pending_drops.push(0);
+                self.counters.push(InterfaceCounters::default());
             },
             Block::InterfaceStatistics(a) => {
                 if a.interface_id as usize >= self.interfaces.len() {
                     return Err(PcapError::InvalidInterfaceId(a.interface_id));
                 }
             },
+            Block::Unknown(a) => {
+                if let Some(endianness) = a.endianness().filter(|&endianness| endianness != self.section.endianness) {
+                    let (_, transcode) = self
+                        .registered_blocks
+                        .iter()
+                        .find(|(type_, _)| *type_ == a.type_)
+                        .ok_or(PcapError::InvalidField("UnknownBlock: endianness != section endianness and block type not registered"))?;
+
+                    let value = transcode(&a.value, endianness)?;
+                    let block = UnknownBlock::new(a.type_, a.length, &value).with_endianness(self.section.endianness);
+                    return self.write_to_writer(&Block::Unknown(block));
+                }
+            },
+            Block::Registered(a) => {
+                if is_standard_block_type(a.block_type()) {
+                    return Err(PcapError::InvalidField("RegisteredBlock: user-defined block with the type of a standard block"));
+                }
+            },
+            Block::SimplePacket(a) => {
+                let interface = self.interfaces.first().ok_or(PcapError::InvalidInterfaceId(0))?;
+                if !SnapLen(interface.snaplen).allows(a.data.len()) {
+                    return Err(PcapError::InvalidField("SimplePacketBlock: data length > snaplen"));
+                }
+                if a.data.len() != a.captured_len(interface.snaplen) {
+                    return Err(PcapError::InvalidField("SimplePacketBlock: data length != min(original_len, snaplen)"));
+                }
+            },
             Block::EnhancedPacket(a) => {
                 if a.interface_id as usize >= self.interfaces.len() {
                     return Err(PcapError::InvalidInterfaceId(a.interface_id));
                 }
+
+                let drops = self.pending_drops[a.interface_id as usize];
+                if drops > 0 {
+                    let mut packet = a.clone();
+                    match packet.options.iter_mut().find_map(|opt| match opt {
+                        EnhancedPacketOption::DropCount(count) => Some(count),
+                        _ => None,
+                    }) {
+                        Some(count) => *count = count.saturating_add(drops),
+                        None => packet.options.push(EnhancedPacketOption::DropCount(drops)),
+                    }
+
+                    let len = self.write_to_writer(&packet.into_block())?;
+                    self.pending_drops[a.interface_id as usize] = 0;
+
+                    return Ok(len);
+                }
             },
 
             _ => (),
         }
 
-        match self.section.endianness {
-            Endianness::Big => block.write_to::<BigEndian, _>(&mut self.writer).map_err(PcapError::IoError),
-            Endianness::Little => block.write_to::<LittleEndian, _>(&mut self.writer).map_err(PcapError::IoError),
-        }
+        self.write_to_writer(block)
     }
 
     /// Writes a [`PcapNgBlock`].
@@ -175,21 +386,145 @@ impl<W: Write> PcapNgWriter<W> {
     /// let file = File::create("out.pcap").expect("Error creating file");
     /// let mut pcap_ng_writer = PcapNgWriter::new(file).unwrap();
     ///
-    /// pcap_ng_writer.write_pcapng_block(interface).unwrap();
-    /// pcap_ng_writer.write_pcapng_block(packet).unwrap();
+    /// pcap_ng_writer.write_pcapng_block(&interface).unwrap();
+    /// pcap_ng_writer.write_pcapng_block(&packet).unwrap();
+    /// ```
+    pub fn write_pcapng_block<'a, B: PcapNgBlock<'a>>(&mut self, block: &B) -> PcapResult<usize> {
+        // The standard blocks are checked and tracked like any other block
+        if let Some(block) = block.to_block() {
+            return self.write_block(&block);
+        }
+
+        // A user-defined block can't be checked against the state of the writer
+        if is_standard_block_type(block.block_type()) {
+            return Err(PcapError::InvalidField("PcapNgWriter: user-defined block with the type of a standard block"));
+        }
+
+        let res = match self.section.endianness {
+            Endianness::Big => write_block_to::<BigEndian, _, _>(block, &mut self.writer),
+            Endianness::Little => write_block_to::<LittleEndian, _, _>(block, &mut self.writer),
+        };
+        self.stats.record(res.map_err(PcapError::IoError).map(|len| (len, len as u64)), |_| false)
+    }
+
+    /// Writes an [`InterfaceDescriptionBlock`] and returns the [`InterfaceId`] to use to write its packets.
+    pub fn add_interface(&mut self, interface: &InterfaceDescriptionBlock) -> PcapResult<InterfaceId> {
+        self.write_block(&Block::InterfaceDescription(interface.clone()))?;
+        Ok(InterfaceId(self.interfaces.len() as u32 - 1))
+    }
+
+    /// Writes a packet captured on the given interface at the given time.
+    ///
+    /// The [`EnhancedPacketBlock`] is built internally: the timestamp is encoded according to the
+    /// `if_tsresol` and `if_tsoffset` options of the interface and the data is truncated to its snaplen.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use std::fs::File;
+    /// use std::time::SystemTime;
+    ///
+    /// use pcap_file::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+    /// use pcap_file::pcapng::PcapNgWriter;
+    /// use pcap_file::DataLink;
+    ///
+    /// let file = File::create("out.pcapng").expect("Error creating file");
+    /// let mut pcap_ng_writer = PcapNgWriter::new(file).unwrap();
+    ///
+    /// let interface = pcap_ng_writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF)).unwrap();
+    /// pcap_ng_writer.write_packet(interface, SystemTime::now(), &[0u8; 10]).unwrap();
     /// ```
-    pub fn write_pcapng_block<'a, B: PcapNgBlock<'a>>(&mut self, block: B) -> PcapResult<usize> {
-        self.write_block(&block.into_block())
+    pub fn write_packet(&mut self, interface: InterfaceId, ts: SystemTime, data: &[u8]) -> PcapResult<usize> {
+        let idb = self.interfaces.get(interface.0 as usize).ok_or(PcapError::InvalidInterfaceId(interface.0))?;
+
+        let packet = EnhancedPacketBlock::builder(interface, data).time(ts).build(idb)?;
+
+        self.write_block(&packet.into_block())
+    }
+
+    /// Writes a batch of packets with a single call to the underlying writer, returning the number of bytes written.
+    ///
+    /// The packets are encoded straight into the internal buffer, which keeps up to 1 MiB of its allocation for the next
+    /// batches, or its capacity if larger. This amortizes the cost of the writes for the capture sources which already receive the packets by
+    /// batches, like AF_XDP or DPDK.
+    ///
+    /// If a packet is invalid, the previous packets of the batch are still written before the error is returned.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use std::fs::File;
+    /// use std::time::SystemTime;
+    ///
+    /// use pcap_file::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+    /// use pcap_file::pcapng::{PacketRef, PcapNgWriter};
+    /// use pcap_file::DataLink;
+    ///
+    /// let file = File::create("out.pcapng").expect("Error creating file");
+    /// let mut pcap_ng_writer = PcapNgWriter::new(file).unwrap();
+    ///
+    /// let interface = pcap_ng_writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF)).unwrap();
+    /// let now = SystemTime::now();
+    /// let batch = [PacketRef::new(interface, now, &[0u8; 10]), PacketRef::new(interface, now, &[1u8; 20])];
+    /// pcap_ng_writer.write_packets(&batch).unwrap();
+    /// ```
+    pub fn write_packets(&mut self, packets: &[PacketRef]) -> PcapResult<usize> {
+        let capacity = self.writer.start_batch();
+        let res = packets.iter().try_fold(0, |len, packet| self.write_batched_packet(packet).map(|n| len + n));
+        let end = self.writer.end_batch(capacity).map_err(PcapError::IoError);
+
+        let len = res?;
+        end?;
+        Ok(len)
+    }
+
+    /// Writes a packet of a batch, see [`Self::write_packets()`].
+    ///
+    /// Its [`EnhancedPacketBlock`] is encoded straight into the buffer of the batch, without building it first.
+    fn write_batched_packet(&mut self, packet: &PacketRef) -> PcapResult<usize> {
+        let interface_id = packet.interface.0;
+        let interface = self.interfaces.get(interface_id as usize).ok_or(PcapError::InvalidInterfaceId(interface_id))?;
+
+        // The pending drops are written in an option of the packet
+        if self.pending_drops[interface_id as usize] > 0 {
+            return self.write_packet(packet.interface, packet.timestamp, packet.data);
+        }
+
+        let since_epoch = packet
+            .timestamp
+            .since_epoch()
+            .ok_or(PcapError::InvalidField("EnhancedPacketBlock: timestamp before the Unix epoch"))?;
+        let timestamp = interface
+            .duration_to_timestamp(since_epoch)
+            .ok_or(PcapError::InvalidField("EnhancedPacketBlock: timestamp not representable by the interface"))?;
+        let data = &packet.data[..SnapLen(interface.snaplen).captured_len(packet.data.len())];
+
+        let buffer = self.writer.batch_buffer();
+        let len = match self.section.endianness {
+            Endianness::Big => encode_packet::<BigEndian>(buffer, interface_id, timestamp, packet.data.len() as u32, data),
+            Endianness::Little => encode_packet::<LittleEndian>(buffer, interface_id, timestamp, packet.data.len() as u32, data),
+        };
+
+        self.count_packet(interface_id);
+        self.stats.record(Ok::<_, PcapError>((len, len as u64)), |_| true)?;
+        self.tick_metadata()?;
+
+        Ok(len)
     }
 
     /// Writes a [`RawBlock`].
     ///
     /// Doesn't check the validity of the written blocks.
     pub fn write_raw_block(&mut self, block: &RawBlock) -> PcapResult<usize> {
-        return match self.section.endianness {
+        let res = match self.section.endianness {
             Endianness::Big => inner::<BigEndian, _>(&mut self.section, block, &mut self.writer),
             Endianness::Little => inner::<LittleEndian, _>(&mut self.section, block, &mut self.writer),
         };
+        let is_packet = matches!(block.type_, ENHANCED_PACKET_BLOCK | SIMPLE_PACKET_BLOCK | PACKET_BLOCK);
+        let len = self.stats.record(res.map(|len| (len, len as u64)), |_| is_packet)?;
+        if block.type_ == SECTION_HEADER_BLOCK {
+            self.start_section(len);
+        }
+
+        return Ok(len);
 
         fn inner<B: ByteOrder, W: Write>(section: &mut SectionHeaderBlock, block: &RawBlock, writer: &mut W) -> PcapResult<usize> {
             if block.type_ == SECTION_HEADER_BLOCK {
@@ -200,21 +535,309 @@ impl<W: Write> PcapNgWriter<W> {
         }
     }
 
+    /// Copies a [`RawBlock`] read from a section of the given endianness, typically from a [`PcapNgReader`](super::PcapNgReader).
+    ///
+    /// When the endianness matches the one of the current section, the block is written byte-for-byte,
+    /// without parsing the packet data. Otherwise it is parsed and written again in the endianness of the
+    /// current section, an unknown block being refused unless its type is registered, see [`Self::write_block()`].
+    ///
+    /// A [`SectionHeaderBlock`] starts a new section with its own endianness.
+    pub fn copy_block(&mut self, block: &RawBlock, endianness: Endianness) -> PcapResult<usize> {
+        match block.type_ {
+            SECTION_HEADER_BLOCK => {
+                let section = block.clone().try_into_block::<BigEndian>()?.into_owned();
+                if section.as_section_header().map(|section| section.endianness) != Some(self.section.endianness) {
+                    return self.write_block(&section);
+                }
+
+                self.interfaces.clear();
+                self.pending_drops.clear();
+                self.counters.clear();
+            },
+            _ if endianness != self.section.endianness => {
+                // An unregistered unknown block is refused, its body can't be re-encoded
+                let block = match endianness {
+                    Endianness::Big => block.clone().try_into_block::<BigEndian>()?,
+                    Endianness::Little => block.clone().try_into_block::<LittleEndian>()?,
+                };
+                return self.write_block(&block);
+            },
+            INTERFACE_DESCRIPTION_BLOCK => {
+                let interface = match endianness {
+                    Endianness::Big => block.clone().try_into_block::<BigEndian>()?,
+                    Endianness::Little => block.clone().try_into_block::<LittleEndian>()?,
+                };
+                self.interfaces.push(interface.into_owned().into_interface_description().unwrap());
+                self.pending_drops.push(0);
+                self.counters.push(InterfaceCounters::default());
+            },
+            _ => (),
+        }
+
+        let len = self.write_raw_block(block)?;
+
+        let read_u32 = |src: &[u8]| match endianness {
+            Endianness::Big => BigEndian::read_u32(src),
+            Endianness::Little => LittleEndian::read_u32(src),
+        };
+        let read_u16 = |src: &[u8]| match endianness {
+            Endianness::Big => BigEndian::read_u16(src),
+            Endianness::Little => LittleEndian::read_u16(src),
+        };
+        match block.type_ {
+            ENHANCED_PACKET_BLOCK if block.body.len() >= 4 => self.count_packet(read_u32(&block.body[..4])),
+            PACKET_BLOCK if block.body.len() >= 2 => self.count_packet(read_u16(&block.body[..2]) as u32),
+            SIMPLE_PACKET_BLOCK => self.count_packet(0),
+            _ => (),
+        }
+
+        match block.type_ {
+            SECTION_HEADER_BLOCK => self.write_metadata_at(MetadataPoint::AfterSectionHeader)?,
+            ENHANCED_PACKET_BLOCK | PACKET_BLOCK | SIMPLE_PACKET_BLOCK => self.tick_metadata()?,
+            _ => (),
+        }
+
+        Ok(len)
+    }
+
+    /// Writes a [`PreservedBlock`], keeping the original bytes of the untouched blocks.
+    ///
+    /// An unmodified block is copied from its original bytes with [`copy_block()`](Self::copy_block), which is
+    /// byte-for-byte when its endianness matches the one of the current section. A modified block is written with
+    /// [`write_block()`](Self::write_block).
+    pub fn write_preserved_block(&mut self, block: &PreservedBlock) -> PcapResult<usize> {
+        if block.is_modified() {
+            self.write_block(block.block())
+        }
+        else {
+            self.copy_block(block.raw(), block.endianness())
+        }
+    }
+
+    /// Registers a user-defined [`PcapNgBlock`].
+    ///
+    /// The [`Block::Unknown`] of this type read in a section of the other endianness are decoded and encoded again
+    /// in the endianness of the current section, instead of being refused.
+    pub fn register_block<T>(&mut self)
+    where
+        T: for<'b> PcapNgBlock<'b>,
+    {
+        self.registered_blocks.push((T::BLOCK_TYPE, transcode::<T>));
+
+        fn transcode<T: for<'b> PcapNgBlock<'b>>(body: &[u8], endianness: Endianness) -> Result<Vec<u8>, PcapError> {
+            let mut value = vec![];
+            match endianness {
+                Endianness::Big => T::from_slice::<BigEndian>(body)?.1.write_to::<LittleEndian, _>(&mut value),
+                Endianness::Little => T::from_slice::<LittleEndian>(body)?.1.write_to::<BigEndian, _>(&mut value),
+            }
+            .map_err(PcapError::IoError)?;
+
+            Ok(value)
+        }
+    }
+
+    /// Reports `count` packets dropped by the capture source on the given interface.
+    ///
+    /// The drops are accumulated and attached as an `epb_dropcount` option to the next
+    /// [`EnhancedPacketBlock`](super::blocks::enhanced_packet::EnhancedPacketBlock) written for this interface.
+    pub fn report_drops(&mut self, interface_id: u32, count: u64) -> PcapResult<()> {
+        let pending = self
+            .pending_drops
+            .get_mut(interface_id as usize)
+            .ok_or(PcapError::InvalidInterfaceId(interface_id))?;
+
+        *pending = pending.saturating_add(count);
+
+        let counters = &mut self.counters[interface_id as usize];
+        counters.drops = counters.drops.saturating_add(count);
+
+        Ok(())
+    }
+
+    /// Returns the number of reported drops not yet written for the given interface.
+    pub fn pending_drops(&self, interface_id: u32) -> u64 {
+        self.pending_drops.get(interface_id as usize).copied().unwrap_or(0)
+    }
+
+    /// Enables or disables the closing statistics, disabled by default.
+    ///
+    /// When enabled, [`finish()`](Self::finish) writes an [`InterfaceStatisticsBlock`] for each interface of the current section,
+    /// with the end time, the number of packets written and the number of drops reported with [`report_drops()`](Self::report_drops).
+    pub fn set_closing_statistics(&mut self, enabled: bool) {
+        self.closing_statistics = enabled;
+    }
+
+    /// Sets the order in which the options of the blocks are written, [`OptionOrder::AsProvided`] by default.
+    ///
+    /// The repeated options are always all written. The raw blocks, e.g. copied with [`copy_block()`](Self::copy_block),
+    /// are written unchanged.
+    pub fn set_option_order(&mut self, order: OptionOrder) {
+        self.option_order = order;
+    }
+
+    /// Returns the order in which the options of the blocks are written.
+    pub fn option_order(&self) -> OptionOrder {
+        self.option_order
+    }
+
+    /// Writes application metadata in a copiable [`CustomBlock`](super::blocks::custom::CustomBlock) of the given
+    /// Private Enterprise Number, see [`ApplicationMetadata`].
+    pub fn write_metadata(&mut self, pen: u32, data: &[u8]) -> PcapResult<usize> {
+        self.write_block(&Block::Custom(ApplicationMetadata::new(MetadataPoint::Explicit, data).to_custom_block(pen)))
+    }
+
+    /// Sets the application metadata written automatically at the points enabled in the [`MetadataConfig`], or disables it.
+    ///
+    /// If it is enabled after the section headers and no block was written after the current one, the metadata is
+    /// written right away. The count of packets of the periodic metadata is reset.
+    pub fn set_metadata(&mut self, metadata: Option<MetadataConfig>) -> PcapResult<()> {
+        self.metadata = metadata;
+        self.packets_since_metadata = 0;
+
+        if self.stats.bytes == self.current_section.offset + self.current_section.header_len {
+            self.write_metadata_at(MetadataPoint::AfterSectionHeader)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the application metadata written automatically.
+    pub fn metadata(&self) -> Option<&MetadataConfig> {
+        self.metadata.as_ref()
+    }
+
+    /// Writes the closing statistics and the metadata at the end if enabled, flushes the writer and returns the wrapped writer.
+    ///
+    /// Unlike [`into_inner()`](Self::into_inner) and the drop of the writer, which write the internal buffer ignoring the errors,
+    /// the errors are returned.
+    pub fn finish(mut self) -> PcapResult<W> {
+        self.finish_inner()?;
+        Ok(self.into_inner())
+    }
+
+    /// Writes the closing statistics and the metadata at the end if enabled and flushes the writer.
+    fn finish_inner(&mut self) -> PcapResult<()> {
+        if self.closing_statistics {
+            let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default();
+            for interface_id in 0..self.interfaces.len() {
+                let timestamp = self.interfaces[interface_id]
+                    .duration_to_timestamp(now)
+                    .ok_or(PcapError::InvalidField("InterfaceStatisticsBlock: timestamp not representable by the interface"))?;
+                let counters = self.counters[interface_id];
+                let statistics = InterfaceStatisticsBlock {
+                    interface_id: interface_id as u32,
+                    timestamp,
+                    options: vec![
+                        InterfaceStatisticsOption::IsbEndTime(timestamp),
+                        InterfaceStatisticsOption::IsbIfDrop(counters.drops),
+                        InterfaceStatisticsOption::IsbUsrDeliv(counters.packets),
+                    ],
+                };
+                self.write_block(&statistics.into_block())?;
+            }
+        }
+
+        self.write_metadata_at(MetadataPoint::AtEnd)?;
+        self.flush()
+    }
+
+    /// Closes the current section and tracks the section header just written.
+    fn start_section(&mut self, header_len: usize) {
+        let offset = self.stats.bytes - header_len as u64;
+        let mut closed = std::mem::replace(&mut self.current_section, SectionSpan::new(&self.section, offset, header_len as u64));
+        if closed.unspecified {
+            closed.len = offset - closed.offset - closed.header_len;
+            self.closed_sections.push(closed);
+        }
+    }
+
+    /// Writes the application metadata if enabled at the given point.
+    fn write_metadata_at(&mut self, point: MetadataPoint) -> PcapResult<()> {
+        match self.metadata.as_ref().and_then(|metadata| metadata.block_at(point)) {
+            Some(block) => self.write_block(&block).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    /// Counts a packet for the periodic metadata, writing it when due.
+    fn tick_metadata(&mut self) -> PcapResult<()> {
+        self.packets_since_metadata += 1;
+        match &self.metadata {
+            Some(metadata) if metadata.interval > 0 && self.packets_since_metadata >= metadata.interval => {
+                self.packets_since_metadata = 0;
+                self.write_metadata_at(MetadataPoint::Periodic)
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts a packet written on the given interface.
+    fn count_packet(&mut self, interface_id: u32) {
+        if let Some(counters) = self.counters.get_mut(interface_id as usize) {
+            counters.packets += 1;
+        }
+    }
+
+    /// Writes a [`Block`] to the underlying writer, using the endianness of the current section.
+    fn write_to_writer(&mut self, block: &Block) -> PcapResult<usize> {
+        if self.option_order == OptionOrder::Canonical && !block.option_codes().windows(2).all(|pair| pair[0] <= pair[1]) {
+            let mut block = block.clone();
+            block.sort_options();
+            return self.write_to_writer(&block);
+        }
+
+        match self.section.endianness {
+            Endianness::Big => block.write_to::<BigEndian, _>(&mut self.writer).map_err(PcapError::IoError),
+            Endianness::Little => block.write_to::<LittleEndian, _>(&mut self.writer).map_err(PcapError::IoError),
+        }
+    }
+
+    /// Writes the content of the internal buffer and flushes the underlying writer.
+    pub fn flush(&mut self) -> PcapResult<()> {
+        self.writer.flush().map_err(PcapError::IoError)
+    }
+
+    /// Sets the capacity of the internal buffer, 0 by default.
+    ///
+    /// With a capacity of 0, each block is written to the underlying writer as soon as it is given. Otherwise the blocks
+    /// are kept in the buffer until it is full, which saves a lot of calls for the small blocks when the underlying writer
+    /// is an unbuffered file or socket, but delays them: call [`flush()`](Self::flush) to write them. The buffer is also
+    /// written by [`into_inner()`](Self::into_inner) and when the writer is dropped, ignoring the errors.
+    ///
+    /// The content of the buffer is written first if it doesn't fit in the new capacity.
+    pub fn set_buffer_capacity(&mut self, capacity: usize) -> PcapResult<()> {
+        self.writer.set_capacity(capacity).map_err(PcapError::IoError)
+    }
+
+    /// Returns the capacity of the internal buffer.
+    pub fn buffer_capacity(&self) -> usize {
+        self.writer.capacity()
+    }
+
     /// Consumes [`Self`], returning the wrapped writer.
+    ///
+    /// The content of the internal buffer is written first, ignoring the errors: call [`flush()`](Self::flush) before to check them.
     pub fn into_inner(self) -> W {
-        self.writer
+        self.writer.into_inner()
     }
 
     /// Gets a reference to the underlying writer.
     pub fn get_ref(&self) -> &W {
-        &self.writer
+        self.writer.get_ref()
     }
 
     /// Gets a mutable reference to the underlying writer.
     ///
-    /// You should not be used unless you really know what you're doing
+    /// You should not be used unless you really know what you're doing, the internal buffer being bypassed.
     pub fn get_mut(&mut self) -> &mut W {
-        &mut self.writer
+        self.writer.get_mut()
+    }
+
+    /// Returns the counters of the blocks written since the creation of the writer, including its section header.
+    ///
+    /// The blocks kept in the internal buffer are counted as written.
+    pub fn stats(&self) -> IoStats {
+        self.stats
     }
 
     /// Returns the current [`SectionHeaderBlock`].
@@ -227,3 +850,130 @@ impl<W: Write> PcapNgWriter<W> {
         &self.interfaces
     }
 }
+
+impl<W: FileWriter> PcapNgWriter<W> {
+    /// Flushes the writer then syncs the data of the file to the disk, see [`File::sync_data()`](std::fs::File::sync_data).
+    pub fn sync_data(&mut self) -> PcapResult<()> {
+        self.flush()?;
+        self.writer.get_ref().file().sync_data().map_err(PcapError::IoError)
+    }
+
+    /// Flushes the writer then syncs the data and the metadata of the file to the disk, see [`File::sync_all()`](std::fs::File::sync_all).
+    pub fn sync_all(&mut self) -> PcapResult<()> {
+        self.flush()?;
+        self.writer.get_ref().file().sync_all().map_err(PcapError::IoError)
+    }
+
+    /// Flushes the writer then extends the file so that it holds `len` more bytes, e.g. the expected size of the capture,
+    /// to reduce its fragmentation on the disk during a long capture.
+    ///
+    /// The disk space is reserved with `posix_fallocate()`, so that a full disk is reported here rather than in the middle of
+    /// the capture. Where it isn't supported, the file is only extended with [`File::set_len()`](std::fs::File::set_len),
+    /// which reserves nothing. The preallocated space must be removed by [`finish_truncated()`](Self::finish_truncated),
+    /// otherwise the file ends with zeros.
+    pub fn preallocate(&mut self, len: u64) -> PcapResult<()> {
+        self.flush()?;
+        preallocate(self.writer.get_ref(), len)
+    }
+
+    /// Like [`finish()`](Self::finish), then truncates the file to the written data, removing the space preallocated by
+    /// [`preallocate()`](Self::preallocate).
+    pub fn finish_truncated(self) -> PcapResult<W> {
+        let writer = self.finish()?;
+        truncate(&writer)?;
+        Ok(writer)
+    }
+}
+
+impl<W: Write + Seek> PcapNgWriter<W> {
+    /// Like [`finish()`](Self::finish), then writes the length of all the sections in their section header,
+    /// unless it was already specified.
+    ///
+    /// The readers can then skip a whole section without reading its blocks. The positions are computed from the number
+    /// of bytes written by the writer, so the underlying writer must not have been written to directly in the meantime.
+    pub fn finish_with_section_lengths(mut self) -> PcapResult<W> {
+        self.finish_inner()?;
+
+        let mut sections = std::mem::take(&mut self.closed_sections);
+        if self.current_section.unspecified {
+            let mut current = self.current_section;
+            current.len = self.stats.bytes - current.offset - current.header_len;
+            sections.push(current);
+        }
+
+        let bytes = self.stats.bytes;
+        let mut writer = self.into_inner();
+
+        let end = writer.stream_position().map_err(PcapError::IoError)?;
+        let start = end
+            .checked_sub(bytes)
+            .ok_or(PcapError::InvalidField("PcapNgWriter: stream position < number of bytes written"))?;
+
+        for section in sections {
+            let len = i64::try_from(section.len).map_err(|_| PcapError::InvalidField("SectionHeaderBlock: section_length > i64::MAX"))?;
+            let mut buf = [0_u8; 8];
+            match section.endianness {
+                Endianness::Big => BigEndian::write_i64(&mut buf, len),
+                Endianness::Little => LittleEndian::write_i64(&mut buf, len),
+            }
+
+            // The section length follows the block type, the block length, the byte-order magic and the versions
+            writer.seek(SeekFrom::Start(start + section.offset + 16)).map_err(PcapError::IoError)?;
+            writer.write_all(&buf).map_err(PcapError::IoError)?;
+        }
+
+        writer.seek(SeekFrom::Start(end)).map_err(PcapError::IoError)?;
+        writer.flush().map_err(PcapError::IoError)?;
+
+        Ok(writer)
+    }
+}
+
+impl PcapNgWriter<BufWriter<StdoutLock<'static>>> {
+    /// Creates a new [`PcapNgWriter`] writing to the locked and buffered standard output.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use std::time::SystemTime;
+    ///
+    /// use pcap_file::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+    /// use pcap_file::pcapng::PcapNgWriter;
+    /// use pcap_file::DataLink;
+    ///
+    /// // e.g. `my_capture | wireshark -k -i -`
+    /// let mut pcapng_writer = PcapNgWriter::to_stdout().unwrap();
+    /// let interface = pcapng_writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0)).unwrap();
+    /// pcapng_writer.write_packet(interface, SystemTime::now(), &[0_u8; 64]).unwrap();
+    /// pcapng_writer.flush().unwrap();
+    /// ```
+    pub fn to_stdout() -> PcapResult<Self> {
+        Self::new(BufWriter::new(std::io::stdout().lock()))
+    }
+}
+
+
+/// Encodes an [`EnhancedPacketBlock`] without options at the end of `buffer`, returning its length.
+fn encode_packet<B: ByteOrder>(buffer: &mut Vec<u8>, interface_id: u32, timestamp: u64, original_len: u32, data: &[u8]) -> usize {
+    let pad_len = (4 - data.len() % 4) % 4;
+    let block_len = 32 + data.len() + pad_len;
+
+    let mut header = [0_u8; 28];
+    B::write_u32(&mut header[0..4], ENHANCED_PACKET_BLOCK);
+    B::write_u32(&mut header[4..8], block_len as u32);
+    B::write_u32(&mut header[8..12], interface_id);
+    B::write_u32(&mut header[12..16], (timestamp >> 32) as u32);
+    B::write_u32(&mut header[16..20], timestamp as u32);
+    B::write_u32(&mut header[20..24], data.len() as u32);
+    B::write_u32(&mut header[24..28], original_len);
+
+    let mut trailer = [0_u8; 4];
+    B::write_u32(&mut trailer, block_len as u32);
+
+    buffer.reserve(block_len);
+    buffer.extend_from_slice(&header);
+    buffer.extend_from_slice(data);
+    buffer.extend_from_slice(&[0_u8; 3][..pad_len]);
+    buffer.extend_from_slice(&trailer);
+
+    block_len
+}
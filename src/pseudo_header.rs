@@ -0,0 +1,77 @@
+//! Skipping of the pseudo-headers which precede the link-layer payload of some linktypes.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::fs::File;
+//!
+//! use pcap_file::pcap::PcapReader;
+//! use pcap_file::pseudo_header::payload;
+//!
+//! let file_in = File::open("test.pcap").expect("Error opening file");
+//! let mut pcap_reader = PcapReader::new(file_in).unwrap();
+//! let datalink = pcap_reader.header().datalink;
+//!
+//! while let Some(pkt) = pcap_reader.next_packet() {
+//!     let pkt = pkt.unwrap();
+//!     if let Some(payload) = payload(datalink, &pkt.data) {
+//!         println!("{} bytes of payload", payload.len());
+//!     }
+//! }
+//! ```
+
+use byteorder_slice::{ByteOrder, LittleEndian};
+
+use crate::DataLink;
+
+
+/// Length of a [`DataLink::LINUX_SLL`] header
+pub const LINUX_SLL_LEN: usize = 16;
+/// Length of a [`DataLink::LINUX_SLL2`] header
+pub const LINUX_SLL2_LEN: usize = 20;
+/// Length of a [`DataLink::NULL`] or [`DataLink::LOOP`] header
+pub const NULL_LEN: usize = 4;
+/// Minimal length of a [`DataLink::PPI`] header
+pub const PPI_MIN_LEN: usize = 8;
+
+/// Returns the offset at which the link-layer payload begins after the pseudo-header of the packet.
+///
+/// Supports [`DataLink::LINUX_SLL`], [`DataLink::LINUX_SLL2`], [`DataLink::NULL`], [`DataLink::LOOP`] and [`DataLink::PPI`].
+/// Returns `None` for the other linktypes or if the packet is too short to contain the pseudo-header.
+pub fn payload_offset(datalink: DataLink, data: &[u8]) -> Option<usize> {
+    let offset = match datalink {
+        DataLink::LINUX_SLL => LINUX_SLL_LEN,
+        DataLink::LINUX_SLL2 => LINUX_SLL2_LEN,
+        DataLink::NULL | DataLink::LOOP => NULL_LEN,
+        DataLink::PPI => {
+            if data.len() < PPI_MIN_LEN {
+                return None;
+            }
+
+            let len = LittleEndian::read_u16(&data[2..4]) as usize;
+            if len < PPI_MIN_LEN {
+                return None;
+            }
+
+            len
+        },
+        _ => return None,
+    };
+
+    (data.len() >= offset).then_some(offset)
+}
+
+/// Returns the link-layer payload of the packet, after its pseudo-header.
+///
+/// See [`payload_offset()`] for the supported linktypes.
+pub fn payload(datalink: DataLink, data: &[u8]) -> Option<&[u8]> {
+    payload_offset(datalink, data).map(|offset| &data[offset..])
+}
+
+/// Returns the linktype of the payload of a [`DataLink::PPI`] packet.
+pub fn ppi_datalink(data: &[u8]) -> Option<DataLink> {
+    if data.len() < PPI_MIN_LEN {
+        return None;
+    }
+
+    Some(DataLink::from(LittleEndian::read_u32(&data[4..8])))
+}
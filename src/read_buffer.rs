@@ -1,4 +1,4 @@
-use std::io::{Error, ErrorKind, Read};
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom};
 
 use crate::PcapError;
 
@@ -14,6 +14,8 @@ pub(crate) struct ReadBuffer<R: Read> {
     pos: usize,
     /// Current end position of the buffer
     len: usize,
+    /// Number of bytes consumed since the start of the reader
+    offset: u64,
 }
 
 impl<R: Read> ReadBuffer<R> {
@@ -24,7 +26,7 @@ impl<R: Read> ReadBuffer<R> {
 
     /// Creates a new ReadBuffer with the given capacity
     pub fn with_capacity(reader: R, capacity: usize) -> Self {
-        Self { reader, buffer: vec![0_u8; capacity], pos: 0, len: 0 }
+        Self { reader, buffer: vec![0_u8; capacity], pos: 0, len: 0, offset: 0 }
     }
 
     /// Parse data from the internal buffer
@@ -32,7 +34,31 @@ impl<R: Read> ReadBuffer<R> {
     /// Safety
     ///
     /// The parser must NOT keep a reference to the buffer in input.
-    pub fn parse_with<'a, 'b: 'a, 'c: 'a, F, O>(&'c mut self, mut parser: F) -> Result<O, PcapError>
+    pub fn parse_with<'a, 'b: 'a, 'c: 'a, F, O>(&'c mut self, parser: F) -> Result<O, PcapError>
+    where
+        F: FnMut(&'a [u8]) -> Result<(&'a [u8], O), PcapError>,
+        F: 'b,
+        O: 'a,
+    {
+        self.parse_inner(parser, true)
+    }
+
+    /// Parse data from the internal buffer without consuming it
+    ///
+    /// Safety
+    ///
+    /// The parser must NOT keep a reference to the buffer in input.
+    pub fn peek_with<'a, 'b: 'a, 'c: 'a, F, O>(&'c mut self, parser: F) -> Result<O, PcapError>
+    where
+        F: FnMut(&'a [u8]) -> Result<(&'a [u8], O), PcapError>,
+        F: 'b,
+        O: 'a,
+    {
+        self.parse_inner(parser, false)
+    }
+
+    /// Inner function to parse data from the internal buffer, consuming it or not
+    fn parse_inner<'a, 'b: 'a, 'c: 'a, F, O>(&'c mut self, mut parser: F, consume: bool) -> Result<O, PcapError>
     where
         F: FnMut(&'a [u8]) -> Result<(&'a [u8], O), PcapError>,
         F: 'b,
@@ -46,7 +72,9 @@ impl<R: Read> ReadBuffer<R> {
 
             match parser(buf) {
                 Ok((rem, value)) => {
-                    self.advance_with_slice(rem);
+                    if consume {
+                        self.advance_with_slice(rem);
+                    }
                     return Ok(value);
                 },
 
@@ -90,6 +118,7 @@ impl<R: Read> ReadBuffer<R> {
     fn advance(&mut self, nb_bytes: usize) {
         assert!(self.pos + nb_bytes <= self.len);
         self.pos += nb_bytes;
+        self.offset += nb_bytes as u64;
     }
 
     /// Advance the internal buffer position.
@@ -102,6 +131,24 @@ impl<R: Read> ReadBuffer<R> {
         self.advance(diff_len)
     }
 
+    /// Read data without parsing it, first from the internal buffer then from the inner reader
+    pub fn read_raw(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
+        if !self.has_data_left()? {
+            return Ok(0);
+        }
+
+        let len = buf.len().min(self.len - self.pos);
+        buf[..len].copy_from_slice(&self.buffer[self.pos..self.pos + len]);
+        self.advance(len);
+
+        Ok(len)
+    }
+
+    /// Return the number of bytes consumed since the start of the reader
+    pub fn position(&self) -> u64 {
+        self.offset
+    }
+
     /// Return the valid data of the internal buffer
     pub fn buffer(&self) -> &[u8] {
         &self.buffer[self.pos..self.len]
@@ -129,6 +176,56 @@ impl<R: Read> ReadBuffer<R> {
     pub fn get_ref(&self) -> &R {
         &self.reader
     }
+
+    /// Return a mutable reference over the inner reader
+    ///
+    /// The inner reader must be left where it was, after the buffered data.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+}
+
+impl<R: Read + Seek> ReadBuffer<R> {
+    /// Skip `nb_bytes` bytes, first from the internal buffer then by seeking the inner reader
+    pub fn skip(&mut self, nb_bytes: u64) -> Result<(), std::io::Error> {
+        let buffered = (self.len - self.pos) as u64;
+        if nb_bytes <= buffered {
+            self.advance(nb_bytes as usize);
+            return Ok(());
+        }
+
+        let seek_len = i64::try_from(nb_bytes - buffered).map_err(|_| Error::new(ErrorKind::InvalidInput, "Seek offset > i64::MAX"))?;
+        self.reader.seek(SeekFrom::Current(seek_len))?;
+        self.pos = 0;
+        self.len = 0;
+        self.offset += nb_bytes;
+
+        Ok(())
+    }
+
+    /// Returns the length of the stream from the start of the reader, leaving the position unchanged
+    pub fn stream_len(&mut self) -> Result<u64, std::io::Error> {
+        // The inner reader is after the buffered data
+        let current = self.offset + (self.len - self.pos) as u64;
+        let inner_pos = self.reader.stream_position()?;
+        let end = self.reader.seek(SeekFrom::End(0))?;
+        self.reader.seek(SeekFrom::Start(inner_pos))?;
+
+        Ok(current + (end - inner_pos))
+    }
+
+    /// Seek to `offset` bytes from the start of the reader, discarding the buffered data
+    pub fn seek_to(&mut self, offset: u64) -> Result<(), std::io::Error> {
+        // The inner reader is after the buffered data
+        let current = self.offset + (self.len - self.pos) as u64;
+        let seek_len = i64::try_from(offset as i128 - current as i128).map_err(|_| Error::new(ErrorKind::InvalidInput, "Seek offset > i64::MAX"))?;
+        self.reader.seek(SeekFrom::Current(seek_len))?;
+        self.pos = 0;
+        self.len = 0;
+        self.offset = offset;
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
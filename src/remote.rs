@@ -0,0 +1,277 @@
+//! Pcap-over-IP: captures streamed over a TCP connection.
+//!
+//! The server writes a regular pcap or pcapng stream on the socket, as done by `tcpdump -w - | nc -l` or
+//! by the remote capture setups of Wireshark, and the client reads it until the connection is closed.
+//!
+//! Requires the `remote` feature.
+//!
+//! # Client example
+//! ```rust,no_run
+//! use pcap_file::remote::PcapOverIpClient;
+//!
+//! let mut client = PcapOverIpClient::connect("127.0.0.1:57012").unwrap();
+//!
+//! while let Some(packet) = client.next_packet() {
+//!     let packet = packet.unwrap();
+//!     println!("{:?}: {} bytes", packet.datalink, packet.data.len());
+//! }
+//! ```
+//!
+//! # Server example
+//! ```rust,no_run
+//! use std::time::SystemTime;
+//!
+//! use pcap_file::pcapng::blocks::interface_description::InterfaceDescriptionBlock;
+//! use pcap_file::remote::PcapOverIpServer;
+//! use pcap_file::DataLink;
+//!
+//! let mut server = PcapOverIpServer::bind("127.0.0.1:57012").unwrap();
+//! let interface = server.add_interface(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0)).unwrap();
+//!
+//! // Wait for Wireshark to attach, e.g. with `nc 127.0.0.1 57012 | wireshark -k -i -`
+//! server.accept().unwrap();
+//!
+//! loop {
+//!     server.accept_pending().unwrap();
+//!     server.write_packet(interface, SystemTime::now(), &[0_u8; 64]).unwrap();
+//! }
+//! ```
+
+use std::io::{Chain, Cursor, ErrorKind, Read, Sink};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::time::{Duration, SystemTime};
+
+use crate::pcap::PcapReader;
+use crate::pcapng::blocks::enhanced_packet::EnhancedPacketBlock;
+use crate::pcapng::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceId};
+use crate::pcapng::blocks::section_header::SectionHeaderBlock;
+use crate::pcapng::{Block, PcapNgBlock, PcapNgReader, PcapNgWriter};
+use crate::{DataLink, PcapError, PcapResult};
+
+
+/// Packet received by a [`PcapOverIpClient`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RemotePacket {
+    /// Link type of the interface the packet was captured on
+    pub datalink: DataLink,
+
+    /// Timestamp of the packet since the Unix epoch, `None` for the pcapng simple packets
+    pub timestamp: Option<Duration>,
+
+    /// Original length of the packet on the wire
+    pub original_len: u32,
+
+    /// Captured data of the packet
+    pub data: Vec<u8>,
+}
+
+/// Reads the packets of a pcap or pcapng stream, whose format is detected from its first bytes.
+pub struct PcapOverIpClient<R: Read = TcpStream> {
+    inner: Inner<R>,
+}
+
+enum Inner<R: Read> {
+    Pcap(PcapReader<Chain<Cursor<[u8; 4]>, R>>),
+    PcapNg(Box<PcapNgReader<Chain<Cursor<[u8; 4]>, R>>>, Vec<InterfaceDescriptionBlock<'static>>),
+}
+
+impl PcapOverIpClient<TcpStream> {
+    /// Connects to a Pcap-over-IP server and reads the header of its stream.
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> PcapResult<Self> {
+        let stream = TcpStream::connect(addr).map_err(PcapError::IoError)?;
+        Self::new(stream)
+    }
+}
+
+impl<R: Read> PcapOverIpClient<R> {
+    /// Creates a new [`PcapOverIpClient`] from a connected stream and reads the header of the capture.
+    pub fn new(mut reader: R) -> PcapResult<Self> {
+        let mut magic = [0_u8; 4];
+        reader.read_exact(&mut magic).map_err(PcapError::IoError)?;
+        let reader = Cursor::new(magic).chain(reader);
+
+        let inner = match magic {
+            [0x0A, 0x0D, 0x0D, 0x0A] => Inner::PcapNg(Box::new(PcapNgReader::new(reader)?), vec![]),
+            _ => Inner::Pcap(PcapReader::new(reader)?),
+        };
+
+        Ok(PcapOverIpClient { inner })
+    }
+
+    /// Returns true if the stream is a pcapng.
+    pub fn is_pcapng(&self) -> bool {
+        matches!(self.inner, Inner::PcapNg(..))
+    }
+
+    /// Returns the next packet, or `None` once the connection has been closed.
+    ///
+    /// The non-packet blocks of a pcapng stream are skipped.
+    pub fn next_packet(&mut self) -> Option<PcapResult<RemotePacket>> {
+        match &mut self.inner {
+            Inner::Pcap(reader) => {
+                let datalink = reader.header().datalink;
+                reader.next_packet().map(|res| {
+                    res.map(|packet| RemotePacket {
+                        datalink,
+                        timestamp: Some(packet.timestamp),
+                        original_len: packet.orig_len,
+                        data: packet.data.into_owned(),
+                    })
+                })
+            },
+            Inner::PcapNg(reader, interfaces) => loop {
+                let block = match reader.next_block()? {
+                    Ok(block) => block,
+                    Err(err) => return Some(Err(err)),
+                };
+
+                let (interface_id, timestamp, original_len, data) = match block {
+                    Block::SectionHeader(_) => {
+                        interfaces.clear();
+                        continue;
+                    },
+                    Block::InterfaceDescription(idb) => {
+                        interfaces.push(idb.into_owned());
+                        continue;
+                    },
+                    Block::EnhancedPacket(epb) => (epb.interface_id, Some(epb.timestamp.as_nanos() as u64), epb.original_len, epb.data),
+                    Block::Packet(pb) => (pb.interface_id as u32, Some(pb.timestamp), pb.original_len, pb.data),
+                    Block::SimplePacket(spb) => (0, None, spb.original_len, spb.data),
+                    _ => continue,
+                };
+
+                let interface = match interfaces.get(interface_id as usize) {
+                    Some(interface) => interface,
+                    None => return Some(Err(PcapError::InvalidInterfaceId(interface_id))),
+                };
+
+                return Some(Ok(RemotePacket {
+                    datalink: interface.linktype,
+                    timestamp: timestamp.map(|ts| interface.timestamp_to_duration(ts)),
+                    original_len,
+                    data: data.into_owned(),
+                }));
+            },
+        }
+    }
+}
+
+
+/// Streams a pcapng section to the clients connected over TCP.
+///
+/// Each new client first receives the current section header and interfaces, then the blocks written
+/// after its connection. The clients whose connection fails are dropped silently.
+pub struct PcapOverIpServer {
+    listener: TcpListener,
+    /// Tracks and checks the section, without writing it anywhere
+    state: PcapNgWriter<Sink>,
+    clients: Vec<(PcapNgWriter<TcpStream>, SocketAddr)>,
+}
+
+impl PcapOverIpServer {
+    /// Creates a new [`PcapOverIpServer`] listening on the given address, streaming a section in the native endianness.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> PcapResult<Self> {
+        Self::with_section_header(addr, SectionHeaderBlock::default())
+    }
+
+    /// Creates a new [`PcapOverIpServer`] listening on the given address, streaming the given section.
+    pub fn with_section_header<A: ToSocketAddrs>(addr: A, section: SectionHeaderBlock<'static>) -> PcapResult<Self> {
+        let listener = TcpListener::bind(addr).map_err(PcapError::IoError)?;
+        let state = PcapNgWriter::with_section_header(std::io::sink(), section)?;
+
+        Ok(PcapOverIpServer { listener, state, clients: vec![] })
+    }
+
+    /// Returns the local address of the server.
+    pub fn local_addr(&self) -> PcapResult<SocketAddr> {
+        self.listener.local_addr().map_err(PcapError::IoError)
+    }
+
+    /// Waits for a client to connect and sends it the section header and the interfaces.
+    pub fn accept(&mut self) -> PcapResult<SocketAddr> {
+        let (stream, addr) = self.listener.accept().map_err(PcapError::IoError)?;
+        self.add_client(stream, addr)?;
+
+        Ok(addr)
+    }
+
+    /// Accepts all the clients waiting to connect, without blocking.
+    ///
+    /// Returns the number of new clients.
+    pub fn accept_pending(&mut self) -> PcapResult<usize> {
+        self.listener.set_nonblocking(true).map_err(PcapError::IoError)?;
+
+        let mut count = 0;
+        let res = loop {
+            match self.listener.accept() {
+                Ok((stream, addr)) => {
+                    if let Err(err) = stream.set_nonblocking(false) {
+                        break Err(PcapError::IoError(err));
+                    }
+                    match self.add_client(stream, addr) {
+                        Ok(()) => count += 1,
+                        Err(PcapError::IoError(_)) => {},
+                        Err(err) => break Err(err),
+                    }
+                },
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break Ok(count),
+                Err(err) => break Err(PcapError::IoError(err)),
+            }
+        };
+
+        self.listener.set_nonblocking(false).map_err(PcapError::IoError)?;
+        res
+    }
+
+    /// Returns the addresses of the connected clients.
+    pub fn clients(&self) -> impl Iterator<Item = SocketAddr> + '_ {
+        self.clients.iter().map(|(_, addr)| *addr)
+    }
+
+    /// Writes a [`Block`] to all the connected clients.
+    ///
+    /// The block is checked like with [`PcapNgWriter::write_block()`], even if no client is connected.
+    /// Returns the number of clients the block was sent to.
+    pub fn write_block(&mut self, block: &Block) -> PcapResult<usize> {
+        self.state.write_block(block)?;
+
+        self.clients.retain_mut(|(writer, _)| writer.write_block(block).is_ok());
+        Ok(self.clients.len())
+    }
+
+    /// Adds an interface to the section and returns the [`InterfaceId`] to use to write its packets.
+    pub fn add_interface(&mut self, interface: &InterfaceDescriptionBlock) -> PcapResult<InterfaceId> {
+        self.write_block(&Block::InterfaceDescription(interface.clone()))?;
+        Ok(InterfaceId(self.state.interfaces().len() as u32 - 1))
+    }
+
+    /// Writes a packet captured on the given interface at the given time to all the connected clients.
+    pub fn write_packet(&mut self, interface: InterfaceId, ts: SystemTime, data: &[u8]) -> PcapResult<usize> {
+        let idb = self.state.interfaces().get(interface.0 as usize).ok_or(PcapError::InvalidInterfaceId(interface.0))?;
+        let packet = EnhancedPacketBlock::builder(interface, data).time(ts).build(idb)?;
+
+        self.write_block(&packet.into_block())
+    }
+
+    /// Returns the current [`SectionHeaderBlock`].
+    pub fn section(&self) -> &SectionHeaderBlock<'static> {
+        self.state.section()
+    }
+
+    /// Returns all the current [`InterfaceDescriptionBlock`].
+    pub fn interfaces(&self) -> &[InterfaceDescriptionBlock<'static>] {
+        self.state.interfaces()
+    }
+
+    fn add_client(&mut self, stream: TcpStream, addr: SocketAddr) -> PcapResult<()> {
+        stream.set_nodelay(true).map_err(PcapError::IoError)?;
+
+        let mut writer = PcapNgWriter::with_section_header(stream, self.state.section().clone())?;
+        for interface in self.state.interfaces() {
+            writer.add_interface(interface)?;
+        }
+        self.clients.push((writer, addr));
+
+        Ok(())
+    }
+}
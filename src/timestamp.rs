@@ -0,0 +1,126 @@
+//! Representations of the packet timestamps.
+//!
+//! The timestamps are stored in the captures as ticks of the resolution of the capture. The `timestamp_as()` methods of
+//! the packets convert them directly into any type implementing [`Timestamp`], so that an application working with its
+//! own time type doesn't convert them twice:
+//! - `u64`, the raw ticks, left unconverted
+//! - [`Duration`] since the Unix epoch
+//! - [`SystemTime`]
+//! - `chrono::DateTime<Utc>`, with the `chrono` feature
+//! - `time::OffsetDateTime`, with the `time` feature
+//!
+//! The other way around, the `set_timestamp()` methods and the constructors taking a time accept any type implementing
+//! [`IntoTimestamp`], which also includes a `chrono::DateTime` of any time zone. With the `chrono` and `time` features, the
+//! packets also have `timestamp_utc()` and `timestamp_offset_date_time()` getters returning these types directly.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::fs::File;
+//! use std::time::SystemTime;
+//!
+//! use pcap_file::pcap::PcapReader;
+//!
+//! let file_in = File::open("test.pcap").expect("Error opening file");
+//! let mut pcap_reader = PcapReader::new(file_in).unwrap();
+//!
+//! while let Some(packet) = pcap_reader.next_packet() {
+//!     let time: SystemTime = packet.unwrap().timestamp_as().unwrap();
+//! }
+//! ```
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::TsResolution;
+
+
+/// Representation of a packet timestamp, built from the ticks stored in a capture.
+pub trait Timestamp: Sized {
+    /// Builds a timestamp from `ticks` units of `1 / units_per_sec` second, plus `offset_secs` seconds, since the Unix epoch.
+    ///
+    /// Returns `None` if the timestamp can't be represented.
+    fn from_ticks(ticks: u64, units_per_sec: u64, offset_secs: u64) -> Option<Self>;
+}
+
+/// The raw ticks, in the resolution of the capture, whose offset is ignored
+impl Timestamp for u64 {
+    fn from_ticks(ticks: u64, _units_per_sec: u64, _offset_secs: u64) -> Option<Self> {
+        Some(ticks)
+    }
+}
+
+impl Timestamp for Duration {
+    fn from_ticks(ticks: u64, units_per_sec: u64, offset_secs: u64) -> Option<Self> {
+        if units_per_sec == 0 {
+            return None;
+        }
+
+        let nanos = (ticks % units_per_sec) as u128 * 1_000_000_000 / units_per_sec as u128;
+        Duration::new(ticks / units_per_sec, nanos as u32).checked_add(Duration::from_secs(offset_secs))
+    }
+}
+
+impl Timestamp for SystemTime {
+    fn from_ticks(ticks: u64, units_per_sec: u64, offset_secs: u64) -> Option<Self> {
+        UNIX_EPOCH.checked_add(Duration::from_ticks(ticks, units_per_sec, offset_secs)?)
+    }
+}
+
+/// Time which can be written as a packet timestamp.
+pub trait IntoTimestamp {
+    /// Returns the time as a duration since the Unix epoch, `None` if it is before the epoch.
+    fn since_epoch(&self) -> Option<Duration>;
+}
+
+impl IntoTimestamp for Duration {
+    fn since_epoch(&self) -> Option<Duration> {
+        Some(*self)
+    }
+}
+
+impl IntoTimestamp for SystemTime {
+    fn since_epoch(&self) -> Option<Duration> {
+        self.duration_since(UNIX_EPOCH).ok()
+    }
+}
+
+/// Converts a duration since the Unix epoch, whose ticks are nanoseconds
+pub(crate) fn from_duration<T: Timestamp>(duration: Duration) -> Option<T> {
+    T::from_ticks(u64::try_from(duration.as_nanos()).ok()?, 1_000_000_000, 0)
+}
+
+/// Converts the timestamp of a pcap packet, whose ticks are in the resolution of the capture
+pub(crate) fn from_pcap<T: Timestamp>(ts_sec: u32, ts_frac: u32, ts_resolution: TsResolution) -> Option<T> {
+    let units_per_sec = ts_resolution.units_per_sec();
+    T::from_ticks(ts_sec as u64 * units_per_sec + ts_frac as u64, units_per_sec, 0)
+}
+
+#[cfg(feature = "chrono")]
+impl Timestamp for chrono::DateTime<chrono::Utc> {
+    fn from_ticks(ticks: u64, units_per_sec: u64, offset_secs: u64) -> Option<Self> {
+        let duration = Duration::from_ticks(ticks, units_per_sec, offset_secs)?;
+        chrono::DateTime::from_timestamp(i64::try_from(duration.as_secs()).ok()?, duration.subsec_nanos())
+    }
+}
+
+#[cfg(feature = "time")]
+impl Timestamp for time::OffsetDateTime {
+    fn from_ticks(ticks: u64, units_per_sec: u64, offset_secs: u64) -> Option<Self> {
+        let duration = Duration::from_ticks(ticks, units_per_sec, offset_secs)?;
+        time::OffsetDateTime::from_unix_timestamp_nanos(duration.as_nanos() as i128).ok()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> IntoTimestamp for chrono::DateTime<Tz> {
+    fn since_epoch(&self) -> Option<Duration> {
+        Some(Duration::new(u64::try_from(self.timestamp()).ok()?, self.timestamp_subsec_nanos()))
+    }
+}
+
+#[cfg(feature = "time")]
+impl IntoTimestamp for time::OffsetDateTime {
+    fn since_epoch(&self) -> Option<Duration> {
+        let nanos = u128::try_from(self.unix_timestamp_nanos()).ok()?;
+        Some(Duration::new((nanos / 1_000_000_000) as u64, (nanos % 1_000_000_000) as u32))
+    }
+}
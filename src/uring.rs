@@ -0,0 +1,315 @@
+//! Output files written with io_uring, to write the captures to the disk at high rates with fewer syscalls.
+//!
+//! Requires the `io-uring` feature, on Linux 5.6 or later.
+//!
+//! # Example
+//! ```rust,no_run
+//! use pcap_file::pcapng::PcapNgWriter;
+//! use pcap_file::uring::UringWriter;
+//!
+//! let file_out = UringWriter::create("out.pcapng").unwrap();
+//! let mut pcapng_writer = PcapNgWriter::new(file_out).unwrap();
+//!
+//! // Write the packets
+//!
+//! // Waits for the pending writes
+//! pcapng_writer.finish().unwrap().finish().unwrap();
+//! ```
+
+use std::fs::File;
+use std::io::{Error, ErrorKind, Result as IoResult, Seek, SeekFrom, Write};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+use io_uring::{opcode, types, IoUring};
+
+use crate::FileWriter;
+
+
+/// Writer which copies the data into a pool of buffers and submits each full buffer to io_uring, without waiting for
+/// its write to complete.
+///
+/// It only waits when all the buffers are being written, and in [`flush()`](Write::flush), which waits for all the
+/// pending writes. The errors of the writes are returned by the next call.
+///
+/// It can be wrapped by any writer of the crate, like a [`File`]. It writes at the offsets of its own position, so the
+/// file must not be written by another writer at the same time.
+pub struct UringWriter {
+    /// Taken by [`finish()`](Self::finish)
+    file: Option<File>,
+    ring: IoUring,
+    buffers: Vec<Buffer>,
+    /// Completions being handled by [`reap()`](Self::reap), kept to reuse its allocation
+    completed: Vec<(usize, i32)>,
+    /// Size of each buffer
+    buffer_size: usize,
+    /// Index of the buffer being filled
+    current: usize,
+    /// Number of buffers being written
+    in_flight: usize,
+    /// Position of the next write
+    pos: u64,
+    /// Length of the written data
+    len: u64,
+    /// First error of a write, returned by the next call
+    error: Option<Error>,
+}
+
+/// Buffer of a [`UringWriter`], whose data must not move while it is written
+struct Buffer {
+    data: Vec<u8>,
+    /// Offset of the data in the file
+    offset: u64,
+    /// Number of bytes of the data already written
+    written: usize,
+    in_flight: bool,
+}
+
+impl UringWriter {
+    /// Default number of buffers, and of writes submitted at the same time
+    pub const DEFAULT_QUEUE_DEPTH: u32 = 32;
+
+    /// Default size of the buffers, 1 MB
+    pub const DEFAULT_BUFFER_SIZE: usize = 1024 * 1024;
+
+    /// Creates a file, or truncates an existing one, with the default queue depth and buffer size.
+    pub fn create<P: AsRef<Path>>(path: P) -> IoResult<Self> {
+        Self::new(File::create(path)?)
+    }
+
+    /// Creates a new [`UringWriter`] writing a file from its current position, with the default queue depth and buffer size.
+    pub fn new(file: File) -> IoResult<Self> {
+        Self::with_config(file, Self::DEFAULT_QUEUE_DEPTH, Self::DEFAULT_BUFFER_SIZE)
+    }
+
+    /// Creates a new [`UringWriter`] writing a file from its current position, with `queue_depth` buffers of
+    /// `buffer_size` bytes.
+    ///
+    /// # Errors
+    /// The io_uring can't be created, e.g. if the kernel doesn't support it.
+    pub fn with_config(mut file: File, queue_depth: u32, buffer_size: usize) -> IoResult<Self> {
+        let queue_depth = queue_depth.max(1);
+        let buffer_size = buffer_size.clamp(1, u32::MAX as usize);
+
+        let ring = IoUring::new(queue_depth)?;
+        let pos = file.stream_position()?;
+        let buffers = (0..queue_depth)
+            .map(|_| Buffer { data: Vec::with_capacity(buffer_size), offset: 0, written: 0, in_flight: false })
+            .collect();
+
+        Ok(UringWriter {
+            file: Some(file),
+            ring,
+            buffers,
+            completed: Vec::with_capacity(queue_depth as usize),
+            buffer_size,
+            current: 0,
+            in_flight: 0,
+            pos,
+            len: pos,
+            error: None,
+        })
+    }
+
+    /// Returns the length of the written data, including the data not written yet.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns true if no data was written.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Waits for the pending writes and returns the file.
+    pub fn finish(mut self) -> IoResult<File> {
+        self.flush()?;
+        Ok(self.file.take().expect("UringWriter: file already taken"))
+    }
+
+    /// Returns the first error of the writes, if any.
+    fn take_error(&mut self) -> IoResult<()> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Submits the buffer being filled, then waits until the next buffer is free.
+    fn submit_current(&mut self) -> IoResult<()> {
+        if self.buffers[self.current].data.is_empty() {
+            return Ok(());
+        }
+
+        self.push(self.current)?;
+        self.ring.submit()?;
+
+        self.current = (self.current + 1) % self.buffers.len();
+        while self.buffers[self.current].in_flight {
+            self.reap(1)?;
+        }
+
+        Ok(())
+    }
+
+    /// Pushes the write of the rest of a buffer to the submission queue.
+    fn push(&mut self, index: usize) -> IoResult<()> {
+        let fd = types::Fd(self.file().as_raw_fd());
+        let buffer = &mut self.buffers[index];
+        let rem = &buffer.data[buffer.written..];
+        let entry = opcode::Write::new(fd, rem.as_ptr(), rem.len() as u32)
+            .offset(buffer.offset + buffer.written as u64)
+            .build()
+            .user_data(index as u64);
+
+        if !buffer.in_flight {
+            buffer.in_flight = true;
+            self.in_flight += 1;
+        }
+
+        // Sound because the data of the buffer is neither moved nor modified while it is in flight
+        // The queue can't be full because it has one entry per buffer
+        unsafe { self.ring.submission().push(&entry).map_err(|_| Error::other("UringWriter: submission queue full")) }
+    }
+
+    /// Waits for at least `want` writes to complete and handles all the completed ones.
+    fn reap(&mut self, want: usize) -> IoResult<()> {
+        while let Err(err) = self.ring.submit_and_wait(want) {
+            if err.kind() != ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+
+        self.completed.clear();
+        self.completed.extend(self.ring.completion().map(|entry| (entry.user_data() as usize, entry.result())));
+        let mut resubmit = false;
+        for i in 0..self.completed.len() {
+            let (index, res) = self.completed[i];
+            let buffer = &mut self.buffers[index];
+            let res = match res {
+                res if res < 0 => Err(Error::from_raw_os_error(-res)),
+                0 => Err(Error::from(ErrorKind::WriteZero)),
+                res => Ok(res as usize),
+            };
+
+            match res {
+                Ok(len) if buffer.written + len < buffer.data.len() => {
+                    // Short write, the rest of the buffer is submitted again
+                    buffer.written += len;
+                    self.push(index)?;
+                    resubmit = true;
+                    continue;
+                },
+                Ok(_) => {},
+                Err(err) => {
+                    self.error.get_or_insert(err);
+                },
+            }
+
+            let buffer = &mut self.buffers[index];
+            buffer.data.clear();
+            buffer.written = 0;
+            buffer.in_flight = false;
+            self.in_flight -= 1;
+        }
+
+        if resubmit {
+            self.ring.submit()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Write for UringWriter {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        self.take_error()?;
+
+        let mut rem = buf;
+        while !rem.is_empty() {
+            let buffer = &mut self.buffers[self.current];
+            if buffer.data.is_empty() {
+                buffer.offset = self.pos;
+            }
+
+            let len = rem.len().min(self.buffer_size - buffer.data.len());
+            buffer.data.extend_from_slice(&rem[..len]);
+            rem = &rem[len..];
+            self.pos += len as u64;
+
+            if buffer.data.len() == self.buffer_size {
+                self.submit_current()?;
+            }
+        }
+        self.len = self.len.max(self.pos);
+
+        Ok(buf.len())
+    }
+
+    /// Submits the buffered data and waits for all the pending writes.
+    fn flush(&mut self) -> IoResult<()> {
+        self.submit_current()?;
+        while self.in_flight > 0 {
+            self.reap(1)?;
+        }
+
+        self.take_error()
+    }
+}
+
+impl Seek for UringWriter {
+    /// Waits for the pending writes, then seeks within the data.
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        self.flush()?;
+
+        let pos = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        };
+        self.pos = pos.ok_or_else(|| Error::new(ErrorKind::InvalidInput, "UringWriter: invalid seek to a negative position"))?;
+
+        Ok(self.pos)
+    }
+}
+
+impl FileWriter for UringWriter {
+    fn file(&self) -> &File {
+        self.file.as_ref().expect("UringWriter: file already taken")
+    }
+
+    fn data_len(&self) -> IoResult<u64> {
+        Ok(self.len)
+    }
+}
+
+impl std::fmt::Debug for UringWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UringWriter")
+            .field("file", &self.file)
+            .field("buffer_size", &self.buffer_size)
+            .field("queue_depth", &self.buffers.len())
+            .field("in_flight", &self.in_flight)
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for UringWriter {
+    fn drop(&mut self) {
+        // No write is pending once finish() took the file
+        if self.file.is_none() {
+            return;
+        }
+
+        // The errors can't be reported, use finish() to check them
+        let _ = self.submit_current();
+        while self.in_flight > 0 {
+            if self.reap(1).is_err() {
+                // The buffers must outlive their writes
+                std::mem::forget(std::mem::take(&mut self.buffers));
+                break;
+            }
+        }
+    }
+}
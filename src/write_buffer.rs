@@ -0,0 +1,138 @@
+use std::io::{IoSlice, Write};
+
+
+/// Largest allocation kept by the buffer after a batch, unless its capacity is larger
+const KEPT_BATCH_LEN: usize = 1 << 20;
+
+/// Internal structure that optionally bufferize its output, like a [`BufWriter`](std::io::BufWriter) whose capacity can change.
+///
+/// A capacity of 0 writes everything directly to the inner writer.
+#[derive(Debug)]
+pub(crate) struct WriteBuffer<W: Write> {
+    /// Writer to which the data is written, only missing once taken by [`into_inner()`](Self::into_inner)
+    writer: Option<W>,
+    /// Internal buffer
+    buffer: Vec<u8>,
+    /// Maximum number of bytes kept in the buffer
+    capacity: usize,
+}
+
+impl<W: Write> WriteBuffer<W> {
+    /// Creates a new unbuffered WriteBuffer
+    pub fn new(writer: W) -> Self {
+        Self { writer: Some(writer), buffer: vec![], capacity: 0 }
+    }
+
+    /// Return the capacity of the buffer
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change the capacity of the buffer, writing the buffered data if it doesn't fit anymore
+    pub fn set_capacity(&mut self, capacity: usize) -> std::io::Result<()> {
+        if self.buffer.len() > capacity {
+            self.flush_buffer()?;
+        }
+
+        self.capacity = capacity;
+        self.buffer.reserve_exact(capacity.saturating_sub(self.buffer.len()));
+        if capacity == 0 {
+            self.buffer = vec![];
+        }
+
+        Ok(())
+    }
+
+    /// Buffer all the data until [`end_batch()`](Self::end_batch), whatever the capacity, returning the capacity
+    pub fn start_batch(&mut self) -> usize {
+        std::mem::replace(&mut self.capacity, usize::MAX)
+    }
+
+    /// Restore the capacity returned by [`start_batch()`](Self::start_batch), writing the buffered data with a single
+    /// call if it doesn't fit in it
+    ///
+    /// The buffer keeps its allocation for the next batch, shrinking it after a large batch.
+    pub fn end_batch(&mut self, capacity: usize) -> std::io::Result<()> {
+        self.capacity = capacity;
+        let res = if self.buffer.len() > capacity { self.flush_buffer() } else { Ok(()) };
+        self.buffer.shrink_to(capacity.max(KEPT_BATCH_LEN));
+
+        res
+    }
+
+    /// Return the buffer of the current batch, to encode the data straight into it
+    pub fn batch_buffer(&mut self) -> &mut Vec<u8> {
+        &mut self.buffer
+    }
+
+    /// Write the buffered data to the inner writer, without flushing it
+    fn flush_buffer(&mut self) -> std::io::Result<()> {
+        let writer = self.writer.as_mut().expect("WriteBuffer: writer already taken");
+        let res = writer.write_all(&self.buffer);
+        self.buffer.clear();
+        res
+    }
+
+    /// Return the inner writer, after writing the buffered data
+    ///
+    /// An error while writing the buffered data is ignored.
+    pub fn into_inner(mut self) -> W {
+        let _ = self.flush_buffer();
+        self.writer.take().expect("WriteBuffer: writer already taken")
+    }
+
+    /// Return a reference over the inner writer
+    pub fn get_ref(&self) -> &W {
+        self.writer.as_ref().expect("WriteBuffer: writer already taken")
+    }
+
+    /// Return a mutable reference over the inner writer
+    pub fn get_mut(&mut self) -> &mut W {
+        self.writer.as_mut().expect("WriteBuffer: writer already taken")
+    }
+}
+
+impl<W: Write> Write for WriteBuffer<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.buffer.len() + buf.len() > self.capacity {
+            self.flush_buffer()?;
+        }
+
+        if buf.len() >= self.capacity {
+            return self.get_mut().write(buf);
+        }
+
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> std::io::Result<usize> {
+        let len = bufs.iter().map(|buf| buf.len()).sum::<usize>();
+        if self.buffer.len() + len > self.capacity {
+            self.flush_buffer()?;
+        }
+
+        if len >= self.capacity {
+            return self.get_mut().write_vectored(bufs);
+        }
+
+        for buf in bufs {
+            self.buffer.extend_from_slice(buf);
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.flush_buffer()?;
+        self.get_mut().flush()
+    }
+}
+
+impl<W: Write> Drop for WriteBuffer<W> {
+    fn drop(&mut self) {
+        // Like a BufWriter, the errors can't be reported
+        if self.writer.is_some() {
+            let _ = self.flush_buffer();
+        }
+    }
+}
@@ -1,10 +1,16 @@
 extern crate pcap_file;
 
 use std::borrow::Cow;
-use std::time::Duration;
+use std::io::Cursor;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use pcap_file::pcap::{PcapHeader, PcapPacket, PcapReader, PcapWriter};
-use pcap_file::TsResolution;
+use pcap_file::index::{load_index, write_index};
+use pcap_file::info::{capture_info, CaptureFormat};
+use pcap_file::pseudo_header::{payload, payload_offset, ppi_datalink};
+use pcap_file::pcap::{LengthPolicy, PcapHeader, PcapPacket, PcapParserConfig, PcapReader, PcapWriter, RawPcapPacket, RecordReader};
+use pcap_file::{DataLink, DltPlatform, Endianness, IoStats, SnapLen, TsResolution};
+
+use crate::CountingWriter;
 
 static DATA: &[u8; 1455] = include_bytes!("little_endian.pcap");
 
@@ -137,3 +143,538 @@ fn little_endian() {
     assert_eq!(pkt.orig_len, pkt_truth.orig_len);
     assert_eq!(pkt.data, pkt_truth.data);
 }
+
+#[test]
+fn magic_variants() {
+    // Micro and nano magics, as written by a big endian and a little endian host
+    let variants = [
+        ([0xA1, 0xB2, 0xC3, 0xD4], Endianness::Big, TsResolution::MicroSecond),
+        ([0xA1, 0xB2, 0x3C, 0x4D], Endianness::Big, TsResolution::NanoSecond),
+        ([0xD4, 0xC3, 0xB2, 0xA1], Endianness::Little, TsResolution::MicroSecond),
+        ([0x4D, 0x3C, 0xB2, 0xA1], Endianness::Little, TsResolution::NanoSecond),
+    ];
+
+    for (magic, endianness, ts_resolution) in variants {
+        let mut data = magic.to_vec();
+        let u16_bytes = |value: u16| if endianness.is_big() { value.to_be_bytes() } else { value.to_le_bytes() };
+        let u32_bytes = |value: u32| if endianness.is_big() { value.to_be_bytes() } else { value.to_le_bytes() };
+
+        // Version 2.4, snaplen 65535, ethernet, then a packet at 1 s + 5 units with 2 bytes
+        data.extend(u16_bytes(2));
+        data.extend(u16_bytes(4));
+        data.extend([0; 8]);
+        data.extend(u32_bytes(0xFFFF));
+        data.extend(u32_bytes(1));
+        for field in [1, 5, 2, 2] {
+            data.extend(u32_bytes(field));
+        }
+        data.extend_from_slice(&[0xAB, 0xCD]);
+
+        let mut pcap_reader = PcapReader::new(&data[..]).unwrap();
+        let header = pcap_reader.header();
+        assert_eq!((header.version_major, header.version_minor, header.snaplen), (2, 4, 0xFFFF));
+        assert_eq!((header.endianness, header.ts_resolution), (endianness, ts_resolution));
+
+        let packet = pcap_reader.next_packet().unwrap().unwrap();
+        let frac = match ts_resolution {
+            TsResolution::MicroSecond => 5_000,
+            TsResolution::NanoSecond => 5,
+        };
+        assert_eq!(packet.timestamp, Duration::new(1, frac));
+
+        // Writing back with the header of the reader gives the same bytes
+        let mut pcap_writer = PcapWriter::with_header(Vec::new(), header).unwrap();
+        pcap_writer.write_packet(&packet).unwrap();
+        assert_eq!(pcap_writer.into_writer(), data);
+    }
+}
+
+#[test]
+fn ts_correction() {
+    // Captured in Central European Time
+    let header = PcapHeader { ts_correction: -3600, ..Default::default() };
+    let mut pcap_writer = PcapWriter::with_header(Vec::new(), header).unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::new(10_000, 5), 1, &[1])).unwrap();
+    let data = pcap_writer.into_writer();
+
+    let mut pcap_reader = PcapReader::new(&data[..]).unwrap();
+    assert_eq!(pcap_reader.next_packet().unwrap().unwrap().timestamp, Duration::new(10_000, 0));
+
+    let config = PcapParserConfig { apply_ts_correction: true, ..Default::default() };
+    let mut pcap_reader = PcapReader::with_config(&data[..], config).unwrap();
+    assert_eq!(pcap_reader.next_packet().unwrap().unwrap().timestamp, Duration::new(6_400, 0));
+
+    assert_eq!(header.apply_ts_correction(Duration::from_secs(10)), Duration::ZERO);
+    let header = PcapHeader { ts_correction: 7200, ..Default::default() };
+    assert_eq!(header.apply_ts_correction(Duration::from_secs(10)), Duration::from_secs(7210));
+}
+
+#[test]
+fn header_builder() {
+    let header = PcapHeader::builder()
+        .datalink(DataLink::RAW)
+        .snaplen(1500)
+        .ts_resolution(TsResolution::NanoSecond)
+        .endianness(Endianness::Little)
+        .ts_correction(3600)
+        .build()
+        .unwrap();
+
+    let expected = PcapHeader {
+        datalink: DataLink::RAW,
+        snaplen: 1500,
+        ts_resolution: TsResolution::NanoSecond,
+        endianness: Endianness::Little,
+        ts_correction: 3600,
+        ..Default::default()
+    };
+    assert_eq!(header, expected);
+    assert_eq!(PcapHeader::builder().build().unwrap().endianness, Endianness::native());
+
+    assert!(PcapHeader::builder().version(1, 0).build().is_err());
+    assert!(PcapHeader::builder().version(2, 5).build().is_err());
+    assert_eq!(PcapHeader::builder().snaplen(0).build().unwrap().snaplen, 0);
+    assert_eq!(PcapHeader::builder().datalink(DataLink::Unknown(0xFFFF)).build().unwrap().datalink, DataLink::Unknown(0xFFFF));
+}
+
+#[test]
+fn info() {
+    let header = PcapHeader { ts_resolution: TsResolution::NanoSecond, ..Default::default() };
+    let mut pcap_writer = PcapWriter::with_header(Vec::new(), header).unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::new(20, 5), 10, &[1, 2, 3])).unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::new(10, 0), 2, &[1, 2])).unwrap();
+    let data = pcap_writer.into_writer();
+
+    let info = capture_info(&data[..]).unwrap();
+    assert_eq!(info.format, CaptureFormat::Pcap { version_major: 2, version_minor: 4 });
+    assert_eq!(info.section_count, 1);
+    assert_eq!(info.interfaces.len(), 1);
+    assert_eq!(info.interfaces[0].tsresol, 9);
+    assert_eq!(info.interfaces[0].packet_count, 2);
+    assert_eq!(info.packet_count, 2);
+    assert_eq!((info.captured_bytes, info.original_bytes), (5, 12));
+    assert_eq!(info.first_timestamp, Some(Duration::new(10, 0)));
+    assert_eq!(info.duration(), Some(Duration::new(10, 5)));
+    assert!(!info.has_comments);
+}
+
+#[test]
+fn reader_into_iter() {
+    let mut pcap_writer = PcapWriter::new(Vec::new()).unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::new(1, 0), 3, &[1, 2, 3])).unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::new(2, 0), 2, &[1, 2])).unwrap();
+    let data = pcap_writer.into_writer();
+
+    let pcap_reader = PcapReader::new(std::io::Cursor::new(data)).unwrap();
+    let packets = std::thread::spawn(move || pcap_reader.into_iter().collect::<Result<Vec<_>, _>>().unwrap()).join().unwrap();
+
+    assert_eq!(packets.len(), 2);
+    assert_eq!(packets[1].timestamp, Duration::new(2, 0));
+    assert_eq!(packets[1].data.as_ref(), &[1, 2]);
+}
+
+#[test]
+fn reader_packets() {
+    let mut pcap_writer = PcapWriter::new(Vec::new()).unwrap();
+    for i in 0..4_u8 {
+        pcap_writer.write_packet(&PcapPacket::new(Duration::new(i as u64, 0), 1, &[i])).unwrap();
+    }
+    let data = pcap_writer.into_writer();
+
+    let mut pcap_reader = PcapReader::new(&data[..]).unwrap();
+    let first = pcap_reader.packets().take(1).collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(first[0].data.as_ref(), &[0]);
+
+    let remaining = pcap_reader.packets().filter_map(Result::ok).map(|packet| packet.data[0]).collect::<Vec<_>>();
+    assert_eq!(remaining, [1, 2, 3]);
+    assert!(pcap_reader.next_packet().is_none());
+}
+
+#[test]
+fn io_stats() {
+    let mut pcap_writer = PcapWriter::new(Vec::new()).unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::new(1, 0), 3, &[1, 2, 3])).unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::new(2, 0), 2, &[1, 2])).unwrap();
+    let stats = pcap_writer.stats();
+    let data = pcap_writer.into_writer();
+    assert_eq!(stats, IoStats { blocks: 2, packets: 2, bytes: data.len() as u64, errors: 0, skipped: 0, repaired: 0, warnings: 0 });
+
+    // Truncated packet
+    let mut pcap_reader = PcapReader::new(&data[..data.len() - 1]).unwrap();
+    while let Some(Ok(_)) = pcap_reader.next_packet() {}
+    assert_eq!(pcap_reader.stats(), IoStats { blocks: 1, packets: 1, bytes: 24 + 19, errors: 1, skipped: 0, repaired: 0, warnings: 0 });
+}
+
+#[test]
+fn concatenated_captures() {
+    let mut pcap_writer = PcapWriter::new(Vec::new()).unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::new(1, 0), 3, &[1, 2, 3])).unwrap();
+    let mut data = pcap_writer.into_writer();
+
+    let header = PcapHeader { endianness: Endianness::Big, ts_resolution: TsResolution::NanoSecond, ..Default::default() };
+    let mut pcap_writer = PcapWriter::with_header(Vec::new(), header).unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::new(2, 5), 2, &[4, 5])).unwrap();
+    data.extend_from_slice(&pcap_writer.into_writer());
+    // Empty capture at the end
+    data.extend_from_slice(&PcapWriter::new(Vec::new()).unwrap().into_writer());
+
+    let mut pcap_reader = PcapReader::new(&data[..]).unwrap();
+    assert_eq!(pcap_reader.next_packet().unwrap().unwrap().data.as_ref(), &[1, 2, 3]);
+
+    let packet = pcap_reader.next_packet().unwrap().unwrap().into_owned();
+    assert_eq!(packet.timestamp, Duration::new(2, 5));
+    assert_eq!(packet.data.as_ref(), &[4, 5]);
+    assert_eq!(pcap_reader.header(), header);
+
+    assert!(pcap_reader.next_packet().is_none());
+    assert_eq!(pcap_reader.header().endianness, Endianness::native());
+    assert_eq!(pcap_reader.stats().bytes, data.len() as u64);
+}
+
+#[test]
+fn vectored_write() {
+    let header_writes = PcapWriter::new(CountingWriter::default()).unwrap().into_writer().writes;
+
+    let mut pcap_writer = PcapWriter::new(CountingWriter::default()).unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::new(1, 0), 3, &[1, 2, 3])).unwrap();
+    let counting_writer = pcap_writer.into_writer();
+    assert_eq!(counting_writer.writes, header_writes + 1);
+
+    let mut pcap_reader = PcapReader::new(&counting_writer.data[..]).unwrap();
+    assert_eq!(pcap_reader.next_packet().unwrap().unwrap().data.as_ref(), &[1, 2, 3]);
+}
+
+#[cfg(feature = "fallible-iterator")]
+#[test]
+fn reader_fallible_iterator() {
+    use fallible_iterator::FallibleIterator;
+
+    let mut pcap_writer = PcapWriter::new(Vec::new()).unwrap();
+    for i in 0..4_u8 {
+        pcap_writer.write_packet(&PcapPacket::new(Duration::new(i as u64, 0), 1, &[i])).unwrap();
+    }
+    let data = pcap_writer.into_writer();
+
+    let pcap_reader = PcapReader::new(&data[..]).unwrap();
+    let odd = pcap_reader.filter(|packet| Ok(packet.data[0] % 2 == 1)).map(|packet| Ok(packet.timestamp)).collect::<Vec<_>>().unwrap();
+    assert_eq!(odd, [Duration::new(1, 0), Duration::new(3, 0)]);
+
+    // Truncated packet
+    let pcap_reader = PcapReader::new(&data[..data.len() - 1]).unwrap();
+    assert!(pcap_reader.count().is_err());
+}
+
+#[test]
+fn length_policy() {
+    let header = PcapHeader { snaplen: 6, ..Default::default() };
+    let mut pcap_writer = PcapWriter::with_header(Vec::new(), header).unwrap();
+    let raw_packet = RawPcapPacket { ts_sec: 0, ts_frac: 0, incl_len: 8, orig_len: 7, data: Cow::Borrowed(&[0, 1, 2, 3, 4, 5, 6, 7]) };
+    pcap_writer.write_raw_packet(&raw_packet).unwrap();
+    let data = pcap_writer.into_writer();
+
+    let mut pcap_reader = PcapReader::new(&data[..]).unwrap();
+    assert!(pcap_reader.next_packet().unwrap().is_err());
+
+    let config = PcapParserConfig { length_policy: LengthPolicy::Clamp, ..Default::default() };
+    let mut pcap_reader = PcapReader::with_config(&data[..], config).unwrap();
+    let packet = pcap_reader.next_packet().unwrap().unwrap();
+    assert_eq!(packet.orig_len, 7);
+    assert_eq!(&packet.data[..], &[0, 1, 2, 3, 4, 5]);
+    assert_eq!(pcap_reader.stats().repaired, 1);
+
+    let config = PcapParserConfig { length_policy: LengthPolicy::Accept, ..Default::default() };
+    let mut pcap_reader = PcapReader::with_config(&data[..], config).unwrap();
+    let packet = pcap_reader.next_packet().unwrap().unwrap();
+    assert_eq!(packet.orig_len, 7);
+    assert_eq!(packet.data.len(), 8);
+    assert!(!packet.has_consistent_lengths(6));
+    assert_eq!(pcap_reader.stats().warnings, 1);
+}
+
+#[test]
+fn snaplen_unlimited() {
+    let header = PcapHeader { snaplen: 0, ..Default::default() };
+    let mut pcap_writer = PcapWriter::with_header(Vec::new(), header).unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::ZERO, 100_000, &[0; 70_000])).unwrap();
+    let data = pcap_writer.into_writer();
+
+    let mut pcap_reader = PcapReader::new(&data[..]).unwrap();
+    let packet = pcap_reader.next_packet().unwrap().unwrap();
+    assert_eq!((packet.orig_len, packet.data.len()), (100_000, 70_000));
+
+    assert!(SnapLen::UNLIMITED.is_unlimited());
+    assert_eq!(SnapLen(0).limit(), None);
+    assert_eq!(SnapLen(0).captured_len(70_000), 70_000);
+    assert_eq!(SnapLen(1500).captured_len(70_000), 1500);
+    assert!(!SnapLen(1500).allows(1501));
+}
+
+#[test]
+fn pseudo_header() {
+    let mut sll = vec![0_u8; 16];
+    sll[14..16].copy_from_slice(&[0x08, 0x00]);
+    sll.extend_from_slice(&[0x45, 0x00]);
+    assert_eq!(payload(DataLink::LINUX_SLL, &sll), Some(&[0x45, 0x00][..]));
+    assert_eq!(payload_offset(DataLink::LINUX_SLL2, &sll), None);
+
+    let sll2 = [0_u8; 20];
+    assert_eq!(payload_offset(DataLink::LINUX_SLL2, &sll2), Some(20));
+
+    assert_eq!(payload(DataLink::NULL, &[2, 0, 0, 0, 0x45]), Some(&[0x45][..]));
+    assert_eq!(payload(DataLink::LOOP, &[0, 0, 0, 2, 0x45]), Some(&[0x45][..]));
+    assert_eq!(payload_offset(DataLink::ETHERNET, &sll), None);
+
+    // Version 0, no flags, 12 bytes long with a field, then an ethernet frame
+    let ppi = [0, 0, 12, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0xFF];
+    assert_eq!(payload_offset(DataLink::PPI, &ppi), Some(12));
+    assert_eq!(ppi_datalink(&ppi), Some(DataLink::ETHERNET));
+    assert_eq!(payload_offset(DataLink::PPI, &ppi[..10]), None);
+}
+
+#[test]
+fn dlt_mapping() {
+    assert_eq!(DataLink::from_dlt(12, DltPlatform::Common), DataLink::RAW);
+    assert_eq!(DataLink::from_dlt(14, DltPlatform::OpenBsd), DataLink::RAW);
+    assert_eq!(DataLink::from_dlt(12, DltPlatform::OpenBsd), DataLink::LOOP);
+    assert_eq!(DataLink::from_dlt(14, DltPlatform::FreeBsd), DataLink::PPP_BSDOS);
+    assert_eq!(DataLink::from_dlt(149, DltPlatform::Darwin), DataLink::PKTAP);
+    assert_eq!(DataLink::from_dlt(1, DltPlatform::OpenBsd), DataLink::ETHERNET);
+    assert_eq!(DataLink::from_dlt(113, DltPlatform::Common), DataLink::LINUX_SLL);
+
+    assert_eq!(DataLink::RAW.to_dlt(DltPlatform::Common), 12);
+    assert_eq!(DataLink::RAW.to_dlt(DltPlatform::OpenBsd), 14);
+    assert_eq!(DataLink::PFSYNC.to_dlt(DltPlatform::OpenBsd), 18);
+    assert_eq!(DataLink::PFSYNC.to_dlt(DltPlatform::Common), 246);
+    assert_eq!(DataLink::ETHERNET.to_dlt(DltPlatform::Darwin), 1);
+}
+
+#[test]
+fn private_linktypes() {
+    for linktype in (0..=1024).chain([u32::MAX]) {
+        assert_eq!(u32::from(DataLink::from(linktype)), linktype);
+    }
+
+    for datalink in [DataLink::USER0, DataLink::USER15, DataLink::Unknown(5000)] {
+        let header = PcapHeader { datalink, ..Default::default() };
+        let data = PcapWriter::with_header(Vec::new(), header).unwrap().into_writer();
+        assert_eq!(u32::from(PcapReader::new(&data[..]).unwrap().header().datalink), u32::from(datalink));
+    }
+
+    assert_eq!(DataLink::USER14.to_string(), "USER14");
+    DataLink::register_name(161, "MY_PROTOCOL");
+    assert_eq!(DataLink::USER14.registered_name().as_deref(), Some("MY_PROTOCOL"));
+    assert_eq!(DataLink::USER14.to_string(), "MY_PROTOCOL");
+    assert_eq!(DataLink::Unknown(5000).to_string(), "Unknown(5000)");
+}
+
+#[test]
+fn inner_access() {
+    let mut pcap_writer = PcapWriter::new(Cursor::new(Vec::new())).unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::ZERO, 1, &[1])).unwrap();
+    assert_eq!(pcap_writer.get_ref().position(), 24 + 16 + 1);
+    pcap_writer.flush().unwrap();
+    pcap_writer.get_mut().get_mut().extend_from_slice(&[0xFF]);
+    let data = pcap_writer.into_inner().into_inner();
+    assert_eq!(data.len(), 24 + 16 + 1 + 1);
+
+    let mut pcap_reader = PcapReader::new(Cursor::new(&data[..data.len() - 1])).unwrap();
+    assert!(pcap_reader.next_packet().unwrap().is_ok());
+    assert_eq!(pcap_reader.get_ref().position(), 41);
+    pcap_reader.get_mut().set_position(0);
+    assert_eq!(pcap_reader.into_inner().position(), 0);
+}
+
+#[test]
+fn sync_data() {
+    let path = std::env::temp_dir().join(format!("pcap_file_sync_data_{}.pcap", std::process::id()));
+    let file = std::fs::File::create(&path).unwrap();
+    let mut pcap_writer = PcapWriter::new(std::io::BufWriter::new(file)).unwrap();
+    pcap_writer.set_buffer_capacity(1 << 16).unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::ZERO, 1, &[1])).unwrap();
+
+    pcap_writer.sync_data().unwrap();
+    assert_eq!(std::fs::read(&path).unwrap().len(), 24 + 16 + 1);
+
+    pcap_writer.write_packet(&PcapPacket::new(Duration::ZERO, 1, &[2])).unwrap();
+    pcap_writer.sync_all().unwrap();
+    assert_eq!(std::fs::read(&path).unwrap().len(), 24 + 2 * (16 + 1));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn preallocate() {
+    let path = std::env::temp_dir().join(format!("pcap_file_preallocate_{}.pcap", std::process::id()));
+    let file = std::fs::File::create(&path).unwrap();
+    let mut pcap_writer = PcapWriter::new(std::io::BufWriter::new(file)).unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::ZERO, 1, &[1])).unwrap();
+
+    pcap_writer.preallocate(4096).unwrap();
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), 24 + 16 + 1 + 4096);
+
+    // The packets are written over the preallocated space
+    pcap_writer.write_packet(&PcapPacket::new(Duration::ZERO, 1, &[2])).unwrap();
+    pcap_writer.finish_truncated().unwrap();
+
+    let data = std::fs::read(&path).unwrap();
+    assert_eq!(data.len(), 24 + 2 * (16 + 1));
+    let mut pcap_reader = PcapReader::new(&data[..]).unwrap();
+    assert_eq!(pcap_reader.next_packet().unwrap().unwrap().data[..], [1]);
+    assert_eq!(pcap_reader.next_packet().unwrap().unwrap().data[..], [2]);
+    assert!(pcap_reader.next_packet().is_none());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn time_index() {
+    let mut pcap_writer = PcapWriter::new(Vec::new()).unwrap();
+    for i in 0..10 {
+        pcap_writer.write_packet(&PcapPacket::new(Duration::from_secs(i), 1, &[i as u8])).unwrap();
+    }
+    let data = pcap_writer.into_inner();
+
+    let index = PcapReader::new(&data[..]).unwrap().build_index(3).unwrap();
+    let offsets: Vec<_> = index.entries().iter().map(|entry| entry.offset).collect();
+    assert_eq!(offsets, vec![24, 24 + 3 * 17, 24 + 6 * 17, 24 + 9 * 17]);
+
+    let mut buf = Vec::new();
+    write_index(&index, &mut buf).unwrap();
+    assert_eq!(load_index(&buf[..]).unwrap(), index);
+    assert!(load_index(&buf[1..]).is_err());
+
+    let mut pcap_reader = PcapReader::new(Cursor::new(&data[..])).unwrap();
+    assert!(pcap_reader.seek_to_time(&index, Duration::from_millis(7500)).unwrap());
+    assert_eq!(pcap_reader.next_packet().unwrap().unwrap().timestamp, Duration::from_secs(6));
+
+    // Backward, before the first packet
+    assert!(pcap_reader.seek_to_time(&index, Duration::ZERO).unwrap());
+    assert_eq!(pcap_reader.next_packet().unwrap().unwrap().timestamp, Duration::ZERO);
+    assert_eq!(pcap_reader.packets().count(), 9);
+
+    // Another capture
+    let mut pcap_reader = PcapReader::new(Cursor::new(&data[..data.len() - 17])).unwrap();
+    assert!(pcap_reader.seek_to_time(&index, Duration::ZERO).is_err());
+
+    // Packets out of order, the seek stays before the packets at 5s
+    let mut pcap_writer = PcapWriter::new(Vec::new()).unwrap();
+    for secs in [0, 9, 5, 6] {
+        pcap_writer.write_packet(&PcapPacket::new(Duration::from_secs(secs), 1, &[0])).unwrap();
+    }
+    let data = pcap_writer.into_inner();
+    let index = PcapReader::new(&data[..]).unwrap().build_index(1).unwrap();
+    assert_eq!(index.find(Duration::from_secs(5)).unwrap().timestamp, Duration::ZERO);
+    assert_eq!(index.find(Duration::from_secs(9)).unwrap().timestamp, Duration::from_secs(6));
+}
+
+#[cfg(feature = "digest")]
+#[test]
+fn packet_digests() {
+    use pcap_file::digest::{with_digest, DigestAlgorithm};
+
+    let mut pcap_writer = PcapWriter::new(Vec::new()).unwrap();
+    for data in [b"abc", b"abd", b"abc"] {
+        pcap_writer.write_packet(&PcapPacket::new(Duration::ZERO, 3, data)).unwrap();
+    }
+    let data = pcap_writer.into_inner();
+
+    let pcap_reader = PcapReader::new(&data[..]).unwrap();
+    let digests: Vec<_> = with_digest(pcap_reader, DigestAlgorithm::Sha256).map(|res| res.unwrap().1).collect();
+    assert_eq!(digests[0].to_string(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    assert_eq!(digests[0], digests[2]);
+    assert_ne!(digests[0], digests[1]);
+
+    let mut pcap_reader = PcapReader::new(&data[..]).unwrap();
+    let digests: Vec<_> = with_digest(pcap_reader.packets(), DigestAlgorithm::Xxh3).map(|res| res.unwrap().1).collect();
+    assert_eq!(digests[0].as_bytes().len(), 8);
+    assert_eq!(digests[0], digests[2]);
+    assert_ne!(digests[0], DigestAlgorithm::Xxh3_128.compute(b"abc"));
+}
+
+#[test]
+fn record_reader() {
+    for endianness in [Endianness::Big, Endianness::Little] {
+        let header = PcapHeader { endianness, ts_resolution: TsResolution::MicroSecond, ..Default::default() };
+        let mut pcap_writer = PcapWriter::with_header(Vec::new(), header).unwrap();
+        for i in 0..100_u32 {
+            let data = vec![i as u8; i as usize % 60 + 1];
+            pcap_writer.write_packet(&PcapPacket::new(Duration::new(i as u64, i * 1000), 1500, &data)).unwrap();
+        }
+        let data = pcap_writer.into_inner();
+
+        let mut record_reader = RecordReader::with_capacity(&data[..], 256).unwrap();
+        assert_eq!(record_reader.header(), header);
+
+        let mut pcap_reader = PcapReader::new(&data[..]).unwrap();
+        let mut count = 0;
+        while let Some(record) = record_reader.next_record() {
+            let record = record.unwrap();
+            let packet = pcap_reader.next_packet().unwrap().unwrap();
+            assert_eq!(record.timestamp(TsResolution::MicroSecond), packet.timestamp);
+            assert_eq!(record.ts_frac, count);
+            assert_eq!(record.orig_len, packet.orig_len);
+            assert_eq!(record.data, &packet.data[..]);
+            count += 1;
+        }
+        assert_eq!(count, 100);
+        assert_eq!(record_reader.position(), data.len() as u64);
+    }
+
+    // A truncated record
+    let mut pcap_writer = PcapWriter::new(Vec::new()).unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::ZERO, 4, &[1, 2, 3, 4])).unwrap();
+    let data = pcap_writer.into_inner();
+    let mut record_reader = RecordReader::new(&data[..data.len() - 1]).unwrap();
+    assert!(record_reader.next_record().unwrap().is_err());
+}
+
+#[test]
+fn timestamp_as() {
+    let header = PcapHeader { ts_resolution: TsResolution::MicroSecond, ..Default::default() };
+    let mut pcap_writer = PcapWriter::with_header(Vec::new(), header).unwrap();
+    pcap_writer.write_packet(&PcapPacket::new(Duration::new(1, 1000), 1, &[1])).unwrap();
+    let data = pcap_writer.into_inner();
+
+    // The timestamps are converted directly from the fields
+    let mut record_reader = RecordReader::new(&data[..]).unwrap();
+    let record = record_reader.next_record().unwrap().unwrap();
+    assert_eq!(record.timestamp_as::<u64>(TsResolution::MicroSecond), Some(1_000_001));
+    assert_eq!(record.timestamp_as::<Duration>(TsResolution::MicroSecond), Some(Duration::new(1, 1000)));
+    assert_eq!(record.timestamp_as::<SystemTime>(TsResolution::NanoSecond), Some(UNIX_EPOCH + Duration::new(1, 1)));
+
+    let mut pcap_reader = PcapReader::new(&data[..]).unwrap();
+    let packet = pcap_reader.next_raw_packet().unwrap().unwrap();
+    assert_eq!(packet.timestamp_as::<Duration>(TsResolution::MicroSecond), Some(Duration::new(1, 1000)));
+
+    let mut pcap_reader = PcapReader::new(&data[..]).unwrap();
+    let packet = pcap_reader.next_packet().unwrap().unwrap();
+    assert_eq!(packet.timestamp_as::<u64>(), Some(1_000_001_000));
+    assert_eq!(packet.timestamp_as::<Duration>(), Some(Duration::new(1, 1000)));
+}
+
+#[test]
+fn set_timestamp() {
+    let time = UNIX_EPOCH + Duration::new(1, 1000);
+    let mut packet = PcapPacket::with_time(time, 1, &[1]).unwrap();
+    assert_eq!(packet.timestamp, Duration::new(1, 1000));
+    assert!(PcapPacket::with_time(UNIX_EPOCH - Duration::from_secs(1), 1, &[1]).is_err());
+
+    packet.set_timestamp(Duration::from_secs(2)).unwrap();
+    assert_eq!(packet.timestamp_as::<SystemTime>(), Some(UNIX_EPOCH + Duration::from_secs(2)));
+
+    #[cfg(feature = "chrono")]
+    {
+        let utc = chrono::DateTime::from_timestamp(3, 1000).unwrap();
+        packet.set_timestamp(utc).unwrap();
+        assert_eq!(packet.timestamp, Duration::new(3, 1000));
+        assert_eq!(packet.timestamp_utc(), Some(utc));
+    }
+
+    #[cfg(feature = "time")]
+    {
+        let odt = time::OffsetDateTime::from_unix_timestamp_nanos(4_000_001_000).unwrap();
+        packet.set_timestamp(odt).unwrap();
+        assert_eq!(packet.timestamp, Duration::new(4, 1000));
+        assert_eq!(packet.timestamp_offset_date_time(), Some(odt));
+    }
+}
@@ -1,8 +1,45 @@
+use std::borrow::Cow;
 use std::fs::File;
-use std::io::Read;
+use std::io::{Cursor, ErrorKind, Read, Write};
+use std::net::Ipv4Addr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use byteorder_slice::byteorder::WriteBytesExt;
+use byteorder_slice::result::ReadSlice;
+use byteorder_slice::{BigEndian, ByteOrder, LittleEndian, NativeEndian};
 use glob::glob;
-use pcap_file::pcapng::{PcapNgParser, PcapNgReader, PcapNgWriter};
+use pcap_file::pcapng::blocks::custom::CustomBlock;
+use pcap_file::pcapng::blocks::decryption_secrets::{DecryptionSecretsBlock, SecretsType, ZigBeeApsKey, ZigBeeNwkKey};
+use pcap_file::pcapng::blocks::enhanced_packet::{
+    EnhancedPacketBlock, EnhancedPacketOption, LinkLayerError, PacketDirection, PacketFlags, PacketVerdict, ReceptionType,
+    ReservedReceptionType,
+};
+use pcap_file::pcapng::blocks::hone_connection_event::{HoneConnectionEventBlock, HoneConnectionEventOption, HoneEvent};
+use pcap_file::pcapng::blocks::hone_machine_info::{HoneMachineInfoBlock, HoneMachineInfoOption};
+use pcap_file::pcapng::blocks::interface_description::{InterfaceDescriptionBlock, InterfaceDescriptionOption, InterfaceId};
+use pcap_file::pcapng::blocks::interface_statistics::{InterfaceStatisticsBlock, InterfaceStatisticsOption};
+use pcap_file::pcapng::blocks::packet::{PacketBlock, PacketOption};
+use pcap_file::pcapng::blocks::section_header::{SectionHeaderBlock, SectionHeaderOption};
+use pcap_file::pcapng::blocks::simple_packet::SimplePacketBlock;
+use pcap_file::pcapng::blocks::systemd_journal_export::{JournalEntry, SystemdJournalExportBlock};
+use pcap_file::pcapng::blocks::unknown::UnknownBlock;
+use pcap_file::pcapng::blocks::{
+    CUSTOM_BLOCK_NO_COPY, DECRYPTION_SECRETS_BLOCK, ENHANCED_PACKET_BLOCK, INTERFACE_DESCRIPTION_BLOCK, SECTION_HEADER_BLOCK,
+    SYSTEMD_JOURNAL_EXPORT_BLOCK,
+};
+use pcap_file::pcapng::{
+    read_metadata, ApplicationMetadata, Block, BlockArena, BlockPosition, MetadataConfig, MetadataPoint, OptionOrder, OrderedPcapNgReader,
+    OverflowPolicy, PacketRef, PcapNgBlock, PcapNgEvent, PcapNgPacket, PcapNgParser, PcapNgParserConfig, PcapNgPushParser, PcapNgReader,
+    PcapNgWriter, RawBlock, RotatingPcapNgWriter, RotationConfig, ScannedBlock, SortingPcapNgWriter, StatisticsAccumulator, ThreadedWriter,
+    Utf8Mode,
+};
+use pcap_file::fmt::{BlockDump, HexDump};
+use pcap_file::follow::FollowReader;
+use pcap_file::index::TimeIndex;
+use pcap_file::info::{capture_info, CaptureFormat};
+use pcap_file::{DataLink, Endianness, IoStats, PcapError};
+
+use crate::CountingWriter;
 
 #[test]
 fn reader() {
@@ -88,3 +125,3198 @@ fn writer() {
         }
     }
 }
+
+#[test]
+fn strip_fcs() {
+    let mut interface = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF);
+    interface.set_fcs_len(4).unwrap();
+    // 32 bytes are 256 bits
+    assert!(interface.set_fcs_len(32).is_err());
+    assert_eq!(interface.fcs_len(), Some(4));
+
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::ZERO,
+        original_len: 8,
+        data: Cow::Borrowed(&[1, 2, 3, 4, 0xAA, 0xBB, 0xCC, 0xDD]),
+        options: vec![],
+    };
+    assert_eq!(packet.split_fcs(4), (&[1, 2, 3, 4][..], &[0xAA, 0xBB, 0xCC, 0xDD][..]));
+
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.write_pcapng_block(&interface).unwrap();
+    pcapng_writer.write_pcapng_block(&packet).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    reader.next_block().unwrap().unwrap();
+    let block = reader.next_block().unwrap().unwrap();
+    assert_eq!(block.into_enhanced_packet().unwrap(), packet);
+
+    let config = PcapNgParserConfig { strip_fcs: true, ..Default::default() };
+    let mut reader = PcapNgReader::with_config(&data[..], config).unwrap();
+    reader.next_block().unwrap().unwrap();
+    let stripped = reader.next_block().unwrap().unwrap().into_enhanced_packet().unwrap();
+    assert_eq!(&stripped.data[..], &[1, 2, 3, 4]);
+    assert_eq!(stripped.original_len, 4);
+}
+
+#[test]
+fn packet_flags() {
+    let mut flags = PacketFlags::default();
+    flags.set_direction(PacketDirection::Outbound);
+    flags.set_reception_type(ReceptionType::Broadcast);
+    flags.set_fcs_len(4);
+    flags.set_error(LinkLayerError::Crc, true);
+    assert_eq!(flags.bits(), 0x0100_008E);
+
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::ZERO,
+        original_len: 8,
+        data: Cow::Borrowed(&[1, 2, 3, 4, 0xAA, 0xBB, 0xCC, 0xDD]),
+        options: vec![EnhancedPacketOption::Flags(flags)],
+    };
+
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF)).unwrap();
+    pcapng_writer.write_pcapng_block(&packet).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let config = PcapNgParserConfig { strip_fcs: true, ..Default::default() };
+    let mut reader = PcapNgReader::with_config(&data[..], config).unwrap();
+    reader.next_block().unwrap().unwrap();
+    let packet = reader.next_block().unwrap().unwrap().into_enhanced_packet().unwrap();
+
+    let flags = packet.flags().unwrap();
+    assert_eq!(flags.direction(), PacketDirection::Outbound);
+    assert_eq!(flags.reception_type(), ReceptionType::Broadcast);
+    assert_eq!(flags.fcs_len(), Some(4));
+    assert!(flags.has_error(LinkLayerError::Crc));
+    assert!(!flags.has_error(LinkLayerError::Symbol));
+    assert_eq!(&packet.data[..], &[1, 2, 3, 4]);
+
+    // The reserved reception types round-trip, the named ones can't be built as reserved
+    assert!(ReservedReceptionType::new(4).is_none());
+    assert!(ReservedReceptionType::new(8).is_none());
+    let reserved = ReceptionType::Reserved(ReservedReceptionType::new(6).unwrap());
+    let mut flags = PacketFlags::default();
+    flags.set_reception_type(reserved);
+    assert_eq!(flags.reception_type(), reserved);
+    assert_eq!(flags.bits(), 6 << 2);
+}
+
+#[cfg(feature = "hash")]
+#[test]
+fn packet_hash() {
+    use pcap_file::pcapng::blocks::enhanced_packet::HashAlgorithm;
+
+    let mut packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::ZERO,
+        original_len: 4,
+        data: Cow::Borrowed(&[1, 2, 3, 4]),
+        options: vec![],
+    };
+    packet.add_hash(HashAlgorithm::Crc32).unwrap();
+    packet.add_hash(HashAlgorithm::Md5).unwrap();
+    packet.add_hash(HashAlgorithm::Sha1).unwrap();
+    assert!(packet.add_hash(HashAlgorithm::Toeplitz).is_err());
+    assert_eq!(packet.hashes().count(), 3);
+    assert!(packet.verify_hashes().is_empty());
+
+    packet.data = Cow::Borrowed(&[1, 2, 3, 5]);
+    let mismatches = packet.verify_hashes();
+    assert_eq!(mismatches.len(), 3);
+    assert_eq!(mismatches[0].algorithm, HashAlgorithm::Crc32);
+}
+
+#[test]
+fn packet_verdict() {
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::ZERO,
+        original_len: 4,
+        data: Cow::Borrowed(&[1, 2, 3, 4]),
+        options: vec![
+            EnhancedPacketOption::Verdict(PacketVerdict::Hardware(Cow::Borrowed(&[0xAA, 0xBB]))),
+            EnhancedPacketOption::Verdict(PacketVerdict::LinuxEbpfTc(2)),
+            EnhancedPacketOption::Verdict(PacketVerdict::LinuxEbpfXdp(1)),
+        ],
+    };
+
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF)).unwrap();
+    pcapng_writer.write_pcapng_block(&packet).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    reader.next_block().unwrap().unwrap();
+    let block = reader.next_block().unwrap().unwrap();
+    assert_eq!(block.into_enhanced_packet().unwrap(), packet);
+}
+
+#[test]
+fn packet_id_and_queue() {
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::ZERO,
+        original_len: 4,
+        data: Cow::Borrowed(&[1, 2, 3, 4]),
+        options: vec![],
+    }
+    .with_packet_id(0x0123_4567_89AB_CDEF)
+    .with_queue(3);
+
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF)).unwrap();
+    pcapng_writer.write_pcapng_block(&packet).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    reader.next_block().unwrap().unwrap();
+    let packet = reader.next_block().unwrap().unwrap().into_enhanced_packet().unwrap();
+    assert_eq!(packet.packet_id(), Some(0x0123_4567_89AB_CDEF));
+    assert_eq!(packet.queue(), Some(3));
+}
+
+#[test]
+fn report_drops() {
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::ZERO,
+        original_len: 4,
+        data: Cow::Borrowed(&[1, 2, 3, 4]),
+        options: vec![],
+    };
+
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    assert!(pcapng_writer.report_drops(0, 1).is_err());
+
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF)).unwrap();
+    pcapng_writer.report_drops(0, 3).unwrap();
+    pcapng_writer.report_drops(0, 2).unwrap();
+    assert_eq!(pcapng_writer.pending_drops(0), 5);
+
+    pcapng_writer.write_pcapng_block(&packet).unwrap();
+    assert_eq!(pcapng_writer.pending_drops(0), 0);
+    pcapng_writer.write_pcapng_block(&packet).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    reader.next_block().unwrap().unwrap();
+    let first = reader.next_block().unwrap().unwrap().into_enhanced_packet().unwrap();
+    assert_eq!(first.options, vec![EnhancedPacketOption::DropCount(5)]);
+    let second = reader.next_block().unwrap().unwrap().into_enhanced_packet().unwrap();
+    assert!(second.options.is_empty());
+}
+
+#[test]
+fn finish_closing_statistics() {
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::ZERO,
+        original_len: 4,
+        data: Cow::Borrowed(&[1, 2, 3, 4]),
+        options: vec![],
+    };
+
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.set_buffer_capacity(1 << 16).unwrap();
+    pcapng_writer.set_closing_statistics(true);
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF)).unwrap();
+    pcapng_writer.write_pcapng_block(&packet).unwrap();
+    pcapng_writer.report_drops(0, 3).unwrap();
+    pcapng_writer.write_pcapng_block(&packet).unwrap();
+    let data = pcapng_writer.finish().unwrap();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let blocks: Vec<_> = std::iter::from_fn(|| reader.next_block().map(|block| block.unwrap().into_owned())).collect();
+    assert_eq!(blocks.len(), 4);
+
+    let statistics = blocks[3].clone().into_interface_statistics().unwrap();
+    assert_eq!(statistics.interface_id, 0);
+    assert!(statistics.options.contains(&InterfaceStatisticsOption::IsbIfDrop(3)));
+    assert!(statistics.options.contains(&InterfaceStatisticsOption::IsbUsrDeliv(2)));
+
+    // Without closing statistics, finish only writes the buffer
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.set_buffer_capacity(1 << 16).unwrap();
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF)).unwrap();
+    pcapng_writer.write_pcapng_block(&packet).unwrap();
+    let data = pcapng_writer.finish().unwrap();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    assert_eq!(std::iter::from_fn(|| reader.next_block().map(|block| block.is_ok())).count(), 2);
+}
+
+#[test]
+fn finish_with_section_lengths() {
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::ZERO,
+        original_len: 4,
+        data: Cow::Borrowed(&[1, 2, 3, 4]),
+        options: vec![],
+    };
+    let explicit = SectionHeaderBlock { endianness: Endianness::Big, section_length: 0, ..Default::default() };
+
+    let mut pcapng_writer = PcapNgWriter::with_endianness(Cursor::new(Vec::new()), Endianness::Little).unwrap();
+    pcapng_writer.set_buffer_capacity(1 << 16).unwrap();
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF)).unwrap();
+    pcapng_writer.write_pcapng_block(&packet).unwrap();
+    pcapng_writer.write_pcapng_block(&SectionHeaderBlock { endianness: Endianness::Big, ..Default::default() }).unwrap();
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF)).unwrap();
+    pcapng_writer.write_pcapng_block(&packet).unwrap();
+    pcapng_writer.write_pcapng_block(&packet).unwrap();
+    pcapng_writer.write_pcapng_block(&explicit).unwrap();
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF)).unwrap();
+    let data = pcapng_writer.finish_with_section_lengths().unwrap().into_inner();
+
+    // Each section length leads to the next section header
+    let first = SectionHeaderBlock::from_slice::<LittleEndian>(&data[8..]).unwrap().1;
+    let second_offset = 28 + first.section_length as usize;
+    assert_eq!(data[second_offset..second_offset + 4], [0x0A, 0x0D, 0x0D, 0x0A]);
+
+    let second = SectionHeaderBlock::from_slice::<BigEndian>(&data[second_offset + 8..]).unwrap().1;
+    let third_offset = second_offset + 28 + second.section_length as usize;
+    assert_eq!(data[third_offset..third_offset + 4], [0x0A, 0x0D, 0x0D, 0x0A]);
+
+    // An explicit length is kept
+    let third = SectionHeaderBlock::from_slice::<BigEndian>(&data[third_offset + 8..]).unwrap().1;
+    assert_eq!(third.section_length, 0);
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    assert_eq!(std::iter::from_fn(|| reader.next_block().map(|block| block.is_ok())).filter(|&ok| ok).count(), 8);
+}
+
+#[test]
+fn skip_section() {
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::ZERO,
+        original_len: 4,
+        data: Cow::Borrowed(&[1, 2, 3, 4]),
+        options: vec![],
+    };
+
+    let mut pcapng_writer = PcapNgWriter::new(Cursor::new(Vec::new())).unwrap();
+    for linktype in [DataLink::ETHERNET, DataLink::RAW, DataLink::NULL] {
+        pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(linktype, 0xFFFF)).unwrap();
+        pcapng_writer.write_pcapng_block(&packet).unwrap();
+        pcapng_writer.write_pcapng_block(&SectionHeaderBlock::default()).unwrap();
+    }
+    let data = pcapng_writer.finish_with_section_lengths().unwrap().into_inner();
+
+    // The buffer is smaller than a section, so the skip seeks the inner reader
+    let mut reader = PcapNgReader::with_capacity(Cursor::new(&data[..]), 64).unwrap();
+    reader.next_block().unwrap().unwrap();
+    assert!(reader.skip_section().unwrap());
+    assert!(reader.next_block().unwrap().unwrap().into_section_header().is_some());
+    assert!(reader.skip_section().unwrap());
+    assert!(reader.next_block().unwrap().unwrap().into_section_header().is_some());
+
+    let interface = reader.next_block().unwrap().unwrap().into_interface_description().unwrap();
+    assert_eq!(interface.linktype, DataLink::NULL);
+    assert_eq!(reader.position(), 216);
+
+    // The rest of a partially read section is skipped too
+    assert!(reader.skip_section().unwrap());
+    assert_eq!(reader.next_block().unwrap().unwrap().into_section_header().unwrap().section_length, 0);
+    assert!(reader.skip_section().unwrap());
+    assert!(reader.next_block().is_none());
+
+    // Nothing is skipped if the section length is unspecified
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF)).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let mut reader = PcapNgReader::new(Cursor::new(&data[..])).unwrap();
+    assert!(!reader.skip_section().unwrap());
+    assert!(reader.next_block().unwrap().unwrap().into_interface_description().is_some());
+}
+
+#[test]
+fn check_section_length() {
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::ZERO,
+        original_len: 4,
+        data: Cow::Borrowed(&[1, 2, 3, 4]),
+        options: vec![],
+    };
+
+    let mut pcapng_writer = PcapNgWriter::new(Cursor::new(Vec::new())).unwrap();
+    for _ in 0..2 {
+        pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF)).unwrap();
+        pcapng_writer.write_pcapng_block(&packet).unwrap();
+        pcapng_writer.write_pcapng_block(&packet).unwrap();
+    }
+    pcapng_writer.write_pcapng_block(&SectionHeaderBlock::default()).unwrap();
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF)).unwrap();
+    pcapng_writer.write_pcapng_block(&packet).unwrap();
+    let data = pcapng_writer.finish_with_section_lengths().unwrap().into_inner();
+
+    let config = PcapNgParserConfig { check_section_length: true, ..Default::default() };
+    let read_all = |data: &[u8], config: PcapNgParserConfig| {
+        let mut reader = PcapNgReader::with_config(data, config).unwrap();
+        assert_eq!(reader.section().declared_section_length(), Some(20 * 2 + 36 * 4));
+        std::iter::from_fn(|| reader.next_block().map(|block| block.map(|_| ()))).collect::<Vec<_>>()
+    };
+
+    let blocks = read_all(&data, config.clone());
+    assert_eq!(blocks.len(), 9);
+    assert!(blocks.iter().all(|block| block.is_ok()));
+
+    // The last packet is missing, the error is only returned once
+    let blocks = read_all(&data[..data.len() - 36], config.clone());
+    assert_eq!(blocks.len(), 9);
+    assert!(blocks[..8].iter().all(|block| block.is_ok()));
+    assert!(matches!(blocks[8], Err(PcapError::InvalidField(_))));
+    assert!(read_all(&data[..data.len() - 36], PcapNgParserConfig::default()).iter().all(|block| block.is_ok()));
+
+    // A packet of the first section is missing, the next section header is refused
+    let mut truncated = data.clone();
+    truncated.drain(28 + 20 * 2 + 36 * 3..28 + 20 * 2 + 36 * 4);
+    let mut reader = PcapNgReader::with_config(&truncated[..], config).unwrap();
+    for _ in 0..5 {
+        reader.next_block().unwrap().unwrap();
+    }
+    assert!(matches!(reader.next_block(), Some(Err(PcapError::InvalidField(_)))));
+}
+
+#[test]
+fn time_index() {
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    for (section, linktype) in [DataLink::ETHERNET, DataLink::RAW].into_iter().enumerate() {
+        if section > 0 {
+            pcapng_writer.write_pcapng_block(&SectionHeaderBlock { endianness: Endianness::Big, ..Default::default() }).unwrap();
+        }
+        pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(linktype, 0xFFFF)).unwrap();
+        for i in 0..5 {
+            let packet = EnhancedPacketBlock {
+                interface_id: 0,
+                // In microseconds, the default resolution of the interfaces
+                timestamp: Duration::from_nanos((section as u64 * 5 + i) * 1_000_000),
+                original_len: 1,
+                data: Cow::Borrowed(&[1]),
+                options: vec![],
+            };
+            pcapng_writer.write_pcapng_block(&packet).unwrap();
+        }
+    }
+    let data = pcapng_writer.into_inner();
+
+    let index = PcapNgReader::new(&data[..]).unwrap().build_index(2).unwrap();
+    assert_eq!(index.entries().len(), 5);
+    assert!(index.entries()[..3].iter().all(|entry| entry.section == 0));
+    assert!(index.entries()[3..].iter().all(|entry| entry.section > 0));
+
+    // Into the second section, whose endianness and interfaces differ
+    let mut reader = PcapNgReader::new(Cursor::new(&data[..])).unwrap();
+    assert!(reader.seek_to_time(&index, Duration::from_secs(7)).unwrap());
+    let packet = reader.next_packet().unwrap().unwrap();
+    assert_eq!(packet.timestamp, Some(Duration::from_secs(6)));
+    assert_eq!(packet.linktype, DataLink::RAW);
+    assert_eq!(reader.section().endianness, Endianness::Big);
+
+    // Back into the first section
+    assert!(reader.seek_to_time(&index, Duration::from_secs(3)).unwrap());
+    let packet = reader.next_packet().unwrap().unwrap();
+    assert_eq!(packet.timestamp, Some(Duration::from_secs(2)));
+    assert_eq!(packet.linktype, DataLink::ETHERNET);
+    assert_eq!(reader.packets().count(), 7);
+
+    assert!(!reader.seek_to_time(&TimeIndex::new(2), Duration::ZERO).unwrap());
+}
+
+#[test]
+fn write_packet() {
+    let mut interface = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 4);
+    interface.options.push(InterfaceDescriptionOption::IfTsResol(9));
+
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    assert!(pcapng_writer.write_packet(InterfaceId(0), SystemTime::now(), &[1]).is_err());
+
+    let interface_id = pcapng_writer.add_interface(&interface).unwrap();
+    let ts = UNIX_EPOCH + Duration::new(1_600_000_000, 123_456_789);
+    pcapng_writer.write_packet(interface_id, ts, &[1, 2, 3, 4, 5, 6]).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    reader.next_block().unwrap().unwrap();
+    let packet = reader.next_block().unwrap().unwrap().into_enhanced_packet().unwrap();
+    assert_eq!(&packet.data[..], &[1, 2, 3, 4]);
+    assert_eq!(packet.original_len, 6);
+    assert_eq!(packet.timestamp.as_nanos(), 1_600_000_000_123_456_789);
+
+    let timestamp = packet.timestamp.as_nanos() as u64;
+    assert_eq!(interface.timestamp_to_duration(timestamp), Duration::new(1_600_000_000, 123_456_789));
+}
+
+#[test]
+fn interface_builder() {
+    let interface = InterfaceDescriptionBlock::builder()
+        .linktype(DataLink::IEEE802_11)
+        .name("wlan0")
+        .name("eth0")
+        .ipv4_addr(Ipv4Addr::new(192, 168, 1, 1), Ipv4Addr::new(255, 255, 255, 0))
+        .tsresol_nanos()
+        .fcs_len(4)
+        .unwrap()
+        .snaplen(0xFFFF)
+        .build();
+
+    assert_eq!(interface.linktype, DataLink::IEEE802_11);
+    assert_eq!(interface.snaplen, 0xFFFF);
+    assert_eq!(interface.options, vec![
+        InterfaceDescriptionOption::IfName(Cow::Borrowed("eth0")),
+        InterfaceDescriptionOption::IfIpv4Addr(Cow::Borrowed(&[192, 168, 1, 1, 255, 255, 255, 0])),
+        InterfaceDescriptionOption::IfTsResol(9),
+        InterfaceDescriptionOption::IfFcsLen(32),
+    ]);
+
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.write_pcapng_block(&interface).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let read = reader.next_block().unwrap().unwrap().into_interface_description().unwrap();
+    assert_eq!(read, interface);
+    assert_eq!(read.ts_resol(), 9);
+    assert_eq!(read.fcs_len(), Some(4));
+}
+
+#[test]
+fn section_builder() {
+    let section = SectionHeaderBlock::builder()
+        .endianness(Endianness::Little)
+        .os("Linux")
+        .user_application("test")
+        .user_application("pcap-file")
+        .comment("first")
+        .comment("second")
+        .build()
+        .unwrap();
+
+    assert_eq!(section.options, vec![
+        SectionHeaderOption::OS(Cow::Borrowed("Linux")),
+        SectionHeaderOption::UserApplication(Cow::Borrowed("pcap-file")),
+        SectionHeaderOption::Comment(Cow::Borrowed("first")),
+        SectionHeaderOption::Comment(Cow::Borrowed("second")),
+    ]);
+
+    let pcapng_writer = PcapNgWriter::with_section_header(Vec::new(), section.clone()).unwrap();
+    let data = pcapng_writer.into_inner();
+    assert_eq!(&data[..4], &[0x0A, 0x0D, 0x0D, 0x0A]);
+    assert_eq!(&data[8..12], &[0x4D, 0x3C, 0x2B, 0x1A]);
+
+    let reader = PcapNgReader::new(&data[..]).unwrap();
+    assert_eq!(reader.section(), &section);
+
+    assert!(SectionHeaderBlock::builder().version(2, 0).build().is_err());
+    assert!(SectionHeaderBlock::builder().section_length(-2).build().is_err());
+    assert!(SectionHeaderBlock::builder().section_length(6).build().is_err());
+    assert!(SectionHeaderBlock::builder().section_length(64).build().is_ok());
+}
+
+#[test]
+fn packet_builder() {
+    let interface = InterfaceDescriptionBlock::builder().tsresol(3).snaplen(4).build();
+    let ts = UNIX_EPOCH + Duration::new(10, 123_456_789);
+
+    let mut flags = PacketFlags::default();
+    flags.set_direction(PacketDirection::Inbound);
+
+    let packet = EnhancedPacketBlock::builder(InterfaceId(1), &[1, 2, 3, 4, 5])
+        .time(ts)
+        .original_len(60)
+        .flags(flags)
+        .comment("builder")
+        .drop_count(2)
+        .build(&interface)
+        .unwrap();
+
+    assert_eq!(packet.interface_id, 1);
+    assert_eq!(packet.timestamp.as_nanos(), 10_123);
+    assert_eq!(&packet.data[..], &[1, 2, 3, 4]);
+    assert_eq!(packet.original_len, 60);
+    assert_eq!(packet.flags(), Some(flags));
+    assert_eq!(packet.options.len(), 3);
+
+    let before_epoch = UNIX_EPOCH - Duration::from_secs(1);
+    assert!(EnhancedPacketBlock::builder(InterfaceId(0), &[1]).time(before_epoch).build(&interface).is_err());
+    assert!(EnhancedPacketBlock::builder(InterfaceId(0), &[1, 2]).original_len(1).build(&interface).is_err());
+}
+
+#[test]
+fn lenient_trailer_len() {
+    let mut pcapng_writer = PcapNgWriter::with_endianness(Vec::new(), Endianness::Big).unwrap();
+    let interface = pcapng_writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0)).unwrap();
+    pcapng_writer.write_packet(interface, UNIX_EPOCH, &[1, 2, 3, 4, 5, 6]).unwrap();
+    let mut data = pcapng_writer.into_inner();
+
+    // Trailer length without the padding
+    let len = data.len();
+    data[len - 4..].copy_from_slice(&38_u32.to_be_bytes());
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    reader.next_block().unwrap().unwrap();
+    assert!(reader.next_block().unwrap().is_err());
+
+    let config = PcapNgParserConfig { lenient: true, ..Default::default() };
+    let mut reader = PcapNgReader::with_config(&data[..], config.clone()).unwrap();
+    reader.next_block().unwrap().unwrap();
+    let packet = reader.next_block().unwrap().unwrap().into_enhanced_packet().unwrap();
+    assert_eq!(&packet.data[..], &[1, 2, 3, 4, 5, 6]);
+    assert!(reader.next_block().is_none());
+    assert_eq!(reader.stats().repaired, 1);
+
+    data[len - 4..].copy_from_slice(&32_u32.to_be_bytes());
+    let mut reader = PcapNgReader::with_config(&data[..], config).unwrap();
+    reader.next_block().unwrap().unwrap();
+    assert!(reader.next_block().unwrap().is_err());
+}
+
+#[test]
+fn lenient_padding() {
+    let mut pcapng_writer = PcapNgWriter::with_endianness(Vec::new(), Endianness::Big).unwrap();
+    pcapng_writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0)).unwrap();
+    let mut data = pcapng_writer.into_inner();
+
+    // Enhanced packet block without padding after the packet data and the comment
+    let block: &[u8] = &[
+        0, 0, 0, 6, 0, 0, 0, 48, // Type and initial length
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 6, 0, 0, 0, 6, // Interface, timestamp and lengths
+        1, 2, 3, 4, 5, 6, // Packet data
+        0, 1, 0, 2, b'h', b'i', // Comment
+        0, 0, 0, 0, // End of options
+        0, 0, 0, 48, // Trailer length
+    ];
+    data.extend_from_slice(block);
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    reader.next_block().unwrap().unwrap();
+    assert!(reader.next_block().unwrap().is_err());
+
+    let config = PcapNgParserConfig { lenient: true, ..Default::default() };
+    let mut reader = PcapNgReader::with_config(&data[..], config).unwrap();
+    reader.next_block().unwrap().unwrap();
+    let packet = reader.next_block().unwrap().unwrap().into_enhanced_packet().unwrap();
+    assert_eq!(&packet.data[..], &[1, 2, 3, 4, 5, 6]);
+    assert_eq!(packet.options, vec![EnhancedPacketOption::Comment(Cow::Borrowed("hi"))]);
+    assert_eq!(reader.stats().repaired, 1);
+}
+
+#[test]
+fn block_position() {
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    let interface = pcapng_writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0)).unwrap();
+    pcapng_writer.write_packet(interface, UNIX_EPOCH, &[1, 2, 3, 4, 5, 6]).unwrap();
+    let mut data = pcapng_writer.into_inner();
+    data.extend_from_slice(&[0xFF; 12]);
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    assert_eq!(reader.position(), 28);
+
+    let (block, position) = reader.next_block_with_position().unwrap().unwrap();
+    assert!(block.as_interface_description().is_some());
+    assert_eq!(position, BlockPosition { offset: 28, len: 20 });
+
+    let (block, position) = reader.next_block_with_position().unwrap().unwrap();
+    assert!(block.as_enhanced_packet().is_some());
+    assert_eq!(position, BlockPosition { offset: 48, len: 40 });
+
+    assert!(reader.next_block_with_position().unwrap().is_err());
+    assert_eq!(reader.position(), 88);
+}
+
+#[test]
+fn copy_block() {
+    let mut pcapng_writer = PcapNgWriter::with_endianness(Vec::new(), Endianness::Big).unwrap();
+    let interface = pcapng_writer.add_interface(&InterfaceDescriptionBlock::builder().name("eth0").build()).unwrap();
+    pcapng_writer.write_packet(interface, UNIX_EPOCH, &[1, 2, 3, 4, 5, 6]).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    for endianness in [Endianness::Big, Endianness::Little] {
+        let mut reader = PcapNgReader::new(&data[..]).unwrap();
+        let section = SectionHeaderBlock { endianness, ..reader.section().clone() };
+        let mut pcapng_writer = PcapNgWriter::with_section_header(Vec::new(), section).unwrap();
+        while let Some(block) = reader.next_raw_block() {
+            let block = block.unwrap();
+            pcapng_writer.copy_block(&block, Endianness::Big).unwrap();
+        }
+        assert_eq!(pcapng_writer.interfaces().len(), 1);
+        let copy = pcapng_writer.into_inner();
+
+        if endianness == Endianness::Big {
+            assert_eq!(copy, data);
+        }
+
+        let mut reader = PcapNgReader::new(&data[..]).unwrap();
+        let mut copy_reader = PcapNgReader::new(&copy[..]).unwrap();
+        assert_eq!(copy_reader.section().endianness, endianness);
+        while let Some(block) = reader.next_block() {
+            assert_eq!(copy_reader.next_block().unwrap().unwrap(), block.unwrap());
+        }
+        assert!(copy_reader.next_block().is_none());
+    }
+}
+
+#[test]
+fn raw_block_len() {
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::builder().name("eth0").build()).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let block = reader.next_raw_block().unwrap().unwrap();
+    let mut out = vec![];
+    let len = block.write_to::<NativeEndian, _>(&mut out).unwrap();
+    assert_eq!(len, out.len());
+    assert_eq!(len, block.initial_len as usize);
+
+    // The length written through the writer includes the header and the trailer of the block
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    let start = pcapng_writer.get_ref().len();
+    assert_eq!(pcapng_writer.write_raw_block(&block).unwrap(), pcapng_writer.get_ref().len() - start);
+}
+
+#[test]
+fn transcode_endianness() {
+    let section = SectionHeaderBlock::builder().endianness(Endianness::Big).os("Linux").build().unwrap();
+    let mut pcapng_writer = PcapNgWriter::with_section_header(Vec::new(), section).unwrap();
+    let interface = InterfaceDescriptionBlock::builder().name("eth0").speed(1_000_000_000).tsresol_nanos().build();
+    pcapng_writer.add_interface(&interface).unwrap();
+
+    let mut flags = PacketFlags::default();
+    flags.set_direction(PacketDirection::Inbound);
+    let packet = EnhancedPacketBlock::builder(InterfaceId(0), &[1, 2, 3, 4, 5])
+        .timestamp(Duration::new(1_600_000_000, 42))
+        .flags(flags)
+        .drop_count(3)
+        .option(EnhancedPacketOption::Verdict(PacketVerdict::LinuxEbpfXdp(7)))
+        .build(&interface)
+        .unwrap();
+    pcapng_writer.write_pcapng_block(&packet).unwrap();
+
+    let statistics = InterfaceStatisticsBlock {
+        interface_id: 0,
+        timestamp: 0x0102_0304_0506_0708,
+        options: vec![InterfaceStatisticsOption::IsbIfRecv(10), InterfaceStatisticsOption::IsbIfDrop(1)],
+    };
+    pcapng_writer.write_pcapng_block(&statistics).unwrap();
+    pcapng_writer.write_block(&Block::Unknown(UnknownBlock::new(0x8000_0001, 16, &[1, 2, 3, 4]))).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let mut little = vec![];
+    pcap_file::pcapng::transcode(&data[..], &mut little, Endianness::Little).unwrap();
+    assert_eq!(little.len(), data.len());
+    assert_eq!(&little[8..12], &[0x4D, 0x3C, 0x2B, 0x1A]);
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let mut little_reader = PcapNgReader::new(&little[..]).unwrap();
+    assert_eq!(little_reader.section().endianness, Endianness::Little);
+    assert_eq!(little_reader.section().options, reader.section().options);
+    while let Some(block) = reader.next_block() {
+        let block = block.unwrap();
+        let little_block = little_reader.next_block().unwrap().unwrap();
+        // The body of the unknown block is copied unchanged, only its recorded endianness differs, which equality ignores
+        if let (Block::Unknown(unknown), Block::Unknown(little_unknown)) = (&block, &little_block) {
+            assert_eq!(unknown.endianness, Some(Endianness::Big));
+            assert_eq!(little_unknown.endianness, Some(Endianness::Little));
+            assert_eq!(*unknown, UnknownBlock::new(0x8000_0001, 16, &[1, 2, 3, 4]));
+        }
+        assert_eq!(little_block, block);
+    }
+    assert!(little_reader.next_block().is_none());
+
+    let mut big = vec![];
+    pcap_file::pcapng::transcode(&little[..], &mut big, Endianness::Big).unwrap();
+    assert_eq!(big, data);
+
+    // A secrets length overflowing with its padding is refused instead of panicking
+    let mut invalid = data[..BigEndian::read_u32(&data[4..8]) as usize].to_vec();
+    for field in [DECRYPTION_SECRETS_BLOCK, 20, u32::from_be_bytes(*b"TLSK"), u32::MAX, 20] {
+        invalid.extend_from_slice(&field.to_be_bytes());
+    }
+    assert!(pcap_file::pcapng::transcode(&invalid[..], &mut vec![], Endianness::Little).is_err());
+}
+
+#[test]
+fn transcode_statistics_times() {
+    let section = SectionHeaderBlock::builder().endianness(Endianness::Big).build().unwrap();
+    let mut pcapng_writer = PcapNgWriter::with_section_header(Vec::new(), section).unwrap();
+    let interface = InterfaceDescriptionBlock::builder().option(InterfaceDescriptionOption::IfEuIAddr(0x0011_2233_4455_6677)).build();
+    pcapng_writer.add_interface(&interface).unwrap();
+
+    let statistics = InterfaceStatisticsBlock {
+        interface_id: 0,
+        timestamp: 0x0102_0304_0506_0708,
+        options: vec![InterfaceStatisticsOption::IsbStartTime(0x1112_1314_1516_1718), InterfaceStatisticsOption::IsbEndTime(0x2122_2324_2526_2728)],
+    };
+    pcapng_writer.write_pcapng_block(&statistics).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let mut little = vec![];
+    pcap_file::pcapng::transcode(&data[..], &mut little, Endianness::Little).unwrap();
+
+    // The timestamps are stored as their high and low halves, the EUI address as a byte string
+    let isb_start = little.len() - 52;
+    assert_eq!(&little[isb_start + 12..isb_start + 20], &[0x04, 0x03, 0x02, 0x01, 0x08, 0x07, 0x06, 0x05]);
+    assert_eq!(&little[isb_start + 24..isb_start + 32], &[0x14, 0x13, 0x12, 0x11, 0x18, 0x17, 0x16, 0x15]);
+    assert!(little.windows(8).any(|bytes| bytes == [0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77]));
+
+    let mut reader = PcapNgReader::new(&little[..]).unwrap();
+    let interface = reader.next_block().unwrap().unwrap().into_interface_description().unwrap();
+    assert!(interface.options.contains(&InterfaceDescriptionOption::IfEuIAddr(0x0011_2233_4455_6677)));
+
+    let statistics = reader.next_block().unwrap().unwrap().into_interface_statistics().unwrap();
+    assert_eq!(statistics.timestamp, 0x0102_0304_0506_0708);
+    assert_eq!(statistics.start_time(), Some(0x1112_1314_1516_1718));
+    assert_eq!(statistics.end_time(), Some(0x2122_2324_2526_2728));
+}
+
+#[test]
+fn little_endian_statistics_times_and_eui_addr() {
+    // The timestamps are stored as their high and low halves, each one in the section endianness
+    let mut body = vec![];
+    body.extend_from_slice(&0_u32.to_le_bytes());
+    body.extend_from_slice(&0x0102_0304_u32.to_le_bytes());
+    body.extend_from_slice(&0x0506_0708_u32.to_le_bytes());
+    body.extend_from_slice(&[2, 0, 8, 0]);
+    body.extend_from_slice(&0x1112_1314_u32.to_le_bytes());
+    body.extend_from_slice(&0x1516_1718_u32.to_le_bytes());
+    body.extend_from_slice(&[0, 0, 0, 0]);
+
+    let (_, statistics) = InterfaceStatisticsBlock::from_slice::<LittleEndian>(&body).unwrap();
+    assert_eq!(statistics.timestamp, 0x0102_0304_0506_0708);
+    assert_eq!(statistics.start_time(), Some(0x1112_1314_1516_1718));
+
+    let mut written = vec![];
+    statistics.write_to::<LittleEndian, _>(&mut written).unwrap();
+    assert_eq!(written, body);
+
+    // The EUI address is a byte string, whatever the section endianness
+    let mut body = vec![1, 0, 0, 0, 0, 0, 0, 0];
+    body.extend_from_slice(&[7, 0, 8, 0, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77]);
+    body.extend_from_slice(&[0, 0, 0, 0]);
+
+    let (_, interface) = InterfaceDescriptionBlock::from_slice::<LittleEndian>(&body).unwrap();
+    assert_eq!(interface.options, vec![InterfaceDescriptionOption::IfEuIAddr(0x0011_2233_4455_6677)]);
+
+    let mut written = vec![];
+    interface.write_to::<LittleEndian, _>(&mut written).unwrap();
+    assert_eq!(written, body);
+}
+
+#[test]
+fn journal_entries() {
+    let mut body = b"__CURSOR=s=1\nMESSAGE=first\n\nMESSAGE\n".to_vec();
+    body.extend_from_slice(&6_u64.to_le_bytes());
+    body.extend_from_slice(b"a\nb=\0c\n_PID=42\n\0\0");
+    let block = SystemdJournalExportBlock { journal_entry: Cow::Owned(body) };
+
+    let entries = block.entries().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].get("__CURSOR"), Some(&b"s=1"[..]));
+    assert_eq!(entries[0].get("MESSAGE"), Some(&b"first"[..]));
+    assert_eq!(entries[1].get("MESSAGE"), Some(&b"a\nb=\0c"[..]));
+    assert_eq!(entries[1].get("_PID"), Some(&b"42"[..]));
+    assert_eq!(entries[1].fields.len(), 2);
+
+    let block = SystemdJournalExportBlock { journal_entry: Cow::Borrowed(b"MESSAGE\n\x10\0\0\0\0\0\0\0short\n") };
+    assert!(block.entries().next().unwrap().is_err());
+}
+
+#[test]
+fn journal_from_entries() {
+    let entries = [
+        JournalEntry::new().with_field("MESSAGE", "first").with_field("PRIORITY", "6"),
+        JournalEntry::new().with_field("MESSAGE", b"multi\nline").with_field("_PID", "42"),
+    ];
+    let block = SystemdJournalExportBlock::from_entries(&entries).unwrap();
+
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.write_pcapng_block(&block).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let read = reader.next_block().unwrap().unwrap().into_systemd_journal_export().unwrap();
+    let read_entries = read.entries().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(read_entries, entries);
+
+    let invalid = JournalEntry::new().with_field("A=B", "value");
+    assert!(SystemdJournalExportBlock::from_entries(&[invalid]).is_err());
+}
+
+#[test]
+fn tls_keylog_secrets() {
+    let keylog = b"CLIENT_RANDOM 0011 2233\nSERVER_HANDSHAKE_TRAFFIC_SECRET 44 55\n";
+    let secrets = DecryptionSecretsBlock::from_tls_keylog(&keylog[..]).unwrap();
+    assert_eq!(secrets.secrets_type, SecretsType::TlsKeyLog);
+
+    let mut pcapng_writer = PcapNgWriter::with_endianness(Vec::new(), Endianness::Big).unwrap();
+    pcapng_writer.write_pcapng_block(&secrets).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    // The secrets type follows the block header, after the 28 bytes of the section header
+    assert_eq!(&data[36..40], b"TLSK");
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let read = reader.next_block().unwrap().unwrap().into_decryption_secrets().unwrap();
+    assert_eq!(read, secrets);
+    assert_eq!(&read.secrets_data[..], &keylog[..]);
+
+    // A secrets length overflowing with its padding is refused instead of panicking
+    let mut body = b"TLSK".to_vec();
+    body.extend_from_slice(&u32::MAX.to_be_bytes());
+    assert!(DecryptionSecretsBlock::from_slice::<BigEndian>(&body).is_err());
+}
+
+#[test]
+fn typed_secrets() {
+    let keylog = b"LOCAL_STATIC_PRIVATE_KEY = QChaGDXeH3eQsbFAhueUNWFdq9KfpF3yl+eITjZbXEk=\n";
+    let wireguard = DecryptionSecretsBlock::from_wireguard_keylog(&keylog[..]).unwrap();
+    let nwk_key = ZigBeeNwkKey { key: [1; 16], pan_id: 0x1234 };
+    let aps_key = ZigBeeApsKey { key: [2; 16], pan_id: 0x1234, low_short_address: 0x0000, high_short_address: 0x5678 };
+    let nwk = DecryptionSecretsBlock::from_zigbee_nwk_key(&nwk_key);
+    let aps = DecryptionSecretsBlock::from_zigbee_aps_key(&aps_key);
+
+    assert_eq!(u32::from(nwk.secrets_type).to_be_bytes(), *b"ZNWK");
+    assert_eq!(&nwk.secrets_data[16..], &[0x34, 0x12]);
+
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    for block in [&wireguard, &nwk, &aps] {
+        pcapng_writer.write_pcapng_block(block).unwrap();
+    }
+    let data = pcapng_writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let read = reader.next_block().unwrap().unwrap().into_decryption_secrets().unwrap();
+    assert_eq!(read.secrets_type, SecretsType::WireGuardKeyLog);
+    assert_eq!(read.key_log(), Some(std::str::from_utf8(keylog).unwrap()));
+    assert_eq!(read.zigbee_nwk_key(), None);
+
+    let read = reader.next_block().unwrap().unwrap().into_decryption_secrets().unwrap();
+    assert_eq!(read.zigbee_nwk_key(), Some(nwk_key));
+    assert_eq!(read.key_log(), None);
+
+    let read = reader.next_block().unwrap().unwrap().into_decryption_secrets().unwrap();
+    assert_eq!(read.zigbee_aps_key(), Some(aps_key));
+    assert_eq!(read.zigbee_nwk_key(), None);
+}
+
+#[test]
+fn statistics_accumulator() {
+    let isb = |interface_id, timestamp, recv, drop| InterfaceStatisticsBlock {
+        interface_id,
+        timestamp,
+        options: vec![InterfaceStatisticsOption::IsbIfRecv(recv), InterfaceStatisticsOption::IsbIfDrop(drop)],
+    };
+    let idb = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0);
+
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.write_pcapng_block(&idb).unwrap();
+    pcapng_writer.write_pcapng_block(&idb).unwrap();
+    pcapng_writer.write_pcapng_block(&isb(0, 10, 100, 1)).unwrap();
+    pcapng_writer.write_pcapng_block(&isb(1, 10, 50, 0)).unwrap();
+    pcapng_writer.write_pcapng_block(&isb(0, 20, 150, 3)).unwrap();
+    // Restarted capture: the counters start over
+    pcapng_writer.write_pcapng_block(&isb(0, 30, 20, 1)).unwrap();
+    pcapng_writer.write_pcapng_block(&SectionHeaderBlock::default()).unwrap();
+    pcapng_writer.write_pcapng_block(&idb).unwrap();
+    pcapng_writer.write_pcapng_block(&isb(0, 40, 7, 0)).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let mut statistics = StatisticsAccumulator::new();
+    while let Some(block) = reader.next_block() {
+        statistics.add_block(&block.unwrap());
+    }
+
+    let totals = statistics.totals();
+    assert_eq!(totals.len(), 3);
+
+    assert_eq!((totals[0].section, totals[0].interface_id), (0, 0));
+    assert_eq!(totals[0].snapshots, 3);
+    assert_eq!((totals[0].start_time, totals[0].end_time), (Some(10), Some(30)));
+    assert_eq!(totals[0].received, Some(170));
+    assert_eq!(totals[0].dropped, Some(4));
+    assert_eq!(totals[0].accepted, None);
+
+    assert_eq!((totals[1].section, totals[1].interface_id), (0, 1));
+    assert_eq!(totals[1].received, Some(50));
+
+    assert_eq!((totals[2].section, totals[2].interface_id), (1, 0));
+    assert_eq!(totals[2].received, Some(7));
+}
+
+#[test]
+fn info() {
+    let interface = InterfaceDescriptionBlock::builder().name("eth0").tsresol_nanos().build();
+    let packet = |secs, data| EnhancedPacketBlock::builder(InterfaceId(0), data).timestamp(Duration::from_secs(secs)).build(&interface).unwrap();
+    let secrets = DecryptionSecretsBlock::from_tls_keylog(&b"CLIENT_RANDOM 00 11\n"[..]).unwrap();
+
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.write_pcapng_block(&interface).unwrap();
+    pcapng_writer.write_pcapng_block(&packet(20, &[1, 2, 3])).unwrap();
+    pcapng_writer.write_pcapng_block(&packet(10, &[1, 2])).unwrap();
+    pcapng_writer.write_pcapng_block(&secrets).unwrap();
+    pcapng_writer.write_pcapng_block(&SectionHeaderBlock::default()).unwrap();
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    pcapng_writer.write_pcapng_block(&SimplePacketBlock { original_len: 4, data: Cow::Borrowed(&[1, 2, 3, 4]) }).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let info = capture_info(&data[..]).unwrap();
+    assert_eq!(info.format, CaptureFormat::PcapNg);
+    assert_eq!(info.endianness, Endianness::native());
+    assert_eq!(info.section_count, 2);
+    assert_eq!(info.interfaces.len(), 2);
+    assert_eq!(info.interfaces[0].name.as_deref(), Some("eth0"));
+    assert_eq!(info.interfaces[0].tsresol, 9);
+    assert_eq!(info.interfaces[0].packet_count, 2);
+    assert_eq!((info.interfaces[1].section, info.interfaces[1].linktype), (1, DataLink::RAW));
+    assert_eq!(info.interfaces[1].packet_count, 1);
+    assert_eq!(info.packet_count, 3);
+    assert_eq!(info.captured_bytes, 9);
+    assert_eq!(info.duration(), Some(Duration::from_secs(10)));
+    assert!(info.has_secrets);
+    assert!(!info.has_comments);
+}
+
+#[test]
+fn push_parser() {
+    let interface = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0);
+    let packet = EnhancedPacketBlock::builder(InterfaceId(0), &[1, 2, 3, 4, 5]).build(&interface).unwrap();
+
+    for endianness in [Endianness::Big, Endianness::Little] {
+        let mut pcapng_writer = PcapNgWriter::with_endianness(Vec::new(), endianness).unwrap();
+        pcapng_writer.write_pcapng_block(&interface).unwrap();
+        pcapng_writer.write_pcapng_block(&packet).unwrap();
+        let data = pcapng_writer.into_inner();
+
+        // Byte by byte
+        let mut parser = PcapNgPushParser::new();
+        let mut blocks = vec![];
+        for byte in &data {
+            for event in parser.feed(&[*byte]) {
+                match event {
+                    PcapNgEvent::Block(block) => blocks.push(block),
+                    PcapNgEvent::NeedMoreData(needed) => assert!(needed >= 1),
+                    PcapNgEvent::Error(err) => panic!("{err}"),
+                }
+            }
+        }
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[2].as_enhanced_packet(), Some(&packet));
+        parser.finish().unwrap();
+
+        // The needed length is the remaining length of the block once its header is known
+        let mut parser = PcapNgPushParser::new();
+        let events = parser.feed(&data[..30]);
+        assert!(matches!(events[..], [PcapNgEvent::Block(Block::SectionHeader(_)), PcapNgEvent::NeedMoreData(6)]));
+        let events = parser.feed(&data[30..40]);
+        assert!(matches!(events[..], [PcapNgEvent::NeedMoreData(8)]));
+        assert_eq!(parser.buffered_len(), 12);
+        assert!(parser.finish().is_err());
+    }
+
+    let mut parser = PcapNgPushParser::new();
+    let events = parser.feed(&[0_u8; 28]);
+    assert!(matches!(events[..], [PcapNgEvent::Error(_)]));
+}
+
+#[test]
+fn buffered_stream() {
+    let mut pcapng_writer = PcapNgWriter::new(std::io::BufWriter::new(Vec::new())).unwrap();
+    let interface = pcapng_writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0)).unwrap();
+    pcapng_writer.write_packet(interface, UNIX_EPOCH, &[1, 2, 3]).unwrap();
+    assert!(pcapng_writer.get_ref().get_ref().is_empty());
+
+    pcapng_writer.flush().unwrap();
+    let data = pcapng_writer.get_ref().get_ref().clone();
+
+    // The section length is unknown when streaming
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    assert_eq!(reader.section().section_length, -1);
+    assert!(reader.next_block().unwrap().unwrap().as_interface_description().is_some());
+    assert!(reader.next_block().unwrap().unwrap().as_enhanced_packet().is_some());
+}
+
+#[test]
+fn follow_reader() {
+    // Returns the chunks one by one, an empty chunk being a temporary end of file
+    struct Chunks(std::collections::VecDeque<Vec<u8>>);
+
+    impl Read for Chunks {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let Some(chunk) = self.0.front_mut() else { return Ok(0) };
+            let len = chunk.len().min(buf.len());
+            buf[..len].copy_from_slice(&chunk[..len]);
+            chunk.drain(..len);
+            if chunk.is_empty() {
+                self.0.pop_front();
+            }
+            Ok(len)
+        }
+    }
+
+    let interface = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0);
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.write_pcapng_block(&interface).unwrap();
+    let header_len = pcapng_writer.get_ref().len();
+    pcapng_writer.write_packet(InterfaceId(0), UNIX_EPOCH, &[1, 2, 3]).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let chunks = vec![data[..header_len].to_vec(), vec![], data[header_len..header_len + 10].to_vec(), vec![], data[header_len + 10..].to_vec()];
+    let calls = std::rc::Rc::new(std::cell::Cell::new(0));
+    let calls_clone = calls.clone();
+    let reader = FollowReader::with_wait(Chunks(chunks.into()), move |attempt| {
+        calls_clone.set(calls_clone.get() + 1);
+        attempt < 3
+    });
+
+    let mut reader = PcapNgReader::new(reader).unwrap();
+    assert!(reader.next_block().unwrap().unwrap().as_interface_description().is_some());
+    assert!(reader.next_block().unwrap().unwrap().as_enhanced_packet().is_some());
+    assert!(reader.next_block().is_none());
+    // Once for each empty chunk, then 3 times at the real end
+    assert_eq!(calls.get(), 5);
+
+    let start = std::time::Instant::now();
+    let mut reader = FollowReader::with_backoff(&[][..], Duration::from_millis(1), Duration::from_millis(5), Some(Duration::from_millis(20)));
+    assert_eq!(reader.read(&mut [0; 4]).unwrap(), 0);
+    assert!(start.elapsed() >= Duration::from_millis(20));
+}
+
+#[test]
+fn rotating_writer() {
+    let dir = std::env::temp_dir().join(format!("pcap_file_rotating_writer_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let config = RotationConfig { max_file_size: Some(100), max_files: Some(2), ..Default::default() };
+    let mut writer = RotatingPcapNgWriter::new(dir.join("capture.pcapng"), config).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    // 28 bytes of section header and 20 of interface, then 48 bytes per packet: 2 packets per file
+    for i in 0..5_u8 {
+        writer.write_packet(interface, UNIX_EPOCH + Duration::from_secs(i as u64), &[i; 16]).unwrap();
+    }
+    writer.flush().unwrap();
+
+    let files = writer.files().map(|path| path.to_path_buf()).collect::<Vec<_>>();
+    assert_eq!(files, vec![dir.join("capture_00001.pcapng"), dir.join("capture_00002.pcapng")]);
+    assert!(!dir.join("capture_00000.pcapng").exists());
+
+    let mut packets = vec![];
+    for file in &files {
+        let data = std::fs::read(file).unwrap();
+        let mut reader = PcapNgReader::new(&data[..]).unwrap();
+        assert!(reader.next_block().unwrap().unwrap().as_interface_description().is_some());
+        while let Some(block) = reader.next_block() {
+            packets.push(block.unwrap().into_enhanced_packet().unwrap().data[0]);
+        }
+    }
+    assert_eq!(packets, vec![2, 3, 4]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn rotating_writer_interval() {
+    let dir = std::env::temp_dir().join(format!("pcap_file_rotating_writer_interval_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let config = RotationConfig { interval: Some(Duration::from_secs(3600)), ..Default::default() };
+    let mut writer = RotatingPcapNgWriter::new(dir.join("capture-%Y-%m-%dT%H:%M.pcapng"), config).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    // 2024-02-29T23:30, 23:59 and 2024-03-01T00:00
+    for secs in [1709249400, 1709251140, 1709251200] {
+        writer.write_packet(interface, UNIX_EPOCH + Duration::from_secs(secs), &[0; 16]).unwrap();
+    }
+    writer.flush().unwrap();
+
+    let files = writer.files().map(|path| path.to_path_buf()).collect::<Vec<_>>();
+    assert_eq!(files, vec![dir.join("capture-2024-02-29T23:00.pcapng"), dir.join("capture-2024-03-01T00:00.pcapng")]);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+    let counts = files
+        .iter()
+        .map(|file| {
+            let data = std::fs::read(file).unwrap();
+            let mut reader = PcapNgReader::new(&data[..]).unwrap();
+            let mut count = 0;
+            while let Some(block) = reader.next_block() {
+                count += block.unwrap().as_enhanced_packet().is_some() as usize;
+            }
+            count
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(counts, vec![2, 1]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn ordered_reader() {
+    let mut writer = PcapNgWriter::new(vec![]).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    for secs in [1, 3, 2, 4, 0] {
+        writer.write_packet(interface, UNIX_EPOCH + Duration::from_secs(secs), &[secs as u8]).unwrap();
+    }
+    let data = writer.into_inner();
+
+    for (window, expected, out_of_order) in [(0, [1, 3, 2, 4, 0], 2), (1, [1, 2, 3, 0, 4], 1), (4, [0, 1, 2, 3, 4], 0)] {
+        let mut reader = OrderedPcapNgReader::new(PcapNgReader::new(&data[..]).unwrap(), window);
+        assert!(reader.next_block().unwrap().unwrap().as_interface_description().is_some());
+
+        let mut packets = vec![];
+        while let Some(block) = reader.next_block() {
+            packets.push(block.unwrap().into_enhanced_packet().unwrap().data[0]);
+        }
+        assert_eq!(packets, expected);
+        assert_eq!(reader.out_of_order_count(), out_of_order);
+    }
+}
+
+#[test]
+fn sorting_writer() {
+    let mut writer = SortingPcapNgWriter::new(PcapNgWriter::new(vec![]).unwrap(), 1);
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    for secs in [2, 0, 3, 1, 5, 4] {
+        writer.write_packet(interface, UNIX_EPOCH + Duration::from_secs(secs), &[secs as u8]).unwrap();
+    }
+    assert_eq!(writer.buffered_len(), 1);
+    let data = writer.into_inner().unwrap().into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    assert!(reader.next_block().unwrap().unwrap().as_interface_description().is_some());
+    let mut packets = vec![];
+    while let Some(block) = reader.next_block() {
+        packets.push(block.unwrap().into_enhanced_packet().unwrap().data[0]);
+    }
+    // 1 arrives after 2 had to leave the buffer
+    assert_eq!(packets, vec![0, 2, 1, 3, 4, 5]);
+}
+
+#[test]
+fn reader_into_iter() {
+    let mut writer = PcapNgWriter::new(vec![]).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    for i in 0..3_u8 {
+        writer.write_packet(interface, UNIX_EPOCH, &[i]).unwrap();
+    }
+    let data = writer.into_inner();
+
+    let reader = PcapNgReader::new(std::io::Cursor::new(data)).unwrap();
+    let blocks = std::thread::spawn(move || reader.into_iter().collect::<Result<Vec<_>, _>>().unwrap()).join().unwrap();
+
+    assert_eq!(blocks.len(), 4);
+    assert_eq!(blocks[3].as_enhanced_packet().unwrap().data.as_ref(), &[2]);
+}
+
+#[test]
+fn reader_blocks() {
+    let mut writer = PcapNgWriter::new(vec![]).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    for i in 0..3_u8 {
+        writer.write_packet(interface, UNIX_EPOCH, &[i]).unwrap();
+    }
+    let data = writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let packets = reader
+        .blocks()
+        .filter_map(|block| block.unwrap().into_enhanced_packet())
+        .take_while(|epb| epb.data[0] < 2)
+        .collect::<Vec<_>>();
+
+    assert_eq!(packets.len(), 2);
+    assert_eq!(reader.interfaces().len(), 1);
+    assert!(reader.next_block().is_none());
+}
+
+#[test]
+fn read_blocks() {
+    let mut writer = PcapNgWriter::new(vec![]).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    for i in 0..5_u8 {
+        writer.write_packet(interface, UNIX_EPOCH, &[i]).unwrap();
+    }
+    let mut data = writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let mut batch = vec![];
+    assert_eq!(reader.read_blocks(&mut batch, 4).unwrap(), 4);
+    assert!(batch[0].as_interface_description().is_some());
+
+    // The blocks are owned and can be sent to another thread
+    let handle = std::thread::spawn(move || batch.iter().filter_map(|block| block.as_enhanced_packet()).count());
+    assert_eq!(handle.join().unwrap(), 3);
+
+    let mut batch = vec![];
+    assert_eq!(reader.read_blocks(&mut batch, 4).unwrap(), 2);
+    assert_eq!(batch[1].as_enhanced_packet().unwrap().data.as_ref(), &[4]);
+    assert_eq!(reader.read_blocks(&mut batch, 4).unwrap(), 0);
+    assert_eq!(batch.len(), 2);
+
+    // The blocks before an invalid one are kept
+    let len = data.len();
+    data[len - 4] ^= 0xFF;
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let mut batch = vec![];
+    assert!(reader.read_blocks(&mut batch, 10).is_err());
+    assert_eq!(batch.len(), 5);
+}
+
+#[test]
+fn read_blocks_in() {
+    let mut writer = PcapNgWriter::new(vec![]).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    for i in 0..4_u8 {
+        writer.write_packet(interface, UNIX_EPOCH, &[i]).unwrap();
+    }
+    writer.write_pcapng_block(&SectionHeaderBlock { endianness: Endianness::Big, ..Default::default() }).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    for i in 4..8_u8 {
+        writer.write_packet(interface, UNIX_EPOCH, &[i]).unwrap();
+    }
+    let mut data = writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let expected = reader.blocks().map(|block| block.unwrap()).collect::<Vec<_>>();
+
+    // The blocks of a batch are parsed as they are read, the second section starting in the middle of one
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let mut arena = BlockArena::with_capacity(3, 1024);
+    let mut lens = vec![];
+    let mut actual = vec![];
+    loop {
+        let blocks = reader.read_blocks_in(&mut arena, 3).unwrap();
+        if blocks.is_empty() {
+            break;
+        }
+        lens.push(blocks.len());
+        actual.extend(blocks.iter().map(|block| block.clone().into_owned()));
+    }
+    assert_eq!(lens, [3, 3, 3, 2]);
+    assert_eq!(actual, expected);
+    assert_eq!(reader.stats().blocks, 1 + 11);
+    assert!(arena.is_empty());
+
+    // The blocks before an invalid one are returned, its error by the next call
+    let len = data.len();
+    data[len - 4] ^= 0xFF;
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    assert_eq!(reader.read_blocks_in(&mut arena, 20).unwrap(), &expected[..10]);
+    assert_eq!(reader.interfaces().len(), 1);
+    assert!(reader.read_blocks_in(&mut arena, 20).is_err());
+}
+
+#[test]
+fn reader_packets() {
+    let mut writer = PcapNgWriter::new(vec![]).unwrap();
+    let eth0 = writer.add_interface(&InterfaceDescriptionBlock::builder().name("eth0").tsresol_nanos().build()).unwrap();
+    writer.write_packet(eth0, UNIX_EPOCH + Duration::new(1, 5), &[1, 2]).unwrap();
+    writer.write_block(&SimplePacketBlock { original_len: 1, data: Cow::Borrowed(&[3]) }.into_block()).unwrap();
+    writer.write_block(&SectionHeaderBlock::default().into_block()).unwrap();
+    let raw = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    writer.write_packet(raw, UNIX_EPOCH + Duration::from_secs(2), &[4]).unwrap();
+    let data = writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let packets = reader.packets().collect::<Result<Vec<_>, _>>().unwrap();
+
+    assert_eq!(packets.len(), 3);
+    assert_eq!(packets[0].interface_name.as_deref(), Some("eth0"));
+    assert_eq!(packets[0].linktype, DataLink::ETHERNET);
+    assert_eq!(packets[0].timestamp, Some(Duration::new(1, 5)));
+    assert_eq!(&packets[0].data[..], &[1, 2]);
+    assert_eq!((packets[1].timestamp, &packets[1].data[..]), (None, &[3][..]));
+    assert_eq!((packets[2].interface_id, packets[2].linktype, packets[2].interface_name.clone()), (0, DataLink::RAW, None));
+    assert_eq!(packets[2].timestamp, Some(Duration::from_secs(2)));
+    assert_eq!(reader.interfaces().len(), 1);
+
+    let interfaces = [InterfaceDescriptionBlock::builder().name("eth0").build()];
+    let block = SimplePacketBlock { original_len: 1, data: Cow::Borrowed(&[3]) }.into_block();
+    let packet = PcapNgPacket::from_block(block, &interfaces).unwrap().unwrap();
+    assert!(matches!(packet.interface_name, Some(Cow::Borrowed("eth0"))));
+}
+
+#[test]
+fn next_packet() {
+    let mut writer = PcapNgWriter::new(vec![]).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::builder().snaplen(1500).tsresol(3).build()).unwrap();
+    let mut flags = PacketFlags::default();
+    flags.set_direction(PacketDirection::Inbound);
+    let idb = writer.interfaces()[0].clone();
+    let packet = EnhancedPacketBlock::builder(interface, &[1, 2, 3]).timestamp(Duration::from_millis(1500)).flags(flags).build(&idb).unwrap();
+    writer.write_block(&packet.into_block()).unwrap();
+    writer.write_block(&SectionHeaderBlock::default().into_block()).unwrap();
+    let data = writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let packet = reader.next_packet().unwrap().unwrap();
+    assert_eq!((packet.snaplen, packet.timestamp), (1500, Some(Duration::from_millis(1500))));
+    assert_eq!(packet.flags.unwrap().direction(), PacketDirection::Inbound);
+    assert_eq!(&packet.data[..], &[1, 2, 3]);
+
+    assert!(reader.next_packet().is_none());
+    assert!(reader.interfaces().is_empty());
+    assert_eq!(reader.stats().blocks, 4);
+}
+
+#[test]
+fn peek_block() {
+    let mut writer = PcapNgWriter::new(vec![]).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    writer.write_packet(interface, UNIX_EPOCH, &[1, 2, 3]).unwrap();
+    writer.write_block(&SectionHeaderBlock::default().into_block()).unwrap();
+    let data = writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    assert!(matches!(reader.peek_block().unwrap().unwrap(), Block::InterfaceDescription(_)));
+    assert!(reader.interfaces().is_empty());
+    assert!(matches!(reader.next_block().unwrap().unwrap(), Block::InterfaceDescription(_)));
+    assert_eq!(reader.interfaces().len(), 1);
+
+    let peeked = reader.peek_block().unwrap().unwrap().into_owned();
+    assert_eq!(reader.next_block().unwrap().unwrap(), peeked);
+
+    assert!(matches!(reader.peek_block().unwrap().unwrap(), Block::SectionHeader(_)));
+    assert_eq!(reader.interfaces().len(), 1);
+    assert!(matches!(reader.next_block().unwrap().unwrap(), Block::SectionHeader(_)));
+    assert!(reader.interfaces().is_empty());
+
+    assert!(reader.peek_block().is_none());
+}
+
+#[test]
+fn count_packets() {
+    let mut writer = PcapNgWriter::new(vec![]).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    for i in 0..3_u8 {
+        writer.write_packet(interface, UNIX_EPOCH, &[i; 10]).unwrap();
+    }
+    writer.write_block(&SimplePacketBlock { original_len: 4, data: Cow::Borrowed(&[1, 2, 3, 4]) }.into_block()).unwrap();
+    let data = writer.into_inner();
+
+    // Second section in big endian
+    let section = SectionHeaderBlock { endianness: Endianness::Big, ..Default::default() };
+    let mut writer = PcapNgWriter::with_section_header(data, section).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    writer.write_packet(interface, UNIX_EPOCH, &[4; 10]).unwrap();
+    let data = writer.into_inner();
+
+    let mut reader = PcapNgReader::new(std::io::Cursor::new(&data)).unwrap();
+    assert_eq!(reader.count_packets().unwrap(), 5);
+    assert_eq!(reader.blocks().count(), 8);
+
+    // The blocks straddle the internal buffer and the inner reader
+    let mut reader = PcapNgReader::with_capacity(std::io::Cursor::new(&data), 64).unwrap();
+    reader.next_block().unwrap().unwrap();
+    reader.next_block().unwrap().unwrap();
+    assert_eq!(reader.count_packets().unwrap(), 4);
+    assert_eq!(reader.blocks().filter(|block| block.as_ref().unwrap().as_enhanced_packet().is_some()).count(), 3);
+}
+
+#[test]
+fn scan_blocks() {
+    let mut writer = PcapNgWriter::new(vec![]).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    writer.write_packet(interface, UNIX_EPOCH, &[1, 2, 3]).unwrap();
+    writer.write_packet(interface, UNIX_EPOCH, &[1, 2, 3, 4, 5]).unwrap();
+    let data = writer.into_inner();
+
+    let mut reader = PcapNgReader::with_capacity(std::io::Cursor::new(&data), 64).unwrap();
+    let scanned = reader.scan_blocks().unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+
+    let mut positions = vec![];
+    while let Some(res) = reader.next_block_with_position() {
+        positions.push(res.unwrap().1);
+    }
+    let types = [INTERFACE_DESCRIPTION_BLOCK, ENHANCED_PACKET_BLOCK, ENHANCED_PACKET_BLOCK];
+    let expected = types.into_iter().zip(positions).map(|(type_, position)| ScannedBlock { type_, position }).collect::<Vec<_>>();
+    assert_eq!(scanned, expected);
+
+    // Truncated header
+    let mut reader = PcapNgReader::new(std::io::Cursor::new(&data[..expected[2].position.offset as usize + 6])).unwrap();
+    let scanned = reader.scan_blocks().unwrap().collect::<Vec<_>>();
+    assert_eq!(scanned.len(), 3);
+    assert!(scanned[1].is_ok());
+    assert!(scanned[2].is_err());
+}
+
+#[test]
+fn io_stats() {
+    let mut writer = PcapNgWriter::new(vec![]).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    for i in 0..3_u8 {
+        writer.write_packet(interface, UNIX_EPOCH, &[i; 10]).unwrap();
+    }
+    let packet = EnhancedPacketBlock { interface_id: 1, timestamp: Duration::ZERO, original_len: 1, data: Cow::Borrowed(&[0]), options: vec![] };
+    assert!(writer.write_block(&packet.into_block()).is_err());
+    let stats = writer.stats();
+    let mut data = writer.into_inner();
+
+    assert_eq!(stats, IoStats { blocks: 5, packets: 3, bytes: data.len() as u64, errors: 1, skipped: 0, repaired: 0, warnings: 0 });
+
+    // Invalid block length
+    data.extend_from_slice(&[1, 0, 0, 0, 13, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    assert_eq!(reader.stats(), IoStats { blocks: 1, packets: 0, bytes: 28, errors: 0, skipped: 0, repaired: 0, warnings: 0 });
+    while let Some(Ok(_)) = reader.next_block() {}
+    assert_eq!(reader.stats(), IoStats { blocks: 5, packets: 3, bytes: stats.bytes, errors: 1, skipped: 0, repaired: 0, warnings: 0 });
+}
+
+#[test]
+fn progress_callback() {
+    let mut writer = PcapNgWriter::new(vec![]).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    for i in 0..10_u8 {
+        writer.write_packet(interface, UNIX_EPOCH, &[i; 20]).unwrap();
+    }
+    let data = writer.into_inner();
+    let total = data.len() as u64;
+
+    let progress = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let progress_clone = progress.clone();
+    reader.set_progress_callback(100, Some(total), move |progress| progress_clone.lock().unwrap().push(progress));
+    while let Some(block) = reader.next_block() {
+        block.unwrap();
+    }
+
+    // 28 bytes of header, 20 of interface and 52 per packet
+    let progress = progress.lock().unwrap();
+    assert_eq!(progress.len(), 5);
+    assert_eq!(progress[0].stats.bytes, 152);
+    assert_eq!(progress[0].stats.packets, 2);
+    assert!(progress.windows(2).all(|w| w[1].stats.bytes - w[0].stats.bytes >= 100));
+    assert_eq!(progress[4].stats.bytes, total);
+    assert_eq!(progress[4].fraction(), Some(1.0));
+}
+
+#[test]
+fn reader_capacity() {
+    struct CountingReader<'a> {
+        data: &'a [u8],
+        reads: usize,
+    }
+
+    impl Read for CountingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.reads += 1;
+            self.data.read(buf)
+        }
+    }
+
+    let mut writer = PcapNgWriter::new(vec![]).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    for _ in 0..100 {
+        writer.write_packet(interface, UNIX_EPOCH, &[0; 16]).unwrap();
+    }
+    writer.write_packet(interface, UNIX_EPOCH, &[0; 2000]).unwrap();
+    let data = writer.into_inner();
+
+    // 1 SHB, 1 IDB and 100 small packets of 48 bytes: the reads fill the buffer instead of following the blocks
+    let mut reader = PcapNgReader::with_capacity(CountingReader { data: &data, reads: 0 }, 1024).unwrap();
+    for _ in 0..101 {
+        reader.next_block().unwrap().unwrap();
+    }
+    assert!(reader.get_ref().reads <= 6);
+
+    // The last packet is bigger than the buffer
+    assert!(matches!(reader.next_block(), Some(Err(PcapError::IoError(_)))));
+}
+
+#[test]
+fn read_block_into() {
+    let mut writer = PcapNgWriter::new(vec![]).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    for i in 0..3_u8 {
+        writer.write_packet(interface, UNIX_EPOCH, &[i; 10]).unwrap();
+    }
+    let data = writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let mut expected = PcapNgReader::new(&data[..]).unwrap();
+    let mut buf = Vec::with_capacity(1024);
+    let capacity = buf.capacity();
+
+    let mut count = 0;
+    while let Some(block) = reader.read_block_into(&mut buf) {
+        assert_eq!(block.unwrap(), expected.next_block().unwrap().unwrap());
+        count += 1;
+    }
+    assert_eq!(count, 4);
+    assert_eq!(buf.capacity(), capacity);
+    assert_eq!(reader.interfaces().len(), 1);
+}
+
+#[test]
+fn one_write_per_block() {
+    let mut writer = PcapNgWriter::new(CountingWriter::default()).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    for i in 0..10_u8 {
+        writer.write_packet(interface, UNIX_EPOCH, &[i; 13]).unwrap();
+    }
+    let counting_writer = writer.into_inner();
+    assert_eq!(counting_writer.writes, 12);
+
+    let mut reader = PcapNgReader::new(&counting_writer.data[..]).unwrap();
+    let mut count = 0;
+    while let Some(block) = reader.next_block() {
+        block.unwrap();
+        count += 1;
+    }
+    assert_eq!(count, 11);
+}
+
+#[test]
+fn large_block_round_trip() {
+    let data = vec![7_u8; 1001];
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::from_secs(1),
+        original_len: 1001,
+        data: Cow::Borrowed(&data),
+        options: vec![EnhancedPacketOption::Comment(Cow::Borrowed("after the data"))],
+    };
+    let long_comment = "x".repeat(300);
+    let section = SectionHeaderBlock::builder().comment(long_comment).build().unwrap();
+
+    let mut bytes = vec![];
+    for block in [section.into_block(), InterfaceDescriptionBlock::new(DataLink::RAW, 0).into_block(), packet.clone().into_block()] {
+        block.write_to::<BigEndian, _>(&mut bytes).unwrap();
+    }
+
+    let mut reader = PcapNgReader::new(&bytes[..]).unwrap();
+    assert_eq!(reader.section().options.len(), 1);
+    reader.next_block().unwrap().unwrap();
+    assert_eq!(reader.next_block().unwrap().unwrap(), packet.into_block());
+    assert!(reader.next_block().is_none());
+}
+
+#[test]
+fn scan_boundaries() {
+    let mut writer = PcapNgWriter::with_endianness(vec![], Endianness::Big).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    for i in 0..100_u8 {
+        writer.write_packet(interface, UNIX_EPOCH, &[i; 20]).unwrap();
+    }
+    writer.write_block(&SectionHeaderBlock::default().into_block()).unwrap();
+    writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0)).unwrap();
+    let data = writer.into_inner();
+
+    // SHB of 28 bytes, IDB of 20 bytes and EPBs of 52 bytes
+    let second_section = 28 + 20 + 100 * 52;
+    assert_eq!(pcap_file::pcapng::find_section_header(&data), Some(0));
+    assert_eq!(pcap_file::pcapng::find_section_header(&data[1..]), Some(second_section - 1));
+    assert_eq!(pcap_file::pcapng::find_block_boundary(&data[200..], Endianness::Big), Some(48 + 3 * 52 - 200));
+    assert_eq!(pcap_file::pcapng::find_block_boundary(&data[200..], Endianness::Little), None);
+
+    let mut cursor = std::io::Cursor::new(&data);
+    assert_eq!(pcap_file::pcapng::seek_to_section_header(&mut cursor, 1).unwrap(), Some(second_section as u64));
+    let mut reader = PcapNgReader::new(cursor).unwrap();
+    assert_eq!(reader.next_block().unwrap().unwrap().into_interface_description().unwrap().linktype, DataLink::ETHERNET);
+}
+
+#[test]
+fn writer_buffer_capacity() {
+    let mut writer = PcapNgWriter::new(CountingWriter::default()).unwrap();
+    writer.set_buffer_capacity(1024).unwrap();
+    assert_eq!(writer.buffer_capacity(), 1024);
+
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    for i in 0..10_u8 {
+        writer.write_packet(interface, UNIX_EPOCH, &[i; 13]).unwrap();
+    }
+    // Only the section header has been written
+    assert_eq!(writer.get_ref().writes, 1);
+    assert_eq!(writer.get_ref().data.len(), 28);
+
+    writer.flush().unwrap();
+    assert_eq!(writer.get_ref().writes, 2);
+
+    // Bigger than the buffer, written directly
+    writer.write_packet(interface, UNIX_EPOCH, &[0; 2000]).unwrap();
+    assert_eq!(writer.get_ref().writes, 3);
+
+    writer.write_packet(interface, UNIX_EPOCH, &[0; 10]).unwrap();
+    let counting_writer = writer.into_inner();
+    assert_eq!(counting_writer.writes, 4);
+
+    let mut reader = PcapNgReader::new(&counting_writer.data[..]).unwrap();
+    let mut count = 0;
+    while let Some(block) = reader.next_block() {
+        block.unwrap();
+        count += 1;
+    }
+    assert_eq!(count, 13);
+}
+
+#[test]
+fn raw_block_parsed() {
+    let mut writer = PcapNgWriter::with_endianness(vec![], Endianness::Big).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    writer.write_packet(interface, UNIX_EPOCH, &[1, 2, 3]).unwrap();
+    let data = writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    reader.next_block().unwrap().unwrap();
+    let raw_block = reader.next_raw_block().unwrap().unwrap().into_owned();
+
+    let block = raw_block.parsed::<BigEndian>().unwrap();
+    let packet = block.as_enhanced_packet().unwrap();
+    assert!(matches!(packet.data, Cow::Borrowed(_)));
+    assert!(raw_block.body.as_ptr_range().contains(&packet.data.as_ptr()));
+
+    let owned = raw_block.parsed_owned::<BigEndian>().unwrap();
+    drop(raw_block);
+    assert_eq!(owned.into_enhanced_packet().unwrap().data.as_ref(), &[1, 2, 3]);
+}
+
+#[test]
+fn options_borrow_source() {
+    use pcap_file::pcapng::blocks::name_resolution::{Ipv4Record, NameResolutionBlock, Record};
+
+    let mut writer = PcapNgWriter::new(vec![]).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::builder().linktype(DataLink::RAW).name("eth0").comment("uplink").build()).unwrap();
+    let record = Ipv4Record { ip_addr: Cow::Borrowed(&[10, 0, 0, 1]), names: vec![Cow::Borrowed("host"), Cow::Borrowed("host.lan")] };
+    writer.write_pcapng_block(&NameResolutionBlock { records: vec![Record::Ipv4(record), Record::End], options: vec![] }).unwrap();
+    let mut packet = EnhancedPacketBlock::builder(interface, &[1, 2, 3]).build(&writer.interfaces()[0]).unwrap();
+    packet.options.push(EnhancedPacketOption::Comment(Cow::Borrowed("first")));
+    writer.write_pcapng_block(&packet).unwrap();
+    let data = writer.into_inner();
+
+    // Parsing a slice, e.g. of a memory-mapped file, doesn't allocate any option value or name
+    let (mut src, mut parser) = PcapNgParser::new(&data).unwrap();
+    while !src.is_empty() {
+        let (rem, block) = parser.next_block(src).unwrap();
+        match block {
+            Block::InterfaceDescription(idb) => {
+                for opt in idb.options {
+                    assert!(matches!(opt, InterfaceDescriptionOption::IfName(Cow::Borrowed(_)) | InterfaceDescriptionOption::Comment(Cow::Borrowed(_))));
+                }
+            },
+            Block::NameResolution(nrb) => match &nrb.records[0] {
+                Record::Ipv4(record) => {
+                    assert!(matches!(record.ip_addr, Cow::Borrowed(_)));
+                    assert!(record.names.iter().all(|name| matches!(name, Cow::Borrowed(_))));
+                },
+                _ => panic!("Not an Ipv4 record"),
+            },
+            Block::EnhancedPacket(epb) => {
+                assert!(matches!(epb.data, Cow::Borrowed(_)));
+                assert!(matches!(epb.options[0], EnhancedPacketOption::Comment(Cow::Borrowed("first"))));
+            },
+            _ => panic!("Unexpected block"),
+        }
+        src = rem;
+    }
+}
+
+#[test]
+fn empty_options_dont_allocate() {
+    let mut writer = PcapNgWriter::new(vec![]).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    writer.write_packet(interface, UNIX_EPOCH, &[1, 2, 3]).unwrap();
+    let data = writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    assert_eq!(reader.section().options.capacity(), 0);
+    while let Some(block) = reader.next_block() {
+        match block.unwrap() {
+            Block::InterfaceDescription(idb) => assert_eq!(idb.options.capacity(), 0),
+            Block::EnhancedPacket(epb) => assert_eq!(epb.options.capacity(), 0),
+            _ => panic!("Unexpected block"),
+        }
+    }
+}
+
+#[test]
+fn lazy_options() {
+    let interface = InterfaceDescriptionBlock::new(DataLink::RAW, 0);
+    let packet = EnhancedPacketBlock::builder(InterfaceId(0), &[1, 2, 3])
+        .flags(PacketFlags::from_bits(1))
+        .comment("lazy")
+        .build(&interface)
+        .unwrap();
+    let mut body = vec![];
+    packet.write_to::<BigEndian, _>(&mut body).unwrap();
+
+    let (lazy, options) = EnhancedPacketBlock::from_slice_lazy::<BigEndian>(&body).unwrap();
+    assert_eq!(lazy.options.capacity(), 0);
+    let options = options.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(EnhancedPacketBlock { options, ..lazy }, packet);
+
+    // Without options
+    let (_, mut options) = EnhancedPacketBlock::from_slice_lazy::<BigEndian>(&body[..20 + 4]).unwrap();
+    assert!(options.next().is_none());
+
+    // The iteration stops after an invalid option, the previous ones being returned
+    let mut options = EnhancedPacketBlock::from_slice_lazy::<BigEndian>(&body[..body.len() - 4]).unwrap().1;
+    assert!(options.next().unwrap().is_ok());
+    assert!(options.next().unwrap().is_ok());
+    assert!(options.next().unwrap().is_err());
+    assert!(options.next().is_none());
+}
+
+#[test]
+fn hone_blocks() {
+    let machine = HoneMachineInfoBlock { options: vec![HoneMachineInfoOption::Comment("sensor".into())] };
+    let connection = HoneConnectionEventBlock {
+        connection_id: 7,
+        process_id: 42,
+        timestamp: 0x0001_0002_0003_0005,
+        options: vec![HoneConnectionEventOption::Comment("opened".into()), HoneConnectionEventOption::Event(HoneEvent::End)],
+    };
+
+    for endianness in [Endianness::Big, Endianness::Little] {
+        let section = SectionHeaderBlock { endianness, ..Default::default() };
+        let mut pcapng_writer = PcapNgWriter::with_section_header(Vec::new(), section).unwrap();
+        pcapng_writer.write_pcapng_block(&machine).unwrap();
+        pcapng_writer.write_pcapng_block(&connection).unwrap();
+        let data = pcapng_writer.into_inner();
+
+        let mut reader = PcapNgReader::new(&data[..]).unwrap();
+        let read = reader.next_block().unwrap().unwrap().into_hone_machine_info().unwrap();
+        assert_eq!(read, machine);
+
+        let read = reader.next_block().unwrap().unwrap().into_hone_connection_event().unwrap();
+        assert_eq!(read, connection);
+        assert_eq!(read.event(), Some(HoneEvent::End));
+        assert!(reader.next_block().is_none());
+    }
+
+    // The options which aren't modeled are written back unchanged
+    let mut data = PcapNgWriter::with_endianness(Vec::new(), Endianness::Big).unwrap().into_inner();
+    data.extend_from_slice(&[0, 0, 1, 1, 0, 0, 0, 24, 0, 2, 0, 4, 1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 24]);
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let block = reader.next_block().unwrap().unwrap();
+    assert!(matches!(block.as_hone_machine_info().unwrap().options[..], [HoneMachineInfoOption::Unknown(_)]));
+
+    let mut pcapng_writer = PcapNgWriter::with_endianness(Vec::new(), Endianness::Big).unwrap();
+    pcapng_writer.write_block(&block).unwrap();
+    assert_eq!(pcapng_writer.into_inner(), data);
+}
+
+#[test]
+fn simple_packet_snaplen() {
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    let packet = SimplePacketBlock { original_len: 6, data: Cow::Borrowed(&[1, 2, 3, 4, 5]) };
+    assert!(pcapng_writer.write_pcapng_block(&packet).is_err());
+
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 4)).unwrap();
+    assert!(pcapng_writer.write_pcapng_block(&packet).is_err());
+
+    let packet = SimplePacketBlock { original_len: 6, data: Cow::Borrowed(&[1, 2, 3]) };
+    assert!(pcapng_writer.write_pcapng_block(&packet).is_err());
+
+    let packet = SimplePacketBlock { original_len: 6, data: Cow::Borrowed(&[1, 2, 3, 4]) };
+    pcapng_writer.write_pcapng_block(&packet).unwrap();
+    let packet = SimplePacketBlock { original_len: 3, data: Cow::Borrowed(&[1, 2, 3]) };
+    pcapng_writer.write_pcapng_block(&packet).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    reader.next_block().unwrap().unwrap();
+    let packet = reader.next_block().unwrap().unwrap().into_simple_packet().unwrap();
+    assert_eq!(&packet.data[..], &[1, 2, 3, 4]);
+    assert_eq!(packet.original_len, 6);
+    let packet = reader.next_block().unwrap().unwrap().into_simple_packet().unwrap();
+    assert_eq!(&packet.data[..], &[1, 2, 3]);
+}
+
+#[test]
+fn upgrade_packet_blocks() {
+    let packet = PacketBlock {
+        interface_id: 0,
+        drop_count: 2,
+        timestamp: 42,
+        captured_len: 4,
+        original_len: 5,
+        data: Cow::Borrowed(&[1, 2, 3, 4]),
+        options: vec![PacketOption::Comment(Cow::Borrowed("comment"))],
+    };
+
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF)).unwrap();
+    pcapng_writer.write_pcapng_block(&packet).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let config = PcapNgParserConfig { upgrade_packet_blocks: true, ..Default::default() };
+    let mut reader = PcapNgReader::with_config(&data[..], config).unwrap();
+    reader.next_block().unwrap().unwrap();
+    let packet = reader.next_block().unwrap().unwrap().into_enhanced_packet().unwrap();
+
+    let expected = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::from_nanos(42),
+        original_len: 5,
+        data: Cow::Borrowed(&[1, 2, 3, 4]),
+        options: vec![EnhancedPacketOption::Comment(Cow::Borrowed("comment")), EnhancedPacketOption::DropCount(2)],
+    };
+    assert_eq!(packet, expected);
+}
+
+#[test]
+fn into_owned() {
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF)).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let raw_block = reader.next_raw_block().unwrap().unwrap().into_owned();
+    let block = std::thread::spawn(move || raw_block.try_into_block::<NativeEndian>().unwrap().into_owned())
+        .join()
+        .unwrap();
+
+    assert_eq!(block.into_interface_description().unwrap(), InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF));
+}
+
+#[test]
+fn block_accessors() {
+    let mut block = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF).into_block();
+    assert!(block.as_enhanced_packet().is_none());
+    assert_eq!(block.as_interface_description().unwrap().snaplen, 0xFFFF);
+
+    block.as_interface_description_mut().unwrap().snaplen = 42;
+    assert_eq!(block.into_interface_description().unwrap().snaplen, 42);
+}
+
+#[test]
+fn block_conversions() {
+    let block = Block::from(InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF));
+    // The block is given back when it is of another type
+    let block = EnhancedPacketBlock::try_from(block).unwrap_err();
+
+    let interface = InterfaceDescriptionBlock::try_from(block).unwrap();
+    assert_eq!(interface.snaplen, 0xFFFF);
+}
+
+#[test]
+fn simple_packet_padding() {
+    let packet = SimplePacketBlock { original_len: 3, data: Cow::Borrowed(&[1, 2, 3]) };
+    let mut body = vec![];
+    assert_eq!(packet.write_to::<BigEndian, _>(&mut body).unwrap(), 8);
+    assert_eq!(body, [0, 0, 0, 3, 1, 2, 3, 0]);
+
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0)).unwrap();
+    assert_eq!(pcapng_writer.write_pcapng_block(&packet).unwrap(), 20);
+    let data = pcapng_writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    assert_eq!(reader.next_packet().unwrap().unwrap().data[..], [1, 2, 3]);
+}
+
+#[test]
+fn block_dump() {
+    let mut interface = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF);
+    interface.options.push(InterfaceDescriptionOption::IfTsResol(9));
+
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::from_nanos(1_600_000_000_123_456_789),
+        original_len: 6,
+        data: Cow::Borrowed(&[1, 2, 3, 4]),
+        options: vec![EnhancedPacketOption::Comment(Cow::Borrowed("comment"))],
+    }
+    .into_block();
+
+    let interfaces = [interface];
+    let dump = BlockDump::new(&packet).with_interfaces(&interfaces).to_string();
+    let expected = "Enhanced Packet Block
+    Interface ID: 0
+    Timestamp: 2020-09-13T12:26:40.123456789Z
+    Captured length: 4
+    Original length: 6
+    Options:
+        Comment(\"comment\")
+";
+    assert_eq!(dump, expected);
+
+    let dump = BlockDump::new(&packet).to_string();
+    assert!(dump.contains("Timestamp: 1600000000123456789 (unknown resolution)"));
+}
+
+#[test]
+fn hex_dump() {
+    let data: Vec<u8> = (0..20).collect();
+    let expected = "\
+0000  00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f   ................
+0010  10 11 12 13                                       ....
+";
+    assert_eq!(HexDump::new(&data).to_string(), expected);
+    assert_eq!(HexDump::new(&[]).to_string(), "");
+}
+
+#[cfg(feature = "remote")]
+#[test]
+fn pcap_over_ip_client() {
+    use std::net::TcpListener;
+
+    use pcap_file::remote::PcapOverIpClient;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let interface = InterfaceDescriptionBlock::builder().linktype(DataLink::RAW).tsresol_nanos().build();
+        let mut pcapng_writer = PcapNgWriter::new(stream).unwrap();
+        pcapng_writer.write_pcapng_block(&interface).unwrap();
+        for i in 0..3_u8 {
+            let data = [i; 10];
+            let packet = EnhancedPacketBlock::builder(InterfaceId(0), &data).timestamp(Duration::from_secs(i as u64)).build(&interface).unwrap();
+            pcapng_writer.write_pcapng_block(&packet).unwrap();
+        }
+    });
+
+    let mut client = PcapOverIpClient::connect(addr).unwrap();
+    assert!(client.is_pcapng());
+
+    let mut packets = vec![];
+    while let Some(packet) = client.next_packet() {
+        packets.push(packet.unwrap());
+    }
+    server.join().unwrap();
+
+    assert_eq!(packets.len(), 3);
+    assert_eq!(packets[2].datalink, DataLink::RAW);
+    assert_eq!(packets[2].timestamp, Some(Duration::from_secs(2)));
+    assert_eq!(packets[2].data, vec![2; 10]);
+}
+
+#[cfg(feature = "remote")]
+#[test]
+fn pcap_over_ip_server() {
+    use pcap_file::remote::{PcapOverIpClient, PcapOverIpServer};
+
+    let mut server = PcapOverIpServer::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap();
+    let interface = server.add_interface(&InterfaceDescriptionBlock::builder().linktype(DataLink::RAW).build()).unwrap();
+
+    // Written before any client is connected
+    assert_eq!(server.write_packet(interface, UNIX_EPOCH, &[0; 4]).unwrap(), 0);
+    assert!(server.write_packet(InterfaceId(1), UNIX_EPOCH, &[0; 4]).is_err());
+
+    let client = std::thread::spawn(move || {
+        let mut client = PcapOverIpClient::connect(addr).unwrap();
+        let mut packets = vec![];
+        while let Some(packet) = client.next_packet() {
+            packets.push(packet.unwrap());
+        }
+        packets
+    });
+
+    server.accept().unwrap();
+    assert_eq!(server.clients().count(), 1);
+    assert_eq!(server.accept_pending().unwrap(), 0);
+    for i in 1..=3_u8 {
+        let ts = UNIX_EPOCH + Duration::from_secs(i as u64);
+        assert_eq!(server.write_packet(interface, ts, &[i; 4]).unwrap(), 1);
+    }
+    drop(server);
+
+    let packets = client.join().unwrap();
+    assert_eq!(packets.len(), 3);
+    assert_eq!(packets[0].datalink, DataLink::RAW);
+    assert_eq!(packets[0].timestamp, Some(Duration::from_secs(1)));
+    assert_eq!(packets[2].data, vec![3; 4]);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_export() {
+    use pcap_file::export::json::{DataEncoding, JsonExporter};
+
+    let mut interface = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF);
+    interface.options.push(InterfaceDescriptionOption::IfTsResol(9));
+
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::from_nanos(1_600_000_000_123_456_789),
+        original_len: 6,
+        data: Cow::Borrowed(&[1, 2, 3, 4]),
+        options: vec![EnhancedPacketOption::Comment(Cow::Borrowed("comment"))],
+    };
+
+    let mut exporter = JsonExporter::with_data_encoding(Vec::new(), DataEncoding::Base64);
+    exporter.export_block(&interface.into_block()).unwrap();
+    exporter.export_block(&packet.into_block()).unwrap();
+    let out = String::from_utf8(exporter.into_inner()).unwrap();
+
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], r#"{"block":"interface_description","comments":[],"linktype":1,"snaplen":65535}"#);
+    assert_eq!(
+        lines[1],
+        r#"{"block":"enhanced_packet","captured_len":4,"comments":["comment"],"data":"AQIDBA==","interface_id":0,"original_len":6,"timestamp":"2020-09-13T12:26:40.123456789Z","timestamp_raw":1600000000123456789}"#
+    );
+}
+
+#[test]
+fn csv_export() {
+    use pcap_file::export::csv::CsvExporter;
+
+    let mut interface = InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF);
+    interface.options.push(InterfaceDescriptionOption::IfTsResol(9));
+
+    let mut flags = PacketFlags::default();
+    flags.set_direction(PacketDirection::Inbound);
+
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::from_nanos(1_600_000_000_123_456_789),
+        original_len: 6,
+        data: Cow::Borrowed(&[1, 2, 3, 4]),
+        options: vec![
+            EnhancedPacketOption::Comment(Cow::Borrowed("first, \"quoted\"")),
+            EnhancedPacketOption::Flags(flags),
+        ],
+    };
+
+    let mut exporter = CsvExporter::new(Vec::new()).unwrap();
+    exporter.export_block(&interface.into_block()).unwrap();
+    exporter.export_block(&packet.clone().into_block()).unwrap();
+    exporter.export_block(&EnhancedPacketBlock { interface_id: 1, options: vec![], ..packet }.into_block()).unwrap();
+    let out = String::from_utf8(exporter.into_inner()).unwrap();
+
+    let expected = "\
+index,timestamp,interface,linktype,captured_len,original_len,flags,comment
+0,2020-09-13T12:26:40.123456789Z,0,ETHERNET,4,6,0x00000001,\"first, \"\"quoted\"\"\"
+1,,1,,4,6,,
+";
+    assert_eq!(out, expected);
+}
+
+/// User-defined block holding a single counter
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct CounterBlock {
+    counter: u32,
+}
+
+impl<'a> PcapNgBlock<'a> for CounterBlock {
+    const BLOCK_TYPE: u32 = 0x8000_0001;
+
+    fn from_slice<B: ByteOrder>(mut slice: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+        let counter = slice.read_u32::<B>().map_err(|_| PcapError::InvalidField("CounterBlock: length < 4"))?;
+        Ok((slice, CounterBlock { counter }))
+    }
+
+    fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> std::io::Result<usize> {
+        writer.write_u32::<B>(self.counter)?;
+        Ok(4)
+    }
+}
+
+#[test]
+fn user_defined_block() {
+    let mut pcapng_writer = PcapNgWriter::with_endianness(Vec::new(), Endianness::Big).unwrap();
+    pcapng_writer.write_pcapng_block(&CounterBlock { counter: 42 }).unwrap();
+    let mut data = pcapng_writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let block = reader.next_block().unwrap().unwrap();
+    let unknown = block.as_unknown().unwrap();
+    assert_eq!(unknown.parse_as::<BigEndian, CounterBlock>().unwrap(), CounterBlock { counter: 42 });
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    reader.register_block::<CounterBlock>();
+    let block = reader.next_block().unwrap().unwrap();
+    let registered = block.as_registered().unwrap();
+    assert_eq!(registered.block_type(), 0x8000_0001);
+    assert_eq!(registered.downcast_ref::<CounterBlock>(), Some(&CounterBlock { counter: 42 }));
+
+    // The registered block is written back unchanged
+    let mut pcapng_writer = PcapNgWriter::with_endianness(Vec::new(), Endianness::Big).unwrap();
+    pcapng_writer.write_block(&block).unwrap();
+    assert_eq!(pcapng_writer.into_inner(), data);
+
+    // Replace the block by an empty one
+    data.truncate(data.len() - 16);
+    data.extend_from_slice(&[0x80, 0, 0, 1, 0, 0, 0, 12, 0, 0, 0, 12]);
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    assert!(reader.next_block().unwrap().is_ok());
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    reader.register_block::<CounterBlock>();
+    assert!(reader.next_block().unwrap().is_err());
+}
+
+/// User-defined block whose two calls to `write_to()` write a different number of bytes
+struct UnstableBlock {
+    lens: [usize; 2],
+    calls: std::cell::Cell<usize>,
+}
+
+impl<'a> PcapNgBlock<'a> for UnstableBlock {
+    const BLOCK_TYPE: u32 = 0x8000_0002;
+
+    fn from_slice<B: ByteOrder>(_: &'a [u8]) -> Result<(&'a [u8], Self), PcapError> {
+        Err(PcapError::InvalidField("UnstableBlock: can't be parsed"))
+    }
+
+    fn write_to<B: ByteOrder, W: Write>(&self, writer: &mut W) -> std::io::Result<usize> {
+        let len = self.lens[self.calls.get()];
+        self.calls.set(self.calls.get() + 1);
+        writer.write_all(&vec![0; len])?;
+        Ok(len)
+    }
+}
+
+#[test]
+fn user_defined_block_unstable_length() {
+    for lens in [[4, 8], [8, 4], [4, 1024], [1024, 4]] {
+        let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+        let block = UnstableBlock { lens, calls: Default::default() };
+        match pcapng_writer.write_pcapng_block(&block) {
+            Err(PcapError::IoError(err)) => assert_eq!(err.kind(), ErrorKind::InvalidData),
+            res => panic!("Unexpected result: {res:?}"),
+        }
+    }
+}
+
+#[cfg(feature = "fallible-iterator")]
+#[test]
+fn reader_fallible_iterator() {
+    use fallible_iterator::FallibleIterator;
+
+    let mut writer = PcapNgWriter::new(vec![]).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    for i in 0..3_u8 {
+        writer.write_packet(interface, UNIX_EPOCH, &[i]).unwrap();
+    }
+    let mut data = writer.into_inner();
+    // Truncated block
+    data.extend_from_slice(&[6, 0, 0, 0, 255, 0, 0, 0]);
+
+    let reader = PcapNgReader::new(&data[..]).unwrap();
+    let res = reader.filter_map(|block| Ok(block.into_enhanced_packet())).map(|epb| Ok(epb.data[0])).collect::<Vec<_>>();
+    assert!(res.is_err());
+
+    let reader = PcapNgReader::new(&data[..]).unwrap();
+    let packets = reader.take(3).filter_map(|block| Ok(block.into_enhanced_packet())).count().unwrap();
+    assert_eq!(packets, 2);
+}
+
+#[test]
+fn unknown_linktype_round_trip() {
+    let datalink = DataLink::Unknown(4000);
+    let mut pcapng_writer = PcapNgWriter::with_endianness(Vec::new(), Endianness::Big).unwrap();
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(datalink, 0)).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let mut little = Vec::new();
+    pcap_file::pcapng::transcode(&data[..], &mut little, Endianness::Little).unwrap();
+
+    for (data, endianness) in [(&data, Endianness::Big), (&little, Endianness::Little)] {
+        let mut pcapng_reader = PcapNgReader::new(&data[..]).unwrap();
+        let interface = pcapng_reader.next_block().unwrap().unwrap().into_owned();
+        assert_eq!(interface.as_interface_description().unwrap().linktype, datalink);
+
+        let mut pcapng_writer = PcapNgWriter::with_endianness(Vec::new(), endianness).unwrap();
+        pcapng_writer.write_block(&interface).unwrap();
+        assert_eq!(&pcapng_writer.into_inner(), data);
+    }
+
+    // Doesn't fit in the 16 bits field of the block
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    let interface = InterfaceDescriptionBlock::new(DataLink::Unknown(0x10000), 0);
+    assert!(matches!(pcapng_writer.write_block(&interface.clone().into_block()), Err(PcapError::InvalidField(_))));
+    assert!(matches!(pcapng_writer.write_pcapng_block(&interface), Err(PcapError::InvalidField(_))));
+}
+
+#[test]
+fn skip_block_types() {
+    let mut writer = PcapNgWriter::new(vec![]).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::RAW, 0)).unwrap();
+    for i in 0..3_u8 {
+        let entry = SystemdJournalExportBlock { journal_entry: Cow::Borrowed(b"MESSAGE=hello\n") };
+        writer.write_block(&entry.into_block()).unwrap();
+        writer.write_packet(interface, UNIX_EPOCH, &[i]).unwrap();
+    }
+    let data = writer.into_inner();
+
+    let config = PcapNgParserConfig { skip_block_types: vec![SYSTEMD_JOURNAL_EXPORT_BLOCK, SECTION_HEADER_BLOCK], ..Default::default() };
+    assert!(config.skips(SYSTEMD_JOURNAL_EXPORT_BLOCK));
+    assert!(!config.skips(SECTION_HEADER_BLOCK));
+
+    let mut reader = PcapNgReader::with_config(&data[..], config.clone()).unwrap();
+    let blocks = reader.blocks().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(blocks.len(), 4);
+    assert!(blocks.iter().all(|block| !matches!(block, Block::SystemdJournalExport(_))));
+    assert_eq!(reader.stats().blocks, 8);
+    assert_eq!(reader.stats().skipped, 3);
+    assert_eq!(reader.stats().repaired, 0);
+
+    let (mut src, mut parser) = PcapNgParser::with_config(&data[..], config).unwrap();
+    let mut count = 0;
+    while !src.is_empty() {
+        let (rem, block) = parser.next_block(src).unwrap();
+        assert!(!matches!(block, Block::SystemdJournalExport(_)));
+        src = rem;
+        count += 1;
+    }
+    assert_eq!(count, 4);
+}
+
+#[test]
+fn write_block_endianness() {
+    let mut big_writer = PcapNgWriter::with_endianness(vec![], Endianness::Big).unwrap();
+    big_writer.write_pcapng_block(&CounterBlock { counter: 1 }).unwrap();
+    let data = big_writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let block = reader.next_block().unwrap().unwrap();
+    assert_eq!(block.as_unknown().unwrap().endianness(), Some(Endianness::Big));
+
+    let mut little_writer = PcapNgWriter::with_endianness(vec![], Endianness::Little).unwrap();
+    assert!(matches!(little_writer.write_block(&block), Err(PcapError::InvalidField(_))));
+    let mut raw_reader = PcapNgReader::new(&data[..]).unwrap();
+    let raw = raw_reader.next_raw_block().unwrap().unwrap();
+    assert!(matches!(little_writer.copy_block(&raw, Endianness::Big), Err(PcapError::InvalidField(_))));
+
+    little_writer.register_block::<CounterBlock>();
+    little_writer.write_block(&block).unwrap();
+    let little = little_writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&little[..]).unwrap();
+    let block = reader.next_block().unwrap().unwrap();
+    let unknown = block.as_unknown().unwrap();
+    assert_eq!(unknown.endianness(), Some(Endianness::Little));
+    assert_eq!(unknown.parse_as::<LittleEndian, CounterBlock>().unwrap(), CounterBlock { counter: 1 });
+}
+
+#[test]
+fn custom_block() {
+    let mut custom = CustomBlock::new(32473, b"abcdefg");
+    custom.copiable = false;
+
+    let mut pcapng_writer = PcapNgWriter::with_endianness(Vec::new(), Endianness::Big).unwrap();
+    pcapng_writer.write_pcapng_block(&custom).unwrap();
+    pcapng_writer.write_pcapng_block(&CustomBlock::new(32473, b"abcd")).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    // The data read back includes the padding
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let read = reader.next_block().unwrap().unwrap().into_custom().unwrap();
+    assert_eq!(read.block_type(), CUSTOM_BLOCK_NO_COPY);
+    assert_eq!(read.pen, 32473);
+    assert!(!read.copiable);
+    assert_eq!(&read.data[..], b"abcdefg\0");
+
+    let read = reader.next_block().unwrap().unwrap().into_custom().unwrap();
+    assert_eq!(read, CustomBlock::new(32473, b"abcd"));
+}
+
+#[cfg(feature = "digest")]
+#[test]
+fn integrity_manifest() {
+    use pcap_file::digest::DigestAlgorithm;
+    use pcap_file::pcapng::{verify_integrity, IntegrityManifest, IntegrityManifestWriter};
+
+    const PEN: u32 = 32473;
+
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::ZERO,
+        original_len: 4,
+        data: Cow::Borrowed(&[1, 2, 3, 4]),
+        options: vec![],
+    };
+
+    let mut writer = IntegrityManifestWriter::new(Vec::new(), PEN).unwrap();
+    writer.set_buffer_capacity(1 << 16).unwrap();
+    writer.write_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF).into_block()).unwrap();
+    writer.write_block(&packet.clone().into_block()).unwrap();
+    writer.write_block(&SectionHeaderBlock::default().into_block()).unwrap();
+    writer.write_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF).into_block()).unwrap();
+    writer.write_block(&packet.clone().into_block()).unwrap();
+    writer.write_block(&packet.into_block()).unwrap();
+    let data = writer.finish().unwrap();
+
+    let sections = verify_integrity(&data[..], PEN).unwrap();
+    assert_eq!(sections.len(), 2);
+    assert!(sections.iter().all(|section| section.is_valid()));
+    assert_eq!(sections[1].packet_count, 2);
+
+    // The manifest is the last block of each section
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let blocks: Vec<_> = std::iter::from_fn(|| reader.next_block().map(|block| block.unwrap().into_owned())).collect();
+    let manifest = IntegrityManifest::from_custom_block(blocks[2].as_custom().unwrap(), PEN).unwrap().unwrap();
+    assert_eq!(manifest.packet_digests, vec![DigestAlgorithm::Sha256.compute(&[1, 2, 3, 4])]);
+    assert!(IntegrityManifest::from_custom_block(blocks[2].as_custom().unwrap(), PEN + 1).is_none());
+
+    // Corruption of the data of the second packet of the second section
+    let mut tampered = data.clone();
+    let pos = tampered.windows(4).rposition(|window| window == [1, 2, 3, 4]).unwrap();
+    tampered[pos] = 5;
+    let sections = verify_integrity(&tampered[..], PEN).unwrap();
+    assert!(sections[0].is_valid());
+    assert!(!sections[1].is_valid());
+    assert!(!sections[1].section_matches);
+    assert_eq!(sections[1].mismatched_packets, vec![1]);
+
+    // Only SHA-256 digests can be stored
+    let xxh3_manifest = IntegrityManifest { section_digest: DigestAlgorithm::Xxh3.compute(&[]), packet_digests: vec![] };
+    assert!(matches!(xxh3_manifest.to_custom_block(PEN), Err(PcapError::InvalidField(_))));
+    assert_eq!(manifest.to_custom_block(PEN).unwrap(), blocks[2].clone().into_custom().unwrap());
+}
+
+#[test]
+fn application_metadata() {
+    const PEN: u32 = 32473;
+
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        timestamp: Duration::ZERO,
+        original_len: 4,
+        data: Cow::Borrowed(&[1, 2, 3, 4]),
+        options: vec![],
+    };
+
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.set_metadata(Some(MetadataConfig { interval: 2, ..MetadataConfig::new(PEN, b"abcde".to_vec()) })).unwrap();
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF)).unwrap();
+    for _ in 0..3 {
+        pcapng_writer.write_pcapng_block(&packet).unwrap();
+    }
+    pcapng_writer.write_metadata(PEN, b"explicit").unwrap();
+    pcapng_writer.write_metadata(PEN + 1, b"other application").unwrap();
+    pcapng_writer.write_pcapng_block(&SectionHeaderBlock::default()).unwrap();
+    let data = pcapng_writer.finish().unwrap();
+
+    let metadata = read_metadata(&data[..], PEN).unwrap();
+    let points: Vec<_> = metadata.iter().map(|metadata| metadata.point).collect();
+    assert_eq!(points, [
+        MetadataPoint::AfterSectionHeader,
+        MetadataPoint::Periodic,
+        MetadataPoint::Explicit,
+        MetadataPoint::AfterSectionHeader,
+        MetadataPoint::AtEnd
+    ]);
+    assert_eq!(&metadata[0].data[..], b"abcde");
+    assert_eq!(&metadata[2].data[..], b"explicit");
+
+    let metadata = read_metadata(&data[..], PEN + 1).unwrap();
+    assert_eq!(metadata, [ApplicationMetadata::new(MetadataPoint::Explicit, b"other application")]);
+}
+
+#[test]
+fn preserved_round_trip() {
+    // The non-zero padding bytes would be lost by decoding and encoding again
+    let mut data = vec![];
+    for endianness in [Endianness::Little, Endianness::Big] {
+        let mut pcapng_writer = PcapNgWriter::with_endianness(Vec::new(), endianness).unwrap();
+        pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF)).unwrap();
+        let mut section = pcapng_writer.into_inner();
+
+        let mut body = vec![0_u8; 24];
+        for field in [12, 16] {
+            match endianness {
+                Endianness::Big => BigEndian::write_u32(&mut body[field..field + 4], 3),
+                Endianness::Little => LittleEndian::write_u32(&mut body[field..field + 4], 3),
+            }
+        }
+        body[20..24].copy_from_slice(&[1, 2, 3, 0xAA]);
+        body.extend_from_slice(&match endianness {
+            Endianness::Big => [0, 1, 0, 3, b'a', b'b', b'c', 0xBB, 0, 0, 0, 0],
+            Endianness::Little => [1, 0, 3, 0, b'a', b'b', b'c', 0xBB, 0, 0, 0, 0],
+        });
+        let len = body.len() as u32 + 12;
+        let raw = RawBlock { type_: ENHANCED_PACKET_BLOCK, initial_len: len, body: Cow::Owned(body), trailer_len: len };
+        match endianness {
+            Endianness::Big => raw.write_to::<BigEndian, _>(&mut section).unwrap(),
+            Endianness::Little => raw.write_to::<LittleEndian, _>(&mut section).unwrap(),
+        };
+        data.extend_from_slice(&section);
+    }
+
+    let write = |modify: bool| {
+        let mut reader = PcapNgReader::new(&data[..]).unwrap();
+        let mut pcapng_writer = PcapNgWriter::with_raw_section_header(Vec::new(), reader.raw_section()).unwrap();
+        while let Some(block) = reader.next_preserved_block() {
+            let mut block = block.unwrap();
+            if modify && block.block().as_enhanced_packet().is_some() {
+                block.block_mut().as_enhanced_packet_mut().unwrap().data.to_mut()[0] = 9;
+            }
+            pcapng_writer.write_preserved_block(&block).unwrap();
+        }
+        pcapng_writer.finish().unwrap()
+    };
+
+    assert_eq!(write(false), data);
+
+    // The modified blocks are encoded again, the others are kept
+    let modified = write(true);
+    assert_ne!(modified, data);
+    assert_eq!(modified.len(), data.len());
+
+    let mut reader = PcapNgReader::new(&modified[..]).unwrap();
+    while let Some(packet) = reader.next_packet() {
+        assert_eq!(packet.unwrap().data[..], [9, 2, 3]);
+    }
+}
+
+#[test]
+fn option_order() {
+    let interface = InterfaceDescriptionBlock {
+        linktype: DataLink::ETHERNET,
+        snaplen: 0xFFFF,
+        options: vec![
+            InterfaceDescriptionOption::IfName("eth0".into()),
+            InterfaceDescriptionOption::Comment("first".into()),
+            InterfaceDescriptionOption::IfDescription("uplink".into()),
+            InterfaceDescriptionOption::Comment("second".into()),
+        ],
+    };
+
+    let write = |order: OptionOrder| {
+        let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+        pcapng_writer.set_option_order(order);
+        pcapng_writer.write_pcapng_block(&interface).unwrap();
+        let data = pcapng_writer.into_inner();
+
+        let mut reader = PcapNgReader::new(&data[..]).unwrap();
+        reader.next_block().unwrap().unwrap().into_owned()
+    };
+
+    // The order and the repeated options are kept
+    let block = write(OptionOrder::AsProvided);
+    assert_eq!(block.as_interface_description().unwrap(), &interface);
+    assert_eq!(block.option_codes(), [2, 1, 3, 1]);
+    assert_eq!(block.duplicate_option_codes(), [1]);
+
+    let block = write(OptionOrder::Canonical);
+    assert_eq!(block.option_codes(), [1, 1, 2, 3]);
+    assert_eq!(block.as_interface_description().unwrap().options[..2], [
+        InterfaceDescriptionOption::Comment("first".into()),
+        InterfaceDescriptionOption::Comment("second".into())
+    ]);
+}
+
+#[test]
+fn utf8_mode() {
+    let interface = InterfaceDescriptionBlock {
+        linktype: DataLink::ETHERNET,
+        snaplen: 0xFFFF,
+        options: vec![InterfaceDescriptionOption::IfName("ethX".into())],
+    };
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.write_pcapng_block(&interface).unwrap();
+    let mut data = pcapng_writer.into_inner();
+
+    let pos = data.windows(4).position(|window| window == b"ethX").unwrap();
+    data[pos + 3] = 0xFF;
+
+    let read = |utf8_mode: Utf8Mode| {
+        let config = PcapNgParserConfig { utf8_mode, ..Default::default() };
+        let mut reader = PcapNgReader::with_config(&data[..], config).unwrap();
+        reader.next_block().unwrap().map(|block| block.into_owned().into_interface_description().unwrap().options)
+    };
+
+    assert!(matches!(read(Utf8Mode::Strict), Err(PcapError::Utf8Error(_))));
+
+    for utf8_mode in [Utf8Mode::Lossy, Utf8Mode::Raw] {
+        match &read(utf8_mode).unwrap()[..] {
+            [InterfaceDescriptionOption::Unknown(option)] => {
+                assert_eq!(option.code, 2);
+                assert_eq!(option.value[..], [b'e', b't', b'h', 0xFF]);
+            },
+            options => panic!("Unexpected options: {options:?}"),
+        }
+    }
+
+    let lossy = read(Utf8Mode::Lossy).unwrap();
+    assert_eq!(lossy[0].as_str().as_deref(), Some("eth\u{FFFD}"));
+    assert_eq!(lossy[0].as_str_raw(), Some(&[b'e', b't', b'h', 0xFF][..]));
+}
+
+#[test]
+fn utf8_mode_custom_option() {
+    let interface = InterfaceDescriptionBlock {
+        linktype: DataLink::ETHERNET,
+        snaplen: 0xFFFF,
+        options: vec![InterfaceDescriptionOption::IfName("1234ethX".into())],
+    };
+    let mut pcapng_writer = PcapNgWriter::with_endianness(Vec::new(), Endianness::Big).unwrap();
+    pcapng_writer.write_pcapng_block(&interface).unwrap();
+    let mut data = pcapng_writer.into_inner();
+
+    // Turns the name into a custom string option whose PEN is "1234"
+    let pos = data.windows(8).position(|window| window == b"1234ethX").unwrap();
+    data[pos - 4..pos - 2].copy_from_slice(&2988_u16.to_be_bytes());
+    data[pos + 7] = 0xFF;
+
+    let read = |utf8_mode: Utf8Mode| {
+        let config = PcapNgParserConfig { utf8_mode, ..Default::default() };
+        let mut reader = PcapNgReader::with_config(&data[..], config).unwrap();
+        reader.next_block().unwrap().map(|block| block.into_owned().into_interface_description().unwrap().options)
+    };
+
+    assert!(matches!(read(Utf8Mode::Strict), Err(PcapError::Utf8Error(_))));
+
+    let raw = read(Utf8Mode::Raw).unwrap();
+    match &raw[..] {
+        [InterfaceDescriptionOption::Unknown(option)] => {
+            assert_eq!(option.code, 2988);
+            assert_eq!(option.value[..], [b'1', b'2', b'3', b'4', b'e', b't', b'h', 0xFF]);
+        },
+        options => panic!("Unexpected options: {options:?}"),
+    }
+    assert_eq!(raw[0].as_str(), None);
+    assert_eq!(raw[0].as_str_raw(), Some(&[b'e', b't', b'h', 0xFF][..]));
+
+    let lossy = read(Utf8Mode::Lossy).unwrap();
+    assert_eq!(lossy[0].as_str().as_deref(), Some("eth\u{FFFD}"));
+
+    // The mode which kept the option doesn't change its equality
+    match (&lossy[..], &raw[..]) {
+        ([InterfaceDescriptionOption::Unknown(lossy)], [InterfaceDescriptionOption::Unknown(raw)]) => {
+            assert_eq!(lossy.kept_by, Some(Utf8Mode::Lossy));
+            assert_eq!(raw.kept_by, Some(Utf8Mode::Raw));
+        },
+        options => panic!("Unexpected options: {options:?}"),
+    }
+    assert_eq!(lossy, raw);
+}
+
+#[test]
+fn option_str_raw() {
+    let option = InterfaceDescriptionOption::IfName("eth0".into());
+    assert_eq!(option.as_str().as_deref(), Some("eth0"));
+    assert_eq!(option.as_str_raw(), Some(&b"eth0"[..]));
+    assert_eq!(InterfaceDescriptionOption::IfTsResol(9).as_str_raw(), None);
+
+    let interface = InterfaceDescriptionBlock {
+        linktype: DataLink::ETHERNET,
+        snaplen: 0xFFFF,
+        options: vec![InterfaceDescriptionOption::IfName("ethX".into()), InterfaceDescriptionOption::IfTsResol(9)],
+    };
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.write_pcapng_block(&interface).unwrap();
+    let mut data = pcapng_writer.into_inner();
+
+    let pos = data.windows(4).position(|window| window == b"ethX").unwrap();
+    data[pos + 3] = 0xFF;
+
+    let config = PcapNgParserConfig { utf8_mode: Utf8Mode::Raw, ..Default::default() };
+    let mut reader = PcapNgReader::with_config(&data[..], config).unwrap();
+    let interface = reader.next_block().unwrap().unwrap().into_interface_description().unwrap();
+
+    assert_eq!(interface.options[0].as_str(), None);
+    assert_eq!(interface.options[0].as_str_raw(), Some(&[b'e', b't', b'h', 0xFF][..]));
+    assert_eq!(interface.options[1].as_str_raw(), None);
+}
+
+#[test]
+fn streamed_block() {
+    let interface = InterfaceDescriptionBlock { linktype: DataLink::ETHERNET, snaplen: 0xFFFF, options: vec![] };
+    let secrets = (0..100_000).map(|i| i as u8).collect::<Vec<_>>();
+    let dsb = DecryptionSecretsBlock { secrets_type: SecretsType::TlsKeyLog, secrets_data: Cow::Borrowed(&secrets), options: vec![] };
+    let packet = EnhancedPacketBlock { interface_id: 0, timestamp: Duration::ZERO, original_len: 4, data: Cow::Borrowed(&[1, 2, 3, 4]), options: vec![] };
+
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.write_pcapng_block(&interface).unwrap();
+    pcapng_writer.write_pcapng_block(&dsb).unwrap();
+    pcapng_writer.write_pcapng_block(&packet).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let mut reader = PcapNgReader::with_capacity(Cursor::new(&data[..]), 4096).unwrap();
+    assert!(reader.next_streamed_block().unwrap().is_err());
+    reader.next_block().unwrap().unwrap();
+
+    let mut block = reader.next_streamed_block().unwrap().unwrap();
+    assert_eq!(block.type_(), DECRYPTION_SECRETS_BLOCK);
+    assert_eq!(block.body_len(), 8 + secrets.len() as u64);
+
+    let mut header = [0_u8; 8];
+    block.read_exact(&mut header).unwrap();
+    let mut streamed = vec![];
+    block.read_to_end(&mut streamed).unwrap();
+    assert_eq!(streamed, secrets);
+    block.finish().unwrap();
+
+    assert_eq!(reader.next_block().unwrap().unwrap(), Block::EnhancedPacket(packet));
+    assert!(reader.next_block().is_none());
+    assert_eq!(reader.stats().blocks, 4);
+
+    // Dropping the block consumes the rest of it
+    let mut reader = PcapNgReader::with_capacity(Cursor::new(&data[..]), 4096).unwrap();
+    reader.next_block().unwrap().unwrap();
+    reader.next_streamed_block().unwrap().unwrap();
+    assert!(matches!(reader.next_block().unwrap().unwrap(), Block::EnhancedPacket(_)));
+
+    // The invalid trailer of a dropped block is reported by the next read
+    let mut data = data;
+    let trailer = data.len() - 36 - 4;
+    data[trailer] ^= 0xFF;
+    let mut reader = PcapNgReader::with_capacity(Cursor::new(&data[..]), 4096).unwrap();
+    reader.next_block().unwrap().unwrap();
+    reader.next_streamed_block().unwrap().unwrap();
+    assert!(matches!(reader.next_block(), Some(Err(PcapError::InvalidField(_)))));
+    assert!(matches!(reader.next_block().unwrap().unwrap(), Block::EnhancedPacket(_)));
+    assert_eq!(reader.stats().errors, 1);
+}
+
+#[test]
+fn streamed_packet() {
+    use pcap_file::pcapng::blocks::name_resolution::{NameResolutionBlock, Record};
+
+    let interface = InterfaceDescriptionBlock { linktype: DataLink::USB_LINUX_MMAPPED, snaplen: 0, options: vec![] };
+    let giant = (0..300_001).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+    let packet = EnhancedPacketBlock {
+        interface_id: 0,
+        // In the microseconds of the interface
+        timestamp: Duration::from_nanos(1_500_000),
+        original_len: giant.len() as u32,
+        data: Cow::Borrowed(&giant),
+        options: vec![EnhancedPacketOption::Comment("giant".into())],
+    };
+
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.write_pcapng_block(&interface).unwrap();
+    pcapng_writer.write_pcapng_block(&NameResolutionBlock { records: vec![Record::End], options: vec![] }).unwrap();
+    pcapng_writer.write_pcapng_block(&packet).unwrap();
+    pcapng_writer.write_pcapng_block(&SimplePacketBlock { original_len: 6, data: Cow::Borrowed(&[1, 2, 3, 4, 5, 6]) }).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let mut reader = PcapNgReader::with_capacity(Cursor::new(&data[..]), 4096).unwrap();
+
+    let mut packet = reader.next_streamed_packet().unwrap().unwrap();
+    assert_eq!(packet.interface_id(), 0);
+    assert_eq!(packet.linktype(), DataLink::USB_LINUX_MMAPPED);
+    assert_eq!(packet.timestamp(), Some(Duration::from_micros(1_500_000)));
+    assert_eq!(packet.captured_len(), giant.len() as u32);
+    assert_eq!(packet.original_len(), giant.len() as u32);
+
+    let mut chunk = [0_u8; 1000];
+    let mut streamed = vec![];
+    loop {
+        let len = packet.read(&mut chunk).unwrap();
+        if len == 0 {
+            break;
+        }
+        streamed.extend_from_slice(&chunk[..len]);
+    }
+    assert_eq!(streamed, giant);
+    packet.finish().unwrap();
+
+    let mut packet = reader.next_streamed_packet().unwrap().unwrap();
+    assert_eq!(packet.timestamp(), None);
+    let mut streamed = vec![];
+    packet.read_to_end(&mut streamed).unwrap();
+    assert_eq!(streamed, [1, 2, 3, 4, 5, 6]);
+    drop(packet);
+
+    assert!(reader.next_streamed_packet().is_none());
+    assert_eq!(reader.stats().packets, 2);
+
+    // The data of a simple packet is bounded by the snaplen of the interface, not by its padding
+    let mut pcapng_writer = PcapNgWriter::new(Vec::new()).unwrap();
+    pcapng_writer.write_pcapng_block(&InterfaceDescriptionBlock { linktype: DataLink::ETHERNET, snaplen: 3, options: vec![] }).unwrap();
+    pcapng_writer.write_pcapng_block(&SimplePacketBlock { original_len: 6, data: Cow::Borrowed(&[1, 2, 3]) }).unwrap();
+    let data = pcapng_writer.into_inner();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let mut packet = reader.next_streamed_packet().unwrap().unwrap();
+    assert_eq!(packet.captured_len(), 3);
+    let mut streamed = vec![];
+    packet.read_to_end(&mut streamed).unwrap();
+    assert_eq!(streamed, [1, 2, 3]);
+    packet.finish().unwrap();
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn mmap_writer() {
+    use pcap_file::mmap::MmapWriter;
+
+    let path = std::env::temp_dir().join(format!("pcap_file_mmap_writer_{}.pcapng", std::process::id()));
+    let file = File::options().read(true).write(true).create(true).truncate(true).open(&path).unwrap();
+    let mmap = MmapWriter::with_preallocation(file, 4096).unwrap();
+    assert_eq!(mmap.capacity(), 4096);
+
+    let mut pcapng_writer = PcapNgWriter::new(mmap).unwrap();
+    let interface = pcapng_writer.add_interface(&InterfaceDescriptionBlock::builder().linktype(DataLink::ETHERNET).build()).unwrap();
+    for i in 0..100_u8 {
+        pcapng_writer.write_packet(interface, UNIX_EPOCH, &[i; 100]).unwrap();
+    }
+
+    let mmap = pcapng_writer.finish_with_section_lengths().unwrap();
+    assert!(mmap.capacity() > 4096);
+    let len = mmap.len();
+    let file = mmap.finish().unwrap();
+    assert_eq!(file.metadata().unwrap().len(), len);
+
+    let data = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    assert_eq!(reader.section().declared_section_length(), Some(len - reader.position()));
+    let mut count = 0;
+    while let Some(packet) = reader.next_packet() {
+        assert_eq!(packet.unwrap().data[..], [count; 100]);
+        count += 1;
+    }
+    assert_eq!(count, 100);
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn mmap_writer_preallocate() {
+    use pcap_file::mmap::MmapWriter;
+
+    let path = std::env::temp_dir().join(format!("pcap_file_mmap_writer_preallocate_{}.pcapng", std::process::id()));
+    let file = File::options().read(true).write(true).create(true).truncate(true).open(&path).unwrap();
+    let mut pcapng_writer = PcapNgWriter::new(MmapWriter::with_preallocation(file, 4096).unwrap()).unwrap();
+    let interface = pcapng_writer.add_interface(&InterfaceDescriptionBlock::builder().linktype(DataLink::ETHERNET).build()).unwrap();
+    pcapng_writer.preallocate(1 << 20).unwrap();
+    let preallocated = std::fs::metadata(&path).unwrap().len();
+    assert!(preallocated >= 1 << 20);
+
+    // The mapping grows over the preallocated space rather than shrinking the file
+    for i in 0..100_u8 {
+        pcapng_writer.write_packet(interface, UNIX_EPOCH, &[i; 100]).unwrap();
+    }
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), preallocated);
+    assert_eq!(pcapng_writer.get_ref().capacity(), preallocated);
+
+    let len = pcapng_writer.get_ref().len();
+    let file = pcapng_writer.finish_truncated().unwrap().finish().unwrap();
+    assert_eq!(file.metadata().unwrap().len(), len);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn writer_backends() {
+    use pcap_file::backend::WriterBackend;
+
+    #[allow(unused_mut)]
+    let mut backends = vec![WriterBackend::File];
+    #[cfg(feature = "mmap")]
+    backends.push(WriterBackend::Mmap);
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    backends.push(WriterBackend::IoUring);
+
+    for backend in backends {
+        let path = std::env::temp_dir().join(format!("pcap_file_writer_backend_{backend:?}_{}.pcapng", std::process::id()));
+        let file_out = match backend.create(&path) {
+            Ok(file_out) => file_out,
+            // io_uring may be disabled in the sandbox running the tests
+            Err(_) if backend != WriterBackend::File => continue,
+            Err(err) => panic!("{err}"),
+        };
+        assert_eq!(file_out.backend(), backend);
+
+        let mut pcapng_writer = PcapNgWriter::new(file_out).unwrap();
+        let interface = pcapng_writer.add_interface(&InterfaceDescriptionBlock::builder().linktype(DataLink::ETHERNET).build()).unwrap();
+        for i in 0..100_u8 {
+            pcapng_writer.write_packet(interface, UNIX_EPOCH, &[i; 100]).unwrap();
+        }
+        pcapng_writer.finish_with_section_lengths().unwrap().finish().unwrap();
+
+        let data = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut reader = PcapNgReader::new(&data[..]).unwrap();
+        assert_eq!(reader.section().declared_section_length(), Some(data.len() as u64 - reader.position()));
+        let mut count = 0;
+        while let Some(packet) = reader.next_packet() {
+            assert_eq!(packet.unwrap().data[..], [count; 100]);
+            count += 1;
+        }
+        assert_eq!(count, 100, "{backend:?}");
+    }
+}
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+#[test]
+fn uring_writer() {
+    use pcap_file::uring::UringWriter;
+
+    let path = std::env::temp_dir().join(format!("pcap_file_uring_writer_{}", std::process::id()));
+    let file = File::create(&path).unwrap();
+
+    // Small buffers, so that the writer waits for free buffers
+    let mut writer = match UringWriter::with_config(file, 2, 64) {
+        Ok(writer) => writer,
+        // io_uring may be disabled in the sandbox running the tests
+        Err(_) => return,
+    };
+
+    let data = (0..10_000).map(|i| i as u8).collect::<Vec<_>>();
+    for chunk in data.chunks(37) {
+        writer.write_all(chunk).unwrap();
+    }
+    assert_eq!(writer.len(), data.len() as u64);
+    writer.finish().unwrap();
+
+    assert_eq!(std::fs::read(&path).unwrap(), data);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn threaded_writer() {
+    use std::sync::{Arc, Mutex};
+
+    let interface = InterfaceDescriptionBlock::builder().linktype(DataLink::ETHERNET).build();
+    let packet = |i: u8| EnhancedPacketBlock::builder(InterfaceId(0), &[i; 4]).build(&interface).unwrap().into_owned();
+
+    // Blocking policy, every packet is written
+    let mut writer = ThreadedWriter::new(PcapNgWriter::new(Vec::new()).unwrap(), 4, OverflowPolicy::Block).unwrap();
+    writer.write_pcapng_block(interface.clone()).unwrap();
+    for i in 0..200 {
+        assert!(writer.write_pcapng_block(packet(i)).unwrap());
+    }
+    writer.flush().unwrap();
+    let data = writer.finish().unwrap();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let mut count = 0;
+    while let Some(packet) = reader.next_packet() {
+        assert_eq!(packet.unwrap().data[..], [count; 4]);
+        count += 1;
+    }
+    assert_eq!(count, 200);
+
+    // Dropping policy, the writer thread is stalled by a gate
+    struct GatedWriter {
+        gate: Arc<Mutex<()>>,
+        data: Arc<Mutex<Vec<u8>>>,
+    }
+
+    impl Write for GatedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let _gate = self.gate.lock().unwrap();
+            self.data.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let gate = Arc::new(Mutex::new(()));
+    let data = Arc::new(Mutex::new(Vec::new()));
+    let pcapng_writer = PcapNgWriter::new(GatedWriter { gate: gate.clone(), data: data.clone() }).unwrap();
+    let mut writer = ThreadedWriter::new(pcapng_writer, 2, OverflowPolicy::Drop).unwrap();
+
+    let closed = gate.lock().unwrap();
+    writer.write_pcapng_block(interface.clone()).unwrap();
+    let written = (0..10).filter(|&i| writer.write_pcapng_block(packet(i)).unwrap()).count() as u64;
+    assert_eq!(written + writer.dropped(), 10);
+    assert!(writer.dropped() >= 7);
+    drop(closed);
+    writer.flush().unwrap();
+
+    // The drops are reported on the next packet
+    assert!(writer.write_pcapng_block(packet(10)).unwrap());
+    let dropped = writer.dropped();
+    writer.finish().unwrap();
+
+    let data = data.lock().unwrap().clone();
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let (mut count, mut drop_count) = (0, 0);
+    while let Some(block) = reader.next_block() {
+        if let Block::EnhancedPacket(packet) = block.unwrap() {
+            count += 1;
+            drop_count += packet.options.iter().filter_map(|opt| match opt {
+                EnhancedPacketOption::DropCount(count) => Some(*count),
+                _ => None,
+            }).sum::<u64>();
+        }
+    }
+    assert_eq!(count, written + 1);
+    assert_eq!(drop_count, dropped);
+}
+
+#[test]
+fn threaded_writer_sinks() {
+    let interface = InterfaceDescriptionBlock::builder().linktype(DataLink::ETHERNET).build();
+
+    let mut writer = ThreadedWriter::with_sorting(PcapNgWriter::new(Vec::new()).unwrap(), 8, OverflowPolicy::Block, 256).unwrap();
+    writer.write_pcapng_block(interface.clone()).unwrap();
+
+    // Each thread writes every fourth timestamp
+    let threads = (0..4_u64)
+        .map(|thread| {
+            let sink = writer.sink().unwrap();
+            let interface = interface.clone();
+            std::thread::spawn(move || {
+                for i in 0..50_u64 {
+                    let micros = i * 4 + thread;
+                    let packet = EnhancedPacketBlock::builder(InterfaceId(0), &micros.to_le_bytes())
+                        .timestamp(Duration::from_micros(micros))
+                        .build(&interface)
+                        .unwrap()
+                        .into_owned();
+                    assert!(sink.write_pcapng_block(packet).unwrap());
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    let data = writer.finish().unwrap();
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let mut count = 0_u64;
+    while let Some(packet) = reader.next_packet() {
+        let packet = packet.unwrap();
+        assert_eq!(packet.timestamp, Some(Duration::from_micros(count)));
+        assert_eq!(packet.data[..], count.to_le_bytes());
+        count += 1;
+    }
+    assert_eq!(count, 200);
+}
+
+#[test]
+fn threaded_writer_sink_alive() {
+    let interface = InterfaceDescriptionBlock::builder().linktype(DataLink::ETHERNET).build();
+
+    let mut writer = ThreadedWriter::new(PcapNgWriter::new(Vec::new()).unwrap(), 8, OverflowPolicy::Block).unwrap();
+    let sink = writer.sink().unwrap();
+    assert_eq!(writer.add_interface(&interface).unwrap(), InterfaceId(0));
+    let interface_id = sink.add_interface(&interface).unwrap();
+    assert_eq!(interface_id, InterfaceId(1));
+
+    let packet = EnhancedPacketBlock::builder(interface_id, &[1, 2, 3]).build(&interface).unwrap().into_owned();
+    assert!(sink.write_pcapng_block(packet.clone()).unwrap());
+
+    // The sink is still alive, the writer doesn't wait for it
+    let data = writer.finish().unwrap();
+    assert!(sink.write_pcapng_block(packet).is_err());
+    assert!(sink.add_interface(&interface).is_err());
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let packet = reader.next_packet().unwrap().unwrap();
+    assert_eq!(packet.data[..], [1, 2, 3]);
+    assert!(reader.next_packet().is_none());
+    assert_eq!(reader.interfaces().len(), 2);
+}
+
+#[test]
+fn write_packets() {
+    let mut writer = PcapNgWriter::new(CountingWriter::default()).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0xFFFF)).unwrap();
+    let writes = writer.get_ref().writes;
+
+    let ts = UNIX_EPOCH + Duration::from_secs(1);
+    let payloads = (0..32_u8).map(|i| vec![i; i as usize + 1]).collect::<Vec<_>>();
+    let batch = payloads.iter().map(|data| PacketRef::new(interface, ts, data)).collect::<Vec<_>>();
+
+    let len = writer.write_packets(&batch).unwrap();
+    assert_eq!(writer.get_ref().writes, writes + 1);
+    assert_eq!(writer.stats().packets, 32);
+
+    // With a buffer, a batch which fits in it is kept
+    writer.set_buffer_capacity(1 << 20).unwrap();
+    assert!(writer.write_packets(&batch[..2]).unwrap() > 0);
+    assert_eq!(writer.get_ref().writes, writes + 1);
+
+    // The packets before an invalid one are written
+    let invalid = [batch[0], PacketRef::new(InterfaceId(5), ts, &[0; 4])];
+    assert!(writer.write_packets(&invalid).is_err());
+
+    let data = writer.into_inner().data;
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let mut lens = vec![];
+    while let Some(block) = reader.next_raw_block() {
+        let block = block.unwrap();
+        if block.type_ == ENHANCED_PACKET_BLOCK {
+            lens.push(block.initial_len as usize);
+        }
+    }
+    assert_eq!(lens.len(), 32 + 2 + 1);
+    assert_eq!(lens[..32].iter().sum::<usize>(), len);
+
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let mut i = 0;
+    while let Some(packet) = reader.next_packet() {
+        let packet = packet.unwrap();
+        assert_eq!(packet.timestamp, Some(Duration::from_secs(1)));
+        assert_eq!(packet.data[..], payloads[if i < 32 { i } else { (i - 32) % 2 }][..]);
+        i += 1;
+    }
+}
+
+#[test]
+fn write_packets_same_as_write_packet() {
+    for endianness in [Endianness::Big, Endianness::Little] {
+        let interface = InterfaceDescriptionBlock::builder().tsresol_nanos().snaplen(6).build();
+        let ts = UNIX_EPOCH + Duration::new(0x1_2345_6789, 0xABCDEF);
+        let payloads = [&[1_u8][..], &[1, 2, 3, 4], &[1, 2, 3, 4, 5, 6, 7, 8]];
+
+        let mut writer = PcapNgWriter::with_endianness(Vec::new(), endianness).unwrap();
+        let interface_id = writer.add_interface(&interface).unwrap();
+        for data in payloads {
+            writer.write_packet(interface_id, ts, data).unwrap();
+        }
+        let expected = writer.into_inner();
+
+        let mut writer = PcapNgWriter::with_endianness(Vec::new(), endianness).unwrap();
+        let interface_id = writer.add_interface(&interface).unwrap();
+        let batch = payloads.map(|data| PacketRef::new(interface_id, ts, data));
+        writer.write_packets(&batch).unwrap();
+        assert_eq!(writer.into_inner(), expected);
+    }
+}
+
+#[test]
+fn native_and_swapped_headers() {
+    for endianness in [Endianness::Big, Endianness::Little] {
+        let mut writer = PcapNgWriter::with_endianness(Vec::new(), endianness).unwrap();
+        let interface = writer.add_interface(&InterfaceDescriptionBlock::builder().tsresol_nanos().build()).unwrap();
+        let ts = UNIX_EPOCH + Duration::new(0x1_2345_6789, 0xABCDEF);
+        writer.write_packet(interface, ts, &[1, 2, 3, 4, 5]).unwrap();
+        let data = writer.into_inner();
+
+        let mut reader = PcapNgReader::new(&data[..]).unwrap();
+        let packet = reader.next_packet().unwrap().unwrap();
+        assert_eq!(packet.timestamp, Some(Duration::new(0x1_2345_6789, 0xABCDEF)));
+        assert_eq!(packet.original_len, 5);
+        assert_eq!(packet.data[..], [1, 2, 3, 4, 5]);
+    }
+}
+
+#[test]
+fn preallocate() {
+    let path = std::env::temp_dir().join(format!("pcap_file_preallocate_{}.pcapng", std::process::id()));
+    let mut writer = PcapNgWriter::new(File::create(&path).unwrap()).unwrap();
+    let interface = writer.add_interface(&InterfaceDescriptionBlock::new(DataLink::ETHERNET, 0)).unwrap();
+    writer.preallocate(1 << 20).unwrap();
+    let len = writer.stats().bytes;
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), len + (1 << 20));
+
+    for i in 0..10_u8 {
+        writer.write_packet(interface, UNIX_EPOCH, &[i; 60]).unwrap();
+    }
+    let len = writer.stats().bytes;
+
+    // A smaller preallocation doesn't shrink the file
+    writer.preallocate(16).unwrap();
+    assert!(std::fs::metadata(&path).unwrap().len() > len + 16);
+
+    writer.finish_truncated().unwrap();
+    let data = std::fs::read(&path).unwrap();
+    assert_eq!(data.len() as u64, len);
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let mut count = 0;
+    while let Some(packet) = reader.next_packet() {
+        assert_eq!(packet.unwrap().data[..], [count; 60]);
+        count += 1;
+    }
+    assert_eq!(count, 10);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn timestamp_as() {
+    // Ticks of 2^-10 second, with an offset of 100 seconds
+    let interface = InterfaceDescriptionBlock::builder().tsresol(0x80 | 10).tsoffset(100).build();
+    let packet = EnhancedPacketBlock::builder(InterfaceId(0), &[0; 4])
+        .timestamp(Duration::from_millis(101_500))
+        .build(&interface)
+        .unwrap();
+
+    let ticks = packet.timestamp.as_nanos() as u64;
+    assert_eq!(ticks, 1_536);
+    assert_eq!(packet.timestamp_as::<u64>(&interface), Some(ticks));
+    assert_eq!(packet.timestamp_as::<Duration>(&interface), Some(Duration::from_millis(101_500)));
+    assert_eq!(packet.timestamp_as::<Duration>(&interface), Some(interface.timestamp_to_duration(ticks)));
+    assert_eq!(packet.timestamp_as::<SystemTime>(&interface), Some(UNIX_EPOCH + interface.timestamp_to_duration(ticks)));
+
+    let mut writer = PcapNgWriter::new(Vec::new()).unwrap();
+    writer.write_pcapng_block(&interface).unwrap();
+    writer.write_pcapng_block(&packet).unwrap();
+    let data = writer.into_inner();
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let read = reader.next_packet().unwrap().unwrap();
+    assert_eq!(read.timestamp_as::<Duration>(), read.timestamp);
+    assert_eq!(read.timestamp_as::<u64>(), Some(read.timestamp.unwrap().as_nanos() as u64));
+
+    #[cfg(feature = "chrono")]
+    {
+        let time = packet.timestamp_as::<chrono::DateTime<chrono::Utc>>(&interface).unwrap();
+        assert_eq!(time.timestamp_nanos_opt(), Some(read.timestamp.unwrap().as_nanos() as i64));
+    }
+
+    #[cfg(feature = "time")]
+    {
+        let time = packet.timestamp_as::<time::OffsetDateTime>(&interface).unwrap();
+        assert_eq!(time.unix_timestamp_nanos(), read.timestamp.unwrap().as_nanos() as i128);
+    }
+}
+
+#[test]
+fn set_timestamp() {
+    let interface = InterfaceDescriptionBlock::builder().tsresol(0x80 | 10).tsoffset(100).build();
+    let time = UNIX_EPOCH + Duration::from_millis(101_500);
+
+    let mut packet = EnhancedPacketBlock::builder(InterfaceId(0), &[0; 4]).time(time).build(&interface).unwrap();
+    assert_eq!(packet.timestamp_as::<SystemTime>(&interface), Some(time));
+    packet.set_timestamp(&interface, Duration::from_secs(102)).unwrap();
+    assert_eq!(packet.timestamp_as::<u64>(&interface), Some(2_048));
+    // Before the tsoffset of the interface
+    assert!(packet.set_timestamp(&interface, Duration::from_secs(99)).is_err());
+
+    let mut stats = InterfaceStatisticsBlock { interface_id: 0, timestamp: 0, options: vec![InterfaceStatisticsOption::IsbStartTime(0)] };
+    stats.set_timestamp(&interface, time).unwrap();
+    stats.set_start_time(&interface, Duration::from_secs(101)).unwrap();
+    stats.set_end_time(&interface, time).unwrap();
+    assert_eq!(stats.timestamp, 1_536);
+    assert_eq!(stats.start_time(), Some(1_024));
+    assert_eq!(stats.end_time(), Some(1_536));
+    assert_eq!(stats.options.len(), 2);
+    assert_eq!(stats.start_time_as::<Duration>(&interface), Some(Duration::from_secs(101)));
+    assert_eq!(stats.end_time_as::<SystemTime>(&interface), Some(time));
+
+    let mut writer = PcapNgWriter::new(Vec::new()).unwrap();
+    writer.write_pcapng_block(&interface).unwrap();
+    writer.write_pcapng_block(&packet).unwrap();
+    let data = writer.into_inner();
+    let mut reader = PcapNgReader::new(&data[..]).unwrap();
+    let mut read = reader.next_packet().unwrap().unwrap();
+    read.set_timestamp(time).unwrap();
+    assert_eq!(read.timestamp, Some(Duration::from_millis(101_500)));
+
+    // A simple packet has no timestamp
+    let mut simple = read.clone();
+    simple.timestamp = None;
+    assert!(simple.set_timestamp(time).is_err());
+
+    #[cfg(feature = "chrono")]
+    {
+        let utc = chrono::DateTime::from_timestamp(101, 500_000_000).unwrap();
+        assert_eq!(read.timestamp_utc(), Some(utc));
+        assert_eq!(stats.timestamp_utc(&interface), Some(utc));
+        assert_eq!(stats.end_time_utc(&interface), Some(utc));
+        packet.set_timestamp(&interface, utc).unwrap();
+        assert_eq!(packet.timestamp_utc(&interface), Some(utc));
+        packet.set_timestamp(&interface, utc.with_timezone(&chrono::FixedOffset::east_opt(3600).unwrap())).unwrap();
+        assert_eq!(packet.timestamp_utc(&interface), Some(utc));
+        stats.set_start_time(&interface, utc).unwrap();
+        assert_eq!(stats.start_time_utc(&interface), Some(utc));
+    }
+
+    #[cfg(feature = "time")]
+    {
+        let odt = time::OffsetDateTime::from_unix_timestamp_nanos(101_500_000_000).unwrap();
+        assert_eq!(read.timestamp_offset_date_time(), Some(odt));
+        assert_eq!(stats.timestamp_offset_date_time(&interface), Some(odt));
+        assert_eq!(stats.end_time_offset_date_time(&interface), Some(odt));
+        packet.set_timestamp(&interface, odt).unwrap();
+        assert_eq!(packet.timestamp_offset_date_time(&interface), Some(odt));
+        stats.set_start_time(&interface, odt).unwrap();
+        assert_eq!(stats.start_time_offset_date_time(&interface), Some(odt));
+    }
+}
@@ -1,4 +1,30 @@
 #![allow(clippy::unreadable_literal)]
 
+use std::io::{IoSlice, Write};
+
 mod pcap;
 mod pcapng;
+
+
+/// Writer counting its calls, a vectored write being a single call like `writev()`
+#[derive(Default)]
+struct CountingWriter {
+    data: Vec<u8>,
+    writes: usize,
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.writes += 1;
+        self.data.write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> std::io::Result<usize> {
+        self.writes += 1;
+        self.data.write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}